@@ -0,0 +1,72 @@
+// Two fact sources on independent clocks.
+//
+// Run with:
+//   cargo run --release --example concurrent_scanners
+//
+// A vulnerability scanner and a firewall config feed report updates on
+// their own clocks.  The scanner finds a new bug on db01 and the
+// firewall team opens web01 -> db01; the goal is printed for every
+// combination of the two, whichever arrived first.
+
+use dynamic_attack_graphs::{
+    AttackerStartingPosition, AttackerTargetGoal, BaseFacts, ConcurrentSourcesDriver,
+    FirewallRuleRecord, NetworkAccessRule, PrivilegeLevel, VulnerabilityRecord,
+};
+
+fn main() {
+    println!("=================================================");
+    println!("  Concurrent Vulnerability and Firewall Updates");
+    println!("=================================================\n");
+
+    let web_db_deny = FirewallRuleRecord::create_deny_rule("web01", "db01", "mysql");
+    let facts = BaseFacts {
+        vulnerabilities: vec![VulnerabilityRecord::new(
+            "web01",
+            "CVE-2024-1234",
+            "http",
+            PrivilegeLevel::User,
+        )],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "http"),
+            NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+        ],
+        firewall_rules: vec![web_db_deny.clone()],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "db01")],
+        ..BaseFacts::default()
+    };
+
+    timely::execute_directly(move |worker| {
+        let mut driver = ConcurrentSourcesDriver::new(worker, &facts);
+
+        // The firewall change lands first, then the scan, then the
+        // deny is put back; each clock only moves its own axis.
+        driver.remove_firewall_rule(web_db_deny.clone());
+        driver.advance_firewall_clock(worker);
+        driver.insert_vulnerability(VulnerabilityRecord::new(
+            "db01",
+            "CVE-2024-5678",
+            "mysql",
+            PrivilegeLevel::Root,
+        ));
+        driver.advance_vulnerability_clock(worker);
+        driver.insert_firewall_rule(web_db_deny);
+        driver.advance_firewall_clock(worker);
+
+        let settled = driver.settled_time();
+        println!("scans \\ firewall changes");
+        for scans in 0..=settled.vulnerability_scans() {
+            for changes in 0..=settled.firewall_changes() {
+                let reached = !driver.goals_reached_at(scans, changes).is_empty();
+                println!(
+                    "  ({scans}, {changes}): db01 {}",
+                    if reached { "REACHED" } else { "safe" }
+                );
+            }
+        }
+    });
+}
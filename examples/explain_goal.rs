@@ -23,8 +23,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         VulnerabilityRecord::new("admin01", "CVE-2024-8888", "smb", PrivilegeLevel::Root),
     ];
     let network_access = vec![
-        NetworkAccessRule::new("internet", "db01", "postgres"),
-        NetworkAccessRule::new("db01", "admin01", "smb"),
+        NetworkAccessRule::new_default_port("internet", "db01", "postgres"),
+        NetworkAccessRule::new_default_port("db01", "admin01", "smb"),
     ];
     let firewall_rules = Vec::new();
     let attacker_positions = vec![AttackerStartingPosition::new(
@@ -62,6 +62,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let target = Fact::GoalReached {
         attacker_id: "eve".to_string(),
         target: "admin01".to_string(),
+        required_privilege: PrivilegeLevel::Root,
     };
     let explanation = explainer
         .explain(&target)
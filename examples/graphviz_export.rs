@@ -2,87 +2,21 @@
 // Run with: cargo run --release --example graphviz_export
 // Then: dot -Tpng graph_initial.dot -o graph_initial.png
 //       dot -Tpng graph_final.dot -o graph_final.png
+// It also writes one snapshot per timestamp to graph_frames/ and
+// renders them as frame_0000.png, frame_0001.png, ... when `dot` is
+// installed, ready to be stitched into a GIF.
 
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::Write;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::sync::{Arc, Mutex};
 
 use differential_dataflow::input::Input;
 use differential_dataflow::operators::iterate::Iterate;
 use differential_dataflow::operators::join::Join;
 use differential_dataflow::operators::reduce::Threshold;
+use dynamic_attack_graphs::{
+    chain_base_facts, export_dot_animation, AttackGraphState, FactUpdate, PrivilegeLevel,
+};
 use timely::dataflow::operators::probe::Handle;
-use timely::dataflow::operators::Probe;
-
-/// Represents the attack graph state for visualization
-#[derive(Clone, Default)]
-struct AttackGraphState {
-    nodes: HashSet<String>,
-    edges: HashSet<(String, String, String)>, // (src, dst, service)
-    compromised: HashSet<String>,             // nodes attacker can reach
-    attacker_start: String,
-    target_node: String,
-}
-
-impl AttackGraphState {
-    fn export_to_dot(&self, filename: &str, title: &str) -> std::io::Result<()> {
-        let mut file = File::create(filename)?;
-
-        writeln!(file, "digraph AttackGraph {{")?;
-        writeln!(file, "    label=\"{}\";", title)?;
-        writeln!(file, "    labelloc=\"t\";")?;
-        writeln!(file, "    fontsize=20;")?;
-        writeln!(file, "    rankdir=LR;")?;
-        writeln!(file, "    node [shape=box, style=filled];")?;
-        writeln!(file)?;
-
-        // Define node styles
-        for node in &self.nodes {
-            let (color, label_suffix) = if node == &self.attacker_start {
-                ("lightblue", " [ATTACKER]")
-            } else if node == &self.target_node {
-                if self.compromised.contains(node) {
-                    ("red", " [TARGET - COMPROMISED!]")
-                } else {
-                    ("lightgreen", " [TARGET - SAFE]")
-                }
-            } else if self.compromised.contains(node) {
-                ("orange", " [COMPROMISED]")
-            } else {
-                ("white", "")
-            };
-
-            writeln!(
-                file,
-                "    \"{}\" [fillcolor={}, label=\"{}{}\"];",
-                node, color, node, label_suffix
-            )?;
-        }
-        writeln!(file)?;
-
-        // Define edges with attack path highlighting
-        for (src, dst, service) in &self.edges {
-            let is_attack_path = self.compromised.contains(src) && self.compromised.contains(dst);
-            let (color, penwidth) = if is_attack_path {
-                ("red", "2.0")
-            } else {
-                ("black", "1.0")
-            };
-
-            writeln!(
-                file,
-                "    \"{}\" -> \"{}\" [label=\"{}\", color={}, penwidth={}];",
-                src, dst, service, color, penwidth
-            )?;
-        }
-
-        writeln!(file, "}}")?;
-
-        println!("Exported: {}", filename);
-        Ok(())
-    }
-}
 
 fn main() {
     println!("=================================================");
@@ -98,6 +32,10 @@ fn main() {
 
         let compromised_nodes = Arc::new(Mutex::new(HashSet::<String>::new()));
         let compromised_clone = Arc::clone(&compromised_nodes);
+        // (host, gained root) for every vulnerability exploited on a
+        // compromised host.
+        let exploited_vulns = Arc::new(Mutex::new(HashSet::<(String, bool)>::new()));
+        let exploited_clone = Arc::clone(&exploited_vulns);
 
         let (mut vuln_input, mut network_input, mut attacker_input) = worker
             .dataflow::<usize, _, _>(|scope| {
@@ -142,6 +80,22 @@ fn main() {
                     })
                     .probe_with(&mut probe);
 
+                // Track the privilege gained on each compromised node
+                reachable
+                    .map(|(att, host)| (host, att))
+                    .join(&vulns.map(|(h, _, is_root)| (h, is_root)))
+                    .map(|(host, (_, is_root))| (host, is_root))
+                    .distinct()
+                    .inspect(move |(exploited, _, diff)| {
+                        let mut set = exploited_clone.lock().unwrap();
+                        if *diff > 0 {
+                            set.insert(exploited.clone());
+                        } else {
+                            set.remove(exploited);
+                        }
+                    })
+                    .probe_with(&mut probe);
+
                 (vuln_handle, net_handle, att_handle)
             });
 
@@ -151,8 +105,8 @@ fn main() {
         // Network: attacker -> node_0 -> node_1 -> ... -> node_8 -> target
 
         let num_nodes = 10;
-        let mut nodes = HashSet::new();
-        let mut edges = HashSet::new();
+        let mut nodes = BTreeSet::new();
+        let mut edges = BTreeSet::new();
 
         // Attacker starting point
         nodes.insert("attacker".to_string());
@@ -203,13 +157,15 @@ fn main() {
         // Export initial graph
         {
             let mut state = graph_state_clone.lock().unwrap();
-            state.compromised = compromised_nodes.lock().unwrap().clone();
+            state.compromised = compromised_nodes.lock().unwrap().iter().cloned().collect();
+            state.privileges = privileges_by_host(&exploited_vulns.lock().unwrap());
             state
                 .export_to_dot(
                     "graph_initial.dot",
                     "Initial Attack Graph - All nodes compromised",
                 )
                 .unwrap();
+            println!("Exported: graph_initial.dot");
         }
 
         println!(
@@ -238,13 +194,21 @@ fn main() {
         // Export final graph
         {
             let mut state = graph_state_clone.lock().unwrap();
-            state.compromised = compromised_nodes.lock().unwrap().clone();
+            state.compromised = compromised_nodes.lock().unwrap().iter().cloned().collect();
+            state.privileges = privileges_by_host(&exploited_vulns.lock().unwrap());
             state
                 .export_to_dot(
                     "graph_final.dot",
                     "After Patching node_4 - Attack path broken",
                 )
                 .unwrap();
+            println!("Exported: graph_final.dot");
+            state.export_to_json("graph_final.json").unwrap();
+            println!("Exported: graph_final.json");
+            state
+                .export_to_cytoscape_json("graph_final.cyjs.json")
+                .unwrap();
+            println!("Exported: graph_final.cyjs.json (Cytoscape.js)");
         }
 
         let final_compromised = compromised_nodes.lock().unwrap().len();
@@ -254,6 +218,55 @@ fn main() {
         );
     });
 
+    // =====================================================
+    // Animation: one snapshot per timestamp
+    // =====================================================
+    // The chain is patched at node_4, the patch is rolled back, and
+    // node_7 is patched instead, so the frames show the attack
+    // retreating, spreading again and retreating less far.
+    let facts = chain_base_facts(10);
+    let vulnerability_on = |host: &str| {
+        facts
+            .vulnerabilities
+            .iter()
+            .find(|vulnerability| vulnerability.host_name == host)
+            .expect("every chain node has a vulnerability")
+            .clone()
+    };
+    let changes_over_time = vec![
+        (
+            1,
+            vec![FactUpdate::RemoveVulnerability(vulnerability_on("node_4"))],
+        ),
+        (
+            2,
+            vec![FactUpdate::InsertVulnerability(vulnerability_on("node_4"))],
+        ),
+        (
+            3,
+            vec![FactUpdate::RemoveVulnerability(vulnerability_on("node_7"))],
+        ),
+    ];
+    let animation = export_dot_animation(
+        "graph_frames",
+        &facts,
+        &changes_over_time,
+        "attacker",
+        "node_9",
+    )
+    .unwrap();
+    for (time, path) in &animation.frames {
+        println!("Exported: {} (t={})", path.display(), time);
+    }
+    match animation.render_pngs() {
+        Ok(pngs) => println!("Rendered {} PNG frames into graph_frames/\n", pngs.len()),
+        Err(error) => {
+            println!("Could not run dot ({error}); render the frames with:");
+            print!("{}", animation.png_commands());
+            println!();
+        }
+    }
+
     println!("=================================================");
     println!("  Visualization files generated!");
     println!("=================================================");
@@ -262,14 +275,38 @@ fn main() {
     println!("  dot -Tpng graph_initial.dot -o graph_initial.png");
     println!("  dot -Tpng graph_final.dot -o graph_final.png");
     println!();
+    println!("To turn the frames into a GIF (ImageMagick):");
+    println!("  convert -delay 100 graph_frames/frame_*.png attack.gif");
+    println!();
     println!("Or use the provided script:");
     println!("  ./generate_graphs.sh");
     println!();
     println!("Legend:");
     println!("  - Blue node: Attacker starting position");
-    println!("  - Orange nodes: Compromised by attacker");
+    println!("  - Yellow nodes: Compromised with user privileges");
+    println!("  - Red nodes: Compromised with root privileges");
     println!("  - Red node: Target compromised");
     println!("  - Green node: Target safe");
     println!("  - Red edges: Active attack path");
     println!("  - Black edges: Network connection (not exploited)");
 }
+
+// Root on a host if any exploited vulnerability there grants root,
+// user otherwise.
+fn privileges_by_host(exploited: &HashSet<(String, bool)>) -> BTreeMap<String, PrivilegeLevel> {
+    let mut privileges = BTreeMap::new();
+    for (host, is_root) in exploited {
+        let privilege = if *is_root {
+            PrivilegeLevel::Root
+        } else {
+            PrivilegeLevel::User
+        };
+        let held = privileges
+            .entry(host.clone())
+            .or_insert(PrivilegeLevel::User);
+        if privilege > *held {
+            *held = privilege;
+        }
+    }
+    privileges
+}
@@ -2,15 +2,26 @@
 // Run with: cargo run --release --example run_benchmarks
 
 use dynamic_attack_graphs::benchmarks::{
-    print_enterprise_benchmark_table, run_chain_benchmark, run_chain_random_cut_benchmark,
-    run_enterprise_benchmark, run_star_benchmark, write_benchmark_csv, BenchmarkCsvRow,
-    BenchmarkResults, EnterpriseScenarioConfig, RandomCutBenchmarkResults,
+    print_enterprise_benchmark_table, run_batching_benchmark, run_chain_benchmark_with_progress,
+    run_chain_benchmark_with_warmup, run_chain_random_cut_benchmark_with_seed,
+    run_enterprise_benchmark, run_firewall_cut_benchmark, run_mesh_benchmark,
+    run_reachability_count_benchmark, run_session_benchmark, run_star_benchmark_with_warmup,
+    warm_up_dataflow, write_benchmark_csv, BenchmarkCsvRow, BenchmarkResults,
+    EnterpriseScenarioConfig, ProgressBar, RandomCutBenchmarkResults,
 };
 use std::fs::File;
+use std::io::Write;
 use std::path::PathBuf;
 
 fn main() {
-    let csv_path = parse_csv_path();
+    // The library logs its summaries and tables; print them bare on
+    // stdout unless RUST_LOG says otherwise.
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format(|buffer, record| writeln!(buffer, "{}", record.args()))
+        .target(env_logger::Target::Stdout)
+        .init();
+
+    let (csv_path, show_progress, seed, warmup) = parse_args();
     let mut csv_rows = Vec::new();
 
     println!("==========================================================");
@@ -23,7 +34,7 @@ fn main() {
     // Good for demonstrating scalability with large N
     println!("PART 1: Star Network Benchmarks (O(1) iteration depth)");
     println!("--------------------------------------------------------");
-    let star_sizes = vec![50, 100, 200, 500, 1000];
+    let star_sizes = [50, 100, 200, 500, 1000];
 
     let mut star_results = Vec::new();
     for (index, &number_of_leaves) in star_sizes.iter().enumerate() {
@@ -35,7 +46,7 @@ fn main() {
         );
         std::io::Write::flush(&mut std::io::stdout()).ok();
 
-        let result = run_star_benchmark(number_of_leaves);
+        let result = run_star_benchmark_with_warmup(number_of_leaves, warmup);
         result.print_summary();
         csv_rows.push(BenchmarkCsvRow::from_star(number_of_leaves, &result));
         star_results.push(result);
@@ -47,7 +58,7 @@ fn main() {
     println!("PART 2: Chain Network Benchmarks (O(N) iteration depth)");
     println!("--------------------------------------------------------");
     println!("Note: Chain requires O(N) iterations - keeping sizes small.");
-    let chain_sizes = vec![10, 50, 100, 200];
+    let chain_sizes = [10, 50, 100, 200];
 
     let mut chain_results = Vec::new();
     for (index, &number_of_nodes) in chain_sizes.iter().enumerate() {
@@ -59,7 +70,15 @@ fn main() {
         );
         std::io::Write::flush(&mut std::io::stdout()).ok();
 
-        let result = run_chain_benchmark(number_of_nodes);
+        let result = if show_progress {
+            if warmup {
+                warm_up_dataflow();
+            }
+            let bar = ProgressBar::new(&format!("chain {number_of_nodes}"));
+            run_chain_benchmark_with_progress(number_of_nodes, 1, Some(bar.into_callback()))
+        } else {
+            run_chain_benchmark_with_warmup(number_of_nodes, warmup)
+        };
         result.print_summary();
         csv_rows.push(BenchmarkCsvRow::from_chain(&result));
         chain_results.push(result);
@@ -89,7 +108,7 @@ fn main() {
     println!("This shows that speedup depends on WHERE you cut the chain.");
     println!();
 
-    let random_cut_sizes = vec![50, 100, 200, 500];
+    let random_cut_sizes = [50, 100, 200, 500];
     let iterations = 100;
 
     let mut random_cut_results = Vec::new();
@@ -103,11 +122,17 @@ fn main() {
         );
         std::io::Write::flush(&mut std::io::stdout()).ok();
 
-        let result = run_chain_random_cut_benchmark(number_of_nodes, iterations);
+        let result = run_chain_random_cut_benchmark_with_seed(
+            number_of_nodes,
+            iterations,
+            seed.map(|seed| seed.wrapping_add(index as u64)),
+        );
         println!(
-            "  Initial: {:.2}ms, Avg Incr: {:.2}us (min: {:.2}us, max: {:.2}us), Speedup: {:.1}x",
+            "  Initial: {:.2}ms, Avg Incr: {:.2}us (median: {:.2}us, stddev: {:.2}us, min: {:.2}us, max: {:.2}us), Speedup: {:.1}x",
             result.initial_computation_time.as_secs_f64() * 1000.0,
             result.average_incremental_time.as_secs_f64() * 1_000_000.0,
+            result.median_incremental_time.as_secs_f64() * 1_000_000.0,
+            result.stddev_incremental_time.as_secs_f64() * 1_000_000.0,
             result.min_incremental_time.as_secs_f64() * 1_000_000.0,
             result.max_incremental_time.as_secs_f64() * 1_000_000.0,
             result.average_speedup
@@ -138,6 +163,95 @@ fn main() {
     print_enterprise_benchmark_table(&enterprise_results);
     println!();
 
+    // PART 5: Batched vs per-edit timestamp advancement
+    println!();
+    println!("PART 5: Batched vs Per-Edit Advancement");
+    println!("----------------------------------------");
+    println!("Applying 500 small edits to a 100-node chain.");
+    println!();
+
+    for batch_size in [10, 50, 100] {
+        let result = run_batching_benchmark(100, 500, batch_size);
+        result.print_summary();
+    }
+
+    // PART 6: Counting reachable pairs vs materializing them
+    println!();
+    println!("PART 6: count_reachable vs Full Materialization");
+    println!("-----------------------------------------------");
+    println!();
+
+    for grid_size in [10, 20, 30] {
+        let result = run_reachability_count_benchmark(grid_size, grid_size);
+        result.print_summary();
+    }
+
+    // PART 7: Mesh (grid) network benchmarks
+    // Many alternative paths: patching one interior node loses only that node
+    println!();
+    println!("PART 7: Mesh Network Benchmarks (O(width + height) iteration depth)");
+    println!("--------------------------------------------------------------------");
+    let mesh_sizes = [(5, 5), (10, 10), (20, 20), (30, 30)];
+
+    let mut mesh_results = Vec::new();
+    for (index, &(width, height)) in mesh_sizes.iter().enumerate() {
+        println!(
+            "[{}/{}] Testing {}x{} mesh...",
+            index + 1,
+            mesh_sizes.len(),
+            width,
+            height
+        );
+        std::io::Write::flush(&mut std::io::stdout()).ok();
+
+        let result = run_mesh_benchmark(width, height);
+        result.print_summary();
+        csv_rows.push(BenchmarkCsvRow::from_mesh(width, height, &result));
+        mesh_results.push(result);
+    }
+
+    println!("--- Mesh Network (grid with alternative paths) ---");
+    print_markdown_table(&mesh_results);
+    println!();
+
+    // PART 8: Topology updates instead of vulnerability patches
+    println!();
+    println!("PART 8: Firewall Cut on a Chain (deny the middle edge)");
+    println!("-------------------------------------------------------");
+    let firewall_cut_sizes = [10, 50, 100, 200];
+
+    let mut firewall_cut_results = Vec::new();
+    for (index, &number_of_nodes) in firewall_cut_sizes.iter().enumerate() {
+        println!(
+            "[{}/{}] Cutting chain with {} nodes...",
+            index + 1,
+            firewall_cut_sizes.len(),
+            number_of_nodes
+        );
+        std::io::Write::flush(&mut std::io::stdout()).ok();
+
+        let result = run_firewall_cut_benchmark(number_of_nodes);
+        result.print_summary();
+        csv_rows.push(BenchmarkCsvRow::from_firewall_cut(&result));
+        firewall_cut_results.push(result);
+    }
+
+    println!("--- Firewall Cut (topology update) ---");
+    print_markdown_table(&firewall_cut_results);
+    println!();
+
+    // PART 9: Rebuilding per query vs a long-lived session
+    println!();
+    println!("PART 9: Rebuilt Dataflow vs AttackGraphSession");
+    println!("----------------------------------------------");
+    println!("Reading reached goals after each of 100 small edits.");
+    println!();
+
+    for number_of_nodes in [50, 100, 200] {
+        let result = run_session_benchmark(number_of_nodes, 100);
+        result.print_summary();
+    }
+
     println!("--- LaTeX format (Star) ---");
     print_latex_table(&star_results);
 
@@ -158,9 +272,12 @@ fn main() {
     }
 }
 
-fn parse_csv_path() -> Option<PathBuf> {
+fn parse_args() -> (Option<PathBuf>, bool, Option<u64>, bool) {
     let mut args = std::env::args().skip(1);
     let mut csv_path = None;
+    let mut show_progress = false;
+    let mut seed = None;
+    let mut warmup = false;
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -170,15 +287,29 @@ fn parse_csv_path() -> Option<PathBuf> {
                     .unwrap_or_else(|| panic!("--csv requires an output path"));
                 csv_path = Some(PathBuf::from(path));
             }
+            "--progress" => show_progress = true,
+            "--warmup" => warmup = true,
+            "--seed" => {
+                let value = args
+                    .next()
+                    .unwrap_or_else(|| panic!("--seed requires a number"));
+                seed = Some(
+                    value
+                        .parse()
+                        .unwrap_or_else(|_| panic!("--seed expects an unsigned integer")),
+                );
+            }
             "--help" | "-h" => {
-                println!("Usage: cargo run --release --example run_benchmarks -- [--csv PATH]");
+                println!(
+                    "Usage: cargo run --release --example run_benchmarks -- [--csv PATH] [--progress] [--seed N] [--warmup]"
+                );
                 std::process::exit(0);
             }
             other => panic!("unknown argument: {other}"),
         }
     }
 
-    csv_path
+    (csv_path, show_progress, seed, warmup)
 }
 
 fn print_latex_table(results: &[BenchmarkResults]) {
@@ -201,36 +332,50 @@ fn print_latex_table(results: &[BenchmarkResults]) {
 
 fn print_markdown_table(results: &[BenchmarkResults]) {
     println!("Markdown table:");
-    println!("| Nodes | Initial (ms) | Incremental (us) | Speedup |");
-    println!("|------:|-------------:|-----------------:|--------:|");
+    println!(
+        "| Nodes | Initial (ms) | Incremental (us) | Speedup | Arrangements (KiB) | Fixpoint rounds |"
+    );
+    println!(
+        "|------:|-------------:|-----------------:|--------:|-------------------:|----------------:|"
+    );
     for r in results {
         let initial_ms = r.initial_computation_time.as_secs_f64() * 1000.0;
         let incremental_us = r.incremental_update_time.as_secs_f64() * 1_000_000.0;
+        let arrangement_kib = r.peak_arrangement_bytes as f64 / 1024.0;
         println!(
-            "| {} | {:.2} | {:.2} | {:.1}x |",
-            r.number_of_nodes, initial_ms, incremental_us, r.speedup_factor
+            "| {} | {:.2} | {:.2} | {:.1}x | {:.1} | {} |",
+            r.number_of_nodes,
+            initial_ms,
+            incremental_us,
+            r.speedup_factor,
+            arrangement_kib,
+            r.fixpoint_iterations
         );
     }
 }
 
 fn print_random_cut_markdown_table(results: &[RandomCutBenchmarkResults]) {
     println!(
-        "| Nodes | Iterations | Initial (ms) | Avg Incr (us) | Min (us) | Max (us) | Speedup |"
+        "| Nodes | Iterations | Initial (ms) | Avg Incr (us) | Median (us) | Stddev (us) | Min (us) | Max (us) | Speedup |"
     );
     println!(
-        "|------:|-----------:|-------------:|--------------:|---------:|---------:|--------:|"
+        "|------:|-----------:|-------------:|--------------:|------------:|------------:|---------:|---------:|--------:|"
     );
     for r in results {
         let initial_ms = r.initial_computation_time.as_secs_f64() * 1000.0;
         let avg_us = r.average_incremental_time.as_secs_f64() * 1_000_000.0;
+        let median_us = r.median_incremental_time.as_secs_f64() * 1_000_000.0;
+        let stddev_us = r.stddev_incremental_time.as_secs_f64() * 1_000_000.0;
         let min_us = r.min_incremental_time.as_secs_f64() * 1_000_000.0;
         let max_us = r.max_incremental_time.as_secs_f64() * 1_000_000.0;
         println!(
-            "| {} | {} | {:.2} | {:.2} | {:.2} | {:.2} | {:.1}x |",
+            "| {} | {} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.2} | {:.1}x |",
             r.number_of_nodes,
             r.number_of_iterations,
             initial_ms,
             avg_us,
+            median_us,
+            stddev_us,
             min_us,
             max_us,
             r.average_speedup
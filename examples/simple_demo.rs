@@ -8,13 +8,15 @@ use differential_dataflow::operators::iterate::Iterate;
 use differential_dataflow::operators::join::Join;
 use differential_dataflow::operators::reduce::Threshold;
 use timely::dataflow::operators::probe::Handle;
-use timely::dataflow::operators::Probe;
 
 fn main() {
     println!("Simple Attack Graph Demo");
     println!("========================\n");
 
     timely::execute_from_args(std::env::args(), |worker| {
+        // Worker 0 feeds the inputs and prints the headers; every
+        // worker prints the derived facts in its own partition.
+        let is_main_worker = worker.index() == 0;
         let mut computation_probe = Handle::new();
 
         // Create the dataflow
@@ -115,19 +117,21 @@ fn main() {
         // ----------------------------------------------------------------
         // Timestamp 0: Initial network state
         // ----------------------------------------------------------------
-        println!("Timestamp 0: Initial network");
-        println!("----------------------------");
+        if is_main_worker {
+            println!("Timestamp 0: Initial network");
+            println!("----------------------------");
 
-        // Simple network: internet -> web -> db
-        network_access_input.insert(("internet".into(), "web".into(), "http".into()));
-        network_access_input.insert(("web".into(), "db".into(), "mysql".into()));
+            // Simple network: internet -> web -> db
+            network_access_input.insert(("internet".into(), "web".into(), "http".into()));
+            network_access_input.insert(("web".into(), "db".into(), "mysql".into()));
 
-        // Vulnerabilities (host, service, grants_root)
-        vulnerability_input.insert(("web".into(), "http".into(), false)); // user level only
-        vulnerability_input.insert(("db".into(), "mysql".into(), true)); // grants root
+            // Vulnerabilities (host, service, grants_root)
+            vulnerability_input.insert(("web".into(), "http".into(), false)); // user level only
+            vulnerability_input.insert(("db".into(), "mysql".into(), true)); // grants root
 
-        // Attacker starts on the internet
-        attacker_input.insert(("eve".into(), "internet".into()));
+            // Attacker starts on the internet
+            attacker_input.insert(("eve".into(), "internet".into()));
+        }
 
         vulnerability_input.advance_to(1);
         vulnerability_input.flush();
@@ -139,16 +143,22 @@ fn main() {
         while computation_probe.less_than(&1) {
             worker.step();
         }
-        println!();
+        if is_main_worker {
+            println!();
+        }
 
         // ----------------------------------------------------------------
         // Timestamp 1: Patch the web server vulnerability
         // ----------------------------------------------------------------
-        println!("Timestamp 1: Patching web server vulnerability");
-        println!("-----------------------------------------------");
+        if is_main_worker {
+            println!("Timestamp 1: Patching web server vulnerability");
+            println!("-----------------------------------------------");
+        }
 
         let update_start = Instant::now();
-        vulnerability_input.remove(("web".into(), "http".into(), false));
+        if is_main_worker {
+            vulnerability_input.remove(("web".into(), "http".into(), false));
+        }
 
         vulnerability_input.advance_to(2);
         vulnerability_input.flush();
@@ -160,17 +170,23 @@ fn main() {
         while computation_probe.less_than(&2) {
             worker.step();
         }
-        println!("  Computed in {:?}", update_start.elapsed());
-        println!();
+        if is_main_worker {
+            println!("  Computed in {:?}", update_start.elapsed());
+            println!();
+        }
 
         // ----------------------------------------------------------------
         // Timestamp 2: New vulnerability discovered on web server
         // ----------------------------------------------------------------
-        println!("Timestamp 2: New vulnerability on web server");
-        println!("---------------------------------------------");
+        if is_main_worker {
+            println!("Timestamp 2: New vulnerability on web server");
+            println!("---------------------------------------------");
+        }
 
         let update_start = Instant::now();
-        vulnerability_input.insert(("web".into(), "http".into(), false));
+        if is_main_worker {
+            vulnerability_input.insert(("web".into(), "http".into(), false));
+        }
 
         vulnerability_input.advance_to(3);
         vulnerability_input.flush();
@@ -182,10 +198,14 @@ fn main() {
         while computation_probe.less_than(&3) {
             worker.step();
         }
-        println!("  Computed in {:?}", update_start.elapsed());
-        println!();
+        if is_main_worker {
+            println!("  Computed in {:?}", update_start.elapsed());
+            println!();
+        }
 
-        println!("Demo complete.");
+        if is_main_worker {
+            println!("Demo complete.");
+        }
     })
     .expect("Computation failed");
 }
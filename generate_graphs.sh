@@ -27,6 +27,14 @@ if [ -f "graph_final.dot" ]; then
     echo "Created: graph_final.png"
 fi
 
+# The example renders graph_frames/frame_*.png itself; stitch them
+# into a GIF when ImageMagick is available.
+if command -v convert &> /dev/null && ls graph_frames/frame_*.png &> /dev/null; then
+    echo "Stitching graph_frames/frame_*.png into attack.gif..."
+    convert -delay 100 graph_frames/frame_*.png attack.gif
+    echo "Created: attack.gif"
+fi
+
 echo ""
 echo "Done! Open the PNG files to see the attack graphs:"
 echo "  open graph_initial.png graph_final.png"
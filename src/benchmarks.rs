@@ -7,14 +7,28 @@
 // incremental update (e.g. patching a vulnerability).  The
 // measurements form the empirical evidence used in the paper.
 
-use std::io::{self, Write};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
 use differential_dataflow::input::Input;
+use serde::{Deserialize, Serialize};
+use timely::communication::Allocate;
 use timely::dataflow::operators::probe::Handle;
-
-use crate::rules::build_attack_graph;
+use timely::worker::Worker;
+
+use crate::driver::{BatchDriver, BatchDriverConfig, FactInputs};
+use crate::engine::{BaseFacts, DerivedFacts, FactUpdate};
+use crate::query::{
+    attack_surface_fraction, count_reachable, reachable_pairs, run_collection_query,
+};
+use crate::rules::{
+    build_attack_graph, build_attack_graph_with_local_vulnerabilities,
+    build_attack_graph_with_options, AttackGraphOptions,
+};
 use crate::schema::*;
+use crate::session::AttackGraphSession;
 
 #[derive(Debug, Clone)]
 pub struct FullRecomputationResult {
@@ -24,54 +38,139 @@ pub struct FullRecomputationResult {
 
 // Results produced by a single benchmark run.  These are used to
 // create the tables and figures in the paper.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkResults {
     pub number_of_nodes: usize,
     pub initial_computation_time: Duration,
     pub incremental_update_time: Duration,
     pub full_recomputation_after_update_time: Duration,
+    // Dropping the dataflow and rebuilding it over the patched input;
+    // see `measure_teardown_and_rebuild`.  Missing from results
+    // recorded before the rebuild baseline existed.
+    #[serde(default)]
+    pub full_rebuild_time: Duration,
+    // Full rebuild time over incremental update time.
     pub speedup_factor: f64,
+    // Initial computation time over incremental update time.
+    #[serde(default)]
+    pub initial_build_speedup: f64,
     pub incremental_vs_recompute_speedup: f64,
     // simple counts useful for debugging/plots
     pub number_of_attack_paths_initial: usize,
     pub number_of_attack_paths_after_patch: usize,
     pub derived_facts_before_update: usize,
     pub derived_facts_after_update: usize,
+    // Fraction of the nodes the attacker reaches; see `attack_surface`.
+    pub attack_surface_initial: f64,
+    pub attack_surface_after_patch: f64,
+    // Estimated size of the arrangements maintained after the initial
+    // computation; see `estimate_arrangement_bytes`.
+    pub peak_arrangement_bytes: usize,
+    // Rounds the execCode fixpoint ran for the initial computation,
+    // counted in the timed dataflow; see `time_update_batches`.
+    pub fixpoint_iterations: usize,
 }
 
 impl BenchmarkResults {
-    // Pretty-print a concise summary for quick CLI inspection
+    // Log a concise summary at info level for quick CLI inspection
     pub fn print_summary(&self) {
-        println!("=== BENCHMARK RESULTS ===");
-        println!("Network size: {} nodes", self.number_of_nodes);
-        println!("Initial computation: {:?}", self.initial_computation_time);
-        println!("Incremental update:  {:?}", self.incremental_update_time);
-        println!("Speedup factor: {:.2}x", self.speedup_factor);
-        println!(
+        info!("=== BENCHMARK RESULTS ===");
+        info!("Network size: {} nodes", self.number_of_nodes);
+        info!("Initial computation: {:?}", self.initial_computation_time);
+        info!("Incremental update:  {:?}", self.incremental_update_time);
+        info!("Full rebuild:        {:?}", self.full_rebuild_time);
+        info!("Speedup factor (vs rebuild): {:.2}x", self.speedup_factor);
+        info!(
+            "Speedup vs initial build: {:.2}x",
+            self.initial_build_speedup
+        );
+        info!(
             "Full recomputation after update: {:?}",
             self.full_recomputation_after_update_time
         );
-        println!(
+        info!(
             "Incremental vs recompute speedup: {:.2}x",
             self.incremental_vs_recompute_speedup
         );
-        println!(
+        info!(
             "Attack paths (initial): {}",
             self.number_of_attack_paths_initial
         );
-        println!(
+        info!(
             "Attack paths (after patch): {}",
             self.number_of_attack_paths_after_patch
         );
-        println!(
+        info!(
             "Derived facts: {} before update, {} after update",
             self.derived_facts_before_update, self.derived_facts_after_update
         );
-        println!();
+        info!(
+            "Attack surface: {:.1}% before update, {:.1}% after update",
+            self.attack_surface_initial * 100.0,
+            self.attack_surface_after_patch * 100.0
+        );
+        info!(
+            "Arrangements (estimated): {:.1} KiB",
+            bytes_to_kib(self.peak_arrangement_bytes)
+        );
+        info!("Fixpoint iterations: {}", self.fixpoint_iterations);
+    }
+
+    // Write one row per result with the columns of
+    // `print_benchmark_table`, readable back as `BenchmarkTableRow`.
+    pub fn write_csv(results: &[BenchmarkResults], path: &Path) -> io::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        for result in results {
+            writer.serialize(BenchmarkTableRow::from(result))?;
+        }
+        writer.flush()
     }
 }
 
-#[derive(Debug, Clone)]
+// One row of `print_benchmark_table`, with times converted to the
+// table's units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkTableRow {
+    pub nodes: usize,
+    pub initial_ms: f64,
+    pub incremental_us: f64,
+    pub recompute_after_update_ms: f64,
+    // Zero when read from a table written before the rebuild baseline
+    // existed.
+    #[serde(default)]
+    pub rebuild_ms: f64,
+    #[serde(default)]
+    pub rebuild_speedup: f64,
+    pub initial_speedup: f64,
+    pub recompute_speedup: f64,
+    pub facts_before: usize,
+    pub facts_after: usize,
+    pub surface_before_percent: f64,
+    pub surface_after_percent: f64,
+    pub arrangement_kib: f64,
+}
+
+impl From<&BenchmarkResults> for BenchmarkTableRow {
+    fn from(result: &BenchmarkResults) -> Self {
+        BenchmarkTableRow {
+            nodes: result.number_of_nodes,
+            initial_ms: duration_to_ms(result.initial_computation_time),
+            incremental_us: duration_to_us(result.incremental_update_time),
+            recompute_after_update_ms: duration_to_ms(result.full_recomputation_after_update_time),
+            rebuild_ms: duration_to_ms(result.full_rebuild_time),
+            rebuild_speedup: result.speedup_factor,
+            initial_speedup: result.initial_build_speedup,
+            recompute_speedup: result.incremental_vs_recompute_speedup,
+            facts_before: result.derived_facts_before_update,
+            facts_after: result.derived_facts_after_update,
+            surface_before_percent: result.attack_surface_initial * 100.0,
+            surface_after_percent: result.attack_surface_after_patch * 100.0,
+            arrangement_kib: bytes_to_kib(result.peak_arrangement_bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct BenchmarkCsvRow {
     pub benchmark_name: String,
     pub topology: String,
@@ -107,6 +206,30 @@ impl BenchmarkCsvRow {
         )
     }
 
+    pub fn from_mesh(width: usize, height: usize, result: &BenchmarkResults) -> Self {
+        Self::from_basic_result(
+            "mesh_benchmark",
+            "mesh",
+            width * height,
+            width.saturating_sub(1) * height + width * height.saturating_sub(1),
+            width * height,
+            "patch_centre_node_vulnerability",
+            result,
+        )
+    }
+
+    pub fn from_firewall_cut(result: &BenchmarkResults) -> Self {
+        Self::from_basic_result(
+            "firewall_cut_benchmark",
+            "chain_firewall_cut",
+            result.number_of_nodes,
+            result.number_of_nodes.saturating_sub(1),
+            result.number_of_nodes,
+            "deny_middle_chain_edge",
+            result,
+        )
+    }
+
     pub fn from_chain(result: &BenchmarkResults) -> Self {
         Self::from_basic_result(
             "chain_benchmark",
@@ -214,6 +337,7 @@ fn affected_hosts_for_basic_update(topology: &str, number_of_nodes: usize) -> Op
     match topology {
         "star" => Some(1),
         "chain" => Some(number_of_nodes.saturating_sub(1)),
+        "chain_firewall_cut" => Some(number_of_nodes.saturating_sub(number_of_nodes / 2 + 1)),
         _ => None,
     }
 }
@@ -257,6 +381,101 @@ fn optional_usize(value: Option<usize>) -> String {
     value.map(|count| count.to_string()).unwrap_or_default()
 }
 
+fn bytes_to_kib(bytes: usize) -> f64 {
+    bytes as f64 / 1024.0
+}
+
+// Coarse estimate of the memory held by the dataflow's arrangements
+// once the initial computation has settled.  Timely does not expose
+// allocation sizes, so this counts maintained tuples instead: every
+// base fact plus every derived fact, each costing its in-memory size,
+// the heap bytes of its strings, and a (timestamp, diff) pair.
+// Derived facts are not materialised here, so their strings are
+// costed at the average host name length of the base facts.  Each
+// tuple is counted once even though joins may arrange a collection
+// under several keys, so read the result as a lower bound that scales
+// like the real footprint, not as an exact figure.
+pub fn estimate_arrangement_bytes(
+    network_topology: &[NetworkAccessRule],
+    vulnerabilities: &[VulnerabilityRecord],
+    firewall_rules: &[FirewallRuleRecord],
+    attacker_positions: &[AttackerStartingPosition],
+    attacker_goals: &[AttackerTargetGoal],
+    derived_fact_count: usize,
+) -> usize {
+    const UPDATE_OVERHEAD_BYTES: usize = std::mem::size_of::<(usize, isize)>();
+
+    let network_bytes: usize = network_topology
+        .iter()
+        .map(|access| {
+            std::mem::size_of::<NetworkAccessRule>()
+                + access.source_host.len()
+                + access.destination_host.len()
+                + access.service_name.len()
+        })
+        .sum();
+    let vulnerability_bytes: usize = vulnerabilities
+        .iter()
+        .map(|vulnerability| {
+            std::mem::size_of::<VulnerabilityRecord>()
+                + vulnerability.host_name.len()
+                + vulnerability.vulnerability_id.len()
+                + vulnerability.affected_service.len()
+        })
+        .sum();
+    let firewall_bytes: usize = firewall_rules
+        .iter()
+        .map(|rule| {
+            std::mem::size_of::<FirewallRuleRecord>()
+                + rule.source_zone.len()
+                + rule.destination_host.len()
+                + rule.service_name.len()
+        })
+        .sum();
+    let position_bytes: usize = attacker_positions
+        .iter()
+        .map(|position| {
+            std::mem::size_of::<AttackerStartingPosition>()
+                + position.attacker_id.len()
+                + position.starting_host.len()
+        })
+        .sum();
+    let goal_bytes: usize = attacker_goals
+        .iter()
+        .map(|goal| {
+            std::mem::size_of::<AttackerTargetGoal>()
+                + goal.attacker_id.len()
+                + goal.target_host_name.len()
+        })
+        .sum();
+
+    let average_host_name_length = if vulnerabilities.is_empty() {
+        0
+    } else {
+        vulnerabilities
+            .iter()
+            .map(|vulnerability| vulnerability.host_name.len())
+            .sum::<usize>()
+            / vulnerabilities.len()
+    };
+    let derived_bytes = derived_fact_count
+        * (std::mem::size_of::<AttackerCodeExecution>() + 2 * average_host_name_length);
+
+    let base_fact_count = network_topology.len()
+        + vulnerabilities.len()
+        + firewall_rules.len()
+        + attacker_positions.len()
+        + attacker_goals.len();
+
+    network_bytes
+        + vulnerability_bytes
+        + firewall_bytes
+        + position_bytes
+        + goal_bytes
+        + derived_bytes
+        + (base_fact_count + derived_fact_count) * UPDATE_OVERHEAD_BYTES
+}
+
 fn duration_to_ms(duration: Duration) -> f64 {
     duration.as_secs_f64() * 1000.0
 }
@@ -292,6 +511,11 @@ pub fn generate_chain_network(
     Vec<AttackerStartingPosition>,
     Vec<AttackerTargetGoal>,
 ) {
+    // No nodes: no attacker and no goal to place either.
+    if number_of_nodes == 0 {
+        return (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    }
+
     let mut network_topology = Vec::with_capacity(number_of_nodes - 1);
     let mut vulnerabilities = Vec::with_capacity(number_of_nodes);
 
@@ -313,7 +537,11 @@ pub fn generate_chain_network(
         // Add directed edge to next node (except for last)
         if node_index < number_of_nodes - 1 {
             let next_node_name = format!("node_{}", node_index + 1);
-            network_topology.push(NetworkAccessRule::new(&node_name, &next_node_name, "ssh"));
+            network_topology.push(NetworkAccessRule::new_default_port(
+                &node_name,
+                &next_node_name,
+                "ssh",
+            ));
         }
     }
 
@@ -353,6 +581,10 @@ pub fn generate_mesh_network(
     Vec<AttackerTargetGoal>,
 ) {
     let total_nodes = grid_width * grid_height;
+    // No nodes: no attacker and no goal to place either.
+    if total_nodes == 0 {
+        return (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    }
     let mut network_topology = Vec::new();
     let mut vulnerabilities = Vec::with_capacity(total_nodes);
 
@@ -373,7 +605,7 @@ pub fn generate_mesh_network(
 
             // connect to right neighbor
             if x + 1 < grid_width {
-                network_topology.push(NetworkAccessRule::new(
+                network_topology.push(NetworkAccessRule::new_default_port(
                     &current_node,
                     &node_name(x + 1, y),
                     "ssh",
@@ -382,7 +614,7 @@ pub fn generate_mesh_network(
 
             // connect to bottom neighbor
             if y + 1 < grid_height {
-                network_topology.push(NetworkAccessRule::new(
+                network_topology.push(NetworkAccessRule::new_default_port(
                     &current_node,
                     &node_name(x, y + 1),
                     "ssh",
@@ -442,7 +674,9 @@ pub fn generate_star_network(
             PrivilegeLevel::Root,
         ));
 
-        network_topology.push(NetworkAccessRule::new("hub", &leaf_name, "ssh"));
+        network_topology.push(NetworkAccessRule::new_default_port(
+            "hub", &leaf_name, "ssh",
+        ));
     }
 
     let attacker_positions = vec![AttackerStartingPosition::new(
@@ -451,9 +685,68 @@ pub fn generate_star_network(
         PrivilegeLevel::Root,
     )];
 
+    // Without leaves the hub is the whole network, so it is the goal.
+    let target = match number_of_leaves.checked_sub(1) {
+        Some(last_leaf_index) => format!("leaf_{}", last_leaf_index),
+        None => "hub".to_string(),
+    };
+    let attacker_goals = vec![AttackerTargetGoal::new("attacker", &target)];
+
+    (
+        network_topology,
+        vulnerabilities,
+        attacker_positions,
+        attacker_goals,
+    )
+}
+
+// Clique: every node can reach every other node.  Each node has a
+// Root vulnerability, so the fixpoint sees a cycle through every pair
+// of hosts; it is the densest cyclic topology for termination tests.
+pub fn generate_clique_network(
+    number_of_nodes: usize,
+) -> (
+    Vec<NetworkAccessRule>,
+    Vec<VulnerabilityRecord>,
+    Vec<AttackerStartingPosition>,
+    Vec<AttackerTargetGoal>,
+) {
+    // No nodes: no attacker and no goal to place either.
+    if number_of_nodes == 0 {
+        return (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    }
+
+    let mut network_topology = Vec::with_capacity(number_of_nodes * (number_of_nodes - 1));
+    let mut vulnerabilities = Vec::with_capacity(number_of_nodes);
+
+    for source_index in 0..number_of_nodes {
+        let source_name = format!("node_{}", source_index);
+
+        vulnerabilities.push(VulnerabilityRecord::new(
+            &source_name,
+            &format!("CVE-CLIQUE-{}", source_index),
+            "ssh",
+            PrivilegeLevel::Root,
+        ));
+
+        for destination_index in (0..number_of_nodes).filter(|&index| index != source_index) {
+            network_topology.push(NetworkAccessRule::new_default_port(
+                &source_name,
+                &format!("node_{}", destination_index),
+                "ssh",
+            ));
+        }
+    }
+
+    let attacker_positions = vec![AttackerStartingPosition::new(
+        "attacker",
+        "node_0",
+        PrivilegeLevel::Root,
+    )];
+
     let attacker_goals = vec![AttackerTargetGoal::new(
         "attacker",
-        &format!("leaf_{}", number_of_leaves - 1),
+        &format!("node_{}", number_of_nodes - 1),
     )];
 
     (
@@ -688,7 +981,8 @@ pub fn apply_enterprise_update_pattern(
                     &access.source_host,
                     &access.destination_host,
                     &access.service_name,
-                );
+                )
+                .with_port(access.port);
                 updated_scenario.firewall_rules.push(rule.clone());
                 added_firewall_rules.push(rule);
             }
@@ -741,7 +1035,11 @@ fn add_layer_edges(
     for source in sources {
         for destination in destinations {
             for service in services {
-                network_access.push(NetworkAccessRule::new(source, destination, service));
+                network_access.push(NetworkAccessRule::new_default_port(
+                    source,
+                    destination,
+                    service,
+                ));
             }
         }
     }
@@ -804,6 +1102,7 @@ fn should_include_enterprise_vulnerability(
 // Rebuild the attack graph from scratch with the supplied facts.
 // This deliberately creates a fresh dataflow instance, so the timing is
 // a full recomputation baseline rather than an incremental update.
+// It covers building the dataflow and running it to completion.
 pub fn measure_full_recomputation(
     network_topology: &[NetworkAccessRule],
     vulnerabilities: &[VulnerabilityRecord],
@@ -811,330 +1110,608 @@ pub fn measure_full_recomputation(
     attacker_positions: &[AttackerStartingPosition],
     attacker_goals: &[AttackerTargetGoal],
 ) -> FullRecomputationResult {
-    use std::sync::{Arc, Mutex};
+    let facts = benchmark_facts(
+        network_topology,
+        vulnerabilities,
+        firewall_rules,
+        attacker_positions,
+        attacker_goals,
+    );
+
+    timely::execute_directly(move |worker| {
+        let start = Instant::now();
+        let (_, derived_fact_count) = run_dataflow_to_completion(worker, &facts);
+
+        FullRecomputationResult {
+            computation_time: start.elapsed(),
+            derived_fact_count,
+        }
+    })
+}
 
-    let network_topology = network_topology.to_vec();
-    let vulnerabilities = vulnerabilities.to_vec();
-    let firewall_rules = firewall_rules.to_vec();
-    let attacker_positions = attacker_positions.to_vec();
-    let attacker_goals = attacker_goals.to_vec();
+// Time tearing down a settled dataflow over `before` and rebuilding it
+// from scratch over `after`.  This is `measure_full_recomputation` of
+// `after` plus dropping the old dataflow first, so it is the full cost
+// of not updating incrementally.
+pub fn measure_teardown_and_rebuild(before: &BaseFacts, after: &BaseFacts) -> Duration {
+    let before = before.clone();
+    let after = after.clone();
 
     timely::execute_directly(move |worker| {
-        let derived_fact_count = Arc::new(Mutex::new(0usize));
-        let exec_count = Arc::clone(&derived_fact_count);
-        let owns_count = Arc::clone(&derived_fact_count);
-        let goal_count = Arc::clone(&derived_fact_count);
+        let (initial_dataflow, _) = run_dataflow_to_completion(worker, &before);
 
-        let mut probe = Handle::new();
+        let start = Instant::now();
+        worker.drop_dataflow(initial_dataflow);
+        run_dataflow_to_completion(worker, &after);
+        start.elapsed()
+    })
+}
 
-        let (
-            mut vulnerability_input,
-            mut network_input,
-            mut firewall_input,
-            mut attacker_position_input,
-            mut attacker_goal_input,
-        ) = worker.dataflow::<usize, _, _>(|scope| {
-            let (vuln_handle, vuln_collection) =
-                scope.new_collection::<VulnerabilityRecord, isize>();
-            let (network_handle, network_collection) =
-                scope.new_collection::<NetworkAccessRule, isize>();
-            let (firewall_handle, firewall_collection) =
-                scope.new_collection::<FirewallRuleRecord, isize>();
-            let (position_handle, position_collection) =
-                scope.new_collection::<AttackerStartingPosition, isize>();
-            let (goal_handle, goal_collection) =
-                scope.new_collection::<AttackerTargetGoal, isize>();
-
-            let (exec_code, owns_machine, goals_reached) = build_attack_graph(
-                &vuln_collection,
-                &network_collection,
-                &firewall_collection,
-                &position_collection,
-                &goal_collection,
-            );
+// Build `build_attack_graph` over `facts` in a new dataflow and run it
+// to completion.  Returns the dataflow's index and the number of
+// derived facts (execCode, ownsMachine and goalReached) it produced.
+fn run_dataflow_to_completion<A: Allocate>(
+    worker: &mut Worker<A>,
+    facts: &BaseFacts,
+) -> (usize, usize) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
 
-            exec_code.consolidate().inspect(move |(_, _, diff)| {
-                if *diff > 0 {
-                    *exec_count.lock().unwrap() += *diff as usize;
-                }
-            });
-            owns_machine.consolidate().inspect(move |(_, _, diff)| {
-                if *diff > 0 {
-                    *owns_count.lock().unwrap() += *diff as usize;
+    let dataflow_index = worker.next_dataflow_index();
+    let derived_fact_count = Arc::new(AtomicUsize::new(0));
+    let mut probe = Handle::new();
+
+    let (
+        mut vulnerability_input,
+        mut network_input,
+        mut firewall_input,
+        mut attacker_position_input,
+        mut attacker_goal_input,
+    ) = worker.dataflow::<usize, _, _>(|scope| {
+        let (vuln_handle, vuln_collection) = scope.new_collection::<VulnerabilityRecord, isize>();
+        let (network_handle, network_collection) =
+            scope.new_collection::<NetworkAccessRule, isize>();
+        let (firewall_handle, firewall_collection) =
+            scope.new_collection::<FirewallRuleRecord, isize>();
+        let (position_handle, position_collection) =
+            scope.new_collection::<AttackerStartingPosition, isize>();
+        let (goal_handle, goal_collection) = scope.new_collection::<AttackerTargetGoal, isize>();
+
+        let (exec_code, owns_machine, goals_reached) = build_attack_graph(
+            &vuln_collection,
+            &network_collection,
+            &firewall_collection,
+            &position_collection,
+            &goal_collection,
+        );
+
+        let count_derived = |count: Arc<AtomicUsize>| {
+            move |diff: isize| {
+                if diff > 0 {
+                    count.fetch_add(diff as usize, Ordering::SeqCst);
                 }
-            });
-            goals_reached
-                .consolidate()
-                .inspect(move |(_, _, diff)| {
-                    if *diff > 0 {
-                        *goal_count.lock().unwrap() += *diff as usize;
-                    }
-                })
-                .probe_with(&mut probe);
-
-            (
-                vuln_handle,
-                network_handle,
-                firewall_handle,
-                position_handle,
-                goal_handle,
-            )
-        });
+            }
+        };
+        let count_exec = count_derived(Arc::clone(&derived_fact_count));
+        let count_owns = count_derived(Arc::clone(&derived_fact_count));
+        let count_goals = count_derived(Arc::clone(&derived_fact_count));
+        exec_code
+            .consolidate()
+            .inspect(move |(_, _, diff)| count_exec(*diff))
+            .probe_with(&mut probe);
+        owns_machine
+            .consolidate()
+            .inspect(move |(_, _, diff)| count_owns(*diff))
+            .probe_with(&mut probe);
+        goals_reached
+            .consolidate()
+            .inspect(move |(_, _, diff)| count_goals(*diff))
+            .probe_with(&mut probe);
+
+        (
+            vuln_handle,
+            network_handle,
+            firewall_handle,
+            position_handle,
+            goal_handle,
+        )
+    });
 
-        let start = Instant::now();
+    for network_rule in &facts.network_access {
+        network_input.insert(network_rule.clone());
+    }
+    for vulnerability in &facts.vulnerabilities {
+        vulnerability_input.insert(vulnerability.clone());
+    }
+    for firewall_rule in &facts.firewall_rules {
+        firewall_input.insert(firewall_rule.clone());
+    }
+    for position in &facts.attacker_positions {
+        attacker_position_input.insert(position.clone());
+    }
+    for goal in &facts.attacker_goals {
+        attacker_goal_input.insert(goal.clone());
+    }
 
-        for network_rule in &network_topology {
-            network_input.insert(network_rule.clone());
-        }
-        for vulnerability in &vulnerabilities {
-            vulnerability_input.insert(vulnerability.clone());
-        }
-        for firewall_rule in &firewall_rules {
-            firewall_input.insert(firewall_rule.clone());
-        }
-        for position in &attacker_positions {
-            attacker_position_input.insert(position.clone());
-        }
-        for goal in &attacker_goals {
-            attacker_goal_input.insert(goal.clone());
-        }
+    vulnerability_input.close();
+    network_input.close();
+    firewall_input.close();
+    attacker_position_input.close();
+    attacker_goal_input.close();
 
-        vulnerability_input.advance_to(1);
-        network_input.advance_to(1);
-        firewall_input.advance_to(1);
-        attacker_position_input.advance_to(1);
-        attacker_goal_input.advance_to(1);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
+    while !probe.done() {
+        worker.step();
+    }
+    (dataflow_index, derived_fact_count.load(Ordering::SeqCst))
+}
 
-        while probe.less_than(&1) {
-            worker.step();
-        }
+// The teardown-and-rebuild baseline of `run_chain_benchmark`: the chain
+// is rebuilt from scratch with node_1's vulnerability patched.
+pub fn run_full_recompute_baseline(number_of_nodes: usize) -> Duration {
+    let before = chain_base_facts(number_of_nodes);
+    let mut after = before.clone();
+    after
+        .vulnerabilities
+        .retain(|vulnerability| vulnerability.host_name != "node_1");
+    measure_teardown_and_rebuild(&before, &after)
+}
 
-        let derived_fact_count = *derived_fact_count.lock().unwrap();
+// Base facts of a benchmark topology, for `measure_teardown_and_rebuild`.
+fn benchmark_facts(
+    network_topology: &[NetworkAccessRule],
+    vulnerabilities: &[VulnerabilityRecord],
+    firewall_rules: &[FirewallRuleRecord],
+    attacker_positions: &[AttackerStartingPosition],
+    attacker_goals: &[AttackerTargetGoal],
+) -> BaseFacts {
+    BaseFacts {
+        vulnerabilities: vulnerabilities.to_vec(),
+        network_access: network_topology.to_vec(),
+        firewall_rules: firewall_rules.to_vec(),
+        attacker_positions: attacker_positions.to_vec(),
+        attacker_goals: attacker_goals.to_vec(),
+        ..BaseFacts::default()
+    }
+}
 
-        FullRecomputationResult {
-            computation_time: start.elapsed(),
-            derived_fact_count,
+// ----------------------------------------------------------------
+// Progress reporting
+// ----------------------------------------------------------------
+// Large chain benchmarks can take a while within a single size.  The
+// runners can report progress through a callback, one update per
+// fixpoint round; `ProgressBar` renders it as a one-line bar with an
+// ETA extrapolated from the measured time per round.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressUpdate {
+    // Fixpoint rounds finished, out of `total`.
+    pub completed: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+    // Mean measured duration of the finished rounds.
+    pub round_time: Duration,
+}
+
+impl ProgressUpdate {
+    /// Estimated time remaining, assuming the remaining rounds take as
+    /// long as the finished ones did on average.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
         }
-    })
+        let remaining = self.total.saturating_sub(self.completed) as f64;
+        Some(self.round_time.mul_f64(remaining))
+    }
 }
 
-fn vulnerability_matches(
-    vulnerability: &VulnerabilityRecord,
-    host_name: &str,
-    vulnerability_id: &str,
-    affected_service: &str,
-    privilege_gained_on_exploit: PrivilegeLevel,
-) -> bool {
-    vulnerability.host_name == host_name
-        && vulnerability.vulnerability_id == vulnerability_id
-        && vulnerability.affected_service == affected_service
-        && vulnerability.privilege_gained_on_exploit == privilege_gained_on_exploit
+pub type ProgressCallback = Box<dyn FnMut(ProgressUpdate) + Send + Sync>;
+
+#[derive(Debug, Clone)]
+pub struct ProgressBar {
+    pub label: String,
+    pub width: usize,
 }
 
-// Run the chain benchmark: measure initial build time and the time
-// to perform a single incremental patch (remove vulnerability at
-// node_1).  The timings are returned in a `BenchmarkResults` struct.
-pub fn run_chain_benchmark(number_of_nodes: usize) -> BenchmarkResults {
-    use std::sync::atomic::{AtomicU64, Ordering};
-    use std::sync::Arc;
+impl ProgressBar {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            width: 30,
+        }
+    }
 
-    let (network_topology, vulnerabilities, attacker_positions, attacker_goals) =
-        generate_chain_network(number_of_nodes);
-    let firewall_rules: Vec<FirewallRuleRecord> = Vec::new();
+    pub fn render(&self, update: &ProgressUpdate) -> String {
+        let fraction = if update.total == 0 {
+            1.0
+        } else {
+            (update.completed as f64 / update.total as f64).min(1.0)
+        };
+        let filled = (fraction * self.width as f64).round() as usize;
+        let eta = match update.eta() {
+            Some(eta) => format!("{:.1}s", eta.as_secs_f64()),
+            None => "?".to_string(),
+        };
+        format!(
+            "{} [{}{}] {}/{} ETA {}",
+            self.label,
+            "#".repeat(filled),
+            " ".repeat(self.width - filled),
+            update.completed,
+            update.total,
+            eta
+        )
+    }
 
-    let initial_recomputation = measure_full_recomputation(
-        &network_topology,
-        &vulnerabilities,
-        &firewall_rules,
-        &attacker_positions,
-        &attacker_goals,
-    );
-    let vulnerabilities_after_patch: Vec<_> = vulnerabilities
-        .iter()
-        .filter(|vulnerability| {
-            !vulnerability_matches(
-                vulnerability,
-                "node_1",
-                "CVE-CHAIN-1",
-                "ssh",
-                PrivilegeLevel::Root,
-            )
+    // Redraws the bar in place on stderr, ending the line once the
+    // total is reached.
+    pub fn into_callback(self) -> ProgressCallback {
+        Box::new(move |update| {
+            let mut stderr = io::stderr();
+            let _ = write!(stderr, "\r{}", self.render(&update));
+            if update.completed >= update.total {
+                let _ = writeln!(stderr);
+            }
+            let _ = stderr.flush();
         })
-        .cloned()
-        .collect();
-    let recomputation_after_update = measure_full_recomputation(
+    }
+}
+
+// Maintains the number of distinct (attacker, host) pairs with
+// execCode in `counter`.  The counter is updated from the deduplicated
+// output, so once the probe has passed a logical time it holds the
+// number of compromised hosts (the benchmark's attack path count) for
+// that time.
+fn track_compromised_host_count<G>(
+    exec_code: &differential_dataflow::Collection<G, AttackerCodeExecution>,
+    counter: std::sync::Arc<std::sync::atomic::AtomicI64>,
+    probe: &mut Handle<G::Timestamp>,
+) where
+    G: timely::dataflow::Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    use differential_dataflow::operators::Threshold;
+
+    exec_code
+        .map(|exec| (exec.attacker_id, exec.compromised_host))
+        .distinct()
+        .inspect(move |(_, _, diff)| {
+            counter.fetch_add(*diff as i64, std::sync::atomic::Ordering::SeqCst);
+        })
+        .probe_with(probe);
+}
+
+// Build and tear down the attack graph dataflow once on a three-node
+// chain.  The first dataflow in a process pays one-off allocator and
+// page-fault costs; running this before a timed benchmark keeps them
+// out of `initial_computation_time`.
+pub fn warm_up_dataflow() {
+    let (network_topology, vulnerabilities, attacker_positions, attacker_goals) =
+        generate_chain_network(3);
+    measure_full_recomputation(
         &network_topology,
-        &vulnerabilities_after_patch,
-        &firewall_rules,
+        &vulnerabilities,
+        &[],
         &attacker_positions,
         &attacker_goals,
     );
+}
+
+// Timings of one `time_update_batches` run, with the number of
+// compromised hosts (see `track_compromised_host_count`) after the
+// initial computation and after each batch.
+struct TimedUpdateBatches {
+    initial_computation_time: Duration,
+    // Rounds the execCode fixpoint ran for the initial computation:
+    // one plus the last round in which its loop variable changed, so
+    // the seed round alone counts as one.
+    fixpoint_iterations: usize,
+    batch_times: Vec<Duration>,
+    compromised_hosts_initial: usize,
+    compromised_hosts_after_batch: Vec<usize>,
+}
 
-    // We use atomic/u64s to capture timings inside the timely worker
-    // closure because `execute_directly` takes ownership and runs on
-    // the current thread.
-    let initial_nanos = Arc::new(AtomicU64::new(0));
-    let incremental_nanos = Arc::new(AtomicU64::new(0));
-    let initial_clone = Arc::clone(&initial_nanos);
-    let incremental_clone = Arc::clone(&incremental_nanos);
+// The timed dataflow shared by the runners below: load `facts` and
+// wait for the initial computation to settle at time 1, then apply
+// each of `batches` at the next time and wait for it to settle.
+// The fixpoint's rounds are read off the same dataflow through
+// `AttackGraphOptions::track_rounds`.  They leave the loop as it runs,
+// so with `progress` the initial computation reports the rounds
+// finished so far out of the given total while the fixpoint advances,
+// timing each round from when the first one was seen.
+//
+// `build_attack_graph_with_options` has no local vulnerability input,
+// so `facts.local_vulnerabilities` is not loaded; none of the
+// benchmark topologies have any.
+fn time_update_batches(
+    facts: &BaseFacts,
+    batches: &[Vec<FactUpdate>],
+    progress: Option<(ProgressCallback, usize)>,
+) -> TimedUpdateBatches {
+    use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+    use std::sync::{Arc, OnceLock};
+
+    let facts = facts.clone();
+    let batches = batches.to_vec();
+    let mut progress = progress;
 
     // Execute the dataflow synchronously on the current thread
     timely::execute_directly(move |worker| {
         // ProbeHandle allows us to wait until the dataflow has
         // processed all updates up to a given logical time.
         let mut probe = Handle::new();
+        let compromised_hosts = Arc::new(AtomicI64::new(0));
+        let last_round = Arc::new(AtomicU64::new(0));
+        let rounds_started = Arc::new(OnceLock::new());
 
-        // Create input handles and collections inside a single dataflow
-        let (
-            mut vulnerability_input,
-            mut network_input,
-            mut firewall_input,
-            mut attacker_position_input,
-            mut attacker_goal_input,
-        ) = worker.dataflow::<usize, _, _>(|scope| {
-            // Each `new_collection` returns a handle that we can use
-            // to insert/remove/advance data from outside the dataflow
-            // and a `Collection` view that we pass to `build_attack_graph`.
-            let (vuln_handle, vuln_collection) =
+        let mut inputs = worker.dataflow::<usize, _, _>(|scope| {
+            let (vulnerability_handle, vulnerabilities) =
                 scope.new_collection::<VulnerabilityRecord, isize>();
-            let (network_handle, network_collection) =
+            let (local_vulnerability_handle, _) =
+                scope.new_collection::<LocalVulnerabilityRecord, isize>();
+            let (network_handle, network_access) =
                 scope.new_collection::<NetworkAccessRule, isize>();
-            let (firewall_handle, firewall_collection) =
+            let (firewall_handle, firewall_rules) =
                 scope.new_collection::<FirewallRuleRecord, isize>();
-            let (position_handle, position_collection) =
+            let (position_handle, attacker_positions) =
                 scope.new_collection::<AttackerStartingPosition, isize>();
-            let (goal_handle, goal_collection) =
-                scope.new_collection::<AttackerTargetGoal, isize>();
-
-            // Wire the collections into the rules implementation
-            let (exec_code, _owns_machine, _goals_reached) = build_attack_graph(
-                &vuln_collection,
-                &network_collection,
-                &firewall_collection,
-                &position_collection,
-                &goal_collection,
+            let (goal_handle, attacker_goals) = scope.new_collection::<AttackerTargetGoal, isize>();
+
+            let outputs = build_attack_graph_with_options(
+                &vulnerabilities,
+                &network_access,
+                &firewall_rules,
+                &attacker_positions,
+                &attacker_goals,
+                &AttackGraphOptions {
+                    track_rounds: true,
+                    ..AttackGraphOptions::default()
+                },
             );
+            let exec_code = outputs.code_executions;
 
-            // Attach a probe to one of the outputs so we can wait for
-            // the computation to finish up to a logical time.
             exec_code.consolidate().probe_with(&mut probe);
-
-            (
-                vuln_handle,
-                network_handle,
-                firewall_handle,
-                position_handle,
-                goal_handle,
-            )
+            if let Some(rounds) = outputs.fixpoint_rounds {
+                let last_round = Arc::clone(&last_round);
+                let rounds_started = Arc::clone(&rounds_started);
+                rounds
+                    .inspect(move |(round, _, _)| {
+                        rounds_started.get_or_init(Instant::now);
+                        last_round.fetch_max(*round, Ordering::SeqCst);
+                    })
+                    .probe_with(&mut probe);
+            }
+            track_compromised_host_count(&exec_code, Arc::clone(&compromised_hosts), &mut probe);
+
+            FactInputs {
+                vulnerabilities: vulnerability_handle,
+                local_vulnerabilities: local_vulnerability_handle,
+                network_access: network_handle,
+                firewall_rules: firewall_handle,
+                attacker_positions: position_handle,
+                attacker_goals: goal_handle,
+            }
         });
 
         // ------------------ Phase 1: initial computation ------------------
         let start_initial = Instant::now();
 
-        // Bulk-insert initial facts using the input handles
-        for network_rule in &network_topology {
-            network_input.insert(network_rule.clone());
+        for vulnerability in &facts.vulnerabilities {
+            inputs.vulnerabilities.insert(vulnerability.clone());
         }
-        for vulnerability in &vulnerabilities {
-            vulnerability_input.insert(vulnerability.clone());
+        for network_rule in &facts.network_access {
+            inputs.network_access.insert(network_rule.clone());
         }
-        for position in &attacker_positions {
-            attacker_position_input.insert(position.clone());
+        for firewall_rule in &facts.firewall_rules {
+            inputs.firewall_rules.insert(firewall_rule.clone());
         }
-        for goal in &attacker_goals {
-            attacker_goal_input.insert(goal.clone());
+        for position in &facts.attacker_positions {
+            inputs.attacker_positions.insert(position.clone());
         }
-
-        // Advance logical time to 1 and flush buffers so the dataflow
-        // sees a consistent snapshot for the initial computation.
-        vulnerability_input.advance_to(1);
-        network_input.advance_to(1);
-        firewall_input.advance_to(1);
-        attacker_position_input.advance_to(1);
-        attacker_goal_input.advance_to(1);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        // Wait until the probe indicates all updates for time 1 are processed
+        for goal in &facts.attacker_goals {
+            inputs.attacker_goals.insert(goal.clone());
+        }
+        inputs.advance_and_flush(1);
+
+        // Wait until the probe indicates all updates for time 1 are
+        // processed, reporting progress whenever a round finishes: the
+        // loop reaching round r means rounds 0 to r - 1 are done.
+        let mut reported = 0;
+        let mut report = |completed: usize, total: usize| -> Option<ProgressUpdate> {
+            let started: &Instant = rounds_started.get()?;
+            (completed > reported).then(|| {
+                reported = completed;
+                ProgressUpdate {
+                    completed,
+                    total,
+                    elapsed: start_initial.elapsed(),
+                    round_time: started.elapsed() / completed as u32,
+                }
+            })
+        };
         while probe.less_than(&1) {
             worker.step();
+            if let Some((callback, total)) = progress.as_mut() {
+                let finished_rounds = last_round.load(Ordering::SeqCst) as usize;
+                if let Some(update) = report(finished_rounds, *total) {
+                    callback(update);
+                }
+            }
         }
 
-        let initial_elapsed = start_initial.elapsed();
+        let initial_computation_time = start_initial.elapsed();
+        let fixpoint_iterations = last_round.load(Ordering::SeqCst) as usize + 1;
+        // The last round finishes with the fixpoint itself.
+        if let Some((callback, total)) = progress.as_mut() {
+            if let Some(update) = report(fixpoint_iterations, *total) {
+                callback(update);
+            }
+        }
+        let compromised_hosts_initial = compromised_hosts.load(Ordering::SeqCst) as usize;
 
-        // ------------------ Phase 2: incremental update ------------------
-        // Simulate a "patch" by removing the vulnerability on node_1.
-        let start_incremental = Instant::now();
+        // ------------------ Phase 2: incremental updates ------------------
+        let mut batch_times = Vec::with_capacity(batches.len());
+        let mut compromised_hosts_after_batch = Vec::with_capacity(batches.len());
+        for (index, batch) in batches.into_iter().enumerate() {
+            let time = index + 2;
+            let start_incremental = Instant::now();
 
-        vulnerability_input.remove(VulnerabilityRecord::new(
-            "node_1",
-            "CVE-CHAIN-1",
-            "ssh",
-            PrivilegeLevel::Root,
-        ));
+            for update in batch {
+                inputs.send(update);
+            }
+            inputs.advance_and_flush(time);
+            while probe.less_than(&time) {
+                worker.step();
+            }
 
-        // Advance to logical time 2 for the incremental update
-        vulnerability_input.advance_to(2);
-        network_input.advance_to(2);
-        firewall_input.advance_to(2);
-        attacker_position_input.advance_to(2);
-        attacker_goal_input.advance_to(2);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        // Wait until the incremental update has been processed
-        while probe.less_than(&2) {
-            worker.step();
+            batch_times.push(start_incremental.elapsed());
+            compromised_hosts_after_batch.push(compromised_hosts.load(Ordering::SeqCst) as usize);
         }
 
-        let incremental_elapsed = start_incremental.elapsed();
-
-        // Store the timings in the outer-scoped atomics
-        initial_clone.store(initial_elapsed.as_nanos() as u64, Ordering::SeqCst);
-        incremental_clone.store(incremental_elapsed.as_nanos() as u64, Ordering::SeqCst);
-    });
-
-    // Convert stored nanoseconds into Duration for the return value
-    let initial_time = Duration::from_nanos(initial_nanos.load(Ordering::SeqCst));
-    let incremental_time = Duration::from_nanos(incremental_nanos.load(Ordering::SeqCst));
+        TimedUpdateBatches {
+            initial_computation_time,
+            fixpoint_iterations,
+            batch_times,
+            compromised_hosts_initial,
+            compromised_hosts_after_batch,
+        }
+    })
+}
 
-    let speedup = if incremental_time.as_nanos() > 0 {
-        initial_time.as_secs_f64() / incremental_time.as_secs_f64()
-    } else {
-        f64::INFINITY
-    };
-    let incremental_vs_recompute_speedup = if incremental_time.as_nanos() > 0 {
-        recomputation_after_update.computation_time.as_secs_f64() / incremental_time.as_secs_f64()
+// How many times faster `incremental` was than `baseline`; infinite
+// when the incremental time rounds to zero.
+fn speedup_over(baseline: Duration, incremental: Duration) -> f64 {
+    if incremental.as_nanos() > 0 {
+        baseline.as_secs_f64() / incremental.as_secs_f64()
     } else {
         f64::INFINITY
-    };
-
-    BenchmarkResults {
-        number_of_nodes,
-        initial_computation_time: initial_time,
-        incremental_update_time: incremental_time,
-        full_recomputation_after_update_time: recomputation_after_update.computation_time,
-        speedup_factor: speedup,
-        incremental_vs_recompute_speedup,
-        number_of_attack_paths_initial: number_of_nodes,
-        number_of_attack_paths_after_patch: 1,
-        derived_facts_before_update: initial_recomputation.derived_fact_count,
-        derived_facts_after_update: recomputation_after_update.derived_fact_count,
     }
 }
 
-// Run multiple benchmarks with increasing sizes (helper)
+// Benchmark applying `update` to `facts`, over a topology of
+// `number_of_nodes` hosts.  This is the body of every single-update
+// runner below; they differ only in the topology and the update.
+// The incremental update is compared against recomputing the patched
+// facts from scratch and against tearing the dataflow down and
+// rebuilding it.
+fn run_update_benchmark(
+    number_of_nodes: usize,
+    facts: &BaseFacts,
+    update: Vec<FactUpdate>,
+    progress: Option<ProgressCallback>,
+) -> BenchmarkResults {
+    let mut facts_after_update = facts.clone();
+    facts_after_update.apply_updates(&update);
+
+    let initial_recomputation = measure_full_recomputation(
+        &facts.network_access,
+        &facts.vulnerabilities,
+        &facts.firewall_rules,
+        &facts.attacker_positions,
+        &facts.attacker_goals,
+    );
+    let peak_arrangement_bytes = estimate_arrangement_bytes(
+        &facts.network_access,
+        &facts.vulnerabilities,
+        &facts.firewall_rules,
+        &facts.attacker_positions,
+        &facts.attacker_goals,
+        initial_recomputation.derived_fact_count,
+    );
+    let recomputation_after_update = measure_full_recomputation(
+        &facts_after_update.network_access,
+        &facts_after_update.vulnerabilities,
+        &facts_after_update.firewall_rules,
+        &facts_after_update.attacker_positions,
+        &facts_after_update.attacker_goals,
+    );
+    let full_rebuild_time = measure_teardown_and_rebuild(facts, &facts_after_update);
+
+    let timed = time_update_batches(
+        facts,
+        &[update],
+        progress.map(|callback| (callback, number_of_nodes)),
+    );
+    let initial_time = timed.initial_computation_time;
+    let incremental_time = timed.batch_times[0];
+    let attack_paths_initial = timed.compromised_hosts_initial;
+    let attack_paths_after_patch = timed.compromised_hosts_after_batch[0];
+
+    BenchmarkResults {
+        number_of_nodes,
+        initial_computation_time: initial_time,
+        incremental_update_time: incremental_time,
+        full_recomputation_after_update_time: recomputation_after_update.computation_time,
+        full_rebuild_time,
+        speedup_factor: speedup_over(full_rebuild_time, incremental_time),
+        initial_build_speedup: speedup_over(initial_time, incremental_time),
+        incremental_vs_recompute_speedup: speedup_over(
+            recomputation_after_update.computation_time,
+            incremental_time,
+        ),
+        number_of_attack_paths_initial: attack_paths_initial,
+        number_of_attack_paths_after_patch: attack_paths_after_patch,
+        derived_facts_before_update: initial_recomputation.derived_fact_count,
+        derived_facts_after_update: recomputation_after_update.derived_fact_count,
+        attack_surface_initial: attack_surface_fraction(attack_paths_initial, number_of_nodes),
+        attack_surface_after_patch: attack_surface_fraction(
+            attack_paths_after_patch,
+            number_of_nodes,
+        ),
+        peak_arrangement_bytes,
+        fixpoint_iterations: timed.fixpoint_iterations,
+    }
+}
+
+// Run the chain benchmark: measure initial build time and the time
+// to perform a single incremental patch (remove vulnerability at
+// node_1).  The timings are returned in a `BenchmarkResults` struct.
+pub fn run_chain_benchmark(number_of_nodes: usize) -> BenchmarkResults {
+    run_chain_benchmark_with_patch(number_of_nodes, 1)
+}
+
+// Same as `run_chain_benchmark`, calling `warm_up_dataflow` first when
+// `warmup` is set.
+pub fn run_chain_benchmark_with_warmup(number_of_nodes: usize, warmup: bool) -> BenchmarkResults {
+    if warmup {
+        warm_up_dataflow();
+    }
+    run_chain_benchmark(number_of_nodes)
+}
+
+// Same as `run_chain_benchmark`, but patches the vulnerability on
+// `node_{patched_node_index}` instead of node_1.
+pub fn run_chain_benchmark_with_patch(
+    number_of_nodes: usize,
+    patched_node_index: usize,
+) -> BenchmarkResults {
+    run_chain_benchmark_with_progress(number_of_nodes, patched_node_index, None)
+}
+
+// Same as `run_chain_benchmark_with_patch`, reporting progress of the
+// initial computation to `progress` while the fixpoint runs.  Progress
+// is the number of fixpoint rounds finished so far, out of one per
+// chain node: each round compromises the next node.
+pub fn run_chain_benchmark_with_progress(
+    number_of_nodes: usize,
+    patched_node_index: usize,
+    progress: Option<ProgressCallback>,
+) -> BenchmarkResults {
+    let patched_vulnerability = VulnerabilityRecord::new(
+        &format!("node_{}", patched_node_index),
+        &format!("CVE-CHAIN-{}", patched_node_index),
+        "ssh",
+        PrivilegeLevel::Root,
+    );
+
+    run_update_benchmark(
+        number_of_nodes,
+        &chain_base_facts(number_of_nodes),
+        vec![FactUpdate::RemoveVulnerability(patched_vulnerability)],
+        progress,
+    )
+}
+
+// Run multiple benchmarks with increasing sizes (helper)
 pub fn run_scalability_benchmark(sizes: &[usize]) -> Vec<BenchmarkResults> {
     sizes
         .iter()
@@ -1148,9 +1725,10 @@ pub fn run_scalability_benchmark(sizes: &[usize]) -> Vec<BenchmarkResults> {
 // This benchmark repeatedly selects a random node k and removes the
 // vulnerability at node_k.  The cost of the incremental update
 // depends on k: removing near the start of the chain invalidates
-// more downstream facts than removing near the end.
+// more downstream facts than removing near the end.  An empty chain
+// has nothing to cut and yields all-zero results with no iterations.
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct RandomCutBenchmarkResults {
     pub number_of_nodes: usize,
     pub number_of_iterations: usize,
@@ -1159,8 +1737,13 @@ pub struct RandomCutBenchmarkResults {
     pub average_full_recomputation_after_update_time: Duration,
     pub min_incremental_time: Duration,
     pub max_incremental_time: Duration,
+    pub median_incremental_time: Duration,
+    // Population standard deviation of the per-cut incremental times.
+    pub stddev_incremental_time: Duration,
     pub average_speedup: f64,
     pub average_incremental_vs_recompute_speedup: f64,
+    // The chain node cut in each iteration, in order.
+    pub cut_positions: Vec<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -1181,28 +1764,27 @@ pub struct EnterpriseBenchmarkResults {
 
 impl EnterpriseBenchmarkResults {
     pub fn print_summary(&self) {
-        println!("=== ENTERPRISE BENCHMARK RESULT ===");
-        println!("Update pattern: {}", self.update_pattern.label());
-        println!("Nodes: {}", self.number_of_nodes);
-        println!("Network edges: {}", self.number_of_edges);
-        println!("Vulnerabilities: {}", self.number_of_vulnerabilities);
-        println!("Changed base facts: {}", self.changed_base_facts);
-        println!("Initial computation: {:?}", self.initial_computation_time);
-        println!("Incremental update:  {:?}", self.incremental_update_time);
-        println!(
+        info!("=== ENTERPRISE BENCHMARK RESULT ===");
+        info!("Update pattern: {}", self.update_pattern.label());
+        info!("Nodes: {}", self.number_of_nodes);
+        info!("Network edges: {}", self.number_of_edges);
+        info!("Vulnerabilities: {}", self.number_of_vulnerabilities);
+        info!("Changed base facts: {}", self.changed_base_facts);
+        info!("Initial computation: {:?}", self.initial_computation_time);
+        info!("Incremental update:  {:?}", self.incremental_update_time);
+        info!(
             "Full recomputation after update: {:?}",
             self.full_recomputation_after_update_time
         );
-        println!(
+        info!(
             "Incremental vs recompute speedup: {:.2}x",
             self.incremental_vs_recompute_speedup
         );
-        println!(
+        info!(
             "Derived facts: {} before update, {} after update",
             self.derived_facts_before_update, self.derived_facts_after_update
         );
-        println!("Changed derived fact count: {}", self.changed_derived_facts);
-        println!();
+        info!("Changed derived fact count: {}", self.changed_derived_facts);
     }
 }
 
@@ -1210,195 +1792,87 @@ pub fn run_chain_random_cut_benchmark(
     number_of_nodes: usize,
     iterations: usize,
 ) -> RandomCutBenchmarkResults {
-    use rand::Rng;
-    use std::sync::atomic::{AtomicU64, Ordering};
-    use std::sync::Arc;
-
-    let (network_topology, vulnerabilities, attacker_positions, attacker_goals) =
-        generate_chain_network(number_of_nodes);
-
-    let mut rng = rand::thread_rng();
-    let cut_positions: Vec<usize> = (0..iterations)
-        .map(|_| rng.gen_range(0..number_of_nodes))
-        .collect();
-
-    // Storage for timing data across iterations
-    let initial_nanos = Arc::new(AtomicU64::new(0));
-    let incremental_times_nanos = Arc::new(std::sync::Mutex::new(Vec::new()));
-    let initial_clone = Arc::clone(&initial_nanos);
-    let times_clone = Arc::clone(&incremental_times_nanos);
-
-    let iterations_count = iterations;
-    let dataflow_network_topology = network_topology.clone();
-    let dataflow_vulnerabilities = vulnerabilities.clone();
-    let dataflow_attacker_positions = attacker_positions.clone();
-    let dataflow_attacker_goals = attacker_goals.clone();
-    let dataflow_cut_positions = cut_positions.clone();
-
-    timely::execute_directly(move |worker| {
-        let mut probe = Handle::new();
-
-        let (
-            mut vulnerability_input,
-            mut network_input,
-            mut firewall_input,
-            mut attacker_position_input,
-            mut attacker_goal_input,
-        ) = worker.dataflow::<usize, _, _>(|scope| {
-            let (vuln_handle, vuln_collection) =
-                scope.new_collection::<VulnerabilityRecord, isize>();
-            let (network_handle, network_collection) =
-                scope.new_collection::<NetworkAccessRule, isize>();
-            let (firewall_handle, firewall_collection) =
-                scope.new_collection::<FirewallRuleRecord, isize>();
-            let (position_handle, position_collection) =
-                scope.new_collection::<AttackerStartingPosition, isize>();
-            let (goal_handle, goal_collection) =
-                scope.new_collection::<AttackerTargetGoal, isize>();
-
-            let (exec_code, _owns_machine, _goals_reached) = build_attack_graph(
-                &vuln_collection,
-                &network_collection,
-                &firewall_collection,
-                &position_collection,
-                &goal_collection,
-            );
-
-            exec_code.consolidate().probe_with(&mut probe);
-
-            (
-                vuln_handle,
-                network_handle,
-                firewall_handle,
-                position_handle,
-                goal_handle,
-            )
-        });
-
-        // Phase 1: initial computation (identical to the single-shot benchmark)
-        let start_initial = Instant::now();
-
-        for network_rule in &dataflow_network_topology {
-            network_input.insert(network_rule.clone());
-        }
-        for vulnerability in &dataflow_vulnerabilities {
-            vulnerability_input.insert(vulnerability.clone());
-        }
-        for position in &dataflow_attacker_positions {
-            attacker_position_input.insert(position.clone());
-        }
-        for goal in &dataflow_attacker_goals {
-            attacker_goal_input.insert(goal.clone());
-        }
-
-        vulnerability_input.advance_to(1);
-        network_input.advance_to(1);
-        firewall_input.advance_to(1);
-        attacker_position_input.advance_to(1);
-        attacker_goal_input.advance_to(1);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        while probe.less_than(&1) {
-            worker.step();
-        }
-
-        let initial_elapsed = start_initial.elapsed();
-        initial_clone.store(initial_elapsed.as_nanos() as u64, Ordering::SeqCst);
-
-        // Phase 2: Multiple random cut tests
-        let mut times_vec = times_clone.lock().unwrap();
-
-        for i in 0..iterations_count {
-            let time_step = 2 + (i * 2); // Each iteration uses 2 time steps
-
-            // Use the same sampled cuts for incremental and recompute timing.
-            let k = dataflow_cut_positions[i];
-            let node_name = format!("node_{}", k);
-            let cve_name = format!("CVE-CHAIN-{}", k);
+    run_chain_random_cut_benchmark_with_seed(number_of_nodes, iterations, None)
+}
 
-            // Remove vulnerability at node k (cuts chain at position k)
-            let start_incremental = Instant::now();
+// Same as `run_chain_random_cut_benchmark`, but with `Some(seed)` the
+// cut positions are drawn from an RNG seeded with `seed`, so two runs
+// cut the same nodes in the same order.  `None` seeds from entropy.
+pub fn run_chain_random_cut_benchmark_with_seed(
+    number_of_nodes: usize,
+    iterations: usize,
+    seed: Option<u64>,
+) -> RandomCutBenchmarkResults {
+    use rand::SeedableRng;
 
-            vulnerability_input.remove(VulnerabilityRecord::new(
-                &node_name,
-                &cve_name,
-                "ssh",
-                PrivilegeLevel::Root,
-            ));
+    let mut rng = match seed {
+        Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
+        None => rand::rngs::StdRng::from_entropy(),
+    };
+    random_cut_benchmark_with_rng(number_of_nodes, iterations, &mut rng)
+}
 
-            vulnerability_input.advance_to(time_step);
-            network_input.advance_to(time_step);
-            firewall_input.advance_to(time_step);
-            attacker_position_input.advance_to(time_step);
-            attacker_goal_input.advance_to(time_step);
-            vulnerability_input.flush();
-            network_input.flush();
-            firewall_input.flush();
-            attacker_position_input.flush();
-            attacker_goal_input.flush();
-
-            while probe.less_than(&time_step) {
-                worker.step();
-            }
+fn random_cut_benchmark_with_rng<R: rand::Rng>(
+    number_of_nodes: usize,
+    iterations: usize,
+    rng: &mut R,
+) -> RandomCutBenchmarkResults {
+    // An empty chain has no node to cut.
+    if number_of_nodes == 0 {
+        return RandomCutBenchmarkResults::default();
+    }
 
-            let incremental_elapsed = start_incremental.elapsed();
-            times_vec.push(incremental_elapsed.as_nanos() as u64);
+    let facts = chain_base_facts(number_of_nodes);
 
-            // Re-add the vulnerability to restore the chain for next iteration
-            vulnerability_input.insert(VulnerabilityRecord::new(
-                &node_name,
-                &cve_name,
+    let cut_positions: Vec<usize> = (0..iterations)
+        .map(|_| rng.gen_range(0..number_of_nodes))
+        .collect();
+    let cut_vulnerabilities: Vec<VulnerabilityRecord> = cut_positions
+        .iter()
+        .map(|k| {
+            VulnerabilityRecord::new(
+                &format!("node_{}", k),
+                &format!("CVE-CHAIN-{}", k),
                 "ssh",
                 PrivilegeLevel::Root,
-            ));
-
-            vulnerability_input.advance_to(time_step + 1);
-            network_input.advance_to(time_step + 1);
-            firewall_input.advance_to(time_step + 1);
-            attacker_position_input.advance_to(time_step + 1);
-            attacker_goal_input.advance_to(time_step + 1);
-            vulnerability_input.flush();
-            network_input.flush();
-            firewall_input.flush();
-            attacker_position_input.flush();
-            attacker_goal_input.flush();
-
-            while probe.less_than(&(time_step + 1)) {
-                worker.step();
-            }
-        }
-    });
+            )
+        })
+        .collect();
 
-    let initial_time = Duration::from_nanos(initial_nanos.load(Ordering::SeqCst));
-    let times = incremental_times_nanos.lock().unwrap();
-    let firewall_rules: Vec<FirewallRuleRecord> = Vec::new();
-    let recomputation_times_nanos: Vec<u64> = cut_positions
+    // Each cut removes the vulnerability at node k (cutting the chain
+    // at position k) and the following batch re-adds it to restore
+    // the chain for the next iteration; only the cuts are reported.
+    let batches: Vec<Vec<FactUpdate>> = cut_vulnerabilities
         .iter()
-        .map(|k| {
-            let patched_vulnerabilities: Vec<_> = vulnerabilities
-                .iter()
-                .filter(|vulnerability| {
-                    !vulnerability_matches(
-                        vulnerability,
-                        &format!("node_{}", k),
-                        &format!("CVE-CHAIN-{}", k),
-                        "ssh",
-                        PrivilegeLevel::Root,
-                    )
-                })
-                .cloned()
-                .collect();
+        .flat_map(|vulnerability| {
+            [
+                vec![FactUpdate::RemoveVulnerability(vulnerability.clone())],
+                vec![FactUpdate::InsertVulnerability(vulnerability.clone())],
+            ]
+        })
+        .collect();
+    let timed = time_update_batches(&facts, &batches, None);
+    let initial_time = timed.initial_computation_time;
+    let times: Vec<u64> = timed
+        .batch_times
+        .iter()
+        .step_by(2)
+        .map(|time| time.as_nanos() as u64)
+        .collect();
+
+    // Use the same sampled cuts for incremental and recompute timing.
+    let recomputation_times_nanos: Vec<u64> = cut_vulnerabilities
+        .into_iter()
+        .map(|vulnerability| {
+            let mut patched_facts = facts.clone();
+            patched_facts.apply_update(FactUpdate::RemoveVulnerability(vulnerability));
 
             measure_full_recomputation(
-                &network_topology,
-                &patched_vulnerabilities,
-                &firewall_rules,
-                &attacker_positions,
-                &attacker_goals,
+                &patched_facts.network_access,
+                &patched_facts.vulnerabilities,
+                &patched_facts.firewall_rules,
+                &patched_facts.attacker_positions,
+                &patched_facts.attacker_goals,
             )
             .computation_time
             .as_nanos() as u64
@@ -1413,6 +1887,8 @@ pub fn run_chain_random_cut_benchmark(
         times.iter().sum::<u64>() / times.len() as u64
     };
 
+    let (median_nanos, stddev_nanos) = median_and_stddev(&times);
+
     let avg_incremental = Duration::from_nanos(avg_nanos);
     let avg_recompute_nanos = if recomputation_times_nanos.is_empty() {
         0
@@ -1420,16 +1896,6 @@ pub fn run_chain_random_cut_benchmark(
         recomputation_times_nanos.iter().sum::<u64>() / recomputation_times_nanos.len() as u64
     };
     let avg_recompute = Duration::from_nanos(avg_recompute_nanos);
-    let average_speedup = if avg_nanos > 0 {
-        initial_time.as_secs_f64() / avg_incremental.as_secs_f64()
-    } else {
-        f64::INFINITY
-    };
-    let average_incremental_vs_recompute_speedup = if avg_nanos > 0 {
-        avg_recompute.as_secs_f64() / avg_incremental.as_secs_f64()
-    } else {
-        f64::INFINITY
-    };
 
     RandomCutBenchmarkResults {
         number_of_nodes,
@@ -1439,11 +1905,41 @@ pub fn run_chain_random_cut_benchmark(
         average_full_recomputation_after_update_time: avg_recompute,
         min_incremental_time: Duration::from_nanos(min_nanos),
         max_incremental_time: Duration::from_nanos(max_nanos),
-        average_speedup,
-        average_incremental_vs_recompute_speedup,
+        median_incremental_time: Duration::from_nanos(median_nanos),
+        stddev_incremental_time: Duration::from_nanos(stddev_nanos),
+        average_speedup: speedup_over(initial_time, avg_incremental),
+        average_incremental_vs_recompute_speedup: speedup_over(avg_recompute, avg_incremental),
+        cut_positions,
     }
 }
 
+// Median and population standard deviation of `samples`, both zero
+// for an empty slice.  An even count takes the mean of the two middle
+// samples.
+fn median_and_stddev(samples: &[u64]) -> (u64, u64) {
+    if samples.is_empty() {
+        return (0, 0);
+    }
+
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2
+    } else {
+        sorted[middle]
+    };
+
+    let mean = sorted.iter().map(|&sample| sample as f64).sum::<f64>() / sorted.len() as f64;
+    let variance = sorted
+        .iter()
+        .map(|&sample| (sample as f64 - mean).powi(2))
+        .sum::<f64>()
+        / sorted.len() as f64;
+
+    (median, variance.sqrt().round() as u64)
+}
+
 pub fn run_enterprise_benchmark(
     config: EnterpriseScenarioConfig,
 ) -> Vec<EnterpriseBenchmarkResults> {
@@ -1481,14 +1977,29 @@ pub fn run_enterprise_benchmark_for_update(
         &update.updated_scenario.attacker_goals,
     );
 
-    let (initial_computation_time, incremental_update_time) =
-        measure_enterprise_incremental_update(scenario, &update);
-    let incremental_vs_recompute_speedup = if incremental_update_time.as_nanos() > 0 {
-        recomputation_after_update.computation_time.as_secs_f64()
-            / incremental_update_time.as_secs_f64()
-    } else {
-        f64::INFINITY
-    };
+    let facts = benchmark_facts(
+        &scenario.network_access,
+        &scenario.vulnerabilities,
+        &scenario.firewall_rules,
+        &scenario.attacker_positions,
+        &scenario.attacker_goals,
+    );
+    let fact_updates: Vec<FactUpdate> = update
+        .removed_vulnerabilities
+        .iter()
+        .cloned()
+        .map(FactUpdate::RemoveVulnerability)
+        .chain(
+            update
+                .added_firewall_rules
+                .iter()
+                .cloned()
+                .map(FactUpdate::InsertFirewallDeny),
+        )
+        .collect();
+    let timed = time_update_batches(&facts, &[fact_updates], None);
+    let initial_computation_time = timed.initial_computation_time;
+    let incremental_update_time = timed.batch_times[0];
 
     EnterpriseBenchmarkResults {
         update_pattern: pattern,
@@ -1499,7 +2010,10 @@ pub fn run_enterprise_benchmark_for_update(
         initial_computation_time,
         incremental_update_time,
         full_recomputation_after_update_time: recomputation_after_update.computation_time,
-        incremental_vs_recompute_speedup,
+        incremental_vs_recompute_speedup: speedup_over(
+            recomputation_after_update.computation_time,
+            incremental_update_time,
+        ),
         derived_facts_before_update: initial_recomputation.derived_fact_count,
         derived_facts_after_update: recomputation_after_update.derived_fact_count,
         changed_derived_facts: initial_recomputation
@@ -1508,145 +2022,23 @@ pub fn run_enterprise_benchmark_for_update(
     }
 }
 
-fn measure_enterprise_incremental_update(
-    scenario: &EnterpriseScenario,
-    update: &EnterpriseScenarioUpdate,
-) -> (Duration, Duration) {
-    use std::sync::atomic::{AtomicU64, Ordering};
-    use std::sync::Arc;
-
-    let scenario = scenario.clone();
-    let removed_vulnerabilities = update.removed_vulnerabilities.clone();
-    let added_firewall_rules = update.added_firewall_rules.clone();
-    let initial_nanos = Arc::new(AtomicU64::new(0));
-    let incremental_nanos = Arc::new(AtomicU64::new(0));
-    let initial_clone = Arc::clone(&initial_nanos);
-    let incremental_clone = Arc::clone(&incremental_nanos);
-
-    timely::execute_directly(move |worker| {
-        let mut probe = Handle::new();
-
-        let (
-            mut vulnerability_input,
-            mut network_input,
-            mut firewall_input,
-            mut attacker_position_input,
-            mut attacker_goal_input,
-        ) = worker.dataflow::<usize, _, _>(|scope| {
-            let (vuln_handle, vuln_collection) =
-                scope.new_collection::<VulnerabilityRecord, isize>();
-            let (network_handle, network_collection) =
-                scope.new_collection::<NetworkAccessRule, isize>();
-            let (firewall_handle, firewall_collection) =
-                scope.new_collection::<FirewallRuleRecord, isize>();
-            let (position_handle, position_collection) =
-                scope.new_collection::<AttackerStartingPosition, isize>();
-            let (goal_handle, goal_collection) =
-                scope.new_collection::<AttackerTargetGoal, isize>();
-
-            let (exec_code, _owns_machine, _goals_reached) = build_attack_graph(
-                &vuln_collection,
-                &network_collection,
-                &firewall_collection,
-                &position_collection,
-                &goal_collection,
-            );
-
-            exec_code.consolidate().probe_with(&mut probe);
-
-            (
-                vuln_handle,
-                network_handle,
-                firewall_handle,
-                position_handle,
-                goal_handle,
-            )
-        });
-
-        let start_initial = Instant::now();
-
-        for network_rule in &scenario.network_access {
-            network_input.insert(network_rule.clone());
-        }
-        for vulnerability in &scenario.vulnerabilities {
-            vulnerability_input.insert(vulnerability.clone());
-        }
-        for firewall_rule in &scenario.firewall_rules {
-            firewall_input.insert(firewall_rule.clone());
-        }
-        for position in &scenario.attacker_positions {
-            attacker_position_input.insert(position.clone());
-        }
-        for goal in &scenario.attacker_goals {
-            attacker_goal_input.insert(goal.clone());
-        }
-
-        vulnerability_input.advance_to(1);
-        network_input.advance_to(1);
-        firewall_input.advance_to(1);
-        attacker_position_input.advance_to(1);
-        attacker_goal_input.advance_to(1);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        while probe.less_than(&1) {
-            worker.step();
-        }
-
-        let initial_elapsed = start_initial.elapsed();
-        let start_incremental = Instant::now();
-
-        for vulnerability in &removed_vulnerabilities {
-            vulnerability_input.remove(vulnerability.clone());
-        }
-        for firewall_rule in &added_firewall_rules {
-            firewall_input.insert(firewall_rule.clone());
-        }
-
-        vulnerability_input.advance_to(2);
-        network_input.advance_to(2);
-        firewall_input.advance_to(2);
-        attacker_position_input.advance_to(2);
-        attacker_goal_input.advance_to(2);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        while probe.less_than(&2) {
-            worker.step();
-        }
-
-        let incremental_elapsed = start_incremental.elapsed();
-        initial_clone.store(initial_elapsed.as_nanos() as u64, Ordering::SeqCst);
-        incremental_clone.store(incremental_elapsed.as_nanos() as u64, Ordering::SeqCst);
-    });
-
-    (
-        Duration::from_nanos(initial_nanos.load(Ordering::SeqCst)),
-        Duration::from_nanos(incremental_nanos.load(Ordering::SeqCst)),
-    )
-}
-
-// Print results table for random cut benchmark
+// Log results table for random cut benchmark, one info record per line
 pub fn print_random_cut_benchmark_table(results: &[RandomCutBenchmarkResults]) {
-    println!(
-        "| Nodes | Iterations | Initial (ms) | Avg Incr (us) | Avg Recompute (ms) | Min (us) | Max (us) | Avg Speedup | Recompute Speedup |"
+    info!(
+        "| Nodes | Iterations | Initial (ms) | Avg Incr (us) | Median (us) | Stddev (us) | Avg Recompute (ms) | Min (us) | Max (us) | Avg Speedup | Recompute Speedup |"
     );
-    println!(
-        "|-------|------------|--------------|---------------|--------------------|----------|----------|-------------|-------------------|"
+    info!(
+        "|-------|------------|--------------|---------------|-------------|-------------|--------------------|----------|----------|-------------|-------------------|"
     );
     for result in results {
-        println!(
-            "| {:>5} | {:>10} | {:>12.2} | {:>13.2} | {:>18.2} | {:>8.2} | {:>8.2} | {:>11.1}x | {:>17.1}x |",
+        info!(
+            "| {:>5} | {:>10} | {:>12.2} | {:>13.2} | {:>11.2} | {:>11.2} | {:>18.2} | {:>8.2} | {:>8.2} | {:>11.1}x | {:>17.1}x |",
             result.number_of_nodes,
             result.number_of_iterations,
             result.initial_computation_time.as_secs_f64() * 1000.0,
             result.average_incremental_time.as_secs_f64() * 1_000_000.0,
+            result.median_incremental_time.as_secs_f64() * 1_000_000.0,
+            result.stddev_incremental_time.as_secs_f64() * 1_000_000.0,
             result
                 .average_full_recomputation_after_update_time
                 .as_secs_f64()
@@ -1660,14 +2052,14 @@ pub fn print_random_cut_benchmark_table(results: &[RandomCutBenchmarkResults]) {
 }
 
 pub fn print_enterprise_benchmark_table(results: &[EnterpriseBenchmarkResults]) {
-    println!(
+    info!(
         "| Update | Nodes | Edges | Vulns | Changed Base | Initial (ms) | Incremental (us) | Recompute (ms) | Recompute Speedup | Facts Before | Facts After | Changed Facts |"
     );
-    println!(
+    info!(
         "|--------|------:|------:|------:|-------------:|-------------:|-----------------:|---------------:|------------------:|-------------:|------------:|--------------:|"
     );
     for result in results {
-        println!(
+        info!(
             "| {} | {:>5} | {:>5} | {:>5} | {:>12} | {:>12.2} | {:>16.2} | {:>14.2} | {:>17.1}x | {:>12} | {:>11} | {:>13} |",
             result.update_pattern.label(),
             result.number_of_nodes,
@@ -1685,205 +2077,477 @@ pub fn print_enterprise_benchmark_table(results: &[EnterpriseBenchmarkResults])
     }
 }
 
+// Same as `run_star_benchmark`, calling `warm_up_dataflow` first when
+// `warmup` is set.
+pub fn run_star_benchmark_with_warmup(number_of_leaves: usize, warmup: bool) -> BenchmarkResults {
+    if warmup {
+        warm_up_dataflow();
+    }
+    run_star_benchmark(number_of_leaves)
+}
+
 // Run star benchmark - converges in O(1) iterations, good for large N
 pub fn run_star_benchmark(number_of_leaves: usize) -> BenchmarkResults {
-    use std::sync::atomic::{AtomicU64, Ordering};
-    use std::sync::Arc;
-
-    let (network_topology, vulnerabilities, attacker_positions, attacker_goals) =
+    let (network_access, vulnerabilities, attacker_positions, attacker_goals) =
         generate_star_network(number_of_leaves);
+    let facts = BaseFacts {
+        vulnerabilities,
+        network_access,
+        attacker_positions,
+        attacker_goals,
+        ..BaseFacts::default()
+    };
 
+    // Patch the vulnerability on leaf_0
     let total_nodes = number_of_leaves + 1; // leaves + hub
-    let firewall_rules: Vec<FirewallRuleRecord> = Vec::new();
+    run_update_benchmark(
+        total_nodes,
+        &facts,
+        vec![FactUpdate::RemoveVulnerability(VulnerabilityRecord::new(
+            "leaf_0",
+            "CVE-LEAF-0",
+            "ssh",
+            PrivilegeLevel::Root,
+        ))],
+        None,
+    )
+}
 
-    let initial_recomputation = measure_full_recomputation(
-        &network_topology,
-        &vulnerabilities,
-        &firewall_rules,
-        &attacker_positions,
-        &attacker_goals,
+// Run firewall cut benchmark: the update changes the topology instead
+// of a vulnerability.  A deny rule on the middle edge of the chain
+// (node_{n/2} -> node_{n/2+1}) cuts every node past it, so the
+// patch-after count is the n/2 + 1 nodes still reachable.
+pub fn run_firewall_cut_benchmark(number_of_nodes: usize) -> BenchmarkResults {
+    let cut_index = number_of_nodes / 2;
+    let deny_rule = FirewallRuleRecord::create_deny_rule(
+        &format!("node_{}", cut_index),
+        &format!("node_{}", cut_index + 1),
+        "ssh",
     );
-    let vulnerabilities_after_patch: Vec<_> = vulnerabilities
-        .iter()
-        .filter(|vulnerability| {
-            !vulnerability_matches(
-                vulnerability,
-                "leaf_0",
-                "CVE-LEAF-0",
-                "ssh",
-                PrivilegeLevel::Root,
-            )
-        })
-        .cloned()
-        .collect();
-    let recomputation_after_update = measure_full_recomputation(
-        &network_topology,
-        &vulnerabilities_after_patch,
-        &firewall_rules,
-        &attacker_positions,
-        &attacker_goals,
+
+    run_update_benchmark(
+        number_of_nodes,
+        &chain_base_facts(number_of_nodes),
+        vec![FactUpdate::InsertFirewallDeny(deny_rule)],
+        None,
+    )
+}
+
+// Run mesh benchmark - converges in O(width + height) iterations.
+// The patched vulnerability is on the centre node, which is interior
+// for grids of at least 3x3; every other node stays reachable around
+// it, so the update retracts exactly one execCode fact.
+pub fn run_mesh_benchmark(width: usize, height: usize) -> BenchmarkResults {
+    let (patched_x, patched_y) = (width / 2, height / 2);
+    let patched_vulnerability = VulnerabilityRecord::new(
+        &format!("node_{}_{}", patched_x, patched_y),
+        &format!("CVE-MESH-{}-{}", patched_x, patched_y),
+        "ssh",
+        PrivilegeLevel::Root,
     );
 
-    // Use atomics to share timing data (thread-safe)
-    let initial_nanos = Arc::new(AtomicU64::new(0));
-    let incremental_nanos = Arc::new(AtomicU64::new(0));
-    let initial_clone = Arc::clone(&initial_nanos);
-    let incremental_clone = Arc::clone(&incremental_nanos);
+    run_update_benchmark(
+        width * height,
+        &mesh_base_facts(width, height),
+        vec![FactUpdate::RemoveVulnerability(patched_vulnerability)],
+        None,
+    )
+}
 
-    timely::execute_directly(move |worker| {
-        let mut probe = Handle::new();
+// Log a table of benchmark results suitable for a paper, one info
+// record per line
+pub fn print_benchmark_table(results: &[BenchmarkResults]) {
+    info!(
+        "| Nodes | Initial (ms) | Incremental (us) | Recompute After Update (ms) | Rebuild (ms) | Rebuild Speedup | Initial Speedup | Recompute Speedup | Facts Before | Facts After | Surface Before (%) | Surface After (%) | Arrangements (KiB) |"
+    );
+    info!(
+        "|-------|--------------|------------------|-----------------------------|--------------|-----------------|-----------------|-------------------|--------------|-------------|--------------------|-------------------|--------------------|"
+    );
+    for result in results {
+        info!(
+            "| {:>5} | {:>12.2} | {:>16.2} | {:>27.2} | {:>12.2} | {:>15.1}x | {:>15.1}x | {:>17.1}x | {:>12} | {:>11} | {:>18.1} | {:>17.1} | {:>18.1} |",
+            result.number_of_nodes,
+            result.initial_computation_time.as_secs_f64() * 1000.0,
+            result.incremental_update_time.as_secs_f64() * 1_000_000.0,
+            result.full_recomputation_after_update_time.as_secs_f64() * 1000.0,
+            result.full_rebuild_time.as_secs_f64() * 1000.0,
+            result.speedup_factor,
+            result.initial_build_speedup,
+            result.incremental_vs_recompute_speedup,
+            result.derived_facts_before_update,
+            result.derived_facts_after_update,
+            result.attack_surface_initial * 100.0,
+            result.attack_surface_after_patch * 100.0,
+            bytes_to_kib(result.peak_arrangement_bytes),
+        );
+    }
+}
 
-        let (
-            mut vulnerability_input,
-            mut network_input,
-            mut firewall_input,
-            mut attacker_position_input,
-            mut attacker_goal_input,
-        ) = worker.dataflow::<usize, _, _>(|scope| {
-            let (vuln_handle, vuln_collection) =
-                scope.new_collection::<VulnerabilityRecord, isize>();
-            let (network_handle, network_collection) =
-                scope.new_collection::<NetworkAccessRule, isize>();
-            let (firewall_handle, firewall_collection) =
-                scope.new_collection::<FirewallRuleRecord, isize>();
-            let (position_handle, position_collection) =
-                scope.new_collection::<AttackerStartingPosition, isize>();
-            let (goal_handle, goal_collection) =
-                scope.new_collection::<AttackerTargetGoal, isize>();
-
-            let (exec_code, _owns_machine, _goals_reached) = build_attack_graph(
-                &vuln_collection,
-                &network_collection,
-                &firewall_collection,
-                &position_collection,
-                &goal_collection,
-            );
+// ----------------------------------------------------------------
+// Batched vs per-edit advancement
+// ----------------------------------------------------------------
+// Applies the same sequence of small edits twice through a
+// `BatchDriver`: once advancing the timestamp after every edit
+// (batch size 1) and once advancing only every `batch_size` edits.
+// Both runs end with the same settled state; only the number of
+// times the worker has to settle differs.
 
-            exec_code.consolidate().probe_with(&mut probe);
+#[derive(Debug, Clone)]
+pub struct BatchingBenchmarkResults {
+    pub number_of_nodes: usize,
+    pub number_of_edits: usize,
+    pub batch_size: usize,
+    pub per_edit_time: Duration,
+    pub batched_time: Duration,
+    pub speedup_factor: f64,
+}
 
-            (
-                vuln_handle,
-                network_handle,
-                firewall_handle,
-                position_handle,
-                goal_handle,
-            )
-        });
+impl BatchingBenchmarkResults {
+    pub fn print_summary(&self) {
+        info!("=== BATCHING BENCHMARK RESULT ===");
+        info!("Nodes: {}", self.number_of_nodes);
+        info!("Edits: {}", self.number_of_edits);
+        info!("Batch size: {}", self.batch_size);
+        info!("Per-edit advancement: {:?}", self.per_edit_time);
+        info!("Batched advancement:  {:?}", self.batched_time);
+        info!("Speedup: {:.2}x", self.speedup_factor);
+    }
+}
 
-        // Phase 1: Initial computation
-        let start_initial = Instant::now();
+// Deterministic edit sequence on a chain: each edit toggles the
+// vulnerability of a node picked with a fixed stride, so some nodes
+// end up patched and others restored.
+pub fn generate_chain_edit_sequence(
+    number_of_nodes: usize,
+    number_of_edits: usize,
+) -> Vec<FactUpdate> {
+    let (_, vulnerabilities, _, _) = generate_chain_network(number_of_nodes);
+    let mut patched = vec![false; vulnerabilities.len()];
+
+    (0..number_of_edits)
+        .map(|edit_index| {
+            let vulnerability_index = (edit_index * 7) % vulnerabilities.len();
+            let vulnerability = vulnerabilities[vulnerability_index].clone();
+            patched[vulnerability_index] = !patched[vulnerability_index];
+            if patched[vulnerability_index] {
+                FactUpdate::RemoveVulnerability(vulnerability)
+            } else {
+                FactUpdate::InsertVulnerability(vulnerability)
+            }
+        })
+        .collect()
+}
 
-        for network_rule in &network_topology {
-            network_input.insert(network_rule.clone());
+pub fn chain_base_facts(number_of_nodes: usize) -> BaseFacts {
+    let (network_access, vulnerabilities, attacker_positions, attacker_goals) =
+        generate_chain_network(number_of_nodes);
+
+    BaseFacts {
+        vulnerabilities,
+        network_access,
+        attacker_positions,
+        attacker_goals,
+        ..BaseFacts::default()
+    }
+}
+
+// Time applying `edits` through a batch driver after the initial
+// snapshot has settled.  The final `flush_now` is included so that
+// both configurations are measured up to the same settled state.
+fn measure_batched_edits(
+    initial_facts: &BaseFacts,
+    edits: &[FactUpdate],
+    batch_size: usize,
+) -> (Duration, DerivedFacts) {
+    let initial_facts = initial_facts.clone();
+    let edits = edits.to_vec();
+
+    timely::execute_directly(move |worker| {
+        let mut driver = BatchDriver::new(
+            worker,
+            BatchDriverConfig {
+                batch_size,
+                max_batch_delay: None,
+            },
+        );
+
+        for fact in initial_facts.vulnerabilities.iter().cloned() {
+            driver.apply(worker, FactUpdate::InsertVulnerability(fact));
         }
-        for vulnerability in &vulnerabilities {
-            vulnerability_input.insert(vulnerability.clone());
+        for fact in initial_facts.network_access.iter().cloned() {
+            driver.apply(worker, FactUpdate::InsertNetworkAccess(fact));
         }
-        for position in &attacker_positions {
-            attacker_position_input.insert(position.clone());
+        for fact in initial_facts.attacker_positions.iter().cloned() {
+            driver.apply(worker, FactUpdate::InsertAttackerPosition(fact));
         }
-        for goal in &attacker_goals {
-            attacker_goal_input.insert(goal.clone());
+        for fact in initial_facts.attacker_goals.iter().cloned() {
+            driver.apply(worker, FactUpdate::InsertGoal(fact));
         }
+        driver.flush_now(worker);
 
-        vulnerability_input.advance_to(1);
-        network_input.advance_to(1);
-        firewall_input.advance_to(1);
-        attacker_position_input.advance_to(1);
-        attacker_goal_input.advance_to(1);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
+        let start = Instant::now();
+        driver.apply_updates(worker, &edits);
+        driver.flush_now(worker);
+        let elapsed = start.elapsed();
 
-        while probe.less_than(&1) {
-            worker.step();
-        }
+        (elapsed, driver.current_derived_facts())
+    })
+}
 
-        let initial_elapsed = start_initial.elapsed();
+pub fn run_batching_benchmark(
+    number_of_nodes: usize,
+    number_of_edits: usize,
+    batch_size: usize,
+) -> BatchingBenchmarkResults {
+    let initial_facts = chain_base_facts(number_of_nodes);
+    let edits = generate_chain_edit_sequence(number_of_nodes, number_of_edits);
 
-        // Phase 2: Incremental update - patch vulnerability on leaf_0
-        let start_incremental = Instant::now();
+    let (per_edit_time, _) = measure_batched_edits(&initial_facts, &edits, 1);
+    let (batched_time, _) = measure_batched_edits(&initial_facts, &edits, batch_size);
 
-        vulnerability_input.remove(VulnerabilityRecord::new(
-            "leaf_0",
-            "CVE-LEAF-0",
-            "ssh",
-            PrivilegeLevel::Root,
-        ));
+    BatchingBenchmarkResults {
+        number_of_nodes,
+        number_of_edits,
+        batch_size,
+        per_edit_time,
+        batched_time,
+        speedup_factor: per_edit_time.as_secs_f64() / batched_time.as_secs_f64(),
+    }
+}
 
-        vulnerability_input.advance_to(2);
-        network_input.advance_to(2);
-        firewall_input.advance_to(2);
-        attacker_position_input.advance_to(2);
-        attacker_goal_input.advance_to(2);
-        vulnerability_input.flush();
-        network_input.flush();
-        firewall_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        while probe.less_than(&2) {
-            worker.step();
-        }
+// ----------------------------------------------------------------
+// Rebuilt vs long-lived session queries
+// ----------------------------------------------------------------
+// Applies the same sequence of small edits and reads the reached goals
+// after each one: once rebuilding a dataflow from the updated facts
+// every time, and once through an `AttackGraphSession`, whose goal
+// dataflow stays alive and only absorbs the edit.
 
-        let incremental_elapsed = start_incremental.elapsed();
-        initial_clone.store(initial_elapsed.as_nanos() as u64, Ordering::SeqCst);
-        incremental_clone.store(incremental_elapsed.as_nanos() as u64, Ordering::SeqCst);
-    });
+#[derive(Debug, Clone)]
+pub struct SessionBenchmarkResults {
+    pub number_of_nodes: usize,
+    pub number_of_updates: usize,
+    pub rebuild_time: Duration,
+    pub session_time: Duration,
+    pub speedup_factor: f64,
+}
 
-    let initial_time = Duration::from_nanos(initial_nanos.load(Ordering::SeqCst));
-    let incremental_time = Duration::from_nanos(incremental_nanos.load(Ordering::SeqCst));
+impl SessionBenchmarkResults {
+    pub fn print_summary(&self) {
+        info!("=== SESSION BENCHMARK RESULT ===");
+        info!("Nodes: {}", self.number_of_nodes);
+        info!("Updates: {}", self.number_of_updates);
+        info!("Rebuild per query: {:?}", self.rebuild_time);
+        info!("Shared session:    {:?}", self.session_time);
+        info!("Speedup: {:.2}x", self.speedup_factor);
+    }
+}
 
-    let speedup = if incremental_time.as_nanos() > 0 {
-        initial_time.as_secs_f64() / incremental_time.as_secs_f64()
-    } else {
-        f64::INFINITY
-    };
-    let incremental_vs_recompute_speedup = if incremental_time.as_nanos() > 0 {
-        recomputation_after_update.computation_time.as_secs_f64() / incremental_time.as_secs_f64()
-    } else {
-        f64::INFINITY
-    };
+fn rebuilt_goals_reached(facts: &BaseFacts) -> Vec<AttackerGoalReached> {
+    run_collection_query(facts, |inputs| {
+        let (_, _, goals) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals
+    })
+}
 
-    BenchmarkResults {
-        number_of_nodes: total_nodes,
-        initial_computation_time: initial_time,
-        incremental_update_time: incremental_time,
-        full_recomputation_after_update_time: recomputation_after_update.computation_time,
-        speedup_factor: speedup,
-        incremental_vs_recompute_speedup,
-        number_of_attack_paths_initial: total_nodes,
-        number_of_attack_paths_after_patch: total_nodes - 1,
-        derived_facts_before_update: initial_recomputation.derived_fact_count,
-        derived_facts_after_update: recomputation_after_update.derived_fact_count,
+pub fn run_session_benchmark(
+    number_of_nodes: usize,
+    number_of_updates: usize,
+) -> SessionBenchmarkResults {
+    let initial_facts = chain_base_facts(number_of_nodes);
+    let edits = generate_chain_edit_sequence(number_of_nodes, number_of_updates);
+
+    let mut facts = initial_facts.clone();
+    let start = Instant::now();
+    let rebuilt_answers: Vec<_> = edits
+        .iter()
+        .map(|edit| {
+            facts.apply_update(edit.clone());
+            rebuilt_goals_reached(&facts)
+        })
+        .collect();
+    let rebuild_time = start.elapsed();
+
+    let mut session = AttackGraphSession::new(&initial_facts);
+    let start = Instant::now();
+    let session_answers: Vec<_> = edits
+        .iter()
+        .map(|edit| {
+            session.apply(std::slice::from_ref(edit));
+            session.current_goals_reached()
+        })
+        .collect();
+    let session_time = start.elapsed();
+
+    assert_eq!(
+        rebuilt_answers, session_answers,
+        "session goals disagree with a rebuilt dataflow"
+    );
+
+    SessionBenchmarkResults {
+        number_of_nodes,
+        number_of_updates,
+        rebuild_time,
+        session_time,
+        speedup_factor: rebuild_time.as_secs_f64() / session_time.as_secs_f64(),
     }
 }
 
-// Print a table of benchmark results suitable for a paper
-pub fn print_benchmark_table(results: &[BenchmarkResults]) {
-    println!(
-        "| Nodes | Initial (ms) | Incremental (us) | Recompute After Update (ms) | Initial Speedup | Recompute Speedup | Facts Before | Facts After |"
-    );
-    println!(
-        "|-------|--------------|------------------|-----------------------------|-----------------|-------------------|--------------|-------------|"
+// ----------------------------------------------------------------
+// Compares counting the reachable (attacker, host) pairs inside the
+// dataflow against materialising every reachable pair and taking
+// its length.
+
+#[derive(Debug, Clone)]
+pub struct ReachabilityCountBenchmarkResults {
+    pub grid_width: usize,
+    pub grid_height: usize,
+    pub reachable_pairs: usize,
+    pub materialized_time: Duration,
+    pub counted_time: Duration,
+}
+
+impl ReachabilityCountBenchmarkResults {
+    pub fn print_summary(&self) {
+        info!("=== REACHABILITY COUNT BENCHMARK RESULT ===");
+        info!("Mesh: {}x{}", self.grid_width, self.grid_height);
+        info!("Reachable pairs: {}", self.reachable_pairs);
+        info!("Full materialization: {:?}", self.materialized_time);
+        info!("count_reachable:      {:?}", self.counted_time);
+    }
+}
+
+pub fn mesh_base_facts(grid_width: usize, grid_height: usize) -> BaseFacts {
+    let (network_access, vulnerabilities, attacker_positions, attacker_goals) =
+        generate_mesh_network(grid_width, grid_height);
+
+    BaseFacts {
+        vulnerabilities,
+        network_access,
+        attacker_positions,
+        attacker_goals,
+        ..BaseFacts::default()
+    }
+}
+
+pub fn run_reachability_count_benchmark(
+    grid_width: usize,
+    grid_height: usize,
+) -> ReachabilityCountBenchmarkResults {
+    let facts = mesh_base_facts(grid_width, grid_height);
+
+    let start = Instant::now();
+    let materialized = reachable_pairs(&facts).len();
+    let materialized_time = start.elapsed();
+
+    let start = Instant::now();
+    let counted = count_reachable(&facts);
+    let counted_time = start.elapsed();
+
+    assert_eq!(
+        materialized, counted,
+        "count_reachable disagrees with the materialized reachable pairs"
     );
-    for result in results {
-        println!(
-            "| {:>5} | {:>12.2} | {:>16.2} | {:>27.2} | {:>15.1}x | {:>17.1}x | {:>12} | {:>11} |",
-            result.number_of_nodes,
-            result.initial_computation_time.as_secs_f64() * 1000.0,
-            result.incremental_update_time.as_secs_f64() * 1_000_000.0,
-            result.full_recomputation_after_update_time.as_secs_f64() * 1000.0,
-            result.speedup_factor,
-            result.incremental_vs_recompute_speedup,
-            result.derived_facts_before_update,
-            result.derived_facts_after_update,
-        );
+
+    ReachabilityCountBenchmarkResults {
+        grid_width,
+        grid_height,
+        reachable_pairs: counted,
+        materialized_time,
+        counted_time,
+    }
+}
+
+// =========================================================================
+// FULL BENCHMARK SUITE
+// =========================================================================
+// Runs the star, chain and random-cut benchmarks from one pinned
+// configuration and collects them into a single report, so the paper's
+// results can be reproduced with one call and archived as one JSON file.
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchmarkSuiteConfig {
+    pub star_sizes: Vec<usize>,
+    pub chain_sizes: Vec<usize>,
+    pub random_cut_sizes: Vec<usize>,
+    pub random_cut_iterations: usize,
+    pub seed: u64,
+}
+
+impl Default for BenchmarkSuiteConfig {
+    // The sizes used by `examples/run_benchmarks.rs`.
+    fn default() -> Self {
+        Self {
+            star_sizes: vec![50, 100, 200, 500, 1000],
+            chain_sizes: vec![10, 50, 100, 200],
+            random_cut_sizes: vec![50, 100, 200, 500],
+            random_cut_iterations: 100,
+            seed: 42,
+        }
+    }
+}
+
+// One row per benchmark run, in the same shape as the CSV output.
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkSuiteReport {
+    pub config: BenchmarkSuiteConfig,
+    pub star: Vec<BenchmarkCsvRow>,
+    pub chain: Vec<BenchmarkCsvRow>,
+    pub random_cut: Vec<BenchmarkCsvRow>,
+}
+
+pub fn run_full_benchmark_suite(config: &BenchmarkSuiteConfig) -> BenchmarkSuiteReport {
+    let star = config
+        .star_sizes
+        .iter()
+        .map(|&number_of_leaves| {
+            BenchmarkCsvRow::from_star(number_of_leaves, &run_star_benchmark(number_of_leaves))
+        })
+        .collect();
+
+    let chain = config
+        .chain_sizes
+        .iter()
+        .map(|&number_of_nodes| BenchmarkCsvRow::from_chain(&run_chain_benchmark(number_of_nodes)))
+        .collect();
+
+    // Offset the seed per size so every chain gets its own cut sequence.
+    let random_cut = config
+        .random_cut_sizes
+        .iter()
+        .enumerate()
+        .map(|(index, &number_of_nodes)| {
+            let result = run_chain_random_cut_benchmark_with_seed(
+                number_of_nodes,
+                config.random_cut_iterations,
+                Some(config.seed.wrapping_add(index as u64)),
+            );
+            BenchmarkCsvRow::from_random_cut(number_of_nodes, &result)
+        })
+        .collect();
+
+    BenchmarkSuiteReport {
+        config: config.clone(),
+        star,
+        chain,
+        random_cut,
     }
 }
 
+pub fn write_report<P: AsRef<Path>>(report: &BenchmarkSuiteReport, path: P) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, report)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1907,6 +2571,59 @@ mod tests {
         assert_eq!(goals.len(), 1);
     }
 
+    #[test]
+    fn test_generators_without_nodes_are_empty() {
+        let empty = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+
+        assert_eq!(generate_chain_network(0), empty);
+        assert_eq!(generate_mesh_network(0, 3), empty);
+        assert_eq!(generate_mesh_network(3, 0), empty);
+        assert_eq!(generate_clique_network(0), empty);
+    }
+
+    #[test]
+    fn test_single_node_generators_target_the_only_node() {
+        let single_nodes = [
+            (generate_chain_network(1), "node_0"),
+            (generate_mesh_network(1, 1), "node_0_0"),
+            (generate_star_network(0), "hub"),
+            (generate_clique_network(1), "node_0"),
+        ];
+
+        for ((network, vulns, positions, goals), node) in single_nodes {
+            assert!(network.is_empty(), "{node}");
+            assert_eq!(vulns.len(), 1, "{node}");
+            assert_eq!(positions[0].starting_host, node);
+            assert_eq!(goals, vec![AttackerTargetGoal::new("attacker", node)]);
+        }
+    }
+
+    #[test]
+    fn test_empty_scenario_reaches_no_goals() {
+        let goals_reached = |facts: &BaseFacts| {
+            run_collection_query(facts, |inputs| {
+                crate::rules::build_attack_graph(
+                    &inputs.vulnerabilities,
+                    &inputs.network_access,
+                    &inputs.firewall_rules,
+                    &inputs.attacker_positions,
+                    &inputs.attacker_goals,
+                )
+                .2
+            })
+        };
+        let (network_access, vulnerabilities, _, attacker_goals) = generate_chain_network(5);
+        let without_attackers = BaseFacts {
+            network_access,
+            vulnerabilities,
+            attacker_goals,
+            ..BaseFacts::default()
+        };
+
+        assert!(goals_reached(&BaseFacts::default()).is_empty());
+        assert!(goals_reached(&without_attackers).is_empty());
+    }
+
     #[test]
     fn test_star_generation() {
         let (network, vulns, positions, goals) = generate_star_network(10);
@@ -1916,6 +2633,215 @@ mod tests {
         assert_eq!(goals.len(), 1);
     }
 
+    #[test]
+    fn test_clique_generation() {
+        let (network, vulns, positions, goals) = generate_clique_network(4);
+        assert_eq!(network.len(), 12); // 4 * 3 directed edges
+        assert_eq!(vulns.len(), 4);
+        assert_eq!(positions.len(), 1);
+        assert_eq!(goals.len(), 1);
+    }
+
+    #[test]
+    fn test_chain_progress_is_reported_monotonically() {
+        use std::sync::{Arc, Mutex};
+
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&updates);
+        let callback: ProgressCallback = Box::new(move |update: ProgressUpdate| {
+            recorded.lock().unwrap().push(update.completed);
+        });
+
+        run_chain_benchmark_with_progress(40, 1, Some(callback));
+
+        // One update per round as the fixpoint runs, not a single one
+        // once it has settled.
+        let updates = updates.lock().unwrap();
+        assert!(updates.len() > 1);
+        assert!(updates.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(*updates.last().unwrap(), 40);
+    }
+
+    #[test]
+    fn test_benchmark_results_round_trip_through_csv() {
+        let results = vec![run_star_benchmark(4), run_chain_benchmark(5)];
+        let path = std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_benchmark_results_{}.csv",
+            std::process::id()
+        ));
+
+        BenchmarkResults::write_csv(&results, &path).unwrap();
+        let parsed: Vec<BenchmarkTableRow> = csv::Reader::from_path(&path)
+            .unwrap()
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let expected: Vec<_> = results.iter().map(BenchmarkTableRow::from).collect();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed[0].nodes, 5);
+        assert_eq!(parsed[1].nodes, 5);
+    }
+
+    #[test]
+    fn test_table_rows_without_rebuild_columns_still_parse() {
+        let csv = "nodes,initial_ms,incremental_us,recompute_after_update_ms,initial_speedup,\
+                   recompute_speedup,facts_before,facts_after,surface_before_percent,\
+                   surface_after_percent,arrangement_kib\n\
+                   5,1.5,20.0,1.2,75.0,60.0,11,2,100.0,20.0,1.0\n";
+
+        let parsed: Vec<BenchmarkTableRow> = csv::Reader::from_reader(csv.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].nodes, 5);
+        assert_eq!(parsed[0].rebuild_ms, 0.0);
+        assert_eq!(parsed[0].rebuild_speedup, 0.0);
+    }
+
+    #[test]
+    fn test_benchmark_results_serialize_to_json() {
+        let result = run_chain_random_cut_benchmark_with_seed(6, 2, Some(3));
+
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(json["number_of_nodes"], 6);
+        assert_eq!(json["cut_positions"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_arrangement_estimate_grows_with_the_network() {
+        let small = run_chain_benchmark(5);
+        let large = run_chain_benchmark(20);
+
+        assert!(small.peak_arrangement_bytes > 0);
+        assert!(large.peak_arrangement_bytes > 3 * small.peak_arrangement_bytes);
+    }
+
+    #[test]
+    fn test_fixpoint_iterations_grow_with_the_chain_but_not_the_star() {
+        let short_chain = run_chain_benchmark(5);
+        let long_chain = run_chain_benchmark(20);
+        let small_star = run_star_benchmark(5);
+        let large_star = run_star_benchmark(50);
+
+        // One round per chain node; the star reaches every leaf in the
+        // round after the seed.
+        assert_eq!(short_chain.fixpoint_iterations, 5);
+        assert_eq!(long_chain.fixpoint_iterations, 20);
+        assert_eq!(small_star.fixpoint_iterations, 2);
+        assert_eq!(large_star.fixpoint_iterations, 2);
+    }
+
+    #[test]
+    fn test_speedup_factor_compares_against_a_full_rebuild() {
+        let result = run_chain_benchmark(10);
+        let incremental = result.incremental_update_time.as_secs_f64();
+
+        assert!(result.full_rebuild_time > Duration::ZERO);
+        assert_eq!(
+            result.speedup_factor,
+            result.full_rebuild_time.as_secs_f64() / incremental
+        );
+        assert_eq!(
+            result.initial_build_speedup,
+            result.initial_computation_time.as_secs_f64() / incremental
+        );
+        assert!(run_full_recompute_baseline(10) > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_median_and_stddev() {
+        assert_eq!(median_and_stddev(&[]), (0, 0));
+        assert_eq!(median_and_stddev(&[7]), (7, 0));
+        assert_eq!(median_and_stddev(&[9, 1, 5]), (5, 3));
+        assert_eq!(median_and_stddev(&[2, 4, 4, 4, 5, 5, 7, 9]), (4, 2));
+    }
+
+    #[test]
+    fn test_warmup_does_not_change_attack_path_counts() {
+        let cold = run_chain_benchmark(6);
+        let warm = run_chain_benchmark_with_warmup(6, true);
+
+        assert_eq!(
+            warm.number_of_attack_paths_initial,
+            cold.number_of_attack_paths_initial
+        );
+        assert_eq!(
+            warm.number_of_attack_paths_after_patch,
+            cold.number_of_attack_paths_after_patch
+        );
+    }
+
+    #[test]
+    fn test_same_seed_cuts_the_same_nodes() {
+        let first = run_chain_random_cut_benchmark_with_seed(20, 8, Some(17));
+        let second = run_chain_random_cut_benchmark_with_seed(20, 8, Some(17));
+
+        assert_eq!(first.cut_positions.len(), 8);
+        assert_eq!(first.cut_positions, second.cut_positions);
+    }
+
+    #[test]
+    fn test_random_cut_on_an_empty_chain_returns_empty_results() {
+        let result = run_chain_random_cut_benchmark_with_seed(0, 5, Some(1));
+
+        assert_eq!(result.number_of_nodes, 0);
+        assert_eq!(result.number_of_iterations, 0);
+        assert!(result.cut_positions.is_empty());
+        assert_eq!(result.average_incremental_time, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_progress_bar_renders_fraction_and_eta() {
+        let bar = ProgressBar {
+            label: "chain 200".to_string(),
+            width: 10,
+        };
+        let update = ProgressUpdate {
+            completed: 50,
+            total: 200,
+            elapsed: Duration::from_secs(2),
+            round_time: Duration::from_millis(20),
+        };
+
+        assert_eq!(update.eta(), Some(Duration::from_secs(3)));
+        assert_eq!(
+            bar.render(&update),
+            "chain 200 [###       ] 50/200 ETA 3.0s"
+        );
+    }
+
+    #[test]
+    fn test_attack_path_counts_are_measured_after_patch() {
+        let chain_mid_patch = run_chain_benchmark_with_patch(10, 6);
+        assert_eq!(chain_mid_patch.number_of_attack_paths_initial, 10);
+        assert_eq!(chain_mid_patch.number_of_attack_paths_after_patch, 6);
+        assert_eq!(chain_mid_patch.attack_surface_initial, 1.0);
+        assert_eq!(chain_mid_patch.attack_surface_after_patch, 0.6);
+
+        let chain_early_patch = run_chain_benchmark(10);
+        assert_eq!(chain_early_patch.number_of_attack_paths_after_patch, 1);
+
+        let star = run_star_benchmark(8);
+        assert_eq!(star.number_of_attack_paths_initial, 9);
+        assert_eq!(star.number_of_attack_paths_after_patch, 8);
+
+        let firewall_cut = run_firewall_cut_benchmark(10);
+        assert_eq!(firewall_cut.number_of_attack_paths_initial, 10);
+        assert_eq!(firewall_cut.number_of_attack_paths_after_patch, 6);
+        assert!(firewall_cut.derived_facts_after_update < firewall_cut.derived_facts_before_update);
+
+        let mesh = run_mesh_benchmark(4, 3);
+        assert_eq!(mesh.number_of_nodes, 12);
+        assert_eq!(mesh.number_of_attack_paths_initial, 12);
+        assert_eq!(mesh.number_of_attack_paths_after_patch, 11);
+        assert_eq!(mesh.attack_surface_after_patch, 11.0 / 12.0);
+    }
+
     #[test]
     fn test_layered_enterprise_generation() {
         let config = EnterpriseScenarioConfig {
@@ -2062,4 +2988,34 @@ mod tests {
         assert!(csv.contains("\"example,benchmark\",chain,3,2,3,patch"));
         assert!(csv.contains(",9,4,5,1,,,,5,\n"));
     }
+
+    #[test]
+    fn test_full_benchmark_suite_report_contains_every_section() {
+        let config = BenchmarkSuiteConfig {
+            star_sizes: vec![5],
+            chain_sizes: vec![5],
+            random_cut_sizes: vec![5, 6],
+            random_cut_iterations: 2,
+            seed: 7,
+        };
+
+        let report = run_full_benchmark_suite(&config);
+        assert_eq!(report.star.len(), 1);
+        assert_eq!(report.chain.len(), 1);
+        assert_eq!(report.random_cut.len(), 2);
+
+        let path = std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_suite_report_{}.json",
+            std::process::id()
+        ));
+        write_report(&report, &path).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written["config"]["seed"], 7);
+        assert_eq!(written["star"][0]["topology"], "star");
+        assert_eq!(written["chain"][0]["topology"], "chain");
+        assert_eq!(written["random_cut"][1]["number_of_nodes"], 6);
+    }
 }
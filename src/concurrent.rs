@@ -0,0 +1,315 @@
+// Concurrent fact sources
+// -----------------------
+// The drivers in `driver.rs` and `session.rs` put every update on one
+// logical clock, so a vulnerability scan and a firewall change always
+// arrive in some order.  Here each source has its own clock and the
+// dataflow runs at the product of the two: vulnerability updates land
+// at (v, 0), firewall updates at (0, f), and the state at (v, f) is
+// every update of the first v scans and the first f firewall changes.
+// Neither axis waits on the other, so the goals at (1, 0), (0, 1) and
+// (1, 1) can all be read back once both clocks have advanced.
+//
+//   let mut driver = ConcurrentSourcesDriver::new(worker, &facts);
+//   driver.remove_vulnerability(patched);
+//   driver.advance_vulnerability_clock(worker);
+//   driver.insert_firewall_rule(deny);
+//   driver.advance_firewall_clock(worker);
+//   let patched_only = driver.goals_reached_at(1, 0);
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use differential_dataflow::collection::{AsCollection, Collection};
+use differential_dataflow::lattice::Lattice;
+use serde::{Deserialize, Serialize};
+use timely::communication::Allocate;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::unordered_input::{UnorderedHandle, UnorderedInput};
+use timely::dataflow::operators::{ActivateCapability, ToStream};
+use timely::dataflow::Scope;
+use timely::order::{PartialOrder, Product};
+use timely::progress::timestamp::Refines;
+use timely::progress::{PathSummary, Timestamp};
+use timely::worker::Worker;
+
+use crate::engine::BaseFacts;
+use crate::rules::build_attack_graph_with_local_vulnerabilities;
+use crate::schema::*;
+
+/// Logical time of a [`ConcurrentSourcesDriver`]: the number of
+/// vulnerability scans and of firewall changes applied, as timely's
+/// product timestamp.  Times are ordered by the product order, so
+/// (1, 0) and (0, 1) are incomparable; plain tuples would order them
+/// lexicographically instead.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct ScannerTime(pub Product<usize, usize>);
+
+impl ScannerTime {
+    pub fn new(vulnerability_scans: usize, firewall_changes: usize) -> Self {
+        Self(Product::new(vulnerability_scans, firewall_changes))
+    }
+
+    pub fn vulnerability_scans(&self) -> usize {
+        self.0.outer
+    }
+
+    pub fn firewall_changes(&self) -> usize {
+        self.0.inner
+    }
+}
+
+// `Product` only refines its outer timestamp, so it cannot time a
+// top-level dataflow by itself; the wrapper adds `Refines<()>` and
+// leaves order and lattice to `Product`.  No operator advances a
+// `ScannerTime` along a path, hence the empty summary.
+impl PartialOrder for ScannerTime {
+    fn less_equal(&self, other: &Self) -> bool {
+        self.0.less_equal(&other.0)
+    }
+}
+
+impl PathSummary<ScannerTime> for () {
+    fn results_in(&self, timestamp: &ScannerTime) -> Option<ScannerTime> {
+        Some(*timestamp)
+    }
+
+    fn followed_by(&self, _other: &Self) -> Option<Self> {
+        Some(())
+    }
+}
+
+impl Timestamp for ScannerTime {
+    type Summary = ();
+
+    fn minimum() -> Self {
+        Self(Product::minimum())
+    }
+}
+
+impl Refines<()> for ScannerTime {
+    fn to_inner(_outer: ()) -> Self {
+        Self::minimum()
+    }
+
+    fn to_outer(self) {}
+
+    fn summarize(_summary: ()) {}
+}
+
+impl Lattice for ScannerTime {
+    fn join(&self, other: &Self) -> Self {
+        Self(self.0.join(&other.0))
+    }
+
+    fn meet(&self, other: &Self) -> Self {
+        Self(self.0.meet(&other.0))
+    }
+}
+
+// An input whose updates carry their own `ScannerTime`.  The
+// capability is held at the next time updates may be sent at.
+struct ClockedInput<D: timely::Data> {
+    handle: UnorderedHandle<ScannerTime, (D, ScannerTime, isize)>,
+    capability: ActivateCapability<ScannerTime>,
+}
+
+impl<D: timely::Data> ClockedInput<D> {
+    fn send(&mut self, fact: D, diff: isize) {
+        let time = *self.capability.time();
+        self.handle
+            .session(self.capability.clone())
+            .give((fact, time, diff));
+    }
+}
+
+/// Drives the attack graph with vulnerability and firewall updates on
+/// independent clocks.  See the module comment.
+pub struct ConcurrentSourcesDriver {
+    vulnerabilities: ClockedInput<VulnerabilityRecord>,
+    firewall_rules: ClockedInput<FirewallRuleRecord>,
+    probe: Handle<ScannerTime>,
+    goal_changes: Arc<Mutex<Vec<(AttackerGoalReached, ScannerTime, isize)>>>,
+    // Scans and firewall changes that have settled so far.
+    vulnerability_clock: usize,
+    firewall_clock: usize,
+}
+
+impl ConcurrentSourcesDriver {
+    /// Builds the dataflow on `worker` and settles `facts` at (0, 0).
+    /// Only vulnerabilities and firewall rules change afterwards; the
+    /// other relations stay as loaded.
+    pub fn new<A: Allocate>(worker: &mut Worker<A>, facts: &BaseFacts) -> Self {
+        let mut probe = Handle::new();
+        let goal_changes = Arc::new(Mutex::new(Vec::new()));
+        let captured_changes = Arc::clone(&goal_changes);
+        let static_facts = facts.clone();
+
+        let (mut vulnerabilities, mut firewall_rules) =
+            worker.dataflow::<ScannerTime, _, _>(|scope| {
+                let ((vulnerability_handle, vulnerability_capability), vulnerability_stream) =
+                    scope.new_unordered_input();
+                let ((firewall_handle, firewall_capability), firewall_stream) =
+                    scope.new_unordered_input();
+
+                let (_, _, goals_reached) = build_attack_graph_with_local_vulnerabilities(
+                    &vulnerability_stream.as_collection(),
+                    &loaded_once(static_facts.local_vulnerabilities, scope),
+                    &loaded_once(static_facts.network_access, scope),
+                    &firewall_stream.as_collection(),
+                    &loaded_once(static_facts.attacker_positions, scope),
+                    &loaded_once(static_facts.attacker_goals, scope),
+                );
+                goals_reached
+                    .inspect(move |(goal, time, diff)| {
+                        captured_changes
+                            .lock()
+                            .expect("captured goal changes mutex should not be poisoned")
+                            .push((goal.clone(), *time, *diff));
+                    })
+                    .probe_with(&mut probe);
+
+                (
+                    ClockedInput {
+                        handle: vulnerability_handle,
+                        capability: vulnerability_capability,
+                    },
+                    ClockedInput {
+                        handle: firewall_handle,
+                        capability: firewall_capability,
+                    },
+                )
+            });
+
+        for fact in &facts.vulnerabilities {
+            vulnerabilities.send(fact.clone(), 1);
+        }
+        for fact in &facts.firewall_rules {
+            firewall_rules.send(fact.clone(), 1);
+        }
+        vulnerabilities
+            .capability
+            .downgrade(&ScannerTime::new(1, 0));
+        firewall_rules.capability.downgrade(&ScannerTime::new(0, 1));
+
+        let mut driver = Self {
+            vulnerabilities,
+            firewall_rules,
+            probe,
+            goal_changes,
+            vulnerability_clock: 0,
+            firewall_clock: 0,
+        };
+        driver.settle(worker);
+        driver
+    }
+
+    /// Queues `vulnerability` for the next scan.
+    pub fn insert_vulnerability(&mut self, vulnerability: VulnerabilityRecord) {
+        self.vulnerabilities.send(vulnerability, 1);
+    }
+
+    /// Queues the removal of `vulnerability` for the next scan.
+    pub fn remove_vulnerability(&mut self, vulnerability: VulnerabilityRecord) {
+        self.vulnerabilities.send(vulnerability, -1);
+    }
+
+    /// Queues `rule` for the next firewall change.
+    pub fn insert_firewall_rule(&mut self, rule: FirewallRuleRecord) {
+        self.firewall_rules.send(rule, 1);
+    }
+
+    /// Queues the removal of `rule` for the next firewall change.
+    pub fn remove_firewall_rule(&mut self, rule: FirewallRuleRecord) {
+        self.firewall_rules.send(rule, -1);
+    }
+
+    /// Closes the pending scan and steps the worker until it has
+    /// settled against every firewall change seen so far.  Returns the
+    /// scan's number on the vulnerability axis.
+    pub fn advance_vulnerability_clock<A: Allocate>(&mut self, worker: &mut Worker<A>) -> usize {
+        self.vulnerability_clock += 1;
+        self.vulnerabilities
+            .capability
+            .downgrade(&ScannerTime::new(self.vulnerability_clock + 1, 0));
+        self.settle(worker);
+        self.vulnerability_clock
+    }
+
+    /// Like [`ConcurrentSourcesDriver::advance_vulnerability_clock`],
+    /// for the pending firewall change.
+    pub fn advance_firewall_clock<A: Allocate>(&mut self, worker: &mut Worker<A>) -> usize {
+        self.firewall_clock += 1;
+        self.firewall_rules
+            .capability
+            .downgrade(&ScannerTime::new(0, self.firewall_clock + 1));
+        self.settle(worker);
+        self.firewall_clock
+    }
+
+    /// Scans and firewall changes settled so far, as the latest time
+    /// `goals_reached_at` can answer for.
+    pub fn settled_time(&self) -> ScannerTime {
+        ScannerTime::new(self.vulnerability_clock, self.firewall_clock)
+    }
+
+    /// Goals reached once the first `vulnerability_scans` scans and the
+    /// first `firewall_changes` firewall changes have applied, in any
+    /// order, sorted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either coordinate is past its settled clock.
+    pub fn goals_reached_at(
+        &self,
+        vulnerability_scans: usize,
+        firewall_changes: usize,
+    ) -> Vec<AttackerGoalReached> {
+        let time = ScannerTime::new(vulnerability_scans, firewall_changes);
+        assert!(
+            time.less_equal(&self.settled_time()),
+            "time {:?} has not settled yet; settled up to {:?}",
+            time,
+            self.settled_time()
+        );
+
+        let mut counts: BTreeMap<AttackerGoalReached, isize> = BTreeMap::new();
+        for (goal, change_time, diff) in self
+            .goal_changes
+            .lock()
+            .expect("captured goal changes mutex should not be poisoned")
+            .iter()
+        {
+            if change_time.less_equal(&time) {
+                *counts.entry(goal.clone()).or_insert(0) += diff;
+            }
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(goal, _)| goal)
+            .collect()
+    }
+
+    // Steps until every time up to the settled clocks is complete.
+    fn settle<A: Allocate>(&mut self, worker: &mut Worker<A>) {
+        let settled = self.settled_time();
+        while self.probe.less_equal(&settled) {
+            worker.step();
+        }
+    }
+}
+
+// A relation that is loaded at (0, 0) and never changes.
+fn loaded_once<G, D>(facts: Vec<D>, scope: &mut G) -> Collection<G, D>
+where
+    G: Scope<Timestamp = ScannerTime>,
+    D: timely::Data,
+{
+    facts
+        .into_iter()
+        .map(|fact| (fact, ScannerTime::minimum(), 1))
+        .to_stream(scope)
+        .as_collection()
+}
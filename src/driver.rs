@@ -0,0 +1,903 @@
+// Dataflow drivers
+// ----------------
+// Helpers that own the input sessions of an attack graph dataflow and
+// decide when to advance its timestamp.  Advancing and flushing after
+// every tiny edit forces the worker to settle once per edit; batching
+// several edits into one timestamp trades latency for throughput while
+// the settled state stays the same.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use differential_dataflow::collection::Collection;
+use differential_dataflow::input::{Input, InputSession};
+use differential_dataflow::lattice::Lattice;
+use timely::communication::Allocate;
+use timely::dataflow::operators::probe::Handle;
+use timely::dataflow::operators::{Exchange, Inspect, Probe};
+use timely::dataflow::Scope;
+use timely::order::TotalOrder;
+use timely::progress::timestamp::Refines;
+use timely::progress::Timestamp;
+use timely::worker::Worker;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{effective_network_access_from_base, BaseFacts, DerivedFacts, FactUpdate};
+use crate::parser::InputFact;
+use crate::rules::build_attack_graph_with_local_vulnerabilities;
+use crate::schema::{
+    AttackerCodeExecution, AttackerGoalReached, AttackerOwnsMachine, AttackerStartingPosition,
+    AttackerTargetGoal, FirewallRuleAction, FirewallRuleRecord, HostIdentifier,
+    LocalVulnerabilityRecord, NetworkAccessRule, VulnerabilityRecord,
+};
+
+/// Logical time a [`BatchDriver`] can run its dataflow at.  Implemented
+/// for the unsigned and signed integer types, so callers can use plain
+/// counters (`usize`, the default) or epoch timestamps (`u64`, `i64`).
+pub trait DriverTimestamp: Timestamp + Refines<()> + Lattice + TotalOrder + Copy {
+    /// The next logical time, used by [`BatchDriver::flush_now`].
+    fn successor(self) -> Self;
+}
+
+macro_rules! implement_driver_timestamp {
+    ($($index_type:ty),*) => {
+        $(
+            impl DriverTimestamp for $index_type {
+                fn successor(self) -> Self {
+                    self + 1
+                }
+            }
+        )*
+    };
+}
+
+implement_driver_timestamp!(usize, u32, u64, i64);
+
+/// When a [`BatchDriver`] advances the dataflow timestamp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchDriverConfig {
+    /// Number of accepted edits that triggers a flush.
+    pub batch_size: usize,
+    /// Flush once the oldest pending edit has waited this long, even if
+    /// the batch is not full.  `None` disables the time bound.
+    pub max_batch_delay: Option<Duration>,
+}
+
+impl Default for BatchDriverConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 64,
+            max_batch_delay: None,
+        }
+    }
+}
+
+/// Hosts whose compromise status changed in one flush, as reported by
+/// [`BatchDriver::toggle_firewall_rule`].  A host is compromised when
+/// any attacker executes code on it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AffectedHosts {
+    pub newly_compromised: BTreeSet<HostIdentifier>,
+    pub newly_safe: BTreeSet<HostIdentifier>,
+}
+
+impl AffectedHosts {
+    pub fn is_empty(&self) -> bool {
+        self.newly_compromised.is_empty() && self.newly_safe.is_empty()
+    }
+}
+
+/// Derived relation a [`FactChange`] was observed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DerivedFactKind {
+    ExecCode,
+    OwnsMachine,
+    GoalReached,
+}
+
+/// One change to a derived fact: `diff` is positive when the fact is
+/// derived at `timestamp` and negative when it is retracted.
+/// `fact_display` is the fact in MulVAL predicate syntax, e.g.
+/// `execCode(eve, web01, root)`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FactChange<T = usize> {
+    pub timestamp: T,
+    pub kind: DerivedFactKind,
+    pub fact_display: String,
+    pub diff: isize,
+}
+
+/// Shared buffer that [`log_fact_changes`] appends to.
+pub type FactChangeLog<T = usize> = Arc<Mutex<Vec<FactChange<T>>>>;
+
+/// Records every change of `collection` into `log` as it flows past and
+/// returns the collection unchanged, so it can be chained like
+/// `inspect`.
+pub fn log_fact_changes<G, D>(
+    collection: &Collection<G, D>,
+    kind: DerivedFactKind,
+    log: &FactChangeLog<G::Timestamp>,
+) -> Collection<G, D>
+where
+    G: Scope,
+    D: differential_dataflow::Data + fmt::Display,
+{
+    let log = Arc::clone(log);
+    collection.inspect(move |(record, time, diff)| {
+        log.lock()
+            .expect("fact change log mutex should not be poisoned")
+            .push(FactChange {
+                timestamp: time.clone(),
+                kind,
+                fact_display: record.to_string(),
+                diff: *diff,
+            });
+    })
+}
+
+/// What a batch of edits changes in the attack graph, as returned by
+/// [`attack_graph_diff`].  Every list is sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    pub added_code_executions: Vec<AttackerCodeExecution>,
+    pub removed_code_executions: Vec<AttackerCodeExecution>,
+    pub added_goals_reached: Vec<AttackerGoalReached>,
+    pub removed_goals_reached: Vec<AttackerGoalReached>,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_code_executions.is_empty()
+            && self.removed_code_executions.is_empty()
+            && self.added_goals_reached.is_empty()
+            && self.removed_goals_reached.is_empty()
+    }
+}
+
+/// The settled state of a [`BatchDriver`], taken with
+/// [`BatchDriver::snapshot`].  It serializes with serde, and
+/// [`BatchDriver::restore`] loads it into a fresh dataflow, so what-if
+/// analyses can branch off a known state without replaying its history.
+/// The derived facts are sorted and only recorded for reference; a
+/// restored driver recomputes them from `base_facts`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphSnapshot<T = usize> {
+    /// Logical time of the flush the snapshot reflects.
+    pub timestamp: T,
+    pub base_facts: BaseFacts,
+    pub code_executions: Vec<AttackerCodeExecution>,
+    pub machines_owned: Vec<AttackerOwnsMachine>,
+    pub goals_reached: Vec<AttackerGoalReached>,
+}
+
+#[derive(Debug, Default)]
+struct DerivedCounts {
+    code_executions: HashMap<AttackerCodeExecution, isize>,
+    // `code_executions` summed per compromised host.
+    code_executions_per_host: HashMap<HostIdentifier, isize>,
+    machines_owned: HashMap<AttackerOwnsMachine, isize>,
+    goals_reached: HashMap<AttackerGoalReached, isize>,
+    // Changes observed since the last flush started.
+    latest_code_execution_changes: Vec<(AttackerCodeExecution, isize)>,
+    latest_goal_changes: Vec<(AttackerGoalReached, isize)>,
+}
+
+// One input session per base relation; shared with `AttackGraphSession`.
+pub(crate) struct FactInputs<T: DriverTimestamp> {
+    pub(crate) vulnerabilities: InputSession<T, VulnerabilityRecord, isize>,
+    pub(crate) local_vulnerabilities: InputSession<T, LocalVulnerabilityRecord, isize>,
+    pub(crate) network_access: InputSession<T, NetworkAccessRule, isize>,
+    pub(crate) firewall_rules: InputSession<T, FirewallRuleRecord, isize>,
+    pub(crate) attacker_positions: InputSession<T, AttackerStartingPosition, isize>,
+    pub(crate) attacker_goals: InputSession<T, AttackerTargetGoal, isize>,
+}
+
+impl<T: DriverTimestamp> FactInputs<T> {
+    pub(crate) fn send(&mut self, update: FactUpdate) {
+        match update {
+            FactUpdate::InsertVulnerability(fact) => self.vulnerabilities.insert(fact),
+            FactUpdate::RemoveVulnerability(fact) => self.vulnerabilities.remove(fact),
+            FactUpdate::InsertLocalVulnerability(fact) => self.local_vulnerabilities.insert(fact),
+            FactUpdate::RemoveLocalVulnerability(fact) => self.local_vulnerabilities.remove(fact),
+            FactUpdate::InsertNetworkAccess(fact) => self.network_access.insert(fact),
+            FactUpdate::RemoveNetworkAccess(fact) => self.network_access.remove(fact),
+            FactUpdate::InsertFirewallDeny(mut fact) => {
+                fact.rule_action = FirewallRuleAction::Deny;
+                self.firewall_rules.insert(fact)
+            }
+            FactUpdate::RemoveFirewallDeny(fact) => self.firewall_rules.remove(fact),
+            FactUpdate::InsertFirewallAllow(mut fact) => {
+                fact.rule_action = FirewallRuleAction::Allow;
+                self.firewall_rules.insert(fact)
+            }
+            FactUpdate::RemoveFirewallAllow(fact) => self.firewall_rules.remove(fact),
+            FactUpdate::InsertAttackerPosition(fact) => self.attacker_positions.insert(fact),
+            FactUpdate::RemoveAttackerPosition(fact) => self.attacker_positions.remove(fact),
+            FactUpdate::InsertGoal(fact) => self.attacker_goals.insert(fact),
+            FactUpdate::RemoveGoal(fact) => self.attacker_goals.remove(fact),
+        }
+    }
+
+    pub(crate) fn advance_and_flush(&mut self, time: T) {
+        self.vulnerabilities.advance_to(time);
+        self.local_vulnerabilities.advance_to(time);
+        self.network_access.advance_to(time);
+        self.firewall_rules.advance_to(time);
+        self.attacker_positions.advance_to(time);
+        self.attacker_goals.advance_to(time);
+
+        self.vulnerabilities.flush();
+        self.local_vulnerabilities.flush();
+        self.network_access.flush();
+        self.firewall_rules.flush();
+        self.attacker_positions.flush();
+        self.attacker_goals.flush();
+    }
+}
+
+/// Drives an incremental attack graph dataflow inside a timely worker,
+/// accumulating edits and advancing the timestamp only once per batch.
+///
+/// Edits are accepted with [`BatchDriver::apply`]; the batch is flushed
+/// automatically when it reaches `batch_size` edits or exceeds
+/// `max_batch_delay`, or explicitly with [`BatchDriver::flush_now`].
+/// [`BatchDriver::current_derived_facts`] reflects the settled state as
+/// of the last flush.  The dataflow runs at logical time `T`.
+///
+/// With several workers, the derived facts are gathered on worker 0, so
+/// only its driver sees all of them; edits are best applied there too,
+/// while the other workers' drivers merely flush in step.
+pub struct BatchDriver<T: DriverTimestamp = usize> {
+    config: BatchDriverConfig,
+    inputs: FactInputs<T>,
+    probe: Handle<T>,
+    captured: Arc<Mutex<DerivedCounts>>,
+    facts: BaseFacts,
+    current_time: T,
+    pending_edits: usize,
+    batch_started_at: Option<Instant>,
+}
+
+impl BatchDriver {
+    /// Builds the attack graph dataflow on `worker` and returns a driver
+    /// for its inputs.  Nothing is computed until the first flush.
+    pub fn new<A: Allocate>(worker: &mut Worker<A>, config: BatchDriverConfig) -> Self {
+        Self::with_timestamp(worker, config)
+    }
+}
+
+impl<T: DriverTimestamp> BatchDriver<T> {
+    /// Like [`BatchDriver::new`], for a dataflow running at logical time
+    /// `T`, e.g. `BatchDriver::<u64>::with_timestamp(worker, config)`.
+    pub fn with_timestamp<A: Allocate>(worker: &mut Worker<A>, config: BatchDriverConfig) -> Self {
+        Self::build(worker, config, None)
+    }
+
+    /// Like [`BatchDriver::with_timestamp`], additionally recording every
+    /// change of the derived collections into `change_log`.
+    pub fn with_change_log<A: Allocate>(
+        worker: &mut Worker<A>,
+        config: BatchDriverConfig,
+        change_log: &FactChangeLog<T>,
+    ) -> Self {
+        Self::build(worker, config, Some(change_log))
+    }
+
+    fn build<A: Allocate>(
+        worker: &mut Worker<A>,
+        config: BatchDriverConfig,
+        change_log: Option<&FactChangeLog<T>>,
+    ) -> Self {
+        let probe = Handle::new();
+        let captured = Arc::new(Mutex::new(DerivedCounts::default()));
+        let captured_exec = Arc::clone(&captured);
+        let captured_owns = Arc::clone(&captured);
+        let captured_goals = Arc::clone(&captured);
+
+        let inputs = worker.dataflow::<T, _, _>(|scope| {
+            let (vulnerability_handle, vulnerability_collection) =
+                scope.new_collection::<VulnerabilityRecord, isize>();
+            let (local_vulnerability_handle, local_vulnerability_collection) =
+                scope.new_collection::<LocalVulnerabilityRecord, isize>();
+            let (network_handle, network_collection) =
+                scope.new_collection::<NetworkAccessRule, isize>();
+            let (firewall_handle, firewall_collection) =
+                scope.new_collection::<FirewallRuleRecord, isize>();
+            let (position_handle, position_collection) =
+                scope.new_collection::<AttackerStartingPosition, isize>();
+            let (goal_handle, goal_collection) =
+                scope.new_collection::<AttackerTargetGoal, isize>();
+
+            let (exec_code, owns_machine, goals_reached) =
+                build_attack_graph_with_local_vulnerabilities(
+                    &vulnerability_collection,
+                    &local_vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                );
+            let (exec_code, owns_machine, goals_reached) = match change_log {
+                Some(log) => (
+                    log_fact_changes(&exec_code, DerivedFactKind::ExecCode, log),
+                    log_fact_changes(&owns_machine, DerivedFactKind::OwnsMachine, log),
+                    log_fact_changes(&goals_reached, DerivedFactKind::GoalReached, log),
+                ),
+                None => (exec_code, owns_machine, goals_reached),
+            };
+
+            exec_code
+                .inner
+                .exchange(|_| 0)
+                .inspect(move |(record, _time, diff)| {
+                    let mut captured = captured_exec
+                        .lock()
+                        .expect("captured exec counts mutex should not be poisoned");
+                    accumulate(&mut captured.code_executions, record, *diff);
+                    accumulate(
+                        &mut captured.code_executions_per_host,
+                        &record.compromised_host,
+                        *diff,
+                    );
+                    captured
+                        .latest_code_execution_changes
+                        .push((record.clone(), *diff));
+                })
+                .probe_with(&probe);
+
+            owns_machine
+                .inner
+                .exchange(|_| 0)
+                .inspect(move |(record, _time, diff)| {
+                    let mut captured = captured_owns
+                        .lock()
+                        .expect("captured ownership counts mutex should not be poisoned");
+                    accumulate(&mut captured.machines_owned, record, *diff);
+                })
+                .probe_with(&probe);
+
+            goals_reached
+                .inner
+                .exchange(|_| 0)
+                .inspect(move |(record, _time, diff)| {
+                    let mut captured = captured_goals
+                        .lock()
+                        .expect("captured goal counts mutex should not be poisoned");
+                    accumulate(&mut captured.goals_reached, record, *diff);
+                    captured.latest_goal_changes.push((record.clone(), *diff));
+                })
+                .probe_with(&probe);
+
+            FactInputs {
+                vulnerabilities: vulnerability_handle,
+                local_vulnerabilities: local_vulnerability_handle,
+                network_access: network_handle,
+                firewall_rules: firewall_handle,
+                attacker_positions: position_handle,
+                attacker_goals: goal_handle,
+            }
+        });
+
+        Self {
+            config,
+            inputs,
+            probe,
+            captured,
+            facts: BaseFacts::default(),
+            current_time: T::minimum(),
+            pending_edits: 0,
+            batch_started_at: None,
+        }
+    }
+
+    /// Queues one edit, flushing if the batch is now full or too old.
+    /// Removing a fact that is not present is ignored.
+    pub fn apply<A: Allocate>(&mut self, worker: &mut Worker<A>, update: FactUpdate) {
+        if !self.facts.apply_update(update.clone()) {
+            return;
+        }
+
+        self.inputs.send(update);
+        self.pending_edits += 1;
+        let batch_started_at = *self.batch_started_at.get_or_insert_with(Instant::now);
+
+        let batch_is_full = self.pending_edits >= self.config.batch_size.max(1);
+        let batch_is_stale = self
+            .config
+            .max_batch_delay
+            .is_some_and(|delay| batch_started_at.elapsed() >= delay);
+
+        if batch_is_full || batch_is_stale {
+            self.flush_now(worker);
+        }
+    }
+
+    pub fn apply_updates<A: Allocate>(&mut self, worker: &mut Worker<A>, updates: &[FactUpdate]) {
+        for update in updates {
+            self.apply(worker, update.clone());
+        }
+    }
+
+    /// Advances the timestamp past all pending edits and steps the
+    /// worker until the derived collections have settled.
+    pub fn flush_now<A: Allocate>(&mut self, worker: &mut Worker<A>) {
+        let next_time = self.current_time.successor();
+        self.flush_at(worker, next_time);
+    }
+
+    /// Like [`BatchDriver::flush_now`], but closes the batch at `time`
+    /// instead of the next logical time, e.g. the epoch-millisecond
+    /// timestamp at which the edits were observed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `time` is not later than the previous flush.
+    pub fn flush_at<A: Allocate>(&mut self, worker: &mut Worker<A>, time: T) {
+        assert!(
+            time > self.current_time,
+            "batch driver time must advance: {:?} is not after {:?}",
+            time,
+            self.current_time
+        );
+        self.current_time = time;
+        {
+            let mut captured = self
+                .captured
+                .lock()
+                .expect("captured derived counts mutex should not be poisoned");
+            captured.latest_code_execution_changes.clear();
+            captured.latest_goal_changes.clear();
+        }
+        self.inputs.advance_and_flush(self.current_time);
+
+        while self.probe.less_than(&self.current_time) {
+            worker.step();
+        }
+
+        debug!(
+            "batch driver settled {} edits at {:?}",
+            self.pending_edits, self.current_time
+        );
+        self.pending_edits = 0;
+        self.batch_started_at = None;
+    }
+
+    /// Inserts `rule` as a deny rule, or removes it if it is already
+    /// present, and settles the dataflow at the next logical time.
+    /// Pending edits are flushed first so the answer covers the toggle
+    /// alone.  Only the code execution changes at the toggle's timestamp
+    /// and the running per-host counts of the hosts they touch are
+    /// inspected, so the cost follows the size of the change rather
+    /// than the size of the graph.
+    pub fn toggle_firewall_rule<A: Allocate>(
+        &mut self,
+        worker: &mut Worker<A>,
+        rule: FirewallRuleRecord,
+    ) -> AffectedHosts {
+        if self.pending_edits > 0 {
+            self.flush_now(worker);
+        }
+
+        let deny_rule = FirewallRuleRecord {
+            rule_action: FirewallRuleAction::Deny,
+            ..rule
+        };
+        let update = if self.facts.firewall_rules.contains(&deny_rule) {
+            FactUpdate::RemoveFirewallDeny(deny_rule)
+        } else {
+            FactUpdate::InsertFirewallDeny(deny_rule)
+        };
+        self.facts.apply_update(update.clone());
+        self.inputs.send(update);
+        self.flush_now(worker);
+
+        let captured = self
+            .captured
+            .lock()
+            .expect("captured derived counts mutex should not be poisoned");
+
+        // Net change in the number of executions per host; the count
+        // before the toggle is the settled count minus that change.
+        let mut host_changes: HashMap<&HostIdentifier, isize> = HashMap::new();
+        for (execution, diff) in &captured.latest_code_execution_changes {
+            *host_changes.entry(&execution.compromised_host).or_insert(0) += diff;
+        }
+
+        let mut affected = AffectedHosts::default();
+        for (host, change) in host_changes {
+            let after = captured
+                .code_executions_per_host
+                .get(host)
+                .copied()
+                .unwrap_or(0);
+            let before = after - change;
+            match (before > 0, after > 0) {
+                (false, true) => affected.newly_compromised.insert(host.clone()),
+                (true, false) => affected.newly_safe.insert(host.clone()),
+                _ => false,
+            };
+        }
+        affected
+    }
+
+    /// Flushes pending edits and captures the base facts, the derived
+    /// facts and the current time.
+    pub fn snapshot<A: Allocate>(&mut self, worker: &mut Worker<A>) -> GraphSnapshot<T> {
+        if self.pending_edits > 0 {
+            self.flush_now(worker);
+        }
+
+        let derived = self.current_derived_facts();
+        GraphSnapshot {
+            timestamp: self.current_time,
+            base_facts: self.facts.clone(),
+            code_executions: sorted(derived.code_executions),
+            machines_owned: sorted(derived.machines_owned),
+            goals_reached: sorted(derived.goals_reached),
+        }
+    }
+
+    /// Builds a fresh dataflow on `worker` whose inputs hold the
+    /// snapshot's base facts, settled at the snapshot's timestamp.
+    /// Later edits continue from there.
+    pub fn restore<A: Allocate>(
+        worker: &mut Worker<A>,
+        config: BatchDriverConfig,
+        snapshot: &GraphSnapshot<T>,
+    ) -> Self {
+        let mut driver = Self::build(worker, config, None);
+        // Load the facts as one batch, whatever `batch_size` says.
+        for update in snapshot.base_facts.insert_updates() {
+            if driver.facts.apply_update(update.clone()) {
+                driver.inputs.send(update);
+                driver.pending_edits += 1;
+            }
+        }
+        if snapshot.timestamp > driver.current_time {
+            driver.flush_at(worker, snapshot.timestamp);
+        }
+        driver
+    }
+
+    /// Logical time of the last flush.
+    pub fn current_time(&self) -> T {
+        self.current_time
+    }
+
+    /// Number of accepted edits not yet flushed into the dataflow.
+    pub fn pending_edits(&self) -> usize {
+        self.pending_edits
+    }
+
+    /// Base facts including pending edits.
+    pub fn base_facts(&self) -> &BaseFacts {
+        &self.facts
+    }
+
+    /// Derived facts as of the last flush.  Effective access is computed
+    /// from the current base facts, so flush first when edits are pending.
+    pub fn current_derived_facts(&self) -> DerivedFacts {
+        let captured = self
+            .captured
+            .lock()
+            .expect("captured derived counts mutex should not be poisoned");
+
+        DerivedFacts {
+            effective_network_access: effective_network_access_from_base(&self.facts),
+            code_executions: positive_keys(&captured.code_executions),
+            machines_owned: positive_keys(&captured.machines_owned),
+            goals_reached: positive_keys(&captured.goals_reached),
+        }
+    }
+}
+
+/// Loads `scenario` at time 0, applies each batch of
+/// `changes_over_time` at its timestamp and returns every change of the
+/// derived facts, consolidated per (timestamp, kind, fact) and sorted.
+/// Changes at time 0 are applied together with the scenario.
+///
+/// # Panics
+///
+/// Panics if the timestamps of `changes_over_time` decrease.
+pub fn run_scenario_with_log(
+    scenario: &BaseFacts,
+    changes_over_time: &[(usize, Vec<FactUpdate>)],
+) -> Vec<FactChange> {
+    let log: FactChangeLog = Arc::new(Mutex::new(Vec::new()));
+    let log_in_worker = Arc::clone(&log);
+    let initial_updates = scenario.insert_updates();
+    let changes_over_time = changes_over_time.to_vec();
+
+    timely::execute_directly(move |worker| {
+        // Flushes happen only at the requested timestamps.
+        let config = BatchDriverConfig {
+            batch_size: usize::MAX,
+            max_batch_delay: None,
+        };
+        let mut driver = BatchDriver::with_change_log(worker, config, &log_in_worker);
+
+        driver.apply_updates(worker, &initial_updates);
+        for (timestamp, updates) in &changes_over_time {
+            if *timestamp != driver.current_time() {
+                driver.flush_at(worker, *timestamp);
+            }
+            driver.apply_updates(worker, updates);
+        }
+        driver.flush_now(worker);
+    });
+
+    let mut net_changes: BTreeMap<(usize, DerivedFactKind, String), isize> = BTreeMap::new();
+    for change in log
+        .lock()
+        .expect("fact change log mutex should not be poisoned")
+        .drain(..)
+    {
+        *net_changes
+            .entry((change.timestamp, change.kind, change.fact_display))
+            .or_insert(0) += change.diff;
+    }
+    net_changes
+        .into_iter()
+        .filter(|(_, diff)| *diff != 0)
+        .map(|((timestamp, kind, fact_display), diff)| FactChange {
+            timestamp,
+            kind,
+            fact_display,
+            diff,
+        })
+        .collect()
+}
+
+/// The derived facts after one step of [`run_timeline`], consolidated
+/// and sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeStepOutput {
+    /// Logical time the step's updates were applied at.
+    pub timestamp: usize,
+    pub code_executions: Vec<AttackerCodeExecution>,
+    #[serde(default)]
+    pub machines_owned: Vec<AttackerOwnsMachine>,
+    pub goals_reached: Vec<AttackerGoalReached>,
+}
+
+/// Loads `initial` at time 0, applies `steps[i]` at time `i + 1`, and
+/// returns the settled derived facts after every step, the initial
+/// load included, so the result has `steps.len() + 1` entries.  This is
+/// the insert / advance / flush / step loop of the phased demo as a
+/// reusable helper for multi-step scenarios.
+pub fn run_timeline(initial: &BaseFacts, steps: &[Vec<FactUpdate>]) -> Vec<TimeStepOutput> {
+    let batches = timeline_batches(initial, steps);
+    timely::execute_directly(move |worker| settle_timeline(worker, &batches))
+}
+
+/// Like [`run_timeline`], on the workers described by timely's
+/// command-line `args`, e.g. `["-w", "2"]`.  The facts enter through
+/// worker 0 and the workers share the computation.  Errors are the
+/// messages of failed or panicked workers.
+pub fn run_timeline_from_args<I>(
+    args: I,
+    initial: &BaseFacts,
+    steps: &[Vec<FactUpdate>],
+) -> Result<Vec<TimeStepOutput>, String>
+where
+    I: Iterator<Item = String>,
+{
+    let batches = timeline_batches(initial, steps);
+    let workers = timely::execute_from_args(args, move |worker| {
+        if worker.index() == 0 {
+            settle_timeline(worker, &batches)
+        } else {
+            // Loading the facts here as well would insert each of them
+            // once per worker; this driver only flushes in step.
+            settle_timeline(worker, &vec![Vec::new(); batches.len()])
+        }
+    })?;
+
+    let mut timelines = workers
+        .join()
+        .into_iter()
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(timelines.swap_remove(0))
+}
+
+fn timeline_batches(initial: &BaseFacts, steps: &[Vec<FactUpdate>]) -> Vec<Vec<FactUpdate>> {
+    std::iter::once(initial.insert_updates())
+        .chain(steps.iter().cloned())
+        .collect()
+}
+
+// Applies each batch at the next logical time and records the settled
+// derived facts after it.
+fn settle_timeline<A: Allocate>(
+    worker: &mut Worker<A>,
+    batches: &[Vec<FactUpdate>],
+) -> Vec<TimeStepOutput> {
+    // Flushes happen only once per step.
+    let config = BatchDriverConfig {
+        batch_size: usize::MAX,
+        max_batch_delay: None,
+    };
+    let mut driver = BatchDriver::new(worker, config);
+
+    batches
+        .iter()
+        .map(|updates| {
+            let timestamp = driver.current_time();
+            driver.apply_updates(worker, updates);
+            driver.flush_now(worker);
+            let derived = driver.current_derived_facts();
+            TimeStepOutput {
+                timestamp,
+                code_executions: sorted(derived.code_executions),
+                machines_owned: sorted(derived.machines_owned),
+                goals_reached: sorted(derived.goals_reached),
+            }
+        })
+        .collect()
+}
+
+/// Answers "what changes if we apply `changes`?": loads `base` at time
+/// 0, applies `changes` at time 1 and reports the code executions and
+/// reached goals whose net change at time 1 is non-zero.  Only the
+/// second batch is inspected, so the cost follows the size of the
+/// change rather than the size of the graph.
+pub fn attack_graph_diff(base: &BaseFacts, changes: &[FactUpdate]) -> GraphDiff {
+    let initial_updates = base.insert_updates();
+    let changes = changes.to_vec();
+
+    let (code_execution_changes, goal_changes) = timely::execute_directly(move |worker| {
+        let config = BatchDriverConfig {
+            batch_size: usize::MAX,
+            max_batch_delay: None,
+        };
+        let mut driver = BatchDriver::new(worker, config);
+        driver.apply_updates(worker, &initial_updates);
+        driver.flush_at(worker, 1);
+        driver.apply_updates(worker, &changes);
+        driver.flush_at(worker, 2);
+
+        let mut captured = driver
+            .captured
+            .lock()
+            .expect("captured derived counts mutex should not be poisoned");
+        (
+            std::mem::take(&mut captured.latest_code_execution_changes),
+            std::mem::take(&mut captured.latest_goal_changes),
+        )
+    });
+
+    let (added_code_executions, removed_code_executions) =
+        split_net_changes(code_execution_changes);
+    let (added_goals_reached, removed_goals_reached) = split_net_changes(goal_changes);
+    GraphDiff {
+        added_code_executions,
+        removed_code_executions,
+        added_goals_reached,
+        removed_goals_reached,
+    }
+}
+
+/// A base fact that holds from `start` until just before `expiry`, or
+/// indefinitely when `expiry` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedFact {
+    pub fact: InputFact,
+    pub start: usize,
+    pub expiry: Option<usize>,
+}
+
+/// Base facts with lifetimes, e.g. a vulnerability exposed only during
+/// a maintenance window.  Facts are counted, so the same fact may be
+/// listed with overlapping intervals and holds while any of them does.
+///
+///   TimedScenario::new()
+///       .fact(InputFact::Hacl(edge), 0)
+///       .expiring_fact(InputFact::VulExists(vulnerability), 2, 4)
+///       .run_with_log()
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimedScenario {
+    pub facts: Vec<TimedFact>,
+}
+
+impl TimedScenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `fact` from `start` on, never expiring.
+    pub fn fact(mut self, fact: InputFact, start: usize) -> Self {
+        self.facts.push(TimedFact {
+            fact,
+            start,
+            expiry: None,
+        });
+        self
+    }
+
+    /// Adds `fact` for the ticks `start..expiry`.  An empty interval
+    /// adds nothing.
+    pub fn expiring_fact(mut self, fact: InputFact, start: usize, expiry: usize) -> Self {
+        self.facts.push(TimedFact {
+            fact,
+            start,
+            expiry: Some(expiry),
+        });
+        self
+    }
+
+    /// The scenario as update batches in time order: every fact is
+    /// inserted at its start and removed at its expiry.  Inserts come
+    /// before removals within a batch.
+    pub fn changes_over_time(&self) -> Vec<(usize, Vec<FactUpdate>)> {
+        let mut inserts: BTreeMap<usize, Vec<FactUpdate>> = BTreeMap::new();
+        let mut removals: BTreeMap<usize, Vec<FactUpdate>> = BTreeMap::new();
+        for timed in &self.facts {
+            if timed.expiry.is_some_and(|expiry| expiry <= timed.start) {
+                continue;
+            }
+            inserts
+                .entry(timed.start)
+                .or_default()
+                .push(FactUpdate::insert(timed.fact.clone()));
+            if let Some(expiry) = timed.expiry {
+                removals
+                    .entry(expiry)
+                    .or_default()
+                    .push(FactUpdate::remove(timed.fact.clone()));
+            }
+        }
+
+        for (timestamp, updates) in removals {
+            inserts.entry(timestamp).or_default().extend(updates);
+        }
+        inserts.into_iter().collect()
+    }
+
+    /// Runs the scenario from empty inputs and returns the derived-fact
+    /// changes per tick; see `run_scenario_with_log`.
+    pub fn run_with_log(&self) -> Vec<FactChange> {
+        run_scenario_with_log(&BaseFacts::default(), &self.changes_over_time())
+    }
+}
+
+fn sorted<T: Ord>(records: std::collections::HashSet<T>) -> Vec<T> {
+    let mut records: Vec<_> = records.into_iter().collect();
+    records.sort();
+    records
+}
+
+// Consolidates signed changes into (added, removed) sorted records.
+fn split_net_changes<T: Ord>(changes: Vec<(T, isize)>) -> (Vec<T>, Vec<T>) {
+    let mut net_changes: BTreeMap<T, isize> = BTreeMap::new();
+    for (record, diff) in changes {
+        *net_changes.entry(record).or_insert(0) += diff;
+    }
+    let (added, removed): (Vec<_>, Vec<_>) = net_changes
+        .into_iter()
+        .filter(|(_, diff)| *diff != 0)
+        .partition(|(_, diff)| *diff > 0);
+    (
+        added.into_iter().map(|(record, _)| record).collect(),
+        removed.into_iter().map(|(record, _)| record).collect(),
+    )
+}
+
+fn accumulate<T: Eq + Hash + Clone>(counts: &mut HashMap<T, isize>, record: &T, diff: isize) {
+    let count = counts.entry(record.clone()).or_insert(0);
+    *count += diff;
+    if *count == 0 {
+        counts.remove(record);
+    }
+}
+
+fn positive_keys<T: Eq + Hash + Clone>(counts: &HashMap<T, isize>) -> std::collections::HashSet<T> {
+    counts
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(record, _)| record.clone())
+        .collect()
+}
@@ -1,13 +1,16 @@
 use std::collections::{BTreeSet, HashSet};
 
+use serde::{Deserialize, Serialize};
+
 use crate::naive::evaluate_attack_graph_naive_with_local_vulnerabilities;
+use crate::parser::InputFact;
 use crate::schema::{
     AttackerCodeExecution, AttackerGoalReached, AttackerOwnsMachine, AttackerStartingPosition,
     AttackerTargetGoal, EffectiveNetworkAccess, FirewallRuleAction, FirewallRuleRecord,
     LocalVulnerabilityRecord, NetworkAccessRule, VulnerabilityRecord,
 };
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BaseFacts {
     pub vulnerabilities: Vec<VulnerabilityRecord>,
     pub local_vulnerabilities: Vec<LocalVulnerabilityRecord>,
@@ -35,12 +38,40 @@ pub enum FactUpdate {
     RemoveNetworkAccess(NetworkAccessRule),
     InsertFirewallDeny(FirewallRuleRecord),
     RemoveFirewallDeny(FirewallRuleRecord),
+    InsertFirewallAllow(FirewallRuleRecord),
+    RemoveFirewallAllow(FirewallRuleRecord),
     InsertAttackerPosition(AttackerStartingPosition),
     RemoveAttackerPosition(AttackerStartingPosition),
     InsertGoal(AttackerTargetGoal),
     RemoveGoal(AttackerTargetGoal),
 }
 
+impl FactUpdate {
+    /// The update that inserts `fact`.
+    pub fn insert(fact: InputFact) -> Self {
+        match fact {
+            InputFact::VulExists(fact) => FactUpdate::InsertVulnerability(fact),
+            InputFact::LocalVulExists(fact) => FactUpdate::InsertLocalVulnerability(fact),
+            InputFact::Hacl(fact) => FactUpdate::InsertNetworkAccess(fact),
+            InputFact::FirewallDeny(fact) => FactUpdate::InsertFirewallDeny(fact),
+            InputFact::AttackerLocated(fact) => FactUpdate::InsertAttackerPosition(fact),
+            InputFact::AttackGoal(fact) => FactUpdate::InsertGoal(fact),
+        }
+    }
+
+    /// The update that removes `fact`.
+    pub fn remove(fact: InputFact) -> Self {
+        match fact {
+            InputFact::VulExists(fact) => FactUpdate::RemoveVulnerability(fact),
+            InputFact::LocalVulExists(fact) => FactUpdate::RemoveLocalVulnerability(fact),
+            InputFact::Hacl(fact) => FactUpdate::RemoveNetworkAccess(fact),
+            InputFact::FirewallDeny(fact) => FactUpdate::RemoveFirewallDeny(fact),
+            InputFact::AttackerLocated(fact) => FactUpdate::RemoveAttackerPosition(fact),
+            InputFact::AttackGoal(fact) => FactUpdate::RemoveGoal(fact),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EngineRunResult {
     pub engine_name: &'static str,
@@ -61,35 +92,38 @@ pub trait AttackGraphEngine {
 }
 
 impl BaseFacts {
-    pub fn apply_update(&mut self, update: FactUpdate) {
+    /// Applies one update and reports whether the base facts changed.
+    /// Removing a fact that is not present is a no-op.
+    pub fn apply_update(&mut self, update: FactUpdate) -> bool {
         match update {
-            FactUpdate::InsertVulnerability(fact) => self.vulnerabilities.push(fact),
-            FactUpdate::RemoveVulnerability(fact) => {
-                remove_one(&mut self.vulnerabilities, &fact);
+            FactUpdate::InsertVulnerability(fact) => insert_one(&mut self.vulnerabilities, fact),
+            FactUpdate::RemoveVulnerability(fact) => remove_one(&mut self.vulnerabilities, &fact),
+            FactUpdate::InsertLocalVulnerability(fact) => {
+                insert_one(&mut self.local_vulnerabilities, fact)
             }
-            FactUpdate::InsertLocalVulnerability(fact) => self.local_vulnerabilities.push(fact),
             FactUpdate::RemoveLocalVulnerability(fact) => {
-                remove_one(&mut self.local_vulnerabilities, &fact);
-            }
-            FactUpdate::InsertNetworkAccess(fact) => self.network_access.push(fact),
-            FactUpdate::RemoveNetworkAccess(fact) => {
-                remove_one(&mut self.network_access, &fact);
+                remove_one(&mut self.local_vulnerabilities, &fact)
             }
+            FactUpdate::InsertNetworkAccess(fact) => insert_one(&mut self.network_access, fact),
+            FactUpdate::RemoveNetworkAccess(fact) => remove_one(&mut self.network_access, &fact),
             FactUpdate::InsertFirewallDeny(mut fact) => {
                 fact.rule_action = FirewallRuleAction::Deny;
-                self.firewall_rules.push(fact);
+                insert_one(&mut self.firewall_rules, fact)
             }
-            FactUpdate::RemoveFirewallDeny(fact) => {
-                remove_one(&mut self.firewall_rules, &fact);
+            FactUpdate::RemoveFirewallDeny(fact) => remove_one(&mut self.firewall_rules, &fact),
+            FactUpdate::InsertFirewallAllow(mut fact) => {
+                fact.rule_action = FirewallRuleAction::Allow;
+                insert_one(&mut self.firewall_rules, fact)
             }
-            FactUpdate::InsertAttackerPosition(fact) => self.attacker_positions.push(fact),
-            FactUpdate::RemoveAttackerPosition(fact) => {
-                remove_one(&mut self.attacker_positions, &fact);
+            FactUpdate::RemoveFirewallAllow(fact) => remove_one(&mut self.firewall_rules, &fact),
+            FactUpdate::InsertAttackerPosition(fact) => {
+                insert_one(&mut self.attacker_positions, fact)
             }
-            FactUpdate::InsertGoal(fact) => self.attacker_goals.push(fact),
-            FactUpdate::RemoveGoal(fact) => {
-                remove_one(&mut self.attacker_goals, &fact);
+            FactUpdate::RemoveAttackerPosition(fact) => {
+                remove_one(&mut self.attacker_positions, &fact)
             }
+            FactUpdate::InsertGoal(fact) => insert_one(&mut self.attacker_goals, fact),
+            FactUpdate::RemoveGoal(fact) => remove_one(&mut self.attacker_goals, &fact),
         }
     }
 
@@ -98,6 +132,50 @@ impl BaseFacts {
             self.apply_update(update.clone());
         }
     }
+
+    /// The updates that insert every fact, e.g. to load these facts into
+    /// an incremental driver.
+    pub fn insert_updates(&self) -> Vec<FactUpdate> {
+        let firewall_rules =
+            self.firewall_rules
+                .iter()
+                .cloned()
+                .map(|rule| match rule.rule_action {
+                    FirewallRuleAction::Deny => FactUpdate::InsertFirewallDeny(rule),
+                    FirewallRuleAction::Allow => FactUpdate::InsertFirewallAllow(rule),
+                });
+
+        self.vulnerabilities
+            .iter()
+            .cloned()
+            .map(FactUpdate::InsertVulnerability)
+            .chain(
+                self.local_vulnerabilities
+                    .iter()
+                    .cloned()
+                    .map(FactUpdate::InsertLocalVulnerability),
+            )
+            .chain(
+                self.network_access
+                    .iter()
+                    .cloned()
+                    .map(FactUpdate::InsertNetworkAccess),
+            )
+            .chain(firewall_rules)
+            .chain(
+                self.attacker_positions
+                    .iter()
+                    .cloned()
+                    .map(FactUpdate::InsertAttackerPosition),
+            )
+            .chain(
+                self.attacker_goals
+                    .iter()
+                    .cloned()
+                    .map(FactUpdate::InsertGoal),
+            )
+            .collect()
+    }
 }
 
 pub fn evaluate_base_facts(facts: &BaseFacts) -> DerivedFacts {
@@ -128,6 +206,7 @@ pub fn effective_network_access_from_base(facts: &BaseFacts) -> HashSet<Effectiv
                 rule.source_zone.clone(),
                 rule.destination_host.clone(),
                 rule.service_name.clone(),
+                rule.port,
             )
         })
         .collect();
@@ -140,12 +219,14 @@ pub fn effective_network_access_from_base(facts: &BaseFacts) -> HashSet<Effectiv
                 access.source_host.clone(),
                 access.destination_host.clone(),
                 access.service_name.clone(),
+                access.port,
             ))
         })
         .map(|access| EffectiveNetworkAccess {
             source_host: access.source_host.clone(),
             destination_host: access.destination_host.clone(),
             service_name: access.service_name.clone(),
+            port: access.port,
         })
         .collect()
 }
@@ -185,9 +266,18 @@ pub fn compare_derived_facts(left: &DerivedFacts, right: &DerivedFacts) -> Resul
     }
 }
 
-fn remove_one<T: PartialEq>(facts: &mut Vec<T>, fact: &T) {
-    if let Some(index) = facts.iter().position(|candidate| candidate == fact) {
-        facts.remove(index);
+fn insert_one<T>(facts: &mut Vec<T>, fact: T) -> bool {
+    facts.push(fact);
+    true
+}
+
+fn remove_one<T: PartialEq>(facts: &mut Vec<T>, fact: &T) -> bool {
+    match facts.iter().position(|candidate| candidate == fact) {
+        Some(index) => {
+            facts.remove(index);
+            true
+        }
+        None => false,
     }
 }
 
@@ -238,6 +328,7 @@ mod tests {
         left.goals_reached.insert(AttackerGoalReached {
             attacker_id: "eve".to_string(),
             reached_target: "admin".to_string(),
+            required_privilege: PrivilegeLevel::Root,
         });
 
         let error = compare_derived_facts(&left, &right).expect_err("sets should differ");
@@ -256,4 +347,19 @@ mod tests {
         facts.apply_update(FactUpdate::RemoveVulnerability(vulnerability));
         assert_eq!(facts.vulnerabilities.len(), 1);
     }
+
+    #[test]
+    fn insert_updates_keep_allow_rules() {
+        let facts = BaseFacts {
+            firewall_rules: vec![
+                FirewallRuleRecord::create_allow_rule("internet", "web", "https"),
+                FirewallRuleRecord::create_deny_rule("internet", "web", "ssh"),
+            ],
+            ..BaseFacts::default()
+        };
+
+        let mut reloaded = BaseFacts::default();
+        reloaded.apply_updates(&facts.insert_updates());
+        assert_eq!(reloaded, facts);
+    }
 }
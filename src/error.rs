@@ -0,0 +1,117 @@
+// Library-level errors
+// --------------------
+// `AttackGraphError` is what the fallible entry points return to
+// callers embedding the library: either the input could not be read or
+// parsed, or the dataflow itself failed.  Timely reports worker panics
+// as strings, and `execute_directly` simply unwinds, so computation
+// failures carry a message rather than a typed source.
+
+use std::error::Error;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::io::CsvError;
+use crate::nvd::NvdError;
+use crate::parser::ParseError;
+use crate::scenario::ScenarioError;
+
+#[derive(Debug)]
+pub enum AttackGraphError {
+    InputError(Box<dyn Error + Send + Sync>),
+    ComputationError(String),
+}
+
+impl fmt::Display for AttackGraphError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttackGraphError::InputError(source) => write!(formatter, "invalid input: {source}"),
+            AttackGraphError::ComputationError(message) => {
+                write!(formatter, "computation failed: {message}")
+            }
+        }
+    }
+}
+
+impl Error for AttackGraphError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AttackGraphError::InputError(source) => Some(source.as_ref()),
+            AttackGraphError::ComputationError(_) => None,
+        }
+    }
+}
+
+impl From<ParseError> for AttackGraphError {
+    fn from(error: ParseError) -> Self {
+        AttackGraphError::InputError(Box::new(error))
+    }
+}
+
+impl From<ScenarioError> for AttackGraphError {
+    fn from(error: ScenarioError) -> Self {
+        AttackGraphError::InputError(Box::new(error))
+    }
+}
+
+impl From<CsvError> for AttackGraphError {
+    fn from(error: CsvError) -> Self {
+        AttackGraphError::InputError(Box::new(error))
+    }
+}
+
+impl From<NvdError> for AttackGraphError {
+    fn from(error: NvdError) -> Self {
+        AttackGraphError::InputError(Box::new(error))
+    }
+}
+
+/// Runs `computation`, turning a panic inside it (including one raised
+/// in a timely worker closure run by `execute_directly`) into a
+/// `ComputationError` carrying the panic message.
+pub fn catch_computation_panic<T, F>(computation: F) -> Result<T, AttackGraphError>
+where
+    F: FnOnce() -> T,
+{
+    panic::catch_unwind(AssertUnwindSafe(computation))
+        .map_err(|payload| AttackGraphError::ComputationError(panic_message(payload.as_ref())))
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "dataflow worker panicked".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_become_computation_errors() {
+        let error = catch_computation_panic(|| -> usize { panic!("worker exploded") })
+            .expect_err("panic should be caught");
+
+        match error {
+            AttackGraphError::ComputationError(message) => assert_eq!(message, "worker exploded"),
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn input_errors_keep_their_source() {
+        let error = AttackGraphError::from(ParseError::MissingPeriod);
+
+        assert_eq!(
+            error.source().map(ToString::to_string),
+            Some("fact must end with a period".to_string())
+        );
+        assert_eq!(
+            error.to_string(),
+            "invalid input: fact must end with a period"
+        );
+    }
+}
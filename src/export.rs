@@ -0,0 +1,1045 @@
+// Export formats
+// --------------
+// Serialisations of an evaluated attack graph for consumption outside
+// this crate.  Exporters work on plain `BaseFacts` / `DerivedFacts`
+// snapshots, so they can be fed from the naive evaluator, a
+// `BatchDriver`, or any engine that produces `DerivedFacts`.
+//
+// `AttackGraphState` is the smaller single-attacker view used for
+// visualisation: a topology plus the set of compromised hosts.
+// `export_dot_animation` renders one such view per timestamp of an
+// incremental run.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::driver::{BatchDriver, BatchDriverConfig};
+use crate::engine::{BaseFacts, DerivedFacts, FactUpdate};
+use crate::schema::{AttackerCodeExecution, HostIdentifier, PrivilegeLevel, ServiceName};
+
+/// A network topology annotated with what one attacker compromised,
+/// ready to be rendered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttackGraphState {
+    pub nodes: BTreeSet<HostIdentifier>,
+    /// (source, destination, service) network edges.
+    pub edges: BTreeSet<(HostIdentifier, HostIdentifier, ServiceName)>,
+    /// Hosts the attacker can reach.
+    pub compromised: BTreeSet<HostIdentifier>,
+    /// Highest privilege the attacker holds on each compromised host,
+    /// where known.  Hosts missing here are drawn as plainly
+    /// compromised.
+    pub privileges: BTreeMap<HostIdentifier, PrivilegeLevel>,
+    pub attacker_start: HostIdentifier,
+    pub target_node: HostIdentifier,
+}
+
+impl AttackGraphState {
+    /// The view of `attacker_id` on `facts`: the network edges, every
+    /// host they, a vulnerability or a starting position name, and the
+    /// hosts `derived` has the attacker executing code on.
+    pub fn from_facts(
+        facts: &BaseFacts,
+        derived: &DerivedFacts,
+        attacker_id: &str,
+        target_node: &str,
+    ) -> Self {
+        let mut state = AttackGraphState {
+            target_node: target_node.to_string(),
+            ..AttackGraphState::default()
+        };
+        for access in &facts.network_access {
+            state.nodes.insert(access.source_host.clone());
+            state.nodes.insert(access.destination_host.clone());
+            state.edges.insert((
+                access.source_host.clone(),
+                access.destination_host.clone(),
+                access.service_name.clone(),
+            ));
+        }
+        for vulnerability in &facts.vulnerabilities {
+            state.nodes.insert(vulnerability.host_name.clone());
+        }
+        for position in &facts.attacker_positions {
+            state.nodes.insert(position.starting_host.clone());
+            if position.attacker_id == attacker_id && state.attacker_start.is_empty() {
+                state.attacker_start = position.starting_host.clone();
+            }
+        }
+        state.nodes.insert(target_node.to_string());
+        for execution in &derived.code_executions {
+            if execution.attacker_id == attacker_id {
+                state.record_code_execution(execution);
+            }
+        }
+        state
+    }
+
+    /// Marks the host of `execution` as compromised, raising its
+    /// privilege to the execution's if that is higher.
+    pub fn record_code_execution(&mut self, execution: &AttackerCodeExecution) {
+        self.compromised.insert(execution.compromised_host.clone());
+        let privilege = self
+            .privileges
+            .entry(execution.compromised_host.clone())
+            .or_insert_with(|| execution.obtained_privilege.clone());
+        if execution.obtained_privilege > *privilege {
+            *privilege = execution.obtained_privilege.clone();
+        }
+    }
+
+    /// An edge is on the attack path when both of its endpoints are
+    /// compromised.  Every exporter uses this classification.
+    pub fn is_on_attack_path(&self, source: &str, destination: &str) -> bool {
+        self.compromised.contains(source) && self.compromised.contains(destination)
+    }
+
+    /// The graph as Graphviz DOT.  The attacker and the target are
+    /// coloured by role; other compromised hosts are yellow with User
+    /// (or no) privilege, red with Root and orange when the privilege
+    /// is unknown, with the privilege in the label.  Attack-path edges
+    /// are highlighted.
+    pub fn to_dot(&self, title: &str) -> String {
+        let mut dot = String::new();
+        // Writing to a `String` cannot fail.
+        let _ = self.write_dot(&mut dot, title);
+        dot
+    }
+
+    /// Writes `to_dot(title)` to `path`.
+    pub fn export_to_dot<P: AsRef<Path>>(&self, path: P, title: &str) -> io::Result<()> {
+        fs::write(path, self.to_dot(title))
+    }
+
+    fn write_dot(&self, dot: &mut String, title: &str) -> std::fmt::Result {
+        writeln!(dot, "digraph AttackGraph {{")?;
+        writeln!(dot, "    label=\"{}\";", title)?;
+        writeln!(dot, "    labelloc=\"t\";")?;
+        writeln!(dot, "    fontsize=20;")?;
+        writeln!(dot, "    rankdir=LR;")?;
+        writeln!(dot, "    node [shape=box, style=filled];")?;
+        writeln!(dot)?;
+
+        // Define node styles
+        for node in &self.nodes {
+            let privilege_suffix = match self.privileges.get(node) {
+                Some(privilege) => format!(" ({privilege})"),
+                None => String::new(),
+            };
+            let (color, label_suffix) = if node == &self.attacker_start {
+                ("lightblue", " [ATTACKER]".to_string())
+            } else if node == &self.target_node {
+                if self.compromised.contains(node) {
+                    ("red", format!(" [TARGET - COMPROMISED!]{privilege_suffix}"))
+                } else {
+                    ("lightgreen", " [TARGET - SAFE]".to_string())
+                }
+            } else if self.compromised.contains(node) {
+                let color = match self.privileges.get(node) {
+                    Some(PrivilegeLevel::Root) => "red",
+                    Some(PrivilegeLevel::User | PrivilegeLevel::None) => "yellow",
+                    None => "orange",
+                };
+                (color, format!(" [COMPROMISED]{privilege_suffix}"))
+            } else {
+                ("white", String::new())
+            };
+
+            writeln!(
+                dot,
+                "    \"{}\" [fillcolor={}, label=\"{}{}\"];",
+                node, color, node, label_suffix
+            )?;
+        }
+        writeln!(dot)?;
+
+        // Define edges with attack path highlighting
+        for (src, dst, service) in &self.edges {
+            let (color, penwidth) = if self.is_on_attack_path(src, dst) {
+                ("red", "2.0")
+            } else {
+                ("black", "1.0")
+            };
+
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label=\"{}\", color={}, penwidth={}];",
+                src, dst, service, color, penwidth
+            )?;
+        }
+
+        writeln!(dot, "}}")
+    }
+
+    /// The graph as a JSON document:
+    /// `{nodes, edges: [{src, dst, service, on_attack_path}], compromised,
+    /// attacker_start, target}`.
+    pub fn to_json(&self) -> Value {
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|(src, dst, service)| {
+                json!({
+                    "src": src,
+                    "dst": dst,
+                    "service": service,
+                    "on_attack_path": self.is_on_attack_path(src, dst),
+                })
+            })
+            .collect();
+
+        json!({
+            "nodes": self.nodes,
+            "edges": edges,
+            "compromised": self.compromised,
+            "attacker_start": self.attacker_start,
+            "target": self.target_node,
+        })
+    }
+
+    /// Writes `to_json()` to `path`, pretty-printed.
+    pub fn export_to_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.to_json())?;
+        fs::write(path, json)
+    }
+
+    /// The graph as GraphML, for yEd or Gephi.  Nodes carry a boolean
+    /// `compromised` and a `role` of `attacker`, `target` or `normal`;
+    /// edges carry `service` and a boolean `on_attack_path`.
+    pub fn to_graphml(&self) -> String {
+        let mut graphml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"compromised\" for=\"node\" attr.name=\"compromised\" attr.type=\"boolean\"/>\n",
+            "  <key id=\"role\" for=\"node\" attr.name=\"role\" attr.type=\"string\"/>\n",
+            "  <key id=\"service\" for=\"edge\" attr.name=\"service\" attr.type=\"string\"/>\n",
+            "  <key id=\"on_attack_path\" for=\"edge\" attr.name=\"on_attack_path\" attr.type=\"boolean\"/>\n",
+            "  <graph id=\"AttackGraph\" edgedefault=\"directed\">\n",
+        ));
+
+        for node in &self.nodes {
+            graphml.push_str(&format!(
+                "    <node id=\"{}\">\n      <data key=\"compromised\">{}</data>\n      <data key=\"role\">{}</data>\n    </node>\n",
+                escape_xml(node),
+                self.compromised.contains(node),
+                self.node_role(node)
+            ));
+        }
+        for (index, (src, dst, service)) in self.edges.iter().enumerate() {
+            graphml.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n      <data key=\"service\">{}</data>\n      <data key=\"on_attack_path\">{}</data>\n    </edge>\n",
+                index,
+                escape_xml(src),
+                escape_xml(dst),
+                escape_xml(service),
+                self.is_on_attack_path(src, dst)
+            ));
+        }
+
+        graphml.push_str("  </graph>\n</graphml>\n");
+        graphml
+    }
+
+    /// Writes `to_graphml()` to `path`.
+    pub fn export_to_graphml<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_graphml())
+    }
+
+    /// The graph in Cytoscape.js elements format:
+    /// `{elements: {nodes: [{data: {id, role, compromised}}],
+    /// edges: [{data: {source, target, service, attack_path}}]}}`,
+    /// with the same roles as `to_graphml`.
+    pub fn to_cytoscape_json(&self) -> Value {
+        let nodes: Vec<Value> = self
+            .nodes
+            .iter()
+            .map(|node| {
+                json!({
+                    "data": {
+                        "id": node,
+                        "role": self.node_role(node),
+                        "compromised": self.compromised.contains(node),
+                    }
+                })
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|(src, dst, service)| {
+                json!({
+                    "data": {
+                        "source": src,
+                        "target": dst,
+                        "service": service,
+                        "attack_path": self.is_on_attack_path(src, dst),
+                    }
+                })
+            })
+            .collect();
+
+        json!({
+            "elements": {
+                "nodes": nodes,
+                "edges": edges,
+            }
+        })
+    }
+
+    /// Writes `to_cytoscape_json()` to `path`, pretty-printed.
+    pub fn export_to_cytoscape_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.to_cytoscape_json())?;
+        fs::write(path, json)
+    }
+
+    // `attacker`, `target` or `normal`.
+    fn node_role(&self, node: &str) -> &'static str {
+        if node == self.attacker_start {
+            "attacker"
+        } else if node == self.target_node {
+            "target"
+        } else {
+            "normal"
+        }
+    }
+}
+
+/// DOT snapshots written by [`export_dot_animation`], one per
+/// timestamp.  Rendered in order they show the attack spreading and
+/// retreating.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotAnimation {
+    /// (timestamp, path of its `graph_t{N}.dot`), in time order.
+    pub frames: Vec<(usize, PathBuf)>,
+}
+
+impl DotAnimation {
+    /// Shell commands rendering frame `i` to `frame_{i:04}.png` next to
+    /// its DOT file, one `dot` call per line.  The numbering has no
+    /// gaps, so the PNGs can be stitched together directly, e.g. with
+    /// `convert -delay 100 frame_*.png attack.gif`.
+    pub fn png_commands(&self) -> String {
+        self.frames
+            .iter()
+            .zip(self.png_paths())
+            .map(|((_, dot_path), png_path)| {
+                format!(
+                    "dot -Tpng '{}' -o '{}'\n",
+                    dot_path.display(),
+                    png_path.display()
+                )
+            })
+            .collect()
+    }
+
+    /// Runs Graphviz `dot` like `png_commands` and returns the PNG
+    /// paths.  Fails if `dot` is not installed or rejects a frame.
+    pub fn render_pngs(&self) -> io::Result<Vec<PathBuf>> {
+        let png_paths = self.png_paths();
+        for ((_, dot_path), png_path) in self.frames.iter().zip(&png_paths) {
+            let status = Command::new("dot")
+                .arg("-Tpng")
+                .arg(dot_path)
+                .arg("-o")
+                .arg(png_path)
+                .status()?;
+            if !status.success() {
+                return Err(io::Error::other(format!(
+                    "dot failed to render {}: {status}",
+                    dot_path.display()
+                )));
+            }
+        }
+        Ok(png_paths)
+    }
+
+    fn png_paths(&self) -> Vec<PathBuf> {
+        self.frames
+            .iter()
+            .enumerate()
+            .map(|(index, (_, dot_path))| dot_path.with_file_name(format!("frame_{index:04}.png")))
+            .collect()
+    }
+}
+
+/// Loads `scenario` at time 0 and applies each batch of
+/// `changes_over_time` at its timestamp, as `run_scenario_with_log`
+/// does.  Once the graph has settled at a timestamp, the view of
+/// `attacker_id` (see `AttackGraphState::from_facts`) is written to
+/// `directory/graph_t{N}.dot`.  `directory` is created if missing.
+///
+/// # Panics
+///
+/// Panics if the timestamps of `changes_over_time` decrease.
+pub fn export_dot_animation<P: AsRef<Path>>(
+    directory: P,
+    scenario: &BaseFacts,
+    changes_over_time: &[(usize, Vec<FactUpdate>)],
+    attacker_id: &str,
+    target_node: &str,
+) -> io::Result<DotAnimation> {
+    let initial_updates = scenario.insert_updates();
+    let changes_over_time = changes_over_time.to_vec();
+    let attacker = attacker_id.to_string();
+    let target = target_node.to_string();
+
+    let states = timely::execute_directly(move |worker| {
+        // Flushes happen only at the requested timestamps.
+        let config = BatchDriverConfig {
+            batch_size: usize::MAX,
+            max_batch_delay: None,
+        };
+        let mut driver = BatchDriver::new(worker, config);
+        let mut states = Vec::new();
+        let mut settle = |driver: &mut BatchDriver, worker: &mut _, next_time: Option<usize>| {
+            let settled_time = driver.current_time();
+            match next_time {
+                Some(time) => driver.flush_at(worker, time),
+                None => driver.flush_now(worker),
+            }
+            let state = AttackGraphState::from_facts(
+                driver.base_facts(),
+                &driver.current_derived_facts(),
+                &attacker,
+                &target,
+            );
+            states.push((settled_time, state));
+        };
+
+        driver.apply_updates(worker, &initial_updates);
+        for (timestamp, updates) in &changes_over_time {
+            if *timestamp != driver.current_time() {
+                settle(&mut driver, worker, Some(*timestamp));
+            }
+            driver.apply_updates(worker, updates);
+        }
+        settle(&mut driver, worker, None);
+        states
+    });
+
+    fs::create_dir_all(&directory)?;
+    let mut animation = DotAnimation::default();
+    for (time, state) in states {
+        let path = directory.as_ref().join(format!("graph_t{time}.dot"));
+        state.export_to_dot(&path, &format!("Attack graph at t={time}"))?;
+        animation.frames.push((time, path));
+    }
+    Ok(animation)
+}
+
+// Escapes the five XML special characters for use in attribute values
+// and text content.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// One exploitation step: code execution on `source_host` was used to
+/// exploit `vulnerability_id` on `destination_host`.
+/// Local escalations have the same source and destination and no
+/// service.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct ExploitStep {
+    source_host: HostIdentifier,
+    destination_host: HostIdentifier,
+    service_name: Option<ServiceName>,
+    vulnerability_id: String,
+}
+
+/// Exports the attack graph as a STIX 2.1 bundle.
+///
+/// - every attacker becomes a `threat-actor`;
+/// - every compromised host becomes an `infrastructure` object;
+/// - every exploited vulnerability becomes a `vulnerability` object,
+///   with a `cve` external reference when its id looks like a CVE;
+/// - an attacker `uses` each host it compromised, and a compromised
+///   host `exploits` each vulnerability it was used to exploit.
+///
+/// Object ids are UUIDv5s derived from the object's content, so
+/// exporting the same graph twice yields the same ids.
+pub fn export_to_stix(facts: &BaseFacts, derived: &DerivedFacts) -> Value {
+    let timestamp = stix_timestamp(SystemTime::now());
+    let steps = exploit_steps(facts, derived);
+
+    let attackers: BTreeSet<_> = derived
+        .code_executions
+        .iter()
+        .map(|execution| execution.attacker_id.clone())
+        .collect();
+    let compromised: BTreeSet<_> = derived
+        .code_executions
+        .iter()
+        .map(|execution| {
+            (
+                execution.attacker_id.clone(),
+                execution.compromised_host.clone(),
+            )
+        })
+        .collect();
+    let hosts: BTreeSet<_> = compromised.iter().map(|(_, host)| host.clone()).collect();
+    let vulnerabilities: BTreeSet<_> = steps
+        .iter()
+        .map(|step| step.vulnerability_id.clone())
+        .collect();
+
+    let mut objects = Vec::new();
+
+    for attacker in &attackers {
+        objects.push(json!({
+            "type": "threat-actor",
+            "spec_version": "2.1",
+            "id": stix_id("threat-actor", attacker),
+            "created": timestamp,
+            "modified": timestamp,
+            "name": attacker,
+        }));
+    }
+
+    for host in &hosts {
+        objects.push(json!({
+            "type": "infrastructure",
+            "spec_version": "2.1",
+            "id": stix_id("infrastructure", host),
+            "created": timestamp,
+            "modified": timestamp,
+            "name": host,
+            "description": "Host on which an attacker can execute code.",
+        }));
+    }
+
+    for vulnerability in &vulnerabilities {
+        let mut object = json!({
+            "type": "vulnerability",
+            "spec_version": "2.1",
+            "id": stix_id("vulnerability", vulnerability),
+            "created": timestamp,
+            "modified": timestamp,
+            "name": vulnerability,
+        });
+        if vulnerability.starts_with("CVE-") {
+            object["external_references"] = json!([{
+                "source_name": "cve",
+                "external_id": vulnerability,
+            }]);
+        }
+        objects.push(object);
+    }
+
+    for (attacker, host) in &compromised {
+        objects.push(stix_relationship(
+            &timestamp,
+            "uses",
+            &stix_id("threat-actor", attacker),
+            &stix_id("infrastructure", host),
+            &format!("uses|{attacker}|{host}"),
+            format!("{attacker} executes code on {host}."),
+        ));
+    }
+
+    for step in &steps {
+        let description = match &step.service_name {
+            Some(service) => format!(
+                "{} exploits {} on {} over {}.",
+                step.source_host, step.vulnerability_id, step.destination_host, service
+            ),
+            None => format!(
+                "{} exploits local vulnerability {}.",
+                step.source_host, step.vulnerability_id
+            ),
+        };
+        objects.push(stix_relationship(
+            &timestamp,
+            "exploits",
+            &stix_id("infrastructure", &step.source_host),
+            &stix_id("vulnerability", &step.vulnerability_id),
+            &format!(
+                "exploits|{}|{}|{}|{}",
+                step.source_host,
+                step.destination_host,
+                step.service_name.as_deref().unwrap_or(""),
+                step.vulnerability_id
+            ),
+            description,
+        ));
+    }
+
+    let bundle_key = objects
+        .iter()
+        .map(|object| object["id"].as_str().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    json!({
+        "type": "bundle",
+        "id": stix_id("bundle", &bundle_key),
+        "objects": objects,
+    })
+}
+
+// Exploitation steps consistent with the derived code executions.  An
+// attacker with code execution on a host can reach a vulnerable service
+// through effective access (remote step) or exploit a local
+// vulnerability on the same host (escalation step).
+fn exploit_steps(facts: &BaseFacts, derived: &DerivedFacts) -> BTreeSet<ExploitStep> {
+    let has_execution = |attacker: &str, host: &str, privilege| {
+        derived.code_executions.contains(&AttackerCodeExecution {
+            attacker_id: attacker.to_string(),
+            compromised_host: host.to_string(),
+            obtained_privilege: privilege,
+        })
+    };
+
+    let mut steps = BTreeSet::new();
+    for execution in &derived.code_executions {
+        for access in derived
+            .effective_network_access
+            .iter()
+            .filter(|access| access.source_host == execution.compromised_host)
+        {
+            for vulnerability in facts.vulnerabilities.iter().filter(|vulnerability| {
+                vulnerability.host_name == access.destination_host
+                    && vulnerability.affected_service == access.service_name
+                    && vulnerability.port == access.port
+            }) {
+                if has_execution(
+                    &execution.attacker_id,
+                    &vulnerability.host_name,
+                    vulnerability.privilege_gained_on_exploit.clone(),
+                ) {
+                    steps.insert(ExploitStep {
+                        source_host: access.source_host.clone(),
+                        destination_host: access.destination_host.clone(),
+                        service_name: Some(access.service_name.clone()),
+                        vulnerability_id: vulnerability.vulnerability_id.clone(),
+                    });
+                }
+            }
+        }
+
+        for vulnerability in facts
+            .local_vulnerabilities
+            .iter()
+            .filter(|vulnerability| vulnerability.host_name == execution.compromised_host)
+        {
+            if has_execution(
+                &execution.attacker_id,
+                &vulnerability.host_name,
+                vulnerability.privilege_gained_on_exploit.clone(),
+            ) {
+                steps.insert(ExploitStep {
+                    source_host: vulnerability.host_name.clone(),
+                    destination_host: vulnerability.host_name.clone(),
+                    service_name: None,
+                    vulnerability_id: vulnerability.vulnerability_id.clone(),
+                });
+            }
+        }
+    }
+    steps
+}
+
+fn stix_relationship(
+    timestamp: &str,
+    relationship_type: &str,
+    source_ref: &str,
+    target_ref: &str,
+    key: &str,
+    description: String,
+) -> Value {
+    json!({
+        "type": "relationship",
+        "spec_version": "2.1",
+        "id": stix_id("relationship", key),
+        "created": timestamp,
+        "modified": timestamp,
+        "relationship_type": relationship_type,
+        "source_ref": source_ref,
+        "target_ref": target_ref,
+        "description": description,
+    })
+}
+
+// `<type>--<uuid>` with a name-based UUID, so ids are stable across
+// exports of the same graph.
+fn stix_id(object_type: &str, key: &str) -> String {
+    let name = format!("dynamic-attack-graphs:{object_type}:{key}");
+    format!(
+        "{object_type}--{}",
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes())
+    )
+}
+
+// RFC 3339 UTC timestamp with millisecond precision, as STIX requires.
+fn stix_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let days = (seconds / 86_400) as i64;
+    let seconds_of_day = seconds % 86_400;
+
+    // Days since 1970-01-01 to a proleptic Gregorian date.
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        seconds_of_day / 3_600,
+        (seconds_of_day / 60) % 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::engine::evaluate_base_facts;
+    use crate::schema::{
+        AttackerStartingPosition, LocalVulnerabilityRecord, NetworkAccessRule, PrivilegeLevel,
+        VulnerabilityRecord,
+    };
+
+    fn three_host_facts() -> BaseFacts {
+        BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("web01", "CVE-2024-1234", "http", PrivilegeLevel::User),
+                VulnerabilityRecord::new("db01", "CVE-2024-5678", "mysql", PrivilegeLevel::Root),
+            ],
+            local_vulnerabilities: vec![LocalVulnerabilityRecord::new(
+                "web01",
+                "LOCAL-SUDO",
+                PrivilegeLevel::Root,
+            )],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("internet", "web01", "http"),
+                NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+            ],
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            ..BaseFacts::default()
+        }
+    }
+
+    fn objects_of_type<'a>(bundle: &'a Value, object_type: &str) -> Vec<&'a Value> {
+        bundle["objects"]
+            .as_array()
+            .expect("bundle objects should be an array")
+            .iter()
+            .filter(|object| object["type"] == object_type)
+            .collect()
+    }
+
+    fn is_well_formed_id(id: &str, object_type: &str) -> bool {
+        id.strip_prefix(object_type)
+            .and_then(|rest| rest.strip_prefix("--"))
+            .is_some_and(|uuid| Uuid::parse_str(uuid).is_ok())
+    }
+
+    #[test]
+    fn stix_bundle_has_expected_objects_and_well_formed_ids() {
+        let facts = three_host_facts();
+        let bundle = export_to_stix(&facts, &evaluate_base_facts(&facts));
+
+        assert_eq!(bundle["type"], "bundle");
+        assert!(is_well_formed_id(bundle["id"].as_str().unwrap(), "bundle"));
+
+        assert_eq!(objects_of_type(&bundle, "threat-actor").len(), 1);
+        // internet (the starting host), web01 and db01.
+        assert_eq!(objects_of_type(&bundle, "infrastructure").len(), 3);
+        // Two remote CVEs and one local escalation.
+        assert_eq!(objects_of_type(&bundle, "vulnerability").len(), 3);
+
+        let relationships = objects_of_type(&bundle, "relationship");
+        let count = |relationship_type: &str| {
+            relationships
+                .iter()
+                .filter(|relationship| relationship["relationship_type"] == relationship_type)
+                .count()
+        };
+        assert_eq!(count("uses"), 3);
+        assert_eq!(count("exploits"), 3);
+
+        for object in bundle["objects"].as_array().unwrap() {
+            let object_type = object["type"].as_str().unwrap();
+            assert!(is_well_formed_id(
+                object["id"].as_str().unwrap(),
+                object_type
+            ));
+            assert_eq!(object["spec_version"], "2.1");
+        }
+
+        let object_ids: BTreeSet<_> = bundle["objects"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|object| object["id"].as_str().unwrap())
+            .collect();
+        for relationship in relationships {
+            assert!(object_ids.contains(relationship["source_ref"].as_str().unwrap()));
+            assert!(object_ids.contains(relationship["target_ref"].as_str().unwrap()));
+        }
+    }
+
+    // attacker -> node_0 -> node_1 -> target, with node_1 patched.
+    fn patched_chain_state() -> AttackGraphState {
+        let hosts = ["attacker", "node_0", "node_1", "target"];
+        AttackGraphState {
+            nodes: hosts.iter().map(|host| host.to_string()).collect(),
+            edges: hosts
+                .windows(2)
+                .map(|pair| (pair[0].to_string(), pair[1].to_string(), "ssh".to_string()))
+                .collect(),
+            compromised: ["attacker", "node_0"]
+                .iter()
+                .map(|host| host.to_string())
+                .collect(),
+            attacker_start: "attacker".to_string(),
+            target_node: "target".to_string(),
+            ..AttackGraphState::default()
+        }
+    }
+
+    fn node_line<'a>(dot: &'a str, node: &str) -> &'a str {
+        let prefix = format!("    \"{node}\" [");
+        dot.lines()
+            .find(|line| line.starts_with(&prefix))
+            .expect("every node should have a style line")
+    }
+
+    #[test]
+    fn dot_nodes_are_coloured_by_the_privilege_held() {
+        let mut state = patched_chain_state();
+        state.nodes.insert("node_2".to_string());
+        for (host, privilege) in [
+            ("node_0", PrivilegeLevel::User),
+            ("node_1", PrivilegeLevel::User),
+            ("node_1", PrivilegeLevel::Root),
+            ("target", PrivilegeLevel::Root),
+        ] {
+            state.record_code_execution(&AttackerCodeExecution {
+                attacker_id: "eve".to_string(),
+                compromised_host: host.to_string(),
+                obtained_privilege: privilege,
+            });
+        }
+        state.compromised.insert("node_2".to_string());
+
+        let dot = state.to_dot("privileges");
+
+        assert_eq!(
+            node_line(&dot, "node_0"),
+            "    \"node_0\" [fillcolor=yellow, label=\"node_0 [COMPROMISED] (user)\"];"
+        );
+        assert_eq!(
+            node_line(&dot, "node_1"),
+            "    \"node_1\" [fillcolor=red, label=\"node_1 [COMPROMISED] (root)\"];"
+        );
+        // Compromised without a known privilege.
+        assert!(node_line(&dot, "node_2").contains("fillcolor=orange"));
+        assert!(node_line(&dot, "target").contains("[TARGET - COMPROMISED!] (root)"));
+    }
+
+    #[test]
+    fn json_export_round_trips_the_compromised_set() {
+        let state = patched_chain_state();
+        let path = std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_export_test_{}.json",
+            std::process::id()
+        ));
+
+        state.export_to_json(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let json: Value = serde_json::from_str(&contents).unwrap();
+
+        let compromised: BTreeSet<String> =
+            serde_json::from_value(json["compromised"].clone()).unwrap();
+        assert_eq!(compromised, state.compromised);
+        assert_eq!(json["attacker_start"], "attacker");
+        assert_eq!(json["target"], "target");
+
+        let on_attack_path: Vec<(&str, &str)> = json["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|edge| edge["on_attack_path"] == true)
+            .map(|edge| (edge["src"].as_str().unwrap(), edge["dst"].as_str().unwrap()))
+            .collect();
+        assert_eq!(on_attack_path, vec![("attacker", "node_0")]);
+    }
+
+    #[test]
+    fn dot_animation_has_one_frame_per_timestamp() {
+        let facts = three_host_facts();
+        let web_vulnerability = facts.vulnerabilities[0].clone();
+        let directory = std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_animation_test_{}",
+            std::process::id()
+        ));
+
+        let animation = export_dot_animation(
+            &directory,
+            &facts,
+            &[
+                (
+                    1,
+                    vec![FactUpdate::RemoveVulnerability(web_vulnerability.clone())],
+                ),
+                (2, vec![FactUpdate::InsertVulnerability(web_vulnerability)]),
+            ],
+            "eve",
+            "db01",
+        )
+        .unwrap();
+        let frames: Vec<String> = animation
+            .frames
+            .iter()
+            .map(|(_, path)| std::fs::read_to_string(path).unwrap())
+            .collect();
+        let commands = animation.png_commands();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let times: Vec<usize> = animation.frames.iter().map(|(time, _)| *time).collect();
+        assert_eq!(times, vec![0, 1, 2]);
+        assert!(animation.frames[1].1.ends_with("graph_t1.dot"));
+        // The patch cuts the attacker off at web01 and the re-inserted
+        // vulnerability lets it back in.
+        assert!(frames[0].contains("TARGET - COMPROMISED!"));
+        assert!(frames[1].contains("TARGET - SAFE"));
+        assert!(!frames[1].contains("[COMPROMISED]"));
+        assert!(frames[2].contains("TARGET - COMPROMISED!"));
+
+        assert_eq!(commands.lines().count(), 3);
+        assert!(commands
+            .lines()
+            .nth(2)
+            .unwrap()
+            .ends_with("frame_0002.png'"));
+    }
+
+    #[test]
+    fn cytoscape_export_has_one_element_per_node_and_edge() {
+        let state = patched_chain_state();
+        let path = std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_cytoscape_test_{}.json",
+            std::process::id()
+        ));
+
+        state.export_to_cytoscape_json(&path).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let json: Value = serde_json::from_str(&contents).expect("export should be valid JSON");
+
+        let nodes = json["elements"]["nodes"].as_array().unwrap();
+        let edges = json["elements"]["edges"].as_array().unwrap();
+        assert_eq!(nodes.len(), state.nodes.len());
+        assert_eq!(edges.len(), state.edges.len());
+
+        let node = |id: &str| {
+            &nodes
+                .iter()
+                .find(|node| node["data"]["id"] == id)
+                .expect("every host should be a node")["data"]
+        };
+        assert_eq!(node("attacker")["role"], "attacker");
+        assert_eq!(node("target")["role"], "target");
+        assert_eq!(node("node_0")["role"], "normal");
+        assert_eq!(node("node_0")["compromised"], true);
+        assert_eq!(node("node_1")["compromised"], false);
+
+        let attack_path: Vec<(&str, &str)> = edges
+            .iter()
+            .map(|edge| &edge["data"])
+            .filter(|data| data["attack_path"] == true)
+            .map(|data| {
+                (
+                    data["source"].as_str().unwrap(),
+                    data["target"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(attack_path, vec![("attacker", "node_0")]);
+        assert!(edges.iter().all(|edge| edge["data"]["service"] == "ssh"));
+    }
+
+    #[test]
+    fn graphml_is_well_formed_and_escapes_node_ids() {
+        let mut state = patched_chain_state();
+        state.nodes.insert("db \"prod\" <&>".to_string());
+        state.edges.insert((
+            "node_0".to_string(),
+            "db \"prod\" <&>".to_string(),
+            "postgres".to_string(),
+        ));
+        state.compromised.insert("db \"prod\" <&>".to_string());
+
+        let graphml = state.to_graphml();
+        let document = roxmltree::Document::parse(&graphml).expect("GraphML should be well-formed");
+
+        let node = document
+            .descendants()
+            .find(|node| {
+                node.has_tag_name("node") && node.attribute("id") == Some("db \"prod\" <&>")
+            })
+            .expect("special-character node id should survive escaping");
+        let data = |key: &str| {
+            node.children()
+                .find(|child| child.attribute("key") == Some(key))
+                .and_then(|child| child.text())
+        };
+        assert_eq!(data("compromised"), Some("true"));
+        assert_eq!(data("role"), Some("normal"));
+
+        let attack_path_edges = document
+            .descendants()
+            .filter(|node| node.has_tag_name("edge"))
+            .filter(|edge| {
+                edge.children().any(|child| {
+                    child.attribute("key") == Some("on_attack_path") && child.text() == Some("true")
+                })
+            })
+            .count();
+        assert_eq!(attack_path_edges, 2);
+    }
+
+    #[test]
+    fn stix_timestamp_is_rfc3339_with_milliseconds() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_251_199_123);
+
+        assert_eq!(stix_timestamp(time), "2024-02-29T23:59:59.123Z");
+    }
+}
@@ -0,0 +1,104 @@
+// String interning
+// ----------------
+// Host, service and attacker names are `String`s throughout the
+// schema, so every `map` inside a fixed point clones and hashes them.
+// `StringInterner` assigns each name a dense `u32` id, letting the
+// recursive part of a dataflow run over integer keys while its inputs
+// and outputs stay in terms of `String`.
+//
+// The execCode rules in `rules.rs` intern through `global()`, one
+// interner shared by every dataflow and every worker of the process,
+// so a name maps to the same id wherever it is exchanged.  Names are
+// added as the input collections first carry them and are never
+// removed.  The ids mean nothing to another process, so dataflows over
+// them run on the worker threads of a single process.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, PoisonError, RwLock, RwLockReadGuard};
+
+/// Dense id of an interned name.
+pub type NameId = u32;
+
+#[derive(Debug, Default)]
+struct InternTable {
+    ids: HashMap<String, NameId>,
+    names: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct StringInterner {
+    table: RwLock<InternTable>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The interner shared by every dataflow of this process.
+    pub fn global() -> &'static StringInterner {
+        static GLOBAL: OnceLock<StringInterner> = OnceLock::new();
+        GLOBAL.get_or_init(StringInterner::new)
+    }
+
+    /// The id of `name`, assigning the next free one if it is new.
+    pub fn intern(&self, name: &str) -> NameId {
+        if let Some(&id) = self.read_table().ids.get(name) {
+            return id;
+        }
+
+        let mut table = self.table.write().unwrap_or_else(PoisonError::into_inner);
+        // Another worker may have added the name since the read.
+        if let Some(&id) = table.ids.get(name) {
+            return id;
+        }
+        let id = NameId::try_from(table.names.len()).expect("more than u32::MAX interned names");
+        table.names.push(name.to_string());
+        table.ids.insert(name.to_string(), id);
+        id
+    }
+
+    /// The name interned as `id`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was not returned by this interner.
+    pub fn resolve(&self, id: NameId) -> String {
+        self.read_table()
+            .names
+            .get(id as usize)
+            .unwrap_or_else(|| panic!("id {id} was not interned"))
+            .clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.read_table().names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A panic while the table is locked cannot leave it half-updated:
+    // a name and its id are added together after the id is checked.
+    fn read_table(&self) -> RwLockReadGuard<'_, InternTable> {
+        self.table.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_the_same_name_twice_returns_the_same_id() {
+        let interner = StringInterner::new();
+        let web = interner.intern("web01");
+        let db = interner.intern("db01");
+
+        assert_ne!(web, db);
+        assert_eq!(interner.intern("web01"), web);
+        assert_eq!(interner.resolve(db), "db01");
+        assert_eq!(interner.len(), 2);
+    }
+}
@@ -0,0 +1,270 @@
+// CSV import and export
+// ---------------------
+// Asset inventories usually come out of spreadsheets, so network
+// topology and vulnerabilities can be loaded from CSV files with a
+// header row:
+//
+//   network.csv:          src,dst,service
+//   vulnerabilities.csv:  host,cve,service,privilege
+//
+// Neither file carries a port; every record uses the service's
+// well-known port.  Privileges are `none`, `user` or `root` in any
+// case.  Errors carry the 1-based line number of the offending row,
+// counting the header as line 1.
+
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{NetworkAccess, PrivilegeLevel, Vulnerability};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    Io { path: PathBuf, message: String },
+    MalformedRow { line_number: u64, message: String },
+    InvalidPrivilege { line_number: u64, privilege: String },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io { path, message } => {
+                write!(formatter, "failed to access {}: {message}", path.display())
+            }
+            CsvError::MalformedRow {
+                line_number,
+                message,
+            } => write!(formatter, "line {line_number}: {message}"),
+            CsvError::InvalidPrivilege {
+                line_number,
+                privilege,
+            } => write!(
+                formatter,
+                "line {line_number}: invalid privilege value: {privilege}"
+            ),
+        }
+    }
+}
+
+impl Error for CsvError {}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct NetworkRow {
+    src: String,
+    dst: String,
+    service: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct VulnerabilityRow {
+    host: String,
+    cve: String,
+    service: String,
+    privilege: String,
+}
+
+/// Loads `src,dst,service` rows as network access rules.
+pub fn load_network_csv(path: &Path) -> Result<Vec<NetworkAccess>, CsvError> {
+    read_rows(path, |row: NetworkRow, _line_number| {
+        Ok(NetworkAccess::new_default_port(
+            &row.src,
+            &row.dst,
+            &row.service,
+        ))
+    })
+}
+
+/// Loads `host,cve,service,privilege` rows as vulnerabilities.
+pub fn load_vulnerabilities_csv(path: &Path) -> Result<Vec<Vulnerability>, CsvError> {
+    read_rows(path, |row: VulnerabilityRow, line_number| {
+        let privilege =
+            row.privilege
+                .parse::<PrivilegeLevel>()
+                .map_err(|_| CsvError::InvalidPrivilege {
+                    line_number,
+                    privilege: row.privilege.clone(),
+                })?;
+        Ok(Vulnerability::new(
+            &row.host,
+            &row.cve,
+            &row.service,
+            privilege,
+        ))
+    })
+}
+
+/// Writes network access rules in the format `load_network_csv` reads.
+/// Ports are not written.
+pub fn write_network_csv(path: &Path, network_access: &[NetworkAccess]) -> Result<(), CsvError> {
+    write_rows(
+        path,
+        network_access.iter().map(|access| NetworkRow {
+            src: access.source_host.clone(),
+            dst: access.destination_host.clone(),
+            service: access.service_name.clone(),
+        }),
+    )
+}
+
+/// Writes vulnerabilities in the format `load_vulnerabilities_csv`
+/// reads.  Ports are not written.
+pub fn write_vulnerabilities_csv(
+    path: &Path,
+    vulnerabilities: &[Vulnerability],
+) -> Result<(), CsvError> {
+    write_rows(
+        path,
+        vulnerabilities
+            .iter()
+            .map(|vulnerability| VulnerabilityRow {
+                host: vulnerability.host_name.clone(),
+                cve: vulnerability.vulnerability_id.clone(),
+                service: vulnerability.affected_service.clone(),
+                privilege: vulnerability.privilege_gained_on_exploit.to_string(),
+            }),
+    )
+}
+
+fn read_rows<Row, Record, F>(path: &Path, mut convert: F) -> Result<Vec<Record>, CsvError>
+where
+    Row: for<'de> Deserialize<'de>,
+    F: FnMut(Row, u64) -> Result<Record, CsvError>,
+{
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|error| io_error(path, error))?;
+    let headers = reader
+        .headers()
+        .map_err(|error| malformed_row(1, error))?
+        .clone();
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(|error| {
+            let line_number = error.position().map_or(0, |position| position.line());
+            malformed_row(line_number, error)
+        })?;
+        let line_number = row.position().map_or(0, |position| position.line());
+        let row: Row = row
+            .deserialize(Some(&headers))
+            .map_err(|error| malformed_row(line_number, error))?;
+        records.push(convert(row, line_number)?);
+    }
+    Ok(records)
+}
+
+fn write_rows<Row: Serialize>(
+    path: &Path,
+    rows: impl IntoIterator<Item = Row>,
+) -> Result<(), CsvError> {
+    let mut writer = csv::Writer::from_path(path).map_err(|error| io_error(path, error))?;
+    for row in rows {
+        writer
+            .serialize(row)
+            .map_err(|error| io_error(path, error))?;
+    }
+    writer.flush().map_err(|error| CsvError::Io {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })
+}
+
+fn io_error(path: &Path, error: csv::Error) -> CsvError {
+    CsvError::Io {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }
+}
+
+fn malformed_row(line_number: u64, error: csv::Error) -> CsvError {
+    CsvError::MalformedRow {
+        line_number,
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    fn temp_csv(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_io_test_{name}_{}.csv",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn loads_network_fixture_and_round_trips() {
+        let network_access =
+            load_network_csv(&fixture("network.csv")).expect("network fixture should load");
+        assert_eq!(
+            network_access,
+            vec![
+                NetworkAccess::new_default_port("internet", "web01", "https"),
+                NetworkAccess::new_default_port("web01", "app01", "http"),
+                NetworkAccess::new_default_port("app01", "db01", "postgres"),
+            ]
+        );
+
+        let path = temp_csv("network");
+        write_network_csv(&path, &network_access).expect("network csv should be writable");
+        let reloaded = load_network_csv(&path).expect("written network csv should load");
+        std::fs::remove_file(&path).expect("test csv should be removable");
+
+        assert_eq!(reloaded, network_access);
+    }
+
+    #[test]
+    fn loads_vulnerability_fixture_and_round_trips() {
+        let vulnerabilities = load_vulnerabilities_csv(&fixture("vulnerabilities.csv"))
+            .expect("vulnerability fixture should load");
+        assert_eq!(
+            vulnerabilities,
+            vec![
+                Vulnerability::new("web01", "CVE-2024-0001", "https", PrivilegeLevel::User),
+                Vulnerability::new("app01", "CVE-2024-0002", "http", PrivilegeLevel::None),
+                Vulnerability::new("db01", "CVE-2024-0003", "postgres", PrivilegeLevel::Root),
+            ]
+        );
+
+        let path = temp_csv("vulnerabilities");
+        write_vulnerabilities_csv(&path, &vulnerabilities)
+            .expect("vulnerability csv should be writable");
+        let reloaded =
+            load_vulnerabilities_csv(&path).expect("written vulnerability csv should load");
+        std::fs::remove_file(&path).expect("test csv should be removable");
+
+        assert_eq!(reloaded, vulnerabilities);
+    }
+
+    #[test]
+    fn invalid_privilege_reports_its_line() {
+        let path = temp_csv("bad_privilege");
+        std::fs::write(
+            &path,
+            "host,cve,service,privilege\nweb01,CVE-1,https,user\ndb01,CVE-2,postgres,admin\n",
+        )
+        .expect("test csv should be writable");
+
+        let result = load_vulnerabilities_csv(&path);
+        std::fs::remove_file(&path).expect("test csv should be removable");
+
+        assert_eq!(
+            result,
+            Err(CsvError::InvalidPrivilege {
+                line_number: 3,
+                privilege: "admin".to_string(),
+            })
+        );
+    }
+}
@@ -1,22 +1,49 @@
 // Dynamic Attack Graphs Library
 // Types and operators for building attack graphs with differential dataflow
 
+#[macro_use]
+mod logging;
+
 pub mod benchmarks;
+pub mod concurrent;
+pub mod driver;
 pub mod engine;
 pub mod engines;
+pub mod error;
+pub mod export;
+pub mod interner;
+pub mod io;
 pub mod metrics;
+pub mod mulval;
 pub mod naive;
+pub mod nvd;
 pub mod parser;
+pub mod patching;
 pub mod provenance;
+pub mod query;
 pub mod rules;
+pub mod scenario;
 pub mod schema;
+pub mod session;
 
 pub use benchmarks::*;
+pub use concurrent::*;
+pub use driver::*;
 pub use engine::*;
 pub use engines::*;
+pub use error::*;
+pub use export::*;
+pub use interner::*;
+pub use io::*;
 pub use metrics::*;
+pub use mulval::*;
 pub use naive::*;
+pub use nvd::*;
 pub use parser::*;
+pub use patching::*;
 pub use provenance::*;
+pub use query::*;
 pub use rules::*;
+pub use scenario::*;
 pub use schema::*;
+pub use session::*;
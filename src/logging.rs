@@ -0,0 +1,27 @@
+// Logging
+// -------
+// The library never prints: diagnostics go through these macros, so
+// the embedding application decides whether and where they appear.
+// By default they are `log` records.  With the `tracing` feature they
+// are `tracing` events instead, which a `tracing` subscriber picks up
+// and which still fall back to `log` when no subscriber is installed.
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! info {
+    ($($argument:tt)*) => { ::log::info!($($argument)*) };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! info {
+    ($($argument:tt)*) => { ::tracing::info!($($argument)*) };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug {
+    ($($argument:tt)*) => { ::log::debug!($($argument)*) };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! debug {
+    ($($argument:tt)*) => { ::tracing::debug!($($argument)*) };
+}
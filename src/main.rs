@@ -1,19 +1,23 @@
 // Main entry point for the attack graph demonstration
 // Shows how the attack graph updates incrementally when facts change
+//
+// Arguments after `--` are handed to timely, e.g.
+//
+//   cargo run -- --scenario net.json --goal-attacker eve -- -w 2
 
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::time::Instant;
 
-use clap::Parser;
-use differential_dataflow::input::{Input, InputSession};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 use dynamic_attack_graphs::{
-    build_attack_graph, build_attack_graph_with_local_vulnerabilities, parse_facts_file,
-    parse_update_file, AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord, InputFact,
-    InputScenario, InputUpdate, LocalVulnerabilityRecord, NetworkAccessRule, PrivilegeLevel,
+    parse_facts_file, parse_update_file, run_timeline_from_args, AttackGraphError,
+    AttackerStartingPosition, AttackerTargetGoal, BaseFacts, FactUpdate, FirewallRuleRecord,
+    InputUpdate, NetworkAccessRule, PrivilegeLevel, Scenario, ScenarioError, TimeStepOutput,
     VulnerabilityRecord,
 };
-use timely::dataflow::operators::probe::Handle;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -21,10 +25,16 @@ use timely::dataflow::operators::probe::Handle;
     about = "Dynamic attack graph computation with Differential Dataflow"
 )]
 struct Cli {
-    /// Load base facts from a MulVAL-like .facts scenario file.
+    /// Load base facts from a scenario file: a JSON scenario when the
+    /// extension is .json, a MulVAL-like .facts file otherwise.  Without
+    /// it the built-in 4-host demo runs.
     #[arg(long)]
     scenario: Option<PathBuf>,
 
+    /// Only pursue the goals of this attacker.
+    #[arg(long, value_name = "ATTACKER")]
+    goal_attacker: Option<String>,
+
     /// Apply update facts after the scenario has been loaded.
     #[arg(long)]
     update: Option<PathBuf>,
@@ -36,28 +46,84 @@ struct Cli {
     /// Export the computed graph as Graphviz DOT. Reserved for future work.
     #[arg(long)]
     export_dot: Option<PathBuf>,
+
+    /// Arguments passed through to timely, e.g. `-- -w 2`.
+    #[arg(last = true, value_name = "TIMELY_ARGS")]
+    timely_args: Vec<String>,
+}
+
+/// One step of the phased demo: `updates` are applied together at the
+/// next timestamp.
+struct DemoPhase {
+    title: &'static str,
+    details: Vec<&'static str>,
+    updates: Vec<FactUpdate>,
+    outcome: &'static str,
+}
+
+/// What the phased demo runs: the facts loaded in phase 1 and the
+/// phases applied on top of them.
+struct Demo {
+    subtitle: &'static str,
+    description: Vec<String>,
+    facts: BaseFacts,
+    phases: Vec<DemoPhase>,
 }
 
 fn main() {
     let cli = Cli::parse();
+    let goal_attacker = cli.goal_attacker.as_deref();
 
-    if cli.scenario.is_some() {
-        if let Err(error) = run_fact_file_scenario(&cli) {
-            eprintln!("error: {error}");
-            process::exit(1);
+    let result = match cli.scenario.as_deref() {
+        Some(path) if is_json(path) => {
+            let demo =
+                json_scenario_demo(path, goal_attacker).unwrap_or_else(|error| usage_error(error));
+            run_phased_demo(&cli, demo)
+        }
+        Some(_) => run_fact_file_scenario(&cli),
+        None => {
+            let mut demo = builtin_demo();
+            retain_goals_of(&mut demo.facts.attacker_goals, goal_attacker);
+            run_phased_demo(&cli, demo)
         }
-        return;
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {error}");
+        process::exit(1);
     }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+// Reports a bad --scenario file the way clap reports bad arguments,
+// with the usage line, and exits.
+fn usage_error(error: impl std::fmt::Display) -> ! {
+    Cli::command()
+        .error(
+            ErrorKind::ValueValidation,
+            format!("invalid value for --scenario: {error}"),
+        )
+        .exit()
+}
 
-    run_hardcoded_demo();
+// Keeps only the goals of `attacker`, if one was given.
+fn retain_goals_of(goals: &mut Vec<AttackerTargetGoal>, attacker: Option<&str>) {
+    if let Some(attacker) = attacker {
+        goals.retain(|goal| goal.attacker_id == attacker);
+    }
 }
 
-fn run_fact_file_scenario(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+fn run_fact_file_scenario(cli: &Cli) -> Result<(), AttackGraphError> {
     let scenario_path = cli
         .scenario
         .as_ref()
         .expect("scenario path should exist when scenario mode is selected");
-    let scenario = parse_facts_file(scenario_path)?;
+    let mut scenario = parse_facts_file(scenario_path).unwrap_or_else(|error| usage_error(error));
+    retain_goals_of(&mut scenario.attacker_goals, cli.goal_attacker.as_deref());
     let updates = match &cli.update {
         Some(path) => parse_update_file(path)?,
         None => Vec::new(),
@@ -93,392 +159,85 @@ fn run_fact_file_scenario(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
     println!("DERIVED FACTS");
     println!("------------------------------------------------------------------------");
 
-    timely::execute_directly(move |worker| {
-        let mut computation_probe = Handle::new();
-
-        let (
-            mut vulnerability_input,
-            mut local_vulnerability_input,
-            mut network_access_input,
-            mut firewall_rules_input,
-            mut attacker_position_input,
-            mut attacker_goal_input,
-        ) = worker.dataflow::<usize, _, _>(|scope| {
-            let (vuln_handle, vulnerability_collection) =
-                scope.new_collection::<VulnerabilityRecord, isize>();
-            let (local_vuln_handle, local_vulnerability_collection) =
-                scope.new_collection::<LocalVulnerabilityRecord, isize>();
-            let (network_handle, network_access_collection) =
-                scope.new_collection::<NetworkAccessRule, isize>();
-            let (firewall_handle, firewall_rules_collection) =
-                scope.new_collection::<FirewallRuleRecord, isize>();
-            let (position_handle, attacker_positions_collection) =
-                scope.new_collection::<AttackerStartingPosition, isize>();
-            let (goal_handle, attacker_goals_collection) =
-                scope.new_collection::<AttackerTargetGoal, isize>();
-
-            let (code_execution_results, machine_ownership_results, goal_reached_results) =
-                build_attack_graph_with_local_vulnerabilities(
-                    &vulnerability_collection,
-                    &local_vulnerability_collection,
-                    &network_access_collection,
-                    &firewall_rules_collection,
-                    &attacker_positions_collection,
-                    &attacker_goals_collection,
-                );
-
-            code_execution_results
-                .inspect(|(data, timestamp, difference)| {
-                    let change_type = if *difference > 0 { "+" } else { "-" };
-                    println!("  [t={}] {} {}", timestamp, change_type, data);
-                })
-                .probe_with(&mut computation_probe);
-
-            machine_ownership_results
-                .inspect(|(data, timestamp, difference)| {
-                    let change_type = if *difference > 0 { "+" } else { "-" };
-                    println!("  [t={}] {} {}", timestamp, change_type, data);
-                })
-                .probe_with(&mut computation_probe);
-
-            goal_reached_results
-                .inspect(|(data, timestamp, difference)| {
-                    let change_type = if *difference > 0 { "+" } else { "-" };
-                    println!(
-                        "  [t={}] {} {} (TARGET COMPROMISED)",
-                        timestamp, change_type, data
-                    );
-                })
-                .probe_with(&mut computation_probe);
-
-            (
-                vuln_handle,
-                local_vuln_handle,
-                network_handle,
-                firewall_handle,
-                position_handle,
-                goal_handle,
-            )
-        });
-
-        let computation_start_time = Instant::now();
-        insert_scenario_facts(
-            &scenario,
-            &mut vulnerability_input,
-            &mut local_vulnerability_input,
-            &mut network_access_input,
-            &mut firewall_rules_input,
-            &mut attacker_position_input,
-            &mut attacker_goal_input,
-        );
-        advance_all_inputs(
-            1,
-            &mut vulnerability_input,
-            &mut local_vulnerability_input,
-            &mut network_access_input,
-            &mut firewall_rules_input,
-            &mut attacker_position_input,
-            &mut attacker_goal_input,
-        );
+    let facts = BaseFacts {
+        vulnerabilities: scenario.vulnerabilities,
+        local_vulnerabilities: scenario.local_vulnerabilities,
+        network_access: scenario.network_access,
+        firewall_rules: scenario.firewall_rules,
+        attacker_positions: scenario.attacker_positions,
+        attacker_goals: scenario.attacker_goals,
+    };
+    let steps: Vec<Vec<FactUpdate>> = if updates.is_empty() {
+        Vec::new()
+    } else {
+        vec![updates
+            .into_iter()
+            .map(|update| match update {
+                InputUpdate::Insert(fact) => FactUpdate::insert(fact),
+                InputUpdate::Remove(fact) => FactUpdate::remove(fact),
+            })
+            .collect()]
+    };
 
-        while computation_probe.less_than(&1) {
-            worker.step();
-        }
+    let computation_start_time = Instant::now();
+    let timeline = run_timeline_from_args(cli.timely_args.clone().into_iter(), &facts, &steps)
+        .map_err(AttackGraphError::ComputationError)?;
+    let computation_time = computation_start_time.elapsed();
 
+    print_step_changes(&TimeStepOutput::default(), &timeline[0]);
+    if let [initial, updated] = timeline.as_slice() {
         println!("------------------------------------------------------------------------");
-        println!(
-            "Initial scenario computation completed in {:?}",
-            computation_start_time.elapsed()
-        );
-
-        if !updates.is_empty() {
-            println!("------------------------------------------------------------------------");
-            println!("APPLYING UPDATE FACTS");
-            println!("------------------------------------------------------------------------");
-
-            let update_start_time = Instant::now();
-            apply_updates(
-                &updates,
-                &mut vulnerability_input,
-                &mut local_vulnerability_input,
-                &mut network_access_input,
-                &mut firewall_rules_input,
-                &mut attacker_position_input,
-                &mut attacker_goal_input,
-            );
-            advance_all_inputs(
-                2,
-                &mut vulnerability_input,
-                &mut local_vulnerability_input,
-                &mut network_access_input,
-                &mut firewall_rules_input,
-                &mut attacker_position_input,
-                &mut attacker_goal_input,
-            );
-
-            while computation_probe.less_than(&2) {
-                worker.step();
-            }
-
-            println!("------------------------------------------------------------------------");
-            println!(
-                "Incremental update completed in {:?}",
-                update_start_time.elapsed()
-            );
-        }
-    });
-
-    Ok(())
-}
-
-fn insert_scenario_facts(
-    scenario: &InputScenario,
-    vulnerability_input: &mut InputSession<usize, VulnerabilityRecord, isize>,
-    local_vulnerability_input: &mut InputSession<usize, LocalVulnerabilityRecord, isize>,
-    network_access_input: &mut InputSession<usize, NetworkAccessRule, isize>,
-    firewall_rules_input: &mut InputSession<usize, FirewallRuleRecord, isize>,
-    attacker_position_input: &mut InputSession<usize, AttackerStartingPosition, isize>,
-    attacker_goal_input: &mut InputSession<usize, AttackerTargetGoal, isize>,
-) {
-    for vulnerability in &scenario.vulnerabilities {
-        vulnerability_input.insert(vulnerability.clone());
-    }
-    for vulnerability in &scenario.local_vulnerabilities {
-        local_vulnerability_input.insert(vulnerability.clone());
-    }
-    for access in &scenario.network_access {
-        network_access_input.insert(access.clone());
-    }
-    for rule in &scenario.firewall_rules {
-        firewall_rules_input.insert(rule.clone());
-    }
-    for position in &scenario.attacker_positions {
-        attacker_position_input.insert(position.clone());
-    }
-    for goal in &scenario.attacker_goals {
-        attacker_goal_input.insert(goal.clone());
+        println!("APPLYING UPDATE FACTS");
+        println!("------------------------------------------------------------------------");
+        print_step_changes(initial, updated);
     }
+    println!("------------------------------------------------------------------------");
+    println!(
+        "Scenario computation and {} update batch(es) completed in {:?}",
+        steps.len(),
+        computation_time
+    );
+    Ok(())
 }
 
-fn apply_updates(
-    updates: &[InputUpdate],
-    vulnerability_input: &mut InputSession<usize, VulnerabilityRecord, isize>,
-    local_vulnerability_input: &mut InputSession<usize, LocalVulnerabilityRecord, isize>,
-    network_access_input: &mut InputSession<usize, NetworkAccessRule, isize>,
-    firewall_rules_input: &mut InputSession<usize, FirewallRuleRecord, isize>,
-    attacker_position_input: &mut InputSession<usize, AttackerStartingPosition, isize>,
-    attacker_goal_input: &mut InputSession<usize, AttackerTargetGoal, isize>,
-) {
-    for update in updates {
-        match update {
-            InputUpdate::Insert(fact) => apply_fact_insert(
-                fact,
-                vulnerability_input,
-                local_vulnerability_input,
-                network_access_input,
-                firewall_rules_input,
-                attacker_position_input,
-                attacker_goal_input,
-            ),
-            InputUpdate::Remove(fact) => apply_fact_remove(
-                fact,
-                vulnerability_input,
-                local_vulnerability_input,
-                network_access_input,
-                firewall_rules_input,
-                attacker_position_input,
-                attacker_goal_input,
+fn json_scenario_demo(path: &Path, goal_attacker: Option<&str>) -> Result<Demo, ScenarioError> {
+    let mut facts = Scenario::from_json_file(path)?.to_base_facts()?;
+    retain_goals_of(&mut facts.attacker_goals, goal_attacker);
+
+    Ok(Demo {
+        subtitle: "JSON Scenario",
+        description: vec![
+            format!("Scenario: {}", path.display()),
+            format!(
+                "  {} vulnerabilities, {} network edges, {} firewall rules, {} attackers, {} goals",
+                facts.vulnerabilities.len(),
+                facts.network_access.len(),
+                facts.firewall_rules.len(),
+                facts.attacker_positions.len(),
+                facts.attacker_goals.len()
             ),
-        }
-    }
-}
-
-fn apply_fact_insert(
-    fact: &InputFact,
-    vulnerability_input: &mut InputSession<usize, VulnerabilityRecord, isize>,
-    local_vulnerability_input: &mut InputSession<usize, LocalVulnerabilityRecord, isize>,
-    network_access_input: &mut InputSession<usize, NetworkAccessRule, isize>,
-    firewall_rules_input: &mut InputSession<usize, FirewallRuleRecord, isize>,
-    attacker_position_input: &mut InputSession<usize, AttackerStartingPosition, isize>,
-    attacker_goal_input: &mut InputSession<usize, AttackerTargetGoal, isize>,
-) {
-    match fact {
-        InputFact::VulExists(vulnerability) => vulnerability_input.insert(vulnerability.clone()),
-        InputFact::LocalVulExists(vulnerability) => {
-            local_vulnerability_input.insert(vulnerability.clone())
-        }
-        InputFact::Hacl(access) => network_access_input.insert(access.clone()),
-        InputFact::FirewallDeny(rule) => firewall_rules_input.insert(rule.clone()),
-        InputFact::AttackerLocated(position) => attacker_position_input.insert(position.clone()),
-        InputFact::AttackGoal(goal) => attacker_goal_input.insert(goal.clone()),
-    }
-}
-
-fn apply_fact_remove(
-    fact: &InputFact,
-    vulnerability_input: &mut InputSession<usize, VulnerabilityRecord, isize>,
-    local_vulnerability_input: &mut InputSession<usize, LocalVulnerabilityRecord, isize>,
-    network_access_input: &mut InputSession<usize, NetworkAccessRule, isize>,
-    firewall_rules_input: &mut InputSession<usize, FirewallRuleRecord, isize>,
-    attacker_position_input: &mut InputSession<usize, AttackerStartingPosition, isize>,
-    attacker_goal_input: &mut InputSession<usize, AttackerTargetGoal, isize>,
-) {
-    match fact {
-        InputFact::VulExists(vulnerability) => vulnerability_input.remove(vulnerability.clone()),
-        InputFact::LocalVulExists(vulnerability) => {
-            local_vulnerability_input.remove(vulnerability.clone())
-        }
-        InputFact::Hacl(access) => network_access_input.remove(access.clone()),
-        InputFact::FirewallDeny(rule) => firewall_rules_input.remove(rule.clone()),
-        InputFact::AttackerLocated(position) => attacker_position_input.remove(position.clone()),
-        InputFact::AttackGoal(goal) => attacker_goal_input.remove(goal.clone()),
-    }
-}
-
-fn advance_all_inputs(
-    time: usize,
-    vulnerability_input: &mut InputSession<usize, VulnerabilityRecord, isize>,
-    local_vulnerability_input: &mut InputSession<usize, LocalVulnerabilityRecord, isize>,
-    network_access_input: &mut InputSession<usize, NetworkAccessRule, isize>,
-    firewall_rules_input: &mut InputSession<usize, FirewallRuleRecord, isize>,
-    attacker_position_input: &mut InputSession<usize, AttackerStartingPosition, isize>,
-    attacker_goal_input: &mut InputSession<usize, AttackerTargetGoal, isize>,
-) {
-    vulnerability_input.advance_to(time);
-    local_vulnerability_input.advance_to(time);
-    network_access_input.advance_to(time);
-    firewall_rules_input.advance_to(time);
-    attacker_position_input.advance_to(time);
-    attacker_goal_input.advance_to(time);
-
-    vulnerability_input.flush();
-    local_vulnerability_input.flush();
-    network_access_input.flush();
-    firewall_rules_input.flush();
-    attacker_position_input.flush();
-    attacker_goal_input.flush();
+        ],
+        facts,
+        phases: Vec::new(),
+    })
 }
 
-fn run_hardcoded_demo() {
-    println!("========================================================================");
-    println!("     Dynamic Attack Graphs using Differential Dataflow");
-    println!("                    Proof of Concept");
-    println!("========================================================================");
-    println!();
-
-    // Run the timely dataflow computation
-    timely::execute_from_args(std::env::args(), |worker| {
-        let worker_index = worker.index();
-
-        // Only the first worker prints output
-        let is_main_worker = worker_index == 0;
-
-        // Probe to track when computation is complete
-        let mut computation_probe = Handle::new();
-
-        // Create input handles for each type of fact
-        let (
-            mut vulnerability_input,
-            mut network_access_input,
-            mut firewall_rules_input,
-            mut attacker_position_input,
-            mut attacker_goal_input,
-        ) = worker.dataflow::<usize, _, _>(|scope| {
-            // Create input collections for each fact type
-            let (vuln_handle, vulnerability_collection) =
-                scope.new_collection::<VulnerabilityRecord, isize>();
-            let (network_handle, network_access_collection) =
-                scope.new_collection::<NetworkAccessRule, isize>();
-            let (firewall_handle, firewall_rules_collection) =
-                scope.new_collection::<FirewallRuleRecord, isize>();
-            let (position_handle, attacker_positions_collection) =
-                scope.new_collection::<AttackerStartingPosition, isize>();
-            let (goal_handle, attacker_goals_collection) =
-                scope.new_collection::<AttackerTargetGoal, isize>();
-
-            // Build the attack graph using our rules
-            let (code_execution_results, machine_ownership_results, goal_reached_results) =
-                build_attack_graph(
-                    &vulnerability_collection,
-                    &network_access_collection,
-                    &firewall_rules_collection,
-                    &attacker_positions_collection,
-                    &attacker_goals_collection,
-                );
-
-            // Print changes to code execution facts
-            code_execution_results
-                .inspect(move |change| {
-                    if is_main_worker {
-                        let (data, timestamp, difference) = change;
-                        let change_type = if *difference > 0 { "+" } else { "-" };
-                        println!("  [t={}] {} {}", timestamp, change_type, data);
-                    }
-                })
-                .probe_with(&mut computation_probe);
-
-            // Print changes to machine ownership facts
-            machine_ownership_results
-                .inspect(move |change| {
-                    if is_main_worker {
-                        let (data, timestamp, difference) = change;
-                        let change_type = if *difference > 0 { "+" } else { "-" };
-                        println!("  [t={}] {} {}", timestamp, change_type, data);
-                    }
-                })
-                .probe_with(&mut computation_probe);
-
-            // Print changes to goal reached facts
-            goal_reached_results
-                .inspect(move |change| {
-                    if is_main_worker {
-                        let (data, timestamp, difference) = change;
-                        let change_type = if *difference > 0 { "+" } else { "-" };
-                        println!(
-                            "  [t={}] {} {} (TARGET COMPROMISED)",
-                            timestamp, change_type, data
-                        );
-                    }
-                })
-                .probe_with(&mut computation_probe);
-
-            (
-                vuln_handle,
-                network_handle,
-                firewall_handle,
-                position_handle,
-                goal_handle,
-            )
-        });
-
-        // ----------------------------------------------------------------
-        // PHASE 1: Load initial network state (timestamp 0)
-        // ----------------------------------------------------------------
-        if is_main_worker {
-            println!("------------------------------------------------------------------------");
-            println!("PHASE 1: Loading initial network state (time=0)");
-            println!("------------------------------------------------------------------------");
-            println!();
-            println!("Network topology:");
-            println!("  [Internet] -> [DMZ/web01] -> [Internal/db01] -> [Target/admin01]");
-            println!();
-        }
-
+fn builtin_demo() -> Demo {
+    let facts = BaseFacts {
         // Define network connections
-        let network_topology = vec![
+        network_access: vec![
             // From internet to DMZ
-            NetworkAccessRule::new("internet", "web01", "http"),
-            NetworkAccessRule::new("internet", "web01", "https"),
+            NetworkAccessRule::new_default_port("internet", "web01", "http"),
+            NetworkAccessRule::new_default_port("internet", "web01", "https"),
             // From DMZ to internal network
-            NetworkAccessRule::new("web01", "db01", "mysql"),
-            NetworkAccessRule::new("web01", "db01", "ssh"),
+            NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+            NetworkAccessRule::new_default_port("web01", "db01", "ssh"),
             // From internal to admin server
-            NetworkAccessRule::new("db01", "admin01", "ssh"),
-            NetworkAccessRule::new("db01", "admin01", "smb"),
-        ];
-
+            NetworkAccessRule::new_default_port("db01", "admin01", "ssh"),
+            NetworkAccessRule::new_default_port("db01", "admin01", "smb"),
+        ],
         // Define vulnerabilities on each host
-        let known_vulnerabilities = vec![
+        vulnerabilities: vec![
             // Web server vulnerabilities
             VulnerabilityRecord::new("web01", "CVE-2024-1234", "http", PrivilegeLevel::User),
             VulnerabilityRecord::new("web01", "CVE-2024-1234", "https", PrivilegeLevel::User),
@@ -487,225 +246,189 @@ fn run_hardcoded_demo() {
             VulnerabilityRecord::new("db01", "CVE-2024-9999", "ssh", PrivilegeLevel::User),
             // Admin server vulnerabilities
             VulnerabilityRecord::new("admin01", "CVE-2024-8888", "smb", PrivilegeLevel::Root),
-        ];
-
+        ],
         // Attacker starts on the internet
-        let attacker_starting_positions = vec![AttackerStartingPosition::new(
+        attacker_positions: vec![AttackerStartingPosition::new(
             "eve",
             "internet",
             PrivilegeLevel::User,
-        )];
-
+        )],
         // Attacker wants to compromise admin01
-        let attacker_objectives = vec![AttackerTargetGoal::new("eve", "admin01")];
-
-        // Insert all initial facts
-        let computation_start_time = Instant::now();
-
-        for network_connection in network_topology {
-            network_access_input.insert(network_connection);
-        }
-        for vulnerability in known_vulnerabilities {
-            vulnerability_input.insert(vulnerability);
-        }
-        for attacker_position in attacker_starting_positions {
-            attacker_position_input.insert(attacker_position);
-        }
-        for objective in attacker_objectives {
-            attacker_goal_input.insert(objective);
-        }
-
-        // Advance all inputs to timestamp 1 and flush
-        vulnerability_input.advance_to(1);
-        network_access_input.advance_to(1);
-        firewall_rules_input.advance_to(1);
-        attacker_position_input.advance_to(1);
-        attacker_goal_input.advance_to(1);
-        vulnerability_input.flush();
-        network_access_input.flush();
-        firewall_rules_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        // Wait for computation to finish
-        while computation_probe.less_than(&1) {
-            worker.step();
-        }
-
-        if is_main_worker {
-            println!();
-            println!(
-                "  Initial computation completed in {:?}",
-                computation_start_time.elapsed()
-            );
-            println!();
-        }
-
-        // ----------------------------------------------------------------
-        // PHASE 2: Add a firewall rule (timestamp 1)
-        // ----------------------------------------------------------------
-        if is_main_worker {
-            println!("------------------------------------------------------------------------");
-            println!("PHASE 2: Adding firewall rule to block HTTP (time=1)");
-            println!("         Rule: DENY internet -> web01 on http");
-            println!("------------------------------------------------------------------------");
-            println!();
-        }
-
-        let update_start_time = Instant::now();
-
-        // Add firewall rule that blocks HTTP access
-        firewall_rules_input.insert(FirewallRuleRecord::create_deny_rule(
-            "internet", "web01", "http",
-        ));
-
-        // Advance to next timestamp
-        vulnerability_input.advance_to(2);
-        network_access_input.advance_to(2);
-        firewall_rules_input.advance_to(2);
-        attacker_position_input.advance_to(2);
-        attacker_goal_input.advance_to(2);
-        vulnerability_input.flush();
-        network_access_input.flush();
-        firewall_rules_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        while computation_probe.less_than(&2) {
-            worker.step();
-        }
-
-        if is_main_worker {
-            println!();
-            println!(
-                "  Incremental update completed in {:?}",
-                update_start_time.elapsed()
-            );
-            println!("  Note: HTTP path removed, but HTTPS path still exists");
-            println!();
-        }
-
-        // ----------------------------------------------------------------
-        // PHASE 3: Patch the vulnerability (timestamp 2)
-        // ----------------------------------------------------------------
-        if is_main_worker {
-            println!("------------------------------------------------------------------------");
-            println!("PHASE 3: Patching CVE-2024-1234 on web01 (time=2)");
-            println!("         This removes the initial entry point");
-            println!("------------------------------------------------------------------------");
-            println!();
-        }
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "admin01")],
+        ..BaseFacts::default()
+    };
 
-        let update_start_time = Instant::now();
+    let phases = vec![
+        DemoPhase {
+            title: "Adding firewall rule to block HTTP",
+            details: vec!["Rule: DENY internet -> web01 on http"],
+            updates: vec![FactUpdate::InsertFirewallDeny(
+                FirewallRuleRecord::create_deny_rule("internet", "web01", "http"),
+            )],
+            outcome: "Note: HTTP path removed, but HTTPS path still exists",
+        },
+        DemoPhase {
+            title: "Patching CVE-2024-1234 on web01",
+            details: vec!["This removes the initial entry point"],
+            // Remove both HTTP and HTTPS vulnerabilities
+            updates: ["http", "https"]
+                .into_iter()
+                .map(|service| {
+                    FactUpdate::RemoveVulnerability(VulnerabilityRecord::new(
+                        "web01",
+                        "CVE-2024-1234",
+                        service,
+                        PrivilegeLevel::User,
+                    ))
+                })
+                .collect(),
+            outcome: "Target is now protected - all attack paths removed",
+        },
+        DemoPhase {
+            title: "New CVE discovered - CVE-2024-0DAY on web01",
+            details: vec!["HTTPS service is vulnerable again"],
+            updates: vec![FactUpdate::InsertVulnerability(VulnerabilityRecord::new(
+                "web01",
+                "CVE-2024-0DAY",
+                "https",
+                PrivilegeLevel::User,
+            ))],
+            outcome: "Warning: Attack paths restored via new vulnerability",
+        },
+    ];
+
+    Demo {
+        subtitle: "Proof of Concept",
+        description: vec![
+            "Network topology:".to_string(),
+            "  [Internet] -> [DMZ/web01] -> [Internal/db01] -> [Target/admin01]".to_string(),
+        ],
+        facts,
+        phases,
+    }
+}
 
-        // Remove both HTTP and HTTPS vulnerabilities
-        vulnerability_input.remove(VulnerabilityRecord::new(
-            "web01",
-            "CVE-2024-1234",
-            "http",
-            PrivilegeLevel::User,
-        ));
-        vulnerability_input.remove(VulnerabilityRecord::new(
-            "web01",
-            "CVE-2024-1234",
-            "https",
-            PrivilegeLevel::User,
-        ));
-
-        // Advance to next timestamp
-        vulnerability_input.advance_to(3);
-        network_access_input.advance_to(3);
-        firewall_rules_input.advance_to(3);
-        attacker_position_input.advance_to(3);
-        attacker_goal_input.advance_to(3);
-        vulnerability_input.flush();
-        network_access_input.flush();
-        firewall_rules_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        while computation_probe.less_than(&3) {
-            worker.step();
-        }
+fn run_phased_demo(cli: &Cli, demo: Demo) -> Result<(), AttackGraphError> {
+    println!("========================================================================");
+    println!("     Dynamic Attack Graphs using Differential Dataflow");
+    println!("                    {}", demo.subtitle);
+    println!("========================================================================");
+    println!();
 
-        if is_main_worker {
-            println!();
-            println!(
-                "  Incremental update completed in {:?}",
-                update_start_time.elapsed()
-            );
-            println!("  Target is now protected - all attack paths removed");
-            println!();
-        }
+    // Every phase is computed up front, on the workers timely's
+    // arguments ask for; the phases below print what each one changed.
+    let steps: Vec<_> = demo
+        .phases
+        .iter()
+        .map(|phase| phase.updates.clone())
+        .collect();
+    let computation_start_time = Instant::now();
+    let timeline = run_timeline_from_args(cli.timely_args.clone().into_iter(), &demo.facts, &steps)
+        .map_err(AttackGraphError::ComputationError)?;
+    let computation_time = computation_start_time.elapsed();
+
+    // ----------------------------------------------------------------
+    // PHASE 1: Load initial network state (timestamp 0)
+    // ----------------------------------------------------------------
+    println!("------------------------------------------------------------------------");
+    println!("PHASE 1: Loading initial network state (time=0)");
+    println!("------------------------------------------------------------------------");
+    println!();
+    for line in &demo.description {
+        println!("{line}");
+    }
+    println!();
+    print_step_changes(&TimeStepOutput::default(), &timeline[0]);
+    println!();
 
-        // ----------------------------------------------------------------
-        // PHASE 4: New vulnerability discovered (timestamp 3)
-        // ----------------------------------------------------------------
-        if is_main_worker {
-            println!("------------------------------------------------------------------------");
-            println!("PHASE 4: New CVE discovered - CVE-2024-0DAY on web01 (time=3)");
-            println!("         HTTPS service is vulnerable again");
-            println!("------------------------------------------------------------------------");
-            println!();
+    // ----------------------------------------------------------------
+    // PHASES 2..: Each phase's updates were applied at the next timestamp
+    // ----------------------------------------------------------------
+    for (phase_index, (phase, settled)) in demo.phases.iter().zip(timeline.windows(2)).enumerate() {
+        println!("------------------------------------------------------------------------");
+        println!(
+            "PHASE {}: {} (time={})",
+            phase_index + 2,
+            phase.title,
+            settled[1].timestamp
+        );
+        for detail in &phase.details {
+            println!("         {detail}");
         }
+        println!("------------------------------------------------------------------------");
+        println!();
+        print_step_changes(&settled[0], &settled[1]);
+        println!();
+        println!("  {}", phase.outcome);
+        println!();
+    }
 
-        let update_start_time = Instant::now();
+    println!(
+        "  Initial computation and {} incremental updates completed in {:?}",
+        demo.phases.len(),
+        computation_time
+    );
+    println!();
 
-        // Add new vulnerability
-        vulnerability_input.insert(VulnerabilityRecord::new(
-            "web01",
-            "CVE-2024-0DAY",
-            "https",
-            PrivilegeLevel::User,
-        ));
-
-        // Advance to next timestamp
-        vulnerability_input.advance_to(4);
-        network_access_input.advance_to(4);
-        firewall_rules_input.advance_to(4);
-        attacker_position_input.advance_to(4);
-        attacker_goal_input.advance_to(4);
-        vulnerability_input.flush();
-        network_access_input.flush();
-        firewall_rules_input.flush();
-        attacker_position_input.flush();
-        attacker_goal_input.flush();
-
-        while computation_probe.less_than(&4) {
-            worker.step();
+    // ----------------------------------------------------------------
+    // Summary
+    // ----------------------------------------------------------------
+    if !demo.phases.is_empty() {
+        println!("------------------------------------------------------------------------");
+        println!("SUMMARY");
+        println!("------------------------------------------------------------------------");
+        println!();
+        println!("This demonstration showed:");
+        println!("  1. Initial attack graph computation from base facts");
+        for (phase_index, phase) in demo.phases.iter().enumerate() {
+            println!("  {}. Incremental update: {}", phase_index + 2, phase.title);
         }
+        println!();
+        println!("Key observations:");
+        println!("  - Updates only affected relevant derived facts");
+        println!("  - No full recomputation was performed");
+        println!("  - Changes propagated correctly through the graph");
+        println!();
+        println!("This demonstrates the power of differential dataflow for attack graphs.");
+    }
+    Ok(())
+}
 
-        if is_main_worker {
-            println!();
-            println!(
-                "  Incremental update completed in {:?}",
-                update_start_time.elapsed()
-            );
-            println!("  Warning: Attack paths restored via new vulnerability");
-            println!();
-        }
+// Prints the derived facts `after` gained (+) or lost (-) relative to
+// `before`, at `after`'s timestamp.
+fn print_step_changes(before: &TimeStepOutput, after: &TimeStepOutput) {
+    print_changes(
+        after.timestamp,
+        &before.code_executions,
+        &after.code_executions,
+        "",
+    );
+    print_changes(
+        after.timestamp,
+        &before.machines_owned,
+        &after.machines_owned,
+        "",
+    );
+    print_changes(
+        after.timestamp,
+        &before.goals_reached,
+        &after.goals_reached,
+        " (TARGET COMPROMISED)",
+    );
+}
 
-        // ----------------------------------------------------------------
-        // Summary
-        // ----------------------------------------------------------------
-        if is_main_worker {
-            println!("------------------------------------------------------------------------");
-            println!("SUMMARY");
-            println!("------------------------------------------------------------------------");
-            println!();
-            println!("This demonstration showed:");
-            println!("  1. Initial attack graph computation from base facts");
-            println!("  2. Incremental update when firewall rule added");
-            println!("  3. Incremental update when vulnerability patched");
-            println!("  4. Incremental update when new vulnerability discovered");
-            println!();
-            println!("Key observations:");
-            println!("  - Updates only affected relevant derived facts");
-            println!("  - No full recomputation was performed");
-            println!("  - Changes propagated correctly through the graph");
-            println!();
-            println!("This demonstrates the power of differential dataflow for attack graphs.");
-        }
-    })
-    .expect("Computation failed");
+fn print_changes<D: Ord + std::fmt::Display>(
+    timestamp: usize,
+    before: &[D],
+    after: &[D],
+    note: &str,
+) {
+    let before: BTreeSet<_> = before.iter().collect();
+    let after: BTreeSet<_> = after.iter().collect();
+    for removed in before.difference(&after) {
+        println!("  [t={timestamp}] - {removed}{note}");
+    }
+    for added in after.difference(&before) {
+        println!("  [t={timestamp}] + {added}{note}");
+    }
 }
@@ -1,6 +1,6 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
-use crate::engine::DerivedFacts;
+use crate::engine::{effective_network_access_from_base, BaseFacts, DerivedFacts};
 use crate::schema::HostIdentifier;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -78,6 +78,50 @@ pub fn affected_hosts_from_diff(
     hosts
 }
 
+/// Out-degree and in-degree histograms of the effective-access graph,
+/// mapping degree to the number of hosts with that degree.  Parallel
+/// edges on different services count once, and every host named by a
+/// network edge or a vulnerability is included, so hosts without
+/// edges show up under degree 0.
+pub fn degree_distribution(facts: &BaseFacts) -> (HashMap<usize, usize>, HashMap<usize, usize>) {
+    let edges: HashSet<(HostIdentifier, HostIdentifier)> =
+        effective_network_access_from_base(facts)
+            .into_iter()
+            .map(|access| (access.source_host, access.destination_host))
+            .collect();
+
+    let mut hosts: HashSet<HostIdentifier> = facts
+        .vulnerabilities
+        .iter()
+        .map(|vulnerability| vulnerability.host_name.clone())
+        .collect();
+    for rule in &facts.network_access {
+        hosts.insert(rule.source_host.clone());
+        hosts.insert(rule.destination_host.clone());
+    }
+
+    let mut out_degrees: HashMap<&HostIdentifier, usize> =
+        hosts.iter().map(|host| (host, 0)).collect();
+    let mut in_degrees = out_degrees.clone();
+    for (source, destination) in &edges {
+        *out_degrees.entry(source).or_insert(0) += 1;
+        *in_degrees.entry(destination).or_insert(0) += 1;
+    }
+
+    (
+        histogram(out_degrees.values()),
+        histogram(in_degrees.values()),
+    )
+}
+
+fn histogram<'a>(degrees: impl Iterator<Item = &'a usize>) -> HashMap<usize, usize> {
+    let mut histogram = HashMap::new();
+    for degree in degrees {
+        *histogram.entry(*degree).or_insert(0) += 1;
+    }
+    histogram
+}
+
 fn symmetric_difference_count<T>(before: &HashSet<T>, after: &HashSet<T>) -> usize
 where
     T: Eq + std::hash::Hash,
@@ -111,7 +155,7 @@ mod tests {
             VulnerabilityRecord::new("leaf", "CVE-LEAF", "https", PrivilegeLevel::Root);
         let mut facts = BaseFacts {
             vulnerabilities: vec![vulnerability.clone()],
-            network_access: vec![NetworkAccessRule::new("hub", "leaf", "https")],
+            network_access: vec![NetworkAccessRule::new_default_port("hub", "leaf", "https")],
             attacker_positions: vec![AttackerStartingPosition::new(
                 "eve",
                 "hub",
@@ -139,9 +183,9 @@ mod tests {
                 VulnerabilityRecord::new("node_3", "CVE-3", "https", PrivilegeLevel::Root),
             ],
             network_access: vec![
-                NetworkAccessRule::new("node_0", "node_1", "https"),
-                NetworkAccessRule::new("node_1", "node_2", "https"),
-                NetworkAccessRule::new("node_2", "node_3", "https"),
+                NetworkAccessRule::new_default_port("node_0", "node_1", "https"),
+                NetworkAccessRule::new_default_port("node_1", "node_2", "https"),
+                NetworkAccessRule::new_default_port("node_2", "node_3", "https"),
             ],
             attacker_positions: vec![AttackerStartingPosition::new(
                 "eve",
@@ -170,8 +214,8 @@ mod tests {
                 VulnerabilityRecord::new("node_2", "CVE-2", "https", PrivilegeLevel::Root),
             ],
             network_access: vec![
-                NetworkAccessRule::new("node_0", "node_1", "https"),
-                NetworkAccessRule::new("node_1", "node_2", "https"),
+                NetworkAccessRule::new_default_port("node_0", "node_1", "https"),
+                NetworkAccessRule::new_default_port("node_1", "node_2", "https"),
             ],
             attacker_positions: vec![AttackerStartingPosition::new(
                 "eve",
@@ -195,4 +239,23 @@ mod tests {
         assert!(affected_hosts.contains("node_2"));
         assert_eq!(affected_hosts.len(), first_metrics.affected_hosts);
     }
+
+    #[test]
+    fn star_degree_distribution_has_one_hub() {
+        let (network_access, vulnerabilities, attacker_positions, attacker_goals) =
+            crate::benchmarks::generate_star_network(10);
+        let facts = BaseFacts {
+            vulnerabilities,
+            network_access,
+            attacker_positions,
+            attacker_goals,
+            ..BaseFacts::default()
+        };
+
+        let (out_degrees, in_degrees) = degree_distribution(&facts);
+
+        // The hub points at all 10 leaves; leaves point nowhere.
+        assert_eq!(out_degrees, HashMap::from([(10, 1), (0, 10)]));
+        assert_eq!(in_degrees, HashMap::from([(0, 1), (1, 10)]));
+    }
 }
@@ -0,0 +1,594 @@
+// MulVAL input parser
+// -------------------
+// Reads the fixed predicate forms of a MulVAL `.P` input file into
+// the same `InputScenario` the `.facts` parser produces.  Full Datalog
+// (rules, variables in facts, `:-` clauses) is out of scope; every
+// line must be one of:
+//
+//   attackerLocated(Host).
+//   attackGoal(Host).                    or attackGoal(execCode(Host, Priv)).
+//   hacl(Src, Dst, Protocol, Port).      Protocol and Port may be `_`
+//   vulExists(Host, Cve, Program).
+//   vulProperty(Cve, remoteExploit | localExploit, Consequence).
+//   networkServiceInfo(Host, Program, Protocol, Port, Account).
+//   cvss(Cve, Score).                    Score is h, m, l or 0-10
+//
+// MulVAL files have no attacker names, so every fact belongs to the
+// attacker "attacker".  `%` comments, whole-line or after a fact, and
+// `/* */` comments are skipped, and atoms may be single-quoted.
+//
+// A goal's privilege (`user` or `root`) becomes its required
+// privilege; `_` and the bare `attackGoal(Host)` form ask for Root.
+// `cvss` sets the exploitability of the CVE's vulnerabilities: a
+// numeric score is taken as is, and MulVAL's access complexity
+// letters map to the CVSS v2 exploitability of a network exploit
+// without authentication, rescaled so that `l` is 10.0.
+//
+// MulVAL talks about protocols and ports on the network side and about
+// programs on the vulnerability side; `networkServiceInfo` is what
+// links the two.  A hacl edge is therefore resolved against the
+// services running on its destination: each matching service becomes
+// a `hacl(Src, Dst, Program, Port)` edge.  An edge that matches no
+// service falls back to the programs with a remote vulnerability but
+// no `networkServiceInfo` on its destination, each on its well-known
+// port, which must match the edge's unless that is a wildcard; an
+// edge that still matches nothing is dropped.  A remote vulnerability gains the port of its
+// program's service and Root if that service runs as root, User
+// otherwise.  A vulnerability whose CVE has the `localExploit` property
+// becomes a local escalation to Root.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::parser::{InputScenario, ParseError};
+use crate::schema::{
+    default_port, AttackerStartingPosition, AttackerTargetGoal, LocalVulnerabilityRecord,
+    NetworkAccessRule, PortNumber, PrivilegeLevel, UnknownPrivilegeLevel, VulnerabilityRecord,
+};
+
+/// Attacker id given to every MulVAL fact.
+pub const MULVAL_ATTACKER: &str = "attacker";
+
+const WILDCARD: &str = "_";
+
+// CVSS v2 access complexity weights of a network exploit without
+// authentication, as 0.0-10.0 exploitability: 20 * 1.0 * AC * 0.704,
+// rescaled by the low-complexity score.
+const LOW_COMPLEXITY_EXPLOITABILITY: f64 = 10.0;
+const MEDIUM_COMPLEXITY_EXPLOITABILITY: f64 = 10.0 * 0.61 / 0.71;
+const HIGH_COMPLEXITY_EXPLOITABILITY: f64 = 10.0 * 0.35 / 0.71;
+
+#[derive(Debug, Default)]
+struct MulvalFacts {
+    attacker_hosts: Vec<String>,
+    // (host, required privilege)
+    goals: Vec<(String, PrivilegeLevel)>,
+    // (src, dst, protocol, port)
+    hacls: Vec<(String, String, String, String)>,
+    // (host, cve, program)
+    vulnerabilities: Vec<(String, String, String)>,
+    local_exploit_cves: BTreeSet<String>,
+    // cve -> exploitability, 0.0-10.0
+    cvss_scores: BTreeMap<String, f64>,
+    // (host, program, protocol, port, account)
+    services: Vec<(String, String, String, PortNumber, String)>,
+}
+
+/// Parses a MulVAL `.P` input file.
+pub fn parse_mulval_file(path: &Path) -> Result<InputScenario, ParseError> {
+    let contents = fs::read_to_string(path).map_err(|error| ParseError::Io {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })?;
+    parse_mulval_str(&contents)
+}
+
+/// Parses MulVAL input from a string; see the module comment for the
+/// accepted predicates.
+pub fn parse_mulval_str(contents: &str) -> Result<InputScenario, ParseError> {
+    let mut facts = MulvalFacts::default();
+
+    for (line_index, line) in strip_block_comments(contents).lines().enumerate() {
+        parse_mulval_line(line, &mut facts).map_err(|source| ParseError::Line {
+            line_number: line_index + 1,
+            source: Box::new(source),
+        })?;
+    }
+
+    Ok(facts.into_scenario())
+}
+
+fn parse_mulval_line(line: &str, facts: &mut MulvalFacts) -> Result<(), ParseError> {
+    let trimmed = strip_line_comment(line).trim();
+    if trimmed.is_empty() {
+        return Ok(());
+    }
+    let fact_body = trimmed
+        .strip_suffix('.')
+        .ok_or(ParseError::MissingPeriod)?
+        .trim();
+    let (predicate, arguments) = split_term(fact_body)?;
+
+    match predicate.as_str() {
+        "attackerLocated" => {
+            let [host] = expect_arguments(&predicate, arguments)?;
+            facts.attacker_hosts.push(host);
+        }
+        "attackGoal" => {
+            let [goal] = expect_arguments(&predicate, arguments)?;
+            let goal = if goal.contains('(') {
+                let (inner_predicate, inner_arguments) = split_term(&goal)?;
+                if inner_predicate != "execCode" {
+                    return Err(ParseError::UnknownPredicate(inner_predicate));
+                }
+                let [host, privilege] = expect_arguments(&inner_predicate, inner_arguments)?;
+                let privilege = if privilege == WILDCARD {
+                    PrivilegeLevel::Root
+                } else {
+                    privilege.parse().map_err(invalid_privilege)?
+                };
+                (host, privilege)
+            } else {
+                (goal, PrivilegeLevel::Root)
+            };
+            facts.goals.push(goal);
+        }
+        "hacl" => {
+            let [source, destination, protocol, port] = expect_arguments(&predicate, arguments)?;
+            if port != WILDCARD {
+                parse_port(&port)?;
+            }
+            facts.hacls.push((source, destination, protocol, port));
+        }
+        "vulExists" => {
+            let [host, cve, program] = expect_arguments(&predicate, arguments)?;
+            facts.vulnerabilities.push((host, cve, program));
+        }
+        "vulProperty" => {
+            let [cve, exploit_range, _consequence] = expect_arguments(&predicate, arguments)?;
+            match exploit_range.as_str() {
+                "localExploit" => {
+                    facts.local_exploit_cves.insert(cve);
+                }
+                "remoteExploit" => {}
+                _ => return Err(ParseError::MalformedFact(trimmed.to_string())),
+            }
+        }
+        "networkServiceInfo" => {
+            let [host, program, protocol, port, account] = expect_arguments(&predicate, arguments)?;
+            let port = parse_port(&port)?;
+            facts
+                .services
+                .push((host, program, protocol, port, account));
+        }
+        "cvss" => {
+            let [cve, score] = expect_arguments(&predicate, arguments)?;
+            let exploitability = match score.as_str() {
+                "l" => LOW_COMPLEXITY_EXPLOITABILITY,
+                "m" => MEDIUM_COMPLEXITY_EXPLOITABILITY,
+                "h" => HIGH_COMPLEXITY_EXPLOITABILITY,
+                _ => score
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|score| (0.0..=10.0).contains(score))
+                    .ok_or_else(|| ParseError::MalformedFact(trimmed.to_string()))?,
+            };
+            facts.cvss_scores.insert(cve, exploitability);
+        }
+        _ => return Err(ParseError::UnknownPredicate(predicate)),
+    }
+    Ok(())
+}
+
+impl MulvalFacts {
+    fn into_scenario(self) -> InputScenario {
+        let mut scenario = InputScenario::default();
+
+        for host in &self.attacker_hosts {
+            scenario
+                .attacker_positions
+                .push(AttackerStartingPosition::new(
+                    MULVAL_ATTACKER,
+                    host,
+                    PrivilegeLevel::User,
+                ));
+        }
+        for (host, privilege) in &self.goals {
+            scenario.attacker_goals.push(
+                AttackerTargetGoal::new(MULVAL_ATTACKER, host)
+                    .with_required_privilege(privilege.clone()),
+            );
+        }
+
+        for (source, destination, protocol, port) in &self.hacls {
+            let mut matched_service = false;
+            for (_, program, service_protocol, service_port, _) in self
+                .services
+                .iter()
+                .filter(|service| &service.0 == destination)
+            {
+                let protocol_matches = protocol == WILDCARD || protocol == service_protocol;
+                let port_matches = port == WILDCARD || port == &service_port.to_string();
+                if protocol_matches && port_matches {
+                    matched_service = true;
+                    scenario.network_access.push(NetworkAccessRule::new(
+                        source,
+                        destination,
+                        program,
+                        *service_port,
+                    ));
+                }
+            }
+            if matched_service {
+                continue;
+            }
+            let mut fallback_programs = BTreeSet::new();
+            for program in self.programs_without_service_info(destination) {
+                let program_port = default_port(program);
+                if port == WILDCARD || port == &program_port.to_string() {
+                    fallback_programs.insert((program, program_port));
+                }
+            }
+            for (program, program_port) in fallback_programs {
+                scenario.network_access.push(NetworkAccessRule::new(
+                    source,
+                    destination,
+                    program,
+                    program_port,
+                ));
+            }
+        }
+
+        for (host, cve, program) in &self.vulnerabilities {
+            if self.local_exploit_cves.contains(cve) {
+                scenario
+                    .local_vulnerabilities
+                    .push(LocalVulnerabilityRecord::new(
+                        host,
+                        cve,
+                        PrivilegeLevel::Root,
+                    ));
+                continue;
+            }
+            let service = self
+                .services
+                .iter()
+                .find(|service| &service.0 == host && &service.1 == program);
+            let vulnerability = match service {
+                Some((_, _, _, port, account)) => {
+                    let privilege = if account == "root" {
+                        PrivilegeLevel::Root
+                    } else {
+                        PrivilegeLevel::User
+                    };
+                    VulnerabilityRecord::new(host, cve, program, privilege).with_port(*port)
+                }
+                None => VulnerabilityRecord::new(host, cve, program, PrivilegeLevel::User),
+            };
+            scenario
+                .vulnerabilities
+                .push(match self.cvss_scores.get(cve) {
+                    Some(exploitability) => vulnerability.with_exploitability(*exploitability),
+                    None => vulnerability,
+                });
+        }
+
+        scenario
+    }
+
+    // Programs on `host` with a remote vulnerability but no
+    // `networkServiceInfo`, which unmatched hacl edges fall back to.
+    fn programs_without_service_info<'a>(&'a self, host: &'a str) -> impl Iterator<Item = &'a str> {
+        self.vulnerabilities
+            .iter()
+            .filter(move |(vulnerable_host, cve, program)| {
+                vulnerable_host == host
+                    && !self.local_exploit_cves.contains(cve)
+                    && !self
+                        .services
+                        .iter()
+                        .any(|service| service.0 == host && &service.1 == program)
+            })
+            .map(|(_, _, program)| program.as_str())
+    }
+}
+
+// Splits `name(arg, arg, ...)` into its name and top-level arguments,
+// keeping nested terms such as `execCode(h, root)` whole and removing
+// single quotes around atoms.
+fn split_term(term: &str) -> Result<(String, Vec<String>), ParseError> {
+    let malformed = || ParseError::MalformedFact(term.to_string());
+    let open_paren = term.find('(').ok_or_else(malformed)?;
+    let inner = term[open_paren + 1..]
+        .strip_suffix(')')
+        .ok_or_else(malformed)?;
+    let predicate = term[..open_paren].trim().to_string();
+
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    for character in inner.chars() {
+        match character {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => {
+                depth += 1;
+                current.push(character);
+            }
+            ')' if !quoted => {
+                depth = depth.checked_sub(1).ok_or_else(malformed)?;
+                current.push(character);
+            }
+            ',' if !quoted && depth == 0 => {
+                arguments.push(current.trim().to_string());
+                current.clear();
+            }
+            _ => current.push(character),
+        }
+    }
+    arguments.push(current.trim().to_string());
+
+    if quoted || depth != 0 || predicate.is_empty() || arguments.iter().any(String::is_empty) {
+        return Err(malformed());
+    }
+    Ok((predicate, arguments))
+}
+
+fn expect_arguments<const N: usize>(
+    predicate: &str,
+    arguments: Vec<String>,
+) -> Result<[String; N], ParseError> {
+    let found = arguments.len();
+    arguments.try_into().map_err(|_| ParseError::InvalidArity {
+        predicate: predicate.to_string(),
+        expected: N,
+        found,
+    })
+}
+
+fn parse_port(port: &str) -> Result<PortNumber, ParseError> {
+    port.parse()
+        .map_err(|_| ParseError::InvalidPort(port.to_string()))
+}
+
+// The part of `line` before a `%` comment; a `%` inside a quoted atom
+// does not start one.
+fn strip_line_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '\'' => quoted = !quoted,
+            '%' if !quoted => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn invalid_privilege(error: UnknownPrivilegeLevel) -> ParseError {
+    ParseError::InvalidPrivilege(error.0)
+}
+
+// Blanks out `/* ... */` comments, keeping newlines so that error line
+// numbers still match the file.
+fn strip_block_comments(contents: &str) -> String {
+    let mut stripped = String::with_capacity(contents.len());
+    let mut rest = contents;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        let comment_end = rest[start..]
+            .find("*/")
+            .map_or(rest.len(), |end| start + end + 2);
+        stripped.extend(
+            rest[start..comment_end]
+                .chars()
+                .filter(|character| *character == '\n'),
+        );
+        rest = &rest[comment_end..];
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The classic MulVAL single-web-server example.
+    const WEB_SERVER_INPUT: &str = r#"
+/* Attacker on the internet, web server running Apache as root. */
+attackerLocated(internet).
+attackGoal(execCode(webServer, _)).
+
+hacl(internet, webServer, tcp, 80).
+hacl(webServer, fileServer, _, _).
+
+vulExists(webServer, 'CAN-2002-0392', httpd).
+vulProperty('CAN-2002-0392', remoteExploit, privEscalation).
+networkServiceInfo(webServer, httpd, tcp, 80, root).
+cvss('CAN-2002-0392', h).
+
+vulExists(fileServer, 'CVE-2004-0001', kernel).
+vulProperty('CVE-2004-0001', localExploit, privEscalation).
+"#;
+
+    #[test]
+    fn parses_the_web_server_example() {
+        let scenario = parse_mulval_str(WEB_SERVER_INPUT).expect("example should parse");
+
+        assert_eq!(
+            scenario.attacker_positions,
+            vec![AttackerStartingPosition::new(
+                "attacker",
+                "internet",
+                PrivilegeLevel::User
+            )]
+        );
+        assert_eq!(
+            scenario.attacker_goals,
+            vec![AttackerTargetGoal::new("attacker", "webServer")]
+        );
+        // The wildcard edge to the file server matches no service and
+        // is dropped.
+        assert_eq!(
+            scenario.network_access,
+            vec![NetworkAccessRule::new("internet", "webServer", "httpd", 80)]
+        );
+        assert_eq!(
+            scenario.vulnerabilities,
+            vec![VulnerabilityRecord::new(
+                "webServer",
+                "CAN-2002-0392",
+                "httpd",
+                PrivilegeLevel::Root
+            )
+            .with_port(80)
+            .with_exploitability(HIGH_COMPLEXITY_EXPLOITABILITY)]
+        );
+        assert_eq!(
+            scenario.local_vulnerabilities,
+            vec![LocalVulnerabilityRecord::new(
+                "fileServer",
+                "CVE-2004-0001",
+                PrivilegeLevel::Root
+            )]
+        );
+    }
+
+    #[test]
+    fn vulnerability_without_service_info_defaults_to_user() {
+        let scenario =
+            parse_mulval_str("hacl(internet, web, tcp, 22).\nvulExists(web, 'CVE-1', ssh).\n")
+                .expect("input should parse");
+
+        // The edge matches no service, so it falls back to the
+        // vulnerable ssh on its well-known port.
+        assert_eq!(
+            scenario.network_access,
+            vec![NetworkAccessRule::new("internet", "web", "ssh", 22)]
+        );
+        assert_eq!(
+            scenario.vulnerabilities,
+            vec![VulnerabilityRecord::new(
+                "web",
+                "CVE-1",
+                "ssh",
+                PrivilegeLevel::User
+            )]
+        );
+    }
+
+    // A MulVAL input as written by its network scanner adapters, with
+    // trailing comments, a user-level goal and access complexities.
+    const SCANNED_INPUT: &str = r#"
+attackerLocated(internet).   % the attacker starts outside
+attackGoal(execCode(workStation, user)).
+
+hacl(internet, webServer, tcp, 80).        % firewall: http only
+hacl(webServer, workStation, tcp, 22).
+hacl(webServer, dbServer, tcp, 3306).      % no service, no vulnerable program
+
+/* webServer */
+vulExists(webServer, 'CVE-2002-0392', httpd).
+vulProperty('CVE-2002-0392', remoteExploit, privEscalation).
+networkServiceInfo(webServer, httpd, tcp, 80, apache).
+cvss('CVE-2002-0392', m).
+
+/* workStation: no networkServiceInfo for ssh */
+vulExists(workStation, 'CVE-2008-0166', ssh).
+vulProperty('CVE-2008-0166', remoteExploit, privEscalation).
+cvss('CVE-2008-0166', 7.8).   % numeric scores are kept as is
+"#;
+
+    #[test]
+    fn parses_scanner_output_with_trailing_comments() {
+        let scenario = parse_mulval_str(SCANNED_INPUT).expect("scanner output should parse");
+
+        assert_eq!(
+            scenario.attacker_goals,
+            vec![AttackerTargetGoal::new("attacker", "workStation")
+                .with_required_privilege(PrivilegeLevel::User)]
+        );
+        assert_eq!(
+            scenario.network_access,
+            vec![
+                NetworkAccessRule::new("internet", "webServer", "httpd", 80),
+                NetworkAccessRule::new("webServer", "workStation", "ssh", 22),
+            ]
+        );
+        assert_eq!(
+            scenario.vulnerabilities,
+            vec![
+                VulnerabilityRecord::new(
+                    "webServer",
+                    "CVE-2002-0392",
+                    "httpd",
+                    PrivilegeLevel::User
+                )
+                .with_port(80)
+                .with_exploitability(MEDIUM_COMPLEXITY_EXPLOITABILITY),
+                VulnerabilityRecord::new(
+                    "workStation",
+                    "CVE-2008-0166",
+                    "ssh",
+                    PrivilegeLevel::User
+                )
+                .with_exploitability(7.8),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_percent_sign_inside_a_quoted_atom_is_not_a_comment() {
+        let scenario = parse_mulval_str("attackerLocated('lab%1').  % trailing\n")
+            .expect("input should parse");
+
+        assert_eq!(
+            scenario.attacker_positions,
+            vec![AttackerStartingPosition::new(
+                "attacker",
+                "lab%1",
+                PrivilegeLevel::User
+            )]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_goal_privileges_and_cvss_scores() {
+        assert_eq!(
+            parse_mulval_str("attackGoal(execCode(web, admin)).\n"),
+            Err(ParseError::Line {
+                line_number: 1,
+                source: Box::new(ParseError::InvalidPrivilege("admin".to_string())),
+            })
+        );
+        assert_eq!(
+            parse_mulval_str("cvss('CVE-1', x).\n"),
+            Err(ParseError::Line {
+                line_number: 1,
+                source: Box::new(ParseError::MalformedFact("cvss('CVE-1', x).".to_string())),
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unrecognized_lines_with_their_line_number() {
+        assert_eq!(
+            parse_mulval_str("attackerLocated(internet).\n\ninCompetent(alice).\n"),
+            Err(ParseError::Line {
+                line_number: 3,
+                source: Box::new(ParseError::UnknownPredicate("inCompetent".to_string())),
+            })
+        );
+        assert_eq!(
+            parse_mulval_str("execCode(X, root) :- attackerLocated(X).\n"),
+            Err(ParseError::Line {
+                line_number: 1,
+                source: Box::new(ParseError::MalformedFact(
+                    "execCode(X, root) :- attackerLocated(X)".to_string()
+                )),
+            })
+        );
+    }
+}
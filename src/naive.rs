@@ -3,7 +3,8 @@ use std::collections::HashSet;
 use crate::schema::{
     AttackerCodeExecution, AttackerGoalReached, AttackerOwnsMachine, AttackerStartingPosition,
     AttackerTargetGoal, EffectiveNetworkAccess, FirewallRuleAction, FirewallRuleRecord,
-    LocalVulnerabilityRecord, NetworkAccessRule, PrivilegeLevel, VulnerabilityRecord,
+    LocalVulnerabilityRecord, NetworkAccessRule, PrivilegeLattice, PrivilegeLevel,
+    VulnerabilityRecord,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -54,6 +55,7 @@ pub fn evaluate_attack_graph_naive_with_local_vulnerabilities(
                 rule.source_zone.clone(),
                 rule.destination_host.clone(),
                 rule.service_name.clone(),
+                rule.port,
             )
         })
         .collect();
@@ -65,12 +67,14 @@ pub fn evaluate_attack_graph_naive_with_local_vulnerabilities(
                 access.source_host.clone(),
                 access.destination_host.clone(),
                 access.service_name.clone(),
+                access.port,
             ))
         })
         .map(|access| EffectiveNetworkAccess {
             source_host: access.source_host,
             destination_host: access.destination_host,
             service_name: access.service_name,
+            port: access.port,
         })
         .collect();
 
@@ -110,6 +114,7 @@ pub fn evaluate_attack_graph_naive_with_local_vulnerabilities(
                 for vulnerability in vulnerabilities.iter().filter(|vulnerability| {
                     vulnerability.host_name == access.destination_host
                         && vulnerability.affected_service == access.service_name
+                        && vulnerability.port == access.port
                 }) {
                     let derived = AttackerCodeExecution {
                         attacker_id: execution.attacker_id.clone(),
@@ -136,19 +141,21 @@ pub fn evaluate_attack_graph_naive_with_local_vulnerabilities(
         })
         .collect();
 
-    let owned_keys: HashSet<_> = machines_owned
-        .iter()
-        .map(|owned| (owned.attacker_id.clone(), owned.owned_host.clone()))
-        .collect();
-
     let goals_reached: HashSet<_> = attacker_goals
         .into_iter()
         .filter(|goal| {
-            owned_keys.contains(&(goal.attacker_id.clone(), goal.target_host_name.clone()))
+            code_executions.iter().any(|execution| {
+                execution.attacker_id == goal.attacker_id
+                    && execution.compromised_host == goal.target_host_name
+                    && execution
+                        .obtained_privilege
+                        .dominates(&goal.required_privilege)
+            })
         })
         .map(|goal| AttackerGoalReached {
             attacker_id: goal.attacker_id,
             reached_target: goal.target_host_name,
+            required_privilege: goal.required_privilege,
         })
         .collect();
 
@@ -203,6 +210,7 @@ mod tests {
         AttackerGoalReached {
             attacker_id: "attacker".to_string(),
             reached_target: host.to_string(),
+            required_privilege: PrivilegeLevel::Root,
         }
     }
 
@@ -210,7 +218,9 @@ mod tests {
     fn simple_one_hop_compromise_reaches_goal() {
         let graph = evaluate_attack_graph_naive(
             vec![root_vulnerability("web", "CVE-WEB", "https")],
-            vec![NetworkAccessRule::new("internet", "web", "https")],
+            vec![NetworkAccessRule::new_default_port(
+                "internet", "web", "https",
+            )],
             no_firewall_rules(),
             attacker_at("internet"),
             goal("web"),
@@ -222,6 +232,7 @@ mod tests {
                 source_host: "internet".to_string(),
                 destination_host: "web".to_string(),
                 service_name: "https".to_string(),
+                port: 443,
             }));
         assert!(graph
             .code_executions
@@ -241,8 +252,8 @@ mod tests {
                 root_vulnerability("db", "CVE-DB", "postgres"),
             ],
             vec![
-                NetworkAccessRule::new("internet", "web", "https"),
-                NetworkAccessRule::new("web", "db", "postgres"),
+                NetworkAccessRule::new_default_port("internet", "web", "https"),
+                NetworkAccessRule::new_default_port("web", "db", "postgres"),
             ],
             no_firewall_rules(),
             attacker_at("internet"),
@@ -263,7 +274,9 @@ mod tests {
     fn firewall_deny_blocks_path() {
         let graph = evaluate_attack_graph_naive(
             vec![root_vulnerability("web", "CVE-WEB", "https")],
-            vec![NetworkAccessRule::new("internet", "web", "https")],
+            vec![NetworkAccessRule::new_default_port(
+                "internet", "web", "https",
+            )],
             vec![FirewallRuleRecord::create_deny_rule(
                 "internet", "web", "https",
             )],
@@ -284,7 +297,9 @@ mod tests {
 
     #[test]
     fn removing_vulnerability_changes_recomputed_result() {
-        let network = vec![NetworkAccessRule::new("internet", "web", "https")];
+        let network = vec![NetworkAccessRule::new_default_port(
+            "internet", "web", "https",
+        )];
         let initial_graph = evaluate_attack_graph_naive(
             vec![root_vulnerability("web", "CVE-WEB", "https")],
             network.clone(),
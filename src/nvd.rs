@@ -0,0 +1,222 @@
+// NVD feed import
+// ---------------
+// Reads CVEs from an NVD 1.1 JSON data feed (the `nvdcve-1.1-*.json`
+// files) and turns the ones with a CVSS v3 score into vulnerability
+// records.  Feed entries are not tied to hosts, so the caller supplies
+// a closure that places each CVE onto its inventory as
+// (host, service) pairs; one record is produced per placement.
+//
+// The CVSS v3 exploitability subscore (0.0-3.9) is rescaled to the
+// record's 0.0-10.0 exploitability.  The privilege gained is derived
+// from the CVSS v3 vector:
+//
+//   - scope CHANGED: the exploit escapes the vulnerable component
+//     (sandbox or VM escape, container breakout) -> Root;
+//   - no privileges required and high confidentiality, integrity and
+//     availability impact, the usual shape of an unauthenticated RCE
+//     -> Root;
+//   - anything else -> User.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::schema::{HostIdentifier, PrivilegeLevel, ServiceName, Vulnerability};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NvdError {
+    Io { path: PathBuf, message: String },
+    Json(String),
+}
+
+impl fmt::Display for NvdError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NvdError::Io { path, message } => {
+                write!(formatter, "failed to read {}: {message}", path.display())
+            }
+            NvdError::Json(message) => write!(formatter, "invalid NVD feed: {message}"),
+        }
+    }
+}
+
+impl Error for NvdError {}
+
+// The subset of the NVD 1.1 schema this importer reads.
+#[derive(Debug, Deserialize)]
+struct NvdFeed {
+    #[serde(rename = "CVE_Items")]
+    cve_items: Vec<NvdItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdItem {
+    cve: NvdCve,
+    #[serde(default)]
+    impact: NvdImpact,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdCve {
+    #[serde(rename = "CVE_data_meta")]
+    data_meta: NvdDataMeta,
+}
+
+#[derive(Debug, Deserialize)]
+struct NvdDataMeta {
+    #[serde(rename = "ID")]
+    id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NvdImpact {
+    #[serde(rename = "baseMetricV3")]
+    base_metric_v3: Option<NvdBaseMetricV3>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NvdBaseMetricV3 {
+    #[serde(rename = "cvssV3")]
+    cvss_v3: CvssV3,
+    // CVSS v3 exploitability subscore, 0.0-3.9.
+    exploitability_score: Option<f64>,
+}
+
+// Top of the CVSS v3 exploitability subscore range.
+const MAXIMUM_EXPLOITABILITY_SUBSCORE: f64 = 3.9;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CvssV3 {
+    privileges_required: String,
+    scope: String,
+    confidentiality_impact: String,
+    integrity_impact: String,
+    availability_impact: String,
+}
+
+impl CvssV3 {
+    fn privilege_gained(&self) -> PrivilegeLevel {
+        let scope_changed = self.scope == "CHANGED";
+        let unauthenticated_full_compromise = self.privileges_required == "NONE"
+            && self.confidentiality_impact == "HIGH"
+            && self.integrity_impact == "HIGH"
+            && self.availability_impact == "HIGH";
+
+        if scope_changed || unauthenticated_full_compromise {
+            PrivilegeLevel::Root
+        } else {
+            PrivilegeLevel::User
+        }
+    }
+}
+
+/// Loads the CVEs of an NVD 1.1 JSON feed that have a CVSS v3 score.
+/// `host_for_cve` maps a CVE id to the (host, service) pairs it
+/// affects in the caller's inventory; CVEs mapped nowhere produce no
+/// records.  Entries without a v3 score are skipped and counted in an
+/// info log message.
+pub fn load_nvd_feed<F>(path: &Path, host_for_cve: F) -> Result<Vec<Vulnerability>, NvdError>
+where
+    F: Fn(&str) -> Vec<(HostIdentifier, ServiceName)>,
+{
+    let contents = fs::read_to_string(path).map_err(|error| NvdError::Io {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })?;
+    let feed: NvdFeed =
+        serde_json::from_str(&contents).map_err(|error| NvdError::Json(error.to_string()))?;
+
+    let mut vulnerabilities = Vec::new();
+    let mut skipped_without_v3 = 0usize;
+    for item in feed.cve_items {
+        let Some(base_metric) = item.impact.base_metric_v3 else {
+            skipped_without_v3 += 1;
+            continue;
+        };
+        let cve_id = item.cve.data_meta.id;
+        let privilege = base_metric.cvss_v3.privilege_gained();
+        for (host, service) in host_for_cve(&cve_id) {
+            let mut vulnerability = Vulnerability::new(&host, &cve_id, &service, privilege.clone());
+            if let Some(subscore) = base_metric.exploitability_score {
+                vulnerability = vulnerability
+                    .with_exploitability(subscore / MAXIMUM_EXPLOITABILITY_SUBSCORE * 10.0);
+            }
+            vulnerabilities.push(vulnerability);
+        }
+    }
+
+    if skipped_without_v3 > 0 {
+        info!(
+            "skipped {skipped_without_v3} NVD entries without a CVSS v3 score in {}",
+            path.display()
+        );
+    }
+    Ok(vulnerabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/nvd_feed.json")
+    }
+
+    #[test]
+    fn maps_cvss_v3_vectors_onto_inventory_hosts() {
+        let vulnerabilities = load_nvd_feed(&fixture(), |cve_id| match cve_id {
+            "CVE-2021-44228" => vec![
+                ("app01".to_string(), "http".to_string()),
+                ("app02".to_string(), "http".to_string()),
+            ],
+            "CVE-2019-5736" => vec![("docker01".to_string(), "ssh".to_string())],
+            "CVE-2020-1938" => vec![("tomcat01".to_string(), "ajp".to_string())],
+            _ => Vec::new(),
+        })
+        .expect("fixture feed should load");
+
+        assert_eq!(
+            vulnerabilities,
+            vec![
+                Vulnerability::new("app01", "CVE-2021-44228", "http", PrivilegeLevel::Root)
+                    .with_exploitability(10.0),
+                Vulnerability::new("app02", "CVE-2021-44228", "http", PrivilegeLevel::Root)
+                    .with_exploitability(10.0),
+                Vulnerability::new("docker01", "CVE-2019-5736", "ssh", PrivilegeLevel::Root),
+                Vulnerability::new("tomcat01", "CVE-2020-1938", "ajp", PrivilegeLevel::User),
+            ]
+        );
+    }
+
+    #[test]
+    fn entries_without_v3_score_are_skipped() {
+        let vulnerabilities = load_nvd_feed(&fixture(), |cve_id| {
+            vec![("host".to_string(), cve_id.to_string())]
+        })
+        .expect("fixture feed should load");
+
+        assert!(vulnerabilities
+            .iter()
+            .all(|vulnerability| vulnerability.vulnerability_id != "CVE-2004-0001"));
+        assert_eq!(vulnerabilities.len(), 3);
+    }
+
+    #[test]
+    fn malformed_feed_is_a_json_error() {
+        let path = std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_nvd_test_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, r#"{"CVE_Items": [{"cve": {}}]}"#).unwrap();
+
+        let result = load_nvd_feed(&path, |_| Vec::new());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(NvdError::Json(_))));
+    }
+}
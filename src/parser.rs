@@ -4,8 +4,9 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::schema::{
-    AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord, LocalVulnerabilityRecord,
-    NetworkAccessRule, PrivilegeLevel, VulnerabilityRecord,
+    default_port, AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord,
+    LocalVulnerabilityRecord, NetworkAccessRule, PortNumber, PrivilegeLevel, UnknownPrivilegeLevel,
+    VulnerabilityRecord,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -70,6 +71,7 @@ pub enum ParseError {
         found: usize,
     },
     InvalidPrivilege(String),
+    InvalidPort(String),
     Io {
         path: PathBuf,
         message: String,
@@ -99,6 +101,7 @@ impl fmt::Display for ParseError {
             ParseError::InvalidPrivilege(privilege) => {
                 write!(formatter, "invalid privilege value: {privilege}")
             }
+            ParseError::InvalidPort(port) => write!(formatter, "invalid port: {port}"),
             ParseError::Io { path, message } => {
                 write!(formatter, "failed to read {}: {message}", path.display())
             }
@@ -149,13 +152,23 @@ pub fn parse_fact_line(line: &str) -> Result<Option<InputFact>, ParseError> {
 
     match predicate {
         "vulExists" => {
-            require_arity(predicate, &arguments, 4)?;
-            Ok(Some(InputFact::VulExists(VulnerabilityRecord::new(
-                &arguments[0],
-                &arguments[1],
-                &arguments[2],
-                parse_privilege(&arguments[3])?,
-            ))))
+            // `(Host, CVE, Service, Priv)` or `(Host, CVE, Service, Port, Priv)`.
+            let (port, privilege) = match arguments.len() {
+                4 => (default_port(&arguments[2]), &arguments[3]),
+                _ => {
+                    require_arity(predicate, &arguments, 5)?;
+                    (parse_port(&arguments[3])?, &arguments[4])
+                }
+            };
+            Ok(Some(InputFact::VulExists(
+                VulnerabilityRecord::new(
+                    &arguments[0],
+                    &arguments[1],
+                    &arguments[2],
+                    privilege.parse().map_err(invalid_privilege)?,
+                )
+                .with_port(port),
+            )))
         }
         "localVulExists" => {
             require_arity(predicate, &arguments, 3)?;
@@ -163,22 +176,24 @@ pub fn parse_fact_line(line: &str) -> Result<Option<InputFact>, ParseError> {
                 LocalVulnerabilityRecord::new(
                     &arguments[0],
                     &arguments[1],
-                    parse_privilege(&arguments[2])?,
+                    arguments[2].parse().map_err(invalid_privilege)?,
                 ),
             )))
         }
         "hacl" => {
-            require_arity(predicate, &arguments, 3)?;
+            let port = parse_optional_port(predicate, &arguments)?;
             Ok(Some(InputFact::Hacl(NetworkAccessRule::new(
                 &arguments[0],
                 &arguments[1],
                 &arguments[2],
+                port,
             ))))
         }
         "firewallDeny" => {
-            require_arity(predicate, &arguments, 3)?;
+            let port = parse_optional_port(predicate, &arguments)?;
             Ok(Some(InputFact::FirewallDeny(
-                FirewallRuleRecord::create_deny_rule(&arguments[0], &arguments[1], &arguments[2]),
+                FirewallRuleRecord::create_deny_rule(&arguments[0], &arguments[1], &arguments[2])
+                    .with_port(port),
             )))
         }
         "attackerLocated" => {
@@ -187,16 +202,16 @@ pub fn parse_fact_line(line: &str) -> Result<Option<InputFact>, ParseError> {
                 AttackerStartingPosition::new(
                     &arguments[0],
                     &arguments[1],
-                    parse_privilege(&arguments[2])?,
+                    arguments[2].parse().map_err(invalid_privilege)?,
                 ),
             )))
         }
         "attackGoal" => {
-            require_arity(predicate, &arguments, 2)?;
-            Ok(Some(InputFact::AttackGoal(AttackerTargetGoal::new(
-                &arguments[0],
-                &arguments[1],
-            ))))
+            let required_privilege = parse_optional_privilege(predicate, &arguments)?;
+            Ok(Some(InputFact::AttackGoal(
+                AttackerTargetGoal::new(&arguments[0], &arguments[1])
+                    .with_required_privilege(required_privilege),
+            )))
         }
         _ => Err(ParseError::UnknownPredicate(predicate.to_string())),
     }
@@ -289,15 +304,42 @@ fn require_arity(predicate: &str, arguments: &[String], expected: usize) -> Resu
     }
 }
 
-fn parse_privilege(privilege: &str) -> Result<PrivilegeLevel, ParseError> {
-    match privilege {
-        "none" => Ok(PrivilegeLevel::None),
-        "user" => Ok(PrivilegeLevel::User),
-        "root" => Ok(PrivilegeLevel::Root),
-        _ => Err(ParseError::InvalidPrivilege(privilege.to_string())),
+// `(Src, Dst, Service)` or `(Src, Dst, Service, Port)`; without a port
+// the service's default port is assumed.
+fn parse_optional_port(predicate: &str, arguments: &[String]) -> Result<PortNumber, ParseError> {
+    match arguments.len() {
+        3 => Ok(default_port(&arguments[2])),
+        _ => {
+            require_arity(predicate, arguments, 4)?;
+            parse_port(&arguments[3])
+        }
     }
 }
 
+fn parse_port(port: &str) -> Result<PortNumber, ParseError> {
+    port.parse()
+        .map_err(|_| ParseError::InvalidPort(port.to_string()))
+}
+
+// `(Attacker, Host)` or `(Attacker, Host, Privilege)`; without a
+// privilege the goal requires root.
+fn parse_optional_privilege(
+    predicate: &str,
+    arguments: &[String],
+) -> Result<PrivilegeLevel, ParseError> {
+    match arguments.len() {
+        2 => Ok(PrivilegeLevel::Root),
+        _ => {
+            require_arity(predicate, arguments, 3)?;
+            arguments[2].parse().map_err(invalid_privilege)
+        }
+    }
+}
+
+fn invalid_privilege(error: UnknownPrivilegeLevel) -> ParseError {
+    ParseError::InvalidPrivilege(error.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,7 +357,7 @@ mod tests {
         );
         assert_eq!(
             parse_fact_line("hacl(internet, web01, https)."),
-            Ok(Some(InputFact::Hacl(NetworkAccessRule::new(
+            Ok(Some(InputFact::Hacl(NetworkAccessRule::new_default_port(
                 "internet", "web01", "https"
             ))))
         );
@@ -343,6 +385,40 @@ mod tests {
                 "eve", "admin01"
             ))))
         );
+        assert_eq!(
+            parse_fact_line("attackGoal(eve, jump01, user)."),
+            Ok(Some(InputFact::AttackGoal(
+                AttackerTargetGoal::new("eve", "jump01")
+                    .with_required_privilege(PrivilegeLevel::User)
+            )))
+        );
+    }
+
+    #[test]
+    fn parses_explicit_ports_on_hacl_vul_exists_and_firewall_deny() {
+        assert_eq!(
+            parse_fact_line("hacl(internet, web01, ssh, 2222)."),
+            Ok(Some(InputFact::Hacl(NetworkAccessRule::new(
+                "internet", "web01", "ssh", 2222
+            ))))
+        );
+        assert_eq!(
+            parse_fact_line("firewallDeny(internet, web01, http, 8080)."),
+            Ok(Some(InputFact::FirewallDeny(
+                FirewallRuleRecord::create_deny_rule("internet", "web01", "http").with_port(8080)
+            )))
+        );
+        assert_eq!(
+            parse_fact_line("vulExists(web01, cve_2024_1234, http, 8080, user)."),
+            Ok(Some(InputFact::VulExists(
+                VulnerabilityRecord::new("web01", "cve_2024_1234", "http", PrivilegeLevel::User)
+                    .with_port(8080)
+            )))
+        );
+        assert_eq!(
+            parse_fact_line("hacl(internet, web01, ssh, 70000)."),
+            Err(ParseError::InvalidPort("70000".to_string()))
+        );
     }
 
     #[test]
@@ -420,7 +496,7 @@ mod tests {
         assert_eq!(
             parse_update_line("remove(hacl(internet, web01, https))."),
             Ok(Some(InputUpdate::Remove(InputFact::Hacl(
-                NetworkAccessRule::new("internet", "web01", "https")
+                NetworkAccessRule::new_default_port("internet", "web01", "https")
             ))))
         );
         assert_eq!(
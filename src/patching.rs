@@ -0,0 +1,404 @@
+// Minimum patch set
+// -----------------
+// The smallest set of remote vulnerabilities whose patching stops every
+// attacker from owning any of its goal hosts, found as a minimum vertex
+// cut in a flow network built from the base facts:
+//
+//   source -> starting host                       (unbounded)
+//   host S -> vulnerability V on host D            (unbounded, one edge per
+//             effective access S -> D on V's service and port)
+//   V_in   -> V_out                                (capacity 1)
+//   V_out  -> host D                               (unbounded)
+//   V_out  -> sink   when D is a goal and V grants what it requires
+//   host D -> sink   when D is a goal some exploit of D falls short
+//                    of, and a local vulnerability grants enough
+//
+// Every vulnerability is split into an in and an out node joined by a
+// unit edge, so cutting that edge is patching the vulnerability; all
+// other edges are unbounded and never part of a minimum cut.  A goal
+// host is held to the weakest privilege any goal on it requires: an
+// exploit granting less only counts when a local vulnerability can
+// finish the job.  Local vulnerabilities themselves are not patch
+// candidates.
+//
+// Max flow is computed with Edmonds-Karp (BFS augmenting paths).  Each
+// augmentation costs O(N + E) for N nodes and E edges, and since every
+// augmenting path crosses at least one unit edge there are at most K
+// augmentations for K vulnerabilities: O(K * (N + E)) overall.  The cut
+// returned is the one closest to the attackers: the unit edges leaving
+// the set of nodes still reachable from the source in the residual
+// graph.
+//
+// All attackers share one source and all goals one sink, so the cut
+// separates every starting host from every goal.  With a single
+// attacker (or attackers that share their goals) that is exactly the
+// minimum; with several attackers chasing different goals it is a
+// valid patch set but may not be the smallest one, since that problem
+// (minimum multicut) is NP-hard.  Goals on an attacker's own starting
+// host cannot be cut by patching and are ignored.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::engine::{effective_network_access_from_base, BaseFacts};
+use crate::schema::{HostIdentifier, PrivilegeLattice, PrivilegeLevel, VulnerabilityRecord};
+
+const SOURCE: usize = 0;
+const SINK: usize = 1;
+
+/// The smallest set of vulnerabilities to patch so that no attacker
+/// reaches any of its goals, sorted.  Empty when no goal is reachable
+/// in the first place.  See the module comment for the algorithm.
+pub fn minimum_patch_set(facts: &BaseFacts) -> Vec<VulnerabilityRecord> {
+    let vulnerabilities: Vec<VulnerabilityRecord> = facts
+        .vulnerabilities
+        .iter()
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let starting_hosts: BTreeSet<&HostIdentifier> = facts
+        .attacker_positions
+        .iter()
+        .map(|position| &position.starting_host)
+        .collect();
+    // Goal host -> weakest privilege a goal on it requires.
+    let mut goal_requirements: BTreeMap<&HostIdentifier, &PrivilegeLevel> = BTreeMap::new();
+    for goal in &facts.attacker_goals {
+        if starting_hosts.contains(&goal.target_host_name) {
+            continue;
+        }
+        let required = goal_requirements
+            .entry(&goal.target_host_name)
+            .or_insert(&goal.required_privilege);
+        *required = (*required).min(&goal.required_privilege);
+    }
+    let grants_goal = |host: &HostIdentifier, privilege: &PrivilegeLevel| {
+        goal_requirements
+            .get(host)
+            .is_some_and(|required| privilege.dominates(required))
+    };
+    let locally_finished: BTreeSet<&HostIdentifier> = facts
+        .local_vulnerabilities
+        .iter()
+        .filter(|vulnerability| {
+            grants_goal(
+                &vulnerability.host_name,
+                &vulnerability.privilege_gained_on_exploit,
+            )
+        })
+        .map(|vulnerability| &vulnerability.host_name)
+        .collect();
+
+    let mut hosts: BTreeMap<HostIdentifier, usize> = BTreeMap::new();
+    let mut network = FlowNetwork::new(2);
+    let mut host_node = |network: &mut FlowNetwork, host: &HostIdentifier| {
+        *hosts
+            .entry(host.clone())
+            .or_insert_with(|| network.add_node())
+    };
+
+    for host in &starting_hosts {
+        let node = host_node(&mut network, host);
+        network.add_edge(SOURCE, node, UNBOUNDED);
+    }
+    for host in &locally_finished {
+        let falls_short = vulnerabilities.iter().any(|vulnerability| {
+            &vulnerability.host_name == *host
+                && !grants_goal(host, &vulnerability.privilege_gained_on_exploit)
+        });
+        if falls_short {
+            let node = host_node(&mut network, host);
+            network.add_edge(node, SINK, UNBOUNDED);
+        }
+    }
+
+    // Effective access keyed the way vulnerabilities are matched.
+    let mut sources_by_target: BTreeMap<_, BTreeSet<HostIdentifier>> = BTreeMap::new();
+    for access in effective_network_access_from_base(facts) {
+        sources_by_target
+            .entry((access.destination_host, access.service_name, access.port))
+            .or_default()
+            .insert(access.source_host);
+    }
+
+    let mut cut_edges = Vec::with_capacity(vulnerabilities.len());
+    for vulnerability in &vulnerabilities {
+        let vulnerability_in = network.add_node();
+        let vulnerability_out = network.add_node();
+        cut_edges.push((vulnerability_in, vulnerability_out));
+        network.add_edge(vulnerability_in, vulnerability_out, 1);
+
+        let destination = host_node(&mut network, &vulnerability.host_name);
+        network.add_edge(vulnerability_out, destination, UNBOUNDED);
+        if grants_goal(
+            &vulnerability.host_name,
+            &vulnerability.privilege_gained_on_exploit,
+        ) {
+            network.add_edge(vulnerability_out, SINK, UNBOUNDED);
+        }
+
+        let target = (
+            vulnerability.host_name.clone(),
+            vulnerability.affected_service.clone(),
+            vulnerability.port,
+        );
+        for source_host in sources_by_target.get(&target).into_iter().flatten() {
+            let source = host_node(&mut network, source_host);
+            network.add_edge(source, vulnerability_in, UNBOUNDED);
+        }
+    }
+
+    network.saturate(SOURCE, SINK);
+    let source_side = network.reachable_from(SOURCE);
+
+    vulnerabilities
+        .into_iter()
+        .zip(cut_edges)
+        .filter(|(_, (vulnerability_in, vulnerability_out))| {
+            source_side[*vulnerability_in] && !source_side[*vulnerability_out]
+        })
+        .map(|(vulnerability, _)| vulnerability)
+        .collect()
+}
+
+// Larger than any possible flow: every augmenting path crosses a unit
+// edge, so the flow never exceeds the number of vulnerabilities.
+const UNBOUNDED: u32 = u32::MAX;
+
+struct FlowEdge {
+    to: usize,
+    residual: u32,
+    // Index of the paired reverse edge in `adjacency[to]`.
+    reverse: usize,
+}
+
+struct FlowNetwork {
+    adjacency: Vec<Vec<FlowEdge>>,
+}
+
+impl FlowNetwork {
+    fn new(nodes: usize) -> Self {
+        FlowNetwork {
+            adjacency: (0..nodes).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    fn add_node(&mut self) -> usize {
+        self.adjacency.push(Vec::new());
+        self.adjacency.len() - 1
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, capacity: u32) {
+        let forward = self.adjacency[from].len();
+        let backward = self.adjacency[to].len() + usize::from(from == to);
+        self.adjacency[from].push(FlowEdge {
+            to,
+            residual: capacity,
+            reverse: backward,
+        });
+        self.adjacency[to].push(FlowEdge {
+            to: from,
+            residual: 0,
+            reverse: forward,
+        });
+    }
+
+    // Edmonds-Karp: push flow along shortest augmenting paths until the
+    // sink is no longer reachable in the residual graph.
+    fn saturate(&mut self, source: usize, sink: usize) {
+        loop {
+            let mut parent: Vec<Option<(usize, usize)>> = vec![None; self.adjacency.len()];
+            let mut visited = vec![false; self.adjacency.len()];
+            let mut queue = VecDeque::from([source]);
+            visited[source] = true;
+            while let Some(node) = queue.pop_front() {
+                if node == sink {
+                    break;
+                }
+                for (index, edge) in self.adjacency[node].iter().enumerate() {
+                    if edge.residual > 0 && !visited[edge.to] {
+                        visited[edge.to] = true;
+                        parent[edge.to] = Some((node, index));
+                        queue.push_back(edge.to);
+                    }
+                }
+            }
+            if !visited[sink] {
+                return;
+            }
+
+            let mut bottleneck = u32::MAX;
+            let mut node = sink;
+            while let Some((previous, index)) = parent[node] {
+                bottleneck = bottleneck.min(self.adjacency[previous][index].residual);
+                node = previous;
+            }
+            let mut node = sink;
+            while let Some((previous, index)) = parent[node] {
+                let reverse = self.adjacency[previous][index].reverse;
+                self.adjacency[previous][index].residual -= bottleneck;
+                let back_edge = &mut self.adjacency[node][reverse];
+                back_edge.residual = back_edge.residual.saturating_add(bottleneck);
+                node = previous;
+            }
+        }
+    }
+
+    fn reachable_from(&self, source: usize) -> Vec<bool> {
+        let mut visited = vec![false; self.adjacency.len()];
+        let mut queue = VecDeque::from([source]);
+        visited[source] = true;
+        while let Some(node) = queue.pop_front() {
+            for edge in &self.adjacency[node] {
+                if edge.residual > 0 && !visited[edge.to] {
+                    visited[edge.to] = true;
+                    queue.push_back(edge.to);
+                }
+            }
+        }
+        visited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmarks::chain_base_facts;
+    use crate::schema::{
+        AttackerStartingPosition, AttackerTargetGoal, LocalVulnerabilityRecord, NetworkAccessRule,
+    };
+
+    fn facts_from(
+        edges: &[(&str, &str, &str)],
+        vulnerabilities: Vec<VulnerabilityRecord>,
+        goal: &str,
+    ) -> BaseFacts {
+        BaseFacts {
+            vulnerabilities,
+            network_access: edges
+                .iter()
+                .map(|(source, destination, service)| {
+                    NetworkAccessRule::new_default_port(source, destination, service)
+                })
+                .collect(),
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            attacker_goals: vec![AttackerTargetGoal::new("eve", goal)],
+            ..BaseFacts::default()
+        }
+    }
+
+    #[test]
+    fn linear_chain_needs_exactly_one_patch() {
+        let facts = chain_base_facts(6);
+
+        let patches = minimum_patch_set(&facts);
+
+        assert_eq!(patches.len(), 1);
+        assert!(facts.vulnerabilities.contains(&patches[0]));
+    }
+
+    #[test]
+    fn parallel_exploits_are_cut_at_the_shared_chokepoint() {
+        // Two exploitable services into gw, then one into db: patching
+        // the single db exploit beats patching both gw exploits.
+        let facts = facts_from(
+            &[
+                ("internet", "gw", "ssh"),
+                ("internet", "gw", "http"),
+                ("gw", "db", "postgres"),
+            ],
+            vec![
+                VulnerabilityRecord::new("gw", "CVE-GW-SSH", "ssh", PrivilegeLevel::User),
+                VulnerabilityRecord::new("gw", "CVE-GW-HTTP", "http", PrivilegeLevel::User),
+                VulnerabilityRecord::new("db", "CVE-DB", "postgres", PrivilegeLevel::Root),
+            ],
+            "db",
+        );
+
+        assert_eq!(
+            minimum_patch_set(&facts),
+            vec![VulnerabilityRecord::new(
+                "db",
+                "CVE-DB",
+                "postgres",
+                PrivilegeLevel::Root
+            )]
+        );
+    }
+
+    #[test]
+    fn user_exploit_of_goal_counts_only_with_local_root() {
+        let mut facts = facts_from(
+            &[("internet", "db", "ssh"), ("internet", "db", "postgres")],
+            vec![
+                VulnerabilityRecord::new("db", "CVE-DB-SSH", "ssh", PrivilegeLevel::User),
+                VulnerabilityRecord::new("db", "CVE-DB-PG", "postgres", PrivilegeLevel::Root),
+            ],
+            "db",
+        );
+        assert_eq!(
+            minimum_patch_set(&facts),
+            vec![VulnerabilityRecord::new(
+                "db",
+                "CVE-DB-PG",
+                "postgres",
+                PrivilegeLevel::Root
+            )]
+        );
+
+        facts
+            .local_vulnerabilities
+            .push(LocalVulnerabilityRecord::new(
+                "db",
+                "CVE-DB-LPE",
+                PrivilegeLevel::Root,
+            ));
+        assert_eq!(minimum_patch_set(&facts).len(), 2);
+    }
+
+    #[test]
+    fn user_goal_is_cut_at_its_user_exploit() {
+        let mut facts = facts_from(
+            &[("internet", "web", "http"), ("web", "db", "ssh")],
+            vec![
+                VulnerabilityRecord::new("web", "CVE-WEB", "http", PrivilegeLevel::User),
+                VulnerabilityRecord::new("db", "CVE-DB-SSH", "ssh", PrivilegeLevel::User),
+            ],
+            "db",
+        );
+        facts.attacker_goals = vec![
+            AttackerTargetGoal::new("eve", "db").with_required_privilege(PrivilegeLevel::User)
+        ];
+
+        let patches = minimum_patch_set(&facts);
+
+        assert_eq!(patches.len(), 1);
+        let mut patched = facts.clone();
+        patched
+            .vulnerabilities
+            .retain(|vulnerability| !patches.contains(vulnerability));
+        assert!(crate::engine::evaluate_base_facts(&patched)
+            .goals_reached
+            .is_empty());
+    }
+
+    #[test]
+    fn unreachable_goal_needs_no_patches() {
+        let facts = facts_from(
+            &[("internet", "web", "http")],
+            vec![VulnerabilityRecord::new(
+                "web",
+                "CVE-WEB",
+                "http",
+                PrivilegeLevel::Root,
+            )],
+            "db",
+        );
+
+        assert!(minimum_patch_set(&facts).is_empty());
+    }
+}
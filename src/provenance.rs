@@ -6,9 +6,15 @@ use std::path::Path;
 use crate::schema::{
     AttackerCodeExecution, AttackerGoalReached, AttackerOwnsMachine, AttackerStartingPosition,
     AttackerTargetGoal, EffectiveNetworkAccess, FirewallRuleRecord, LocalVulnerabilityRecord,
-    NetworkAccessRule, PrivilegeLevel, VulnerabilityRecord,
+    NetworkAccessRule, PortNumber, PrivilegeLattice, PrivilegeLevel, VulnerabilityRecord,
 };
 
+const PRIVILEGES_DESCENDING: [PrivilegeLevel; 3] = [
+    PrivilegeLevel::Root,
+    PrivilegeLevel::User,
+    PrivilegeLevel::None,
+];
+
 /// Canonical fact representation used by the explanation layer.
 ///
 /// This is intentionally independent of Differential Dataflow internals:
@@ -20,6 +26,7 @@ pub enum Fact {
         host: String,
         vulnerability_id: String,
         service: String,
+        port: PortNumber,
         privilege: PrivilegeLevel,
     },
     LocalVulExists {
@@ -31,11 +38,13 @@ pub enum Fact {
         source: String,
         destination: String,
         service: String,
+        port: PortNumber,
     },
     FirewallDeny {
         source: String,
         destination: String,
         service: String,
+        port: PortNumber,
     },
     AttackerLocated {
         attacker_id: String,
@@ -45,11 +54,13 @@ pub enum Fact {
     AttackGoal {
         attacker_id: String,
         target: String,
+        required_privilege: PrivilegeLevel,
     },
     EffectiveAccess {
         source: String,
         destination: String,
         service: String,
+        port: PortNumber,
     },
     ExecCode {
         attacker_id: String,
@@ -63,6 +74,7 @@ pub enum Fact {
     GoalReached {
         attacker_id: String,
         target: String,
+        required_privilege: PrivilegeLevel,
     },
 }
 
@@ -235,6 +247,7 @@ impl From<&VulnerabilityRecord> for Fact {
             host: record.host_name.clone(),
             vulnerability_id: record.vulnerability_id.clone(),
             service: record.affected_service.clone(),
+            port: record.port,
             privilege: record.privilege_gained_on_exploit.clone(),
         }
     }
@@ -256,6 +269,7 @@ impl From<&NetworkAccessRule> for Fact {
             source: record.source_host.clone(),
             destination: record.destination_host.clone(),
             service: record.service_name.clone(),
+            port: record.port,
         }
     }
 }
@@ -266,6 +280,7 @@ impl From<&FirewallRuleRecord> for Fact {
             source: record.source_zone.clone(),
             destination: record.destination_host.clone(),
             service: record.service_name.clone(),
+            port: record.port,
         }
     }
 }
@@ -285,6 +300,7 @@ impl From<&AttackerTargetGoal> for Fact {
         Fact::AttackGoal {
             attacker_id: record.attacker_id.clone(),
             target: record.target_host_name.clone(),
+            required_privilege: record.required_privilege.clone(),
         }
     }
 }
@@ -295,6 +311,7 @@ impl From<&EffectiveNetworkAccess> for Fact {
             source: record.source_host.clone(),
             destination: record.destination_host.clone(),
             service: record.service_name.clone(),
+            port: record.port,
         }
     }
 }
@@ -323,6 +340,7 @@ impl From<&AttackerGoalReached> for Fact {
         Fact::GoalReached {
             attacker_id: record.attacker_id.clone(),
             target: record.reached_target.clone(),
+            required_privilege: record.required_privilege.clone(),
         }
     }
 }
@@ -334,10 +352,11 @@ impl fmt::Display for Fact {
                 host,
                 vulnerability_id,
                 service,
+                port,
                 privilege,
             } => write!(
                 formatter,
-                "vulExists({host}, {vulnerability_id}, {service}, {privilege})"
+                "vulExists({host}, {vulnerability_id}, {service}, {port}, {privilege})"
             ),
             Fact::LocalVulExists {
                 host,
@@ -351,14 +370,19 @@ impl fmt::Display for Fact {
                 source,
                 destination,
                 service,
-            } => write!(formatter, "hacl({source}, {destination}, {service})"),
+                port,
+            } => write!(
+                formatter,
+                "hacl({source}, {destination}, {service}, {port})"
+            ),
             Fact::FirewallDeny {
                 source,
                 destination,
                 service,
+                port,
             } => write!(
                 formatter,
-                "firewallDeny({source}, {destination}, {service})"
+                "firewallDeny({source}, {destination}, {service}, {port})"
             ),
             Fact::AttackerLocated {
                 attacker_id,
@@ -368,17 +392,28 @@ impl fmt::Display for Fact {
                 formatter,
                 "attackerLocated({attacker_id}, {host}, {privilege})"
             ),
+            // Root is the implied requirement, as in the facts parser.
             Fact::AttackGoal {
                 attacker_id,
                 target,
+                required_privilege: PrivilegeLevel::Root,
             } => write!(formatter, "attackGoal({attacker_id}, {target})"),
+            Fact::AttackGoal {
+                attacker_id,
+                target,
+                required_privilege,
+            } => write!(
+                formatter,
+                "attackGoal({attacker_id}, {target}, {required_privilege})"
+            ),
             Fact::EffectiveAccess {
                 source,
                 destination,
                 service,
+                port,
             } => write!(
                 formatter,
-                "effectiveAccess({source}, {destination}, {service})"
+                "effectiveAccess({source}, {destination}, {service}, {port})"
             ),
             Fact::ExecCode {
                 attacker_id,
@@ -391,7 +426,16 @@ impl fmt::Display for Fact {
             Fact::GoalReached {
                 attacker_id,
                 target,
+                required_privilege: PrivilegeLevel::Root,
             } => write!(formatter, "goalReached({attacker_id}, {target})"),
+            Fact::GoalReached {
+                attacker_id,
+                target,
+                required_privilege,
+            } => write!(
+                formatter,
+                "goalReached({attacker_id}, {target}, {required_privilege})"
+            ),
         }
     }
 }
@@ -482,7 +526,8 @@ impl Explainer {
             Fact::GoalReached {
                 attacker_id,
                 target,
-            } => self.explain_goal_reached(attacker_id, target, visiting),
+                required_privilege,
+            } => self.explain_goal_reached(attacker_id, target, required_privilege, visiting),
             Fact::OwnsMachine { attacker_id, host } => {
                 self.explain_owns_machine(attacker_id, host, visiting)
             }
@@ -495,7 +540,8 @@ impl Explainer {
                 source,
                 destination,
                 service,
-            } => self.explain_effective_access(source, destination, service),
+                port,
+            } => self.explain_effective_access(source, destination, service, *port),
             _ => None,
         };
 
@@ -507,30 +553,47 @@ impl Explainer {
         &self,
         attacker_id: &str,
         target: &str,
+        required_privilege: &PrivilegeLevel,
         visiting: &mut HashSet<Fact>,
     ) -> Option<ExplanationTree> {
         let target_fact = Fact::GoalReached {
             attacker_id: attacker_id.to_string(),
             target: target.to_string(),
+            required_privilege: required_privilege.clone(),
         };
         let goal_fact = Fact::AttackGoal {
             attacker_id: attacker_id.to_string(),
             target: target.to_string(),
+            required_privilege: required_privilege.clone(),
         };
-        let owns_fact = Fact::OwnsMachine {
-            attacker_id: attacker_id.to_string(),
-            host: target.to_string(),
-        };
+        if !self.base_facts.contains(&goal_fact) {
+            return None;
+        }
+        let goal_tree = ExplanationTree::leaf(goal_fact.clone());
 
-        let goal_tree = self.explain_fact(&goal_fact, visiting)?;
-        let owns_tree = self.explain_fact(&owns_fact, visiting)?;
-        let premises = vec![goal_fact, owns_fact];
+        // The weakest execCode that still satisfies the goal.
+        for privilege in PRIVILEGES_DESCENDING.iter().rev() {
+            if !privilege.dominates(required_privilege) {
+                continue;
+            }
+            let exec_fact = Fact::ExecCode {
+                attacker_id: attacker_id.to_string(),
+                host: target.to_string(),
+                privilege: privilege.clone(),
+            };
+            if let Some(exec_tree) = self.explain_fact(&exec_fact, visiting) {
+                return Some(ExplanationTree::derived(
+                    target_fact,
+                    DerivationStep::new(
+                        "goal_reached_from_goal_and_exec_code",
+                        vec![goal_fact, exec_fact],
+                    ),
+                    vec![goal_tree, exec_tree],
+                ));
+            }
+        }
 
-        Some(ExplanationTree::derived(
-            target_fact,
-            DerivationStep::new("goal_reached_from_goal_and_ownership", premises),
-            vec![goal_tree, owns_tree],
-        ))
+        None
     }
 
     fn explain_owns_machine(
@@ -632,7 +695,7 @@ impl Explainer {
                 .collect::<Vec<_>>();
             previous_exec_facts.sort();
 
-            for previous_exec_fact in previous_exec_facts {
+            if let Some(previous_exec_fact) = previous_exec_facts.into_iter().next() {
                 let previous_exec_tree = self.explain_fact(&previous_exec_fact, visiting)?;
                 let local_vulnerability_tree =
                     self.explain_fact(&local_vulnerability_fact, visiting)?;
@@ -654,20 +717,22 @@ impl Explainer {
                     host: vuln_host,
                     vulnerability_id,
                     service,
+                    port,
                     privilege: vuln_privilege,
                 } if vuln_host == host && vuln_privilege == privilege => {
-                    Some((vulnerability_id, service))
+                    Some((vulnerability_id, service, *port))
                 }
                 _ => None,
             })
             .collect::<Vec<_>>();
         vulnerabilities.sort();
 
-        for (vulnerability_id, service) in vulnerabilities {
+        for (vulnerability_id, service, port) in vulnerabilities {
             let vuln_fact = Fact::VulExists {
                 host: host.to_string(),
                 vulnerability_id: vulnerability_id.clone(),
                 service: service.clone(),
+                port,
                 privilege: privilege.clone(),
             };
 
@@ -679,7 +744,11 @@ impl Explainer {
                         source,
                         destination,
                         service: access_service,
-                    } if destination == host && access_service == service => {
+                        port: access_port,
+                    } if destination == host
+                        && access_service == service
+                        && *access_port == port =>
+                    {
                         Some((source.clone(), access_service.clone()))
                     }
                     _ => None,
@@ -692,6 +761,7 @@ impl Explainer {
                     source: source.clone(),
                     destination: host.to_string(),
                     service: access_service,
+                    port,
                 };
 
                 let mut previous_exec_facts = self
@@ -714,7 +784,7 @@ impl Explainer {
                     .collect::<Vec<_>>();
                 previous_exec_facts.sort();
 
-                for previous_exec_fact in previous_exec_facts {
+                if let Some(previous_exec_fact) = previous_exec_facts.into_iter().next() {
                     let previous_exec_tree = self.explain_fact(&previous_exec_fact, visiting)?;
                     let access_tree = self.explain_fact(&access_fact, visiting)?;
                     let vulnerability_tree = self.explain_fact(&vuln_fact, visiting)?;
@@ -740,21 +810,25 @@ impl Explainer {
         source: &str,
         destination: &str,
         service: &str,
+        port: PortNumber,
     ) -> Option<ExplanationTree> {
         let target_fact = Fact::EffectiveAccess {
             source: source.to_string(),
             destination: destination.to_string(),
             service: service.to_string(),
+            port,
         };
         let network_fact = Fact::Hacl {
             source: source.to_string(),
             destination: destination.to_string(),
             service: service.to_string(),
+            port,
         };
         let deny_fact = Fact::FirewallDeny {
             source: source.to_string(),
             destination: destination.to_string(),
             service: service.to_string(),
+            port,
         };
 
         if !self.base_facts.contains(&network_fact) || self.base_facts.contains(&deny_fact) {
@@ -785,8 +859,8 @@ mod tests {
             ],
             local_vulnerabilities: Vec::new(),
             network_access: vec![
-                NetworkAccessRule::new("internet", "db01", "postgres"),
-                NetworkAccessRule::new("db01", "admin01", "smb"),
+                NetworkAccessRule::new_default_port("internet", "db01", "postgres"),
+                NetworkAccessRule::new_default_port("db01", "admin01", "smb"),
             ],
             firewall_rules: Vec::new(),
             attacker_positions: vec![AttackerStartingPosition::new(
@@ -802,11 +876,13 @@ mod tests {
                     source_host: "internet".to_string(),
                     destination_host: "db01".to_string(),
                     service_name: "postgres".to_string(),
+                    port: 5432,
                 },
                 EffectiveNetworkAccess {
                     source_host: "db01".to_string(),
                     destination_host: "admin01".to_string(),
                     service_name: "smb".to_string(),
+                    port: 445,
                 },
             ],
             code_executions: vec![
@@ -833,6 +909,7 @@ mod tests {
             goals_reached: vec![AttackerGoalReached {
                 attacker_id: "eve".to_string(),
                 reached_target: "admin01".to_string(),
+                required_privilege: PrivilegeLevel::Root,
             }],
         };
 
@@ -840,6 +917,7 @@ mod tests {
         let target = Fact::GoalReached {
             attacker_id: "eve".to_string(),
             target: "admin01".to_string(),
+            required_privilege: PrivilegeLevel::Root,
         };
         let explanation = explainer
             .explain(&target)
@@ -851,24 +929,23 @@ mod tests {
                 .as_ref()
                 .expect("goal should be derived")
                 .rule_name,
-            "goal_reached_from_goal_and_ownership"
+            "goal_reached_from_goal_and_exec_code"
         );
         assert_eq!(
             explanation.to_pretty_string(),
             concat!(
                 "goalReached(eve, admin01)\n",
                 "├── attackGoal(eve, admin01)\n",
-                "└── ownsMachine(eve, admin01)\n",
-                "    └── execCode(eve, admin01, root)\n",
-                "        ├── execCode(eve, db01, root)\n",
-                "        │   ├── execCode(eve, internet, user)\n",
-                "        │   │   └── attackerLocated(eve, internet, user)\n",
-                "        │   ├── effectiveAccess(internet, db01, postgres)\n",
-                "        │   │   └── hacl(internet, db01, postgres)\n",
-                "        │   └── vulExists(db01, CVE-2024-DB, postgres, root)\n",
-                "        ├── effectiveAccess(db01, admin01, smb)\n",
-                "        │   └── hacl(db01, admin01, smb)\n",
-                "        └── vulExists(admin01, CVE-2024-8888, smb, root)\n",
+                "└── execCode(eve, admin01, root)\n",
+                "    ├── execCode(eve, db01, root)\n",
+                "    │   ├── execCode(eve, internet, user)\n",
+                "    │   │   └── attackerLocated(eve, internet, user)\n",
+                "    │   ├── effectiveAccess(internet, db01, postgres, 5432)\n",
+                "    │   │   └── hacl(internet, db01, postgres, 5432)\n",
+                "    │   └── vulExists(db01, CVE-2024-DB, postgres, 5432, root)\n",
+                "    ├── effectiveAccess(db01, admin01, smb, 445)\n",
+                "    │   └── hacl(db01, admin01, smb, 445)\n",
+                "    └── vulExists(admin01, CVE-2024-8888, smb, 445, root)\n",
             )
         );
     }
@@ -878,7 +955,9 @@ mod tests {
         let base_facts = ProvenanceBaseFacts {
             vulnerabilities: Vec::new(),
             local_vulnerabilities: Vec::new(),
-            network_access: vec![NetworkAccessRule::new("internet", "web01", "https")],
+            network_access: vec![NetworkAccessRule::new_default_port(
+                "internet", "web01", "https",
+            )],
             firewall_rules: vec![FirewallRuleRecord::create_deny_rule(
                 "internet", "web01", "https",
             )],
@@ -890,6 +969,7 @@ mod tests {
                 source_host: "internet".to_string(),
                 destination_host: "web01".to_string(),
                 service_name: "https".to_string(),
+                port: 443,
             }],
             code_executions: Vec::new(),
             machines_owned: Vec::new(),
@@ -900,6 +980,7 @@ mod tests {
             source: "internet".to_string(),
             destination: "web01".to_string(),
             service: "https".to_string(),
+            port: 443,
         };
 
         assert_eq!(explainer.explain(&target), None);
@@ -919,7 +1000,9 @@ mod tests {
                 "CVE-2024-LOCAL",
                 PrivilegeLevel::Root,
             )],
-            network_access: vec![NetworkAccessRule::new("internet", "web01", "https")],
+            network_access: vec![NetworkAccessRule::new_default_port(
+                "internet", "web01", "https",
+            )],
             firewall_rules: Vec::new(),
             attacker_positions: vec![AttackerStartingPosition::new(
                 "eve",
@@ -933,6 +1016,7 @@ mod tests {
                 source_host: "internet".to_string(),
                 destination_host: "web01".to_string(),
                 service_name: "https".to_string(),
+                port: 443,
             }],
             code_executions: vec![
                 AttackerCodeExecution {
@@ -958,6 +1042,7 @@ mod tests {
             goals_reached: vec![AttackerGoalReached {
                 attacker_id: "eve".to_string(),
                 reached_target: "web01".to_string(),
+                required_privilege: PrivilegeLevel::Root,
             }],
         };
 
@@ -965,6 +1050,7 @@ mod tests {
         let target = Fact::GoalReached {
             attacker_id: "eve".to_string(),
             target: "web01".to_string(),
+            required_privilege: PrivilegeLevel::Root,
         };
         let explanation = explainer
             .explain(&target)
@@ -975,15 +1061,14 @@ mod tests {
             concat!(
                 "goalReached(eve, web01)\n",
                 "├── attackGoal(eve, web01)\n",
-                "└── ownsMachine(eve, web01)\n",
-                "    └── execCode(eve, web01, root)\n",
-                "        ├── execCode(eve, web01, user)\n",
-                "        │   ├── execCode(eve, internet, user)\n",
-                "        │   │   └── attackerLocated(eve, internet, user)\n",
-                "        │   ├── effectiveAccess(internet, web01, https)\n",
-                "        │   │   └── hacl(internet, web01, https)\n",
-                "        │   └── vulExists(web01, CVE-2024-WEB, https, user)\n",
-                "        └── localVulExists(web01, CVE-2024-LOCAL, root)\n",
+                "└── execCode(eve, web01, root)\n",
+                "    ├── execCode(eve, web01, user)\n",
+                "    │   ├── execCode(eve, internet, user)\n",
+                "    │   │   └── attackerLocated(eve, internet, user)\n",
+                "    │   ├── effectiveAccess(internet, web01, https, 443)\n",
+                "    │   │   └── hacl(internet, web01, https, 443)\n",
+                "    │   └── vulExists(web01, CVE-2024-WEB, https, 443, user)\n",
+                "    └── localVulExists(web01, CVE-2024-LOCAL, root)\n",
             )
         );
     }
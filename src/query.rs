@@ -0,0 +1,2122 @@
+// Query helpers
+// -------------
+// One-shot analyses over a set of base facts.  Each helper builds a
+// small dataflow on top of the rules in `rules.rs`, feeds the facts at
+// time 0, runs the worker to completion and returns plain Rust values,
+// so callers never have to write a `worker.dataflow` + input handles +
+// probe loop themselves.
+//
+// Every helper panics if its dataflow fails; its `try_` twin returns
+// the failure as an `AttackGraphError` instead.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use differential_dataflow::collection::Collection;
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::{Count, Join, Threshold};
+use serde::{Deserialize, Serialize};
+use timely::communication::allocator::thread::Thread;
+use timely::dataflow::scopes::Child;
+use timely::worker::Worker;
+
+use crate::engine::BaseFacts;
+use crate::error::{catch_computation_panic, AttackGraphError};
+use crate::rules::{
+    build_attack_graph_full, build_attack_graph_with_distance,
+    build_attack_graph_with_justifications, build_attack_graph_with_local_vulnerabilities,
+    build_attack_graph_with_options, build_attack_graph_with_paths, build_attack_routes,
+    compromise_closure, effective_network_access, exploited_cycle_components,
+    max_privilege_per_host, minimum_cost_attack_paths, network_closure, AttackGraphOptions,
+    AttackGraphOutputs,
+};
+use crate::schema::*;
+
+static QUERY_RUNTIME_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// The scope query dataflows are built in.
+pub type QueryScope<'a> = Child<'a, Worker<Thread>, usize>;
+
+/// The base fact collections handed to a query's dataflow builder.
+pub struct QueryInputs<'a> {
+    pub vulnerabilities: Collection<QueryScope<'a>, VulnerabilityRecord>,
+    pub local_vulnerabilities: Collection<QueryScope<'a>, LocalVulnerabilityRecord>,
+    pub network_access: Collection<QueryScope<'a>, NetworkAccessRule>,
+    pub firewall_rules: Collection<QueryScope<'a>, FirewallRuleRecord>,
+    pub attacker_positions: Collection<QueryScope<'a>, AttackerStartingPosition>,
+    pub attacker_goals: Collection<QueryScope<'a>, AttackerTargetGoal>,
+}
+
+/// Runs `build` over `facts` to completion and returns the records of
+/// the resulting collection with positive multiplicity, sorted.
+/// Panics if the dataflow fails; see `try_run_collection_query`.
+pub fn run_collection_query<T, F>(facts: &BaseFacts, build: F) -> Vec<T>
+where
+    T: timely::ExchangeData + Ord,
+    F: for<'a> Fn(&QueryInputs<'a>) -> Collection<QueryScope<'a>, T> + Send + Sync + 'static,
+{
+    try_run_collection_query(facts, build).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `run_collection_query`, but a panic inside the dataflow is
+/// returned as `AttackGraphError::ComputationError`.  A lock poisoned
+/// by an earlier failed query is recovered, since neither lock guards
+/// state that a panic could leave half-updated.
+pub fn try_run_collection_query<T, F>(
+    facts: &BaseFacts,
+    build: F,
+) -> Result<Vec<T>, AttackGraphError>
+where
+    T: timely::ExchangeData + Ord,
+    F: for<'a> Fn(&QueryInputs<'a>) -> Collection<QueryScope<'a>, T> + Send + Sync + 'static,
+{
+    let _runtime_guard = QUERY_RUNTIME_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let captured_in_dataflow = Arc::clone(&captured);
+    let facts = facts.clone();
+
+    catch_computation_panic(move || {
+        timely::execute_directly(move |worker| {
+            let (
+                mut vulnerability_input,
+                mut local_vulnerability_input,
+                mut network_input,
+                mut firewall_input,
+                mut attacker_position_input,
+                mut attacker_goal_input,
+            ) = worker.dataflow::<usize, _, _>(|scope| {
+                let (vulnerability_handle, vulnerabilities) =
+                    scope.new_collection::<VulnerabilityRecord, isize>();
+                let (local_vulnerability_handle, local_vulnerabilities) =
+                    scope.new_collection::<LocalVulnerabilityRecord, isize>();
+                let (network_handle, network_access) =
+                    scope.new_collection::<NetworkAccessRule, isize>();
+                let (firewall_handle, firewall_rules) =
+                    scope.new_collection::<FirewallRuleRecord, isize>();
+                let (position_handle, attacker_positions) =
+                    scope.new_collection::<AttackerStartingPosition, isize>();
+                let (goal_handle, attacker_goals) =
+                    scope.new_collection::<AttackerTargetGoal, isize>();
+
+                let output = build(&QueryInputs {
+                    vulnerabilities,
+                    local_vulnerabilities,
+                    network_access,
+                    firewall_rules,
+                    attacker_positions,
+                    attacker_goals,
+                });
+
+                let captured_output = Arc::clone(&captured_in_dataflow);
+                output.inspect(move |(record, _time, diff)| {
+                    captured_output
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push((record.clone(), *diff));
+                });
+
+                (
+                    vulnerability_handle,
+                    local_vulnerability_handle,
+                    network_handle,
+                    firewall_handle,
+                    position_handle,
+                    goal_handle,
+                )
+            });
+
+            for fact in facts.vulnerabilities {
+                vulnerability_input.insert(fact);
+            }
+            for fact in facts.local_vulnerabilities {
+                local_vulnerability_input.insert(fact);
+            }
+            for fact in facts.network_access {
+                network_input.insert(fact);
+            }
+            for fact in facts.firewall_rules {
+                firewall_input.insert(fact);
+            }
+            for fact in facts.attacker_positions {
+                attacker_position_input.insert(fact);
+            }
+            for fact in facts.attacker_goals {
+                attacker_goal_input.insert(fact);
+            }
+
+            vulnerability_input.close();
+            local_vulnerability_input.close();
+            network_input.close();
+            firewall_input.close();
+            attacker_position_input.close();
+            attacker_goal_input.close();
+
+            while worker.step() {}
+        })
+    })?;
+
+    let updates = std::mem::take(&mut *captured.lock().unwrap_or_else(PoisonError::into_inner));
+    let mut counts = BTreeMap::new();
+    for (record, diff) in updates {
+        *counts.entry(record).or_insert(0isize) += diff;
+    }
+    Ok(counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(record, _)| record)
+        .collect())
+}
+
+/// Every execCode fact the attackers can derive from the given facts,
+/// sorted.  Convenience wrapper for callers that hold plain vectors
+/// rather than a `BaseFacts`.
+pub fn reachable_hosts(
+    vulnerabilities: Vec<Vulnerability>,
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+    attacker_positions: Vec<AttackerLocation>,
+) -> Vec<ExecCode> {
+    try_reachable_hosts(
+        vulnerabilities,
+        network_access,
+        firewall_rules,
+        attacker_positions,
+    )
+    .unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `reachable_hosts`, returning dataflow failures as errors.
+pub fn try_reachable_hosts(
+    vulnerabilities: Vec<Vulnerability>,
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+    attacker_positions: Vec<AttackerLocation>,
+) -> Result<Vec<ExecCode>, AttackGraphError> {
+    let facts = BaseFacts {
+        vulnerabilities,
+        network_access,
+        firewall_rules,
+        attacker_positions,
+        ..BaseFacts::default()
+    };
+
+    try_run_collection_query(&facts, |inputs| {
+        let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exec_code
+    })
+}
+
+/// Every `(attacker, host)` pair where the attacker gets Root, i.e. the
+/// ownsMachine facts, sorted.  Convenience wrapper like
+/// `reachable_hosts`; see `reachable_pairs` for a `BaseFacts`.
+pub fn owned_hosts(
+    vulnerabilities: Vec<Vulnerability>,
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+    attacker_positions: Vec<AttackerLocation>,
+) -> Vec<(AttackerIdentifier, HostIdentifier)> {
+    try_owned_hosts(
+        vulnerabilities,
+        network_access,
+        firewall_rules,
+        attacker_positions,
+    )
+    .unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `owned_hosts`, returning dataflow failures as errors.
+pub fn try_owned_hosts(
+    vulnerabilities: Vec<Vulnerability>,
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+    attacker_positions: Vec<AttackerLocation>,
+) -> Result<Vec<(AttackerIdentifier, HostIdentifier)>, AttackGraphError> {
+    let facts = BaseFacts {
+        vulnerabilities,
+        network_access,
+        firewall_rules,
+        attacker_positions,
+        ..BaseFacts::default()
+    };
+
+    try_run_collection_query(&facts, |inputs| {
+        owned_machines(inputs).map(|owned| (owned.attacker_id, owned.owned_host))
+    })
+}
+
+/// Every `(src, dst)` host pair where traffic from `src` can reach
+/// `dst` through the firewalled topology, relayed or not, sorted.
+/// Vulnerabilities and attackers are ignored; see `network_closure`.
+pub fn network_reachability(
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+) -> Vec<(HostIdentifier, HostIdentifier)> {
+    try_network_reachability(network_access, firewall_rules)
+        .unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `network_reachability`, returning dataflow failures as errors.
+pub fn try_network_reachability(
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+) -> Result<Vec<(HostIdentifier, HostIdentifier)>, AttackGraphError> {
+    let facts = BaseFacts {
+        network_access,
+        firewall_rules,
+        ..BaseFacts::default()
+    };
+
+    try_run_collection_query(&facts, |inputs| {
+        network_closure(&inputs.network_access, &inputs.firewall_rules)
+    })
+}
+
+/// The network edges the firewall leaves usable, sorted, for checking
+/// firewall rules without running the attack simulation.
+pub fn effective_access(
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+) -> Vec<EffectiveNetworkAccess> {
+    try_effective_access(network_access, firewall_rules).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `effective_access`, returning dataflow failures as errors.
+pub fn try_effective_access(
+    network_access: Vec<NetworkAccess>,
+    firewall_rules: Vec<FirewallRule>,
+) -> Result<Vec<EffectiveNetworkAccess>, AttackGraphError> {
+    let facts = BaseFacts {
+        network_access,
+        firewall_rules,
+        ..BaseFacts::default()
+    };
+
+    try_run_collection_query(&facts, |inputs| {
+        effective_network_access(&inputs.network_access, &inputs.firewall_rules)
+    })
+}
+
+/// Every cycle-free attack path the attackers can follow, sorted.
+pub fn attack_paths(facts: &BaseFacts) -> Vec<AttackPath> {
+    try_attack_paths(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `attack_paths`, returning dataflow failures as errors.
+pub fn try_attack_paths(facts: &BaseFacts) -> Result<Vec<AttackPath>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (_, _, _, paths) = build_attack_graph_with_paths(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        paths
+    })
+}
+
+/// The `k` best attack paths ending at `target_host`: shortest first,
+/// then by the privilege gained on the target, highest first.  Paths
+/// never revisit a host, so cyclic networks still yield finitely many.
+/// Remaining ties are broken by host sequence, then by attacker.
+pub fn top_attack_paths(facts: &BaseFacts, target_host: &str, k: usize) -> Vec<AttackPath> {
+    try_top_attack_paths(facts, target_host, k).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `top_attack_paths`, returning dataflow failures as errors.
+pub fn try_top_attack_paths(
+    facts: &BaseFacts,
+    target_host: &str,
+    k: usize,
+) -> Result<Vec<AttackPath>, AttackGraphError> {
+    let mut paths: Vec<_> = try_attack_paths(facts)?
+        .into_iter()
+        .filter(|path| {
+            path.path.len() > 1 && path.path.last().map(String::as_str) == Some(target_host)
+        })
+        .collect();
+    paths.sort_by(|left, right| {
+        left.path
+            .len()
+            .cmp(&right.path.len())
+            .then_with(|| right.final_privilege.cmp(&left.final_privilege))
+            .then_with(|| left.path.cmp(&right.path))
+            .then_with(|| left.attacker_id.cmp(&right.attacker_id))
+    });
+    paths.truncate(k);
+    Ok(paths)
+}
+
+/// Shortest attack path length from each attacker to every host it
+/// reaches, sorted.
+pub fn host_distances(facts: &BaseFacts) -> Vec<HostDistance> {
+    try_host_distances(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `host_distances`, returning dataflow failures as errors.
+pub fn try_host_distances(facts: &BaseFacts) -> Result<Vec<HostDistance>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (_, _, _, distances) = build_attack_graph_with_distance(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        distances
+    })
+}
+
+/// For every host each attacker reaches, the path with the lowest
+/// summed `10 - exploitability`, sorted.  Sort by `cost_hundredths` to
+/// rank goals by how easy they are to reach.
+pub fn cheapest_attack_paths(facts: &BaseFacts) -> Vec<CheapestAttackPath> {
+    try_cheapest_attack_paths(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `cheapest_attack_paths`, returning dataflow failures as errors.
+pub fn try_cheapest_attack_paths(
+    facts: &BaseFacts,
+) -> Result<Vec<CheapestAttackPath>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        minimum_cost_attack_paths(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+        )
+    })
+}
+
+/// Distinct host sequences, ignoring which service was used on each
+/// hop, along which any attacker can compromise `target_host`.  Routes
+/// that differ only by service collapse into one; each sequence starts
+/// at an attacker's starting host and ends at the target.
+pub fn distinct_host_routes(facts: &BaseFacts, target_host: &str) -> Vec<Vec<HostIdentifier>> {
+    try_distinct_host_routes(facts, target_host).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `distinct_host_routes`, returning dataflow failures as errors.
+pub fn try_distinct_host_routes(
+    facts: &BaseFacts,
+    target_host: &str,
+) -> Result<Vec<Vec<HostIdentifier>>, AttackGraphError> {
+    let target_host = target_host.to_string();
+
+    try_run_collection_query(facts, move |inputs| {
+        let target_host = target_host.clone();
+        build_attack_routes(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+        )
+        .filter(move |route| !route.services.is_empty() && *route.last_host() == target_host)
+        // Project out services and privileges, then deduplicate.
+        .map(|route| route.hosts)
+        .distinct()
+    })
+}
+
+/// Number of distinct attack paths ending at each host an attacker can
+/// compromise, sorted by host.  A path is a host sequence from an
+/// attacker's starting host, counted once per attacker however many
+/// services connect its hops.  Paths never revisit a host, which keeps
+/// the count finite on cyclic topologies: it is the number of simple
+/// paths, bounded by the number of hosts.  Starting hosts are only
+/// counted when some other path leads back to them.
+pub fn count_attack_paths(facts: &BaseFacts) -> Vec<(HostIdentifier, usize)> {
+    try_count_attack_paths(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `count_attack_paths`, returning dataflow failures as errors.
+pub fn try_count_attack_paths(
+    facts: &BaseFacts,
+) -> Result<Vec<(HostIdentifier, usize)>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        build_attack_routes(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+        )
+        .filter(|route| !route.services.is_empty())
+        .map(|route| (route.attacker_id, route.hosts))
+        .distinct()
+        .map(|(_attacker_id, hosts)| {
+            hosts
+                .last()
+                .expect("attack routes always contain the starting host")
+                .clone()
+        })
+        .count()
+        .map(|(host, paths)| (host, paths as usize))
+    })
+}
+
+/// Attack routes that compromise `target_host` and pass through
+/// `waypoint_host` on the way, e.g. to ask whether an attacker forced
+/// through a bastion can still win.  A route starting at the waypoint
+/// counts as passing through it.
+pub fn paths_through_waypoint(
+    facts: &BaseFacts,
+    target_host: &str,
+    waypoint_host: &str,
+) -> Vec<AttackRoute> {
+    try_paths_through_waypoint(facts, target_host, waypoint_host)
+        .unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `paths_through_waypoint`, returning dataflow failures as errors.
+pub fn try_paths_through_waypoint(
+    facts: &BaseFacts,
+    target_host: &str,
+    waypoint_host: &str,
+) -> Result<Vec<AttackRoute>, AttackGraphError> {
+    let target_host = target_host.to_string();
+    let waypoint_host = waypoint_host.to_string();
+
+    try_run_collection_query(facts, move |inputs| {
+        let target_host = target_host.clone();
+        let waypoint_host = waypoint_host.clone();
+        build_attack_routes(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+        )
+        .filter(move |route| {
+            !route.services.is_empty()
+                && *route.last_host() == target_host
+                && route.hosts.contains(&waypoint_host)
+        })
+    })
+}
+
+/// Host pairs `(a, b)` where a foothold on `a` lets an attacker
+/// compromise `b`, but a foothold on `b` does not lead back to `a`.
+/// Only hosts some attacker can actually compromise are considered, so
+/// unreachable parts of the network do not clutter segmentation
+/// reviews.  Sorted.
+pub fn asymmetry_report(facts: &BaseFacts) -> Vec<(HostIdentifier, HostIdentifier)> {
+    try_asymmetry_report(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `asymmetry_report`, returning dataflow failures as errors.
+pub fn try_asymmetry_report(
+    facts: &BaseFacts,
+) -> Result<Vec<(HostIdentifier, HostIdentifier)>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        let compromised = exec_code
+            .map(|execution| execution.compromised_host)
+            .distinct();
+        let closure = compromise_closure(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+        );
+
+        closure
+            .semijoin(&compromised)
+            .map(|(origin, destination)| (destination, origin))
+            .semijoin(&compromised)
+            // Keyed as (destination, origin): drop it when the closure
+            // also holds destination -> origin.
+            .map(|pair| (pair, ()))
+            .antijoin(&closure)
+            .map(|((destination, origin), ())| (origin, destination))
+    })
+}
+
+/// Loops of hosts the attackers can move around laterally: each entry
+/// is a strongly connected component, with at least two hosts, of the
+/// exploit edges leaving compromised hosts.  Hosts within an entry are
+/// sorted, and so are the entries.  See `exploited_cycle_components`.
+pub fn exploited_cycles(facts: &BaseFacts) -> Vec<Vec<HostIdentifier>> {
+    try_exploited_cycles(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `exploited_cycles`, returning dataflow failures as errors.
+pub fn try_exploited_cycles(
+    facts: &BaseFacts,
+) -> Result<Vec<Vec<HostIdentifier>>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exploited_cycle_components(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &exec_code,
+        )
+    })
+}
+
+/// Hosts where an attacker obtains Root although User would have been
+/// enough: the host lies on the way to a goal the attacker reaches, and
+/// continuing from it only takes remote exploits, which need code
+/// execution at any privilege.  A goal host is flagged when the
+/// attacker obtains more than its goal requires, such as Root on a goal
+/// that only needs User.  An attacker's own starting hosts are not
+/// flagged, since no exploit granted their privilege.  Sorted.
+pub fn over_privileged_compromises(facts: &BaseFacts) -> Vec<HostIdentifier> {
+    try_over_privileged_compromises(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `over_privileged_compromises`, returning dataflow failures as errors.
+pub fn try_over_privileged_compromises(
+    facts: &BaseFacts,
+) -> Result<Vec<HostIdentifier>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, goals_reached) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        let footholds = inputs
+            .attacker_positions
+            .map(|position| (position.attacker_id, position.starting_host))
+            .distinct();
+        let obtained_privileges = max_privilege_per_host(&exec_code).antijoin(&footholds);
+        let rooted_hosts = obtained_privileges
+            .filter(|(_, privilege)| *privilege == PrivilegeLevel::Root)
+            .map(|((attacker_id, host), _)| (host, attacker_id));
+        let closure = compromise_closure(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+        );
+        let reached_goals =
+            goals_reached.map(|reached| (reached.attacker_id, reached.reached_target));
+        let goal_requirements = inputs.attacker_goals.map(|goal| {
+            (
+                (goal.attacker_id, goal.target_host_name),
+                goal.required_privilege,
+            )
+        });
+
+        let over_privileged_goals = obtained_privileges
+            .join(&goal_requirements)
+            .filter(|(_, (obtained, required))| {
+                obtained.dominates(required) && obtained != required
+            })
+            .map(|((_, host), _)| host);
+
+        rooted_hosts
+            .join(&closure)
+            .map(|(host, (attacker_id, target))| ((attacker_id, target), host))
+            .semijoin(&reached_goals)
+            .map(|(_, host)| host)
+            .concat(&over_privileged_goals)
+            .distinct()
+    })
+}
+
+/// Fraction of hosts some attacker can execute code on, from 0 to 1:
+/// the hosts with an execCode fact over every host the base facts
+/// name, as a vulnerability holder, a network edge endpoint, a
+/// starting host or a goal.
+pub fn attack_surface(facts: &BaseFacts) -> f64 {
+    try_attack_surface(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `attack_surface`, returning dataflow failures as errors.
+pub fn try_attack_surface(facts: &BaseFacts) -> Result<f64, AttackGraphError> {
+    let compromised_hosts = try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exec_code
+            .map(|execution| execution.compromised_host)
+            .distinct()
+    })?;
+    Ok(attack_surface_fraction(
+        compromised_hosts.len(),
+        named_hosts(facts).len(),
+    ))
+}
+
+/// `reachable_hosts / total_hosts`, or 0 when there are no hosts.
+pub fn attack_surface_fraction(reachable_hosts: usize, total_hosts: usize) -> f64 {
+    if total_hosts == 0 {
+        0.0
+    } else {
+        reachable_hosts as f64 / total_hosts as f64
+    }
+}
+
+/// Sum of the asset values of every host some attacker can execute
+/// code on, beyond its own starting hosts: a foothold counts only when
+/// another attacker compromises it.  Hosts missing from `asset_values`
+/// are worth `DEFAULT_ASSET_VALUE`.
+pub fn total_risk(facts: &BaseFacts, asset_values: &[AssetValue]) -> f64 {
+    try_total_risk(facts, asset_values).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `total_risk`, returning dataflow failures as errors.
+pub fn try_total_risk(
+    facts: &BaseFacts,
+    asset_values: &[AssetValue],
+) -> Result<f64, AttackGraphError> {
+    let compromised_hosts = try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        let footholds = inputs
+            .attacker_positions
+            .map(|position| (position.attacker_id, position.starting_host))
+            .distinct();
+        exec_code
+            .map(|execution| ((execution.attacker_id, execution.compromised_host), ()))
+            .antijoin(&footholds)
+            .map(|((_, host), ())| host)
+            .distinct()
+    })?;
+    Ok(asset_value_sum(&compromised_hosts, asset_values))
+}
+
+/// Like `total_risk`, over the goal hosts some attacker reaches only.
+pub fn risk_at_goal(facts: &BaseFacts, asset_values: &[AssetValue]) -> f64 {
+    try_risk_at_goal(facts, asset_values).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `risk_at_goal`, returning dataflow failures as errors.
+pub fn try_risk_at_goal(
+    facts: &BaseFacts,
+    asset_values: &[AssetValue],
+) -> Result<f64, AttackGraphError> {
+    let reached_targets = try_run_collection_query(facts, |inputs| {
+        let (_, _, goals_reached) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals_reached
+            .map(|reached| reached.reached_target)
+            .distinct()
+    })?;
+    Ok(asset_value_sum(&reached_targets, asset_values))
+}
+
+// Values of `hosts`, each counted once.  A host listed more than once
+// in `asset_values` takes its last value.
+fn asset_value_sum(hosts: &[HostIdentifier], asset_values: &[AssetValue]) -> f64 {
+    let values: BTreeMap<&HostIdentifier, f64> = asset_values
+        .iter()
+        .map(|asset| (&asset.host_name, asset.value))
+        .collect();
+    hosts
+        .iter()
+        .map(|host| values.get(host).copied().unwrap_or(DEFAULT_ASSET_VALUE))
+        .sum()
+}
+
+/// Every `(attacker, host)` pair where the attacker gets execCode at
+/// any privilege, starting hosts included, sorted.  `owned_hosts`
+/// keeps only the pairs with Root.
+pub fn reachable_pairs(facts: &BaseFacts) -> Vec<AttackerAndHostKey> {
+    run_collection_query(facts, reachable_host_pairs)
+}
+
+/// Same as `reachable_pairs`, returning dataflow failures as errors.
+pub fn try_reachable_pairs(facts: &BaseFacts) -> Result<Vec<AttackerAndHostKey>, AttackGraphError> {
+    try_run_collection_query(facts, reachable_host_pairs)
+}
+
+/// Number of (attacker, host) pairs with execCode at any privilege.
+/// Equivalent to `reachable_pairs(facts).len()`, but the dataflow
+/// reduces the pairs to a single count, so only one record ever
+/// leaves it.
+pub fn count_reachable(facts: &BaseFacts) -> usize {
+    try_count_reachable(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `count_reachable`, returning dataflow failures as errors.
+pub fn try_count_reachable(facts: &BaseFacts) -> Result<usize, AttackGraphError> {
+    let counts = try_run_collection_query(facts, |inputs| {
+        reachable_host_pairs(inputs).map(|_| ()).count()
+    })?;
+
+    // `count` emits nothing for an empty collection.
+    match counts.first() {
+        Some((_, count)) => usize::try_from(*count).map_err(|_| {
+            AttackGraphError::ComputationError(format!("negative reachable pair count {count}"))
+        }),
+        None => Ok(0),
+    }
+}
+
+/// Result of `bounded_reachability`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BoundedReachability {
+    pub code_executions: Vec<AttackerCodeExecution>,
+    /// Whether the fixed point finished within the bound, so that
+    /// `code_executions` is complete.
+    pub converged: bool,
+}
+
+/// The execCode facts derived within `max_iterations` rounds of the
+/// fixed point, sorted, and whether that was enough to converge.  A
+/// safety valve for large or untrusted inputs; see
+/// `AttackGraphOptions::max_iterations`.
+pub fn bounded_reachability(facts: &BaseFacts, max_iterations: usize) -> BoundedReachability {
+    try_bounded_reachability(facts, max_iterations).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `bounded_reachability`, returning dataflow failures as errors.
+pub fn try_bounded_reachability(
+    facts: &BaseFacts,
+    max_iterations: usize,
+) -> Result<BoundedReachability, AttackGraphError> {
+    let options = AttackGraphOptions {
+        max_iterations: Some(max_iterations),
+        ..AttackGraphOptions::default()
+    };
+    // One fixed point yields both the executions and the truncation.
+    let outputs = try_run_collection_query(facts, move |inputs| {
+        let outputs = bounded_outputs(inputs, &options);
+        let truncated_executions = outputs
+            .truncated_executions
+            .expect("max_iterations is set")
+            .map(TaggedOutput::TruncatedExecution);
+        outputs
+            .code_executions
+            .map(TaggedOutput::CodeExecution)
+            .concat(&truncated_executions)
+    })?;
+
+    let mut bounded = BoundedReachability {
+        code_executions: Vec::new(),
+        converged: true,
+    };
+    for output in outputs {
+        match output {
+            TaggedOutput::CodeExecution(execution) => bounded.code_executions.push(execution),
+            TaggedOutput::TruncatedExecution(_) => bounded.converged = false,
+            TaggedOutput::GoalReached(_) => {}
+        }
+    }
+    Ok(bounded)
+}
+
+/// For every starting host, how many hosts are compromised only
+/// because of it: the drop in the number of hosts any attacker can
+/// execute code on when all
+/// attacker positions on that host are removed and reachability is
+/// re-run.  The foothold itself counts when no other foothold reaches
+/// it.  Results are sorted by starting host.
+pub fn foothold_marginal_contribution(facts: &BaseFacts) -> Vec<(HostIdentifier, usize)> {
+    try_foothold_marginal_contribution(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `foothold_marginal_contribution`, returning dataflow failures as errors.
+pub fn try_foothold_marginal_contribution(
+    facts: &BaseFacts,
+) -> Result<Vec<(HostIdentifier, usize)>, AttackGraphError> {
+    let baseline = try_compromised_host_count(facts)?;
+    let footholds: BTreeSet<_> = facts
+        .attacker_positions
+        .iter()
+        .map(|position| position.starting_host.clone())
+        .collect();
+
+    footholds
+        .into_iter()
+        .map(|foothold| {
+            let mut without_foothold = facts.clone();
+            without_foothold
+                .attacker_positions
+                .retain(|position| position.starting_host != foothold);
+            let remaining = try_compromised_host_count(&without_foothold)?;
+            Ok((foothold, baseline - remaining))
+        })
+        .collect()
+}
+
+/// Hosts an attacker could go on to execute code on if `from_host`
+/// were compromised with `privilege`, sorted.  The scenario's own
+/// attackers and goals are replaced by a single synthetic attacker
+/// starting there; `from_host` itself is not listed.
+pub fn blast_radius(
+    facts: &BaseFacts,
+    from_host: &str,
+    privilege: PrivilegeLevel,
+) -> Vec<HostIdentifier> {
+    try_blast_radius(facts, from_host, privilege).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `blast_radius`, returning dataflow failures as errors.
+pub fn try_blast_radius(
+    facts: &BaseFacts,
+    from_host: &str,
+    privilege: PrivilegeLevel,
+) -> Result<Vec<HostIdentifier>, AttackGraphError> {
+    let hypothetical = BaseFacts {
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "blast-radius",
+            from_host,
+            privilege,
+        )],
+        attacker_goals: Vec::new(),
+        ..facts.clone()
+    };
+
+    Ok(try_compromised_hosts(&hypothetical)?
+        .into_iter()
+        .filter(|host| host != from_host)
+        .collect())
+}
+
+/// The exploit steps behind every derived execCode fact, sorted.
+pub fn exec_code_justifications(facts: &BaseFacts) -> Vec<ExecCodeJustification> {
+    try_exec_code_justifications(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `exec_code_justifications`, returning dataflow failures as errors.
+pub fn try_exec_code_justifications(
+    facts: &BaseFacts,
+) -> Result<Vec<ExecCodeJustification>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (_, _, _, justifications) = build_attack_graph_with_justifications(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        justifications
+    })
+}
+
+/// Declared goals that no attack path reaches, sorted.
+pub fn unreachable_goals(facts: &BaseFacts) -> Vec<AttackerGoalUnreachable> {
+    try_unreachable_goals(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `unreachable_goals`, returning dataflow failures as errors.
+pub fn try_unreachable_goals(
+    facts: &BaseFacts,
+) -> Result<Vec<AttackerGoalUnreachable>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (_, _, _, goals_unreachable) = build_attack_graph_full(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals_unreachable
+    })
+}
+
+/// What a single attacker achieves; see `per_attacker_reachability`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttackerReachability {
+    pub code_executions: Vec<AttackerCodeExecution>,
+    pub goals_reached: Vec<AttackerGoalReached>,
+}
+
+/// The execCode facts and reached goals of every attacker, keyed by
+/// attacker id.  Rules join on (attacker, host), so one attacker's
+/// footholds never extend another's reach.  Every attacker with a
+/// starting position has an entry.
+pub fn per_attacker_reachability(
+    facts: &BaseFacts,
+) -> BTreeMap<AttackerIdentifier, AttackerReachability> {
+    try_per_attacker_reachability(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `per_attacker_reachability`, returning dataflow failures as errors.
+pub fn try_per_attacker_reachability(
+    facts: &BaseFacts,
+) -> Result<BTreeMap<AttackerIdentifier, AttackerReachability>, AttackGraphError> {
+    let outputs = try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, goals_reached) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exec_code
+            .map(TaggedOutput::CodeExecution)
+            .concat(&goals_reached.map(TaggedOutput::GoalReached))
+    })?;
+
+    let mut by_attacker: BTreeMap<AttackerIdentifier, AttackerReachability> = BTreeMap::new();
+    for output in outputs {
+        match output {
+            TaggedOutput::CodeExecution(execution) => by_attacker
+                .entry(execution.attacker_id.clone())
+                .or_default()
+                .code_executions
+                .push(execution),
+            TaggedOutput::GoalReached(goal) => by_attacker
+                .entry(goal.attacker_id.clone())
+                .or_default()
+                .goals_reached
+                .push(goal),
+            TaggedOutput::TruncatedExecution(_) => {}
+        }
+    }
+    Ok(by_attacker)
+}
+
+/// Hosts whose vulnerabilities, if all patched, would stop at least
+/// one currently reached goal from being reached, sorted.  Every host
+/// an attacker compromises beyond its starting positions is a
+/// candidate, and the attack graph is recomputed without that host's
+/// vulnerabilities (remote and local) for each one.  A reached goal
+/// host is always critical for its own goal.
+pub fn critical_hosts(facts: &BaseFacts) -> Vec<HostIdentifier> {
+    try_critical_hosts(facts).unwrap_or_else(|error| panic!("{error}"))
+}
+
+/// Same as `critical_hosts`, returning dataflow failures as errors.
+pub fn try_critical_hosts(facts: &BaseFacts) -> Result<Vec<HostIdentifier>, AttackGraphError> {
+    let baseline_goals = try_reached_goals(facts)?;
+    if baseline_goals.is_empty() {
+        return Ok(Vec::new());
+    }
+    let starting_hosts: BTreeSet<_> = facts
+        .attacker_positions
+        .iter()
+        .map(|position| position.starting_host.clone())
+        .collect();
+    let candidates = try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exec_code
+            .map(|execution| execution.compromised_host)
+            .distinct()
+    })?;
+
+    let mut critical = Vec::new();
+    for candidate in candidates
+        .into_iter()
+        .filter(|candidate| !starting_hosts.contains(candidate))
+    {
+        let mut patched = facts.clone();
+        patched
+            .vulnerabilities
+            .retain(|vulnerability| vulnerability.host_name != candidate);
+        patched
+            .local_vulnerabilities
+            .retain(|vulnerability| vulnerability.host_name != candidate);
+        let remaining: BTreeSet<_> = try_reached_goals(&patched)?.into_iter().collect();
+        if baseline_goals.iter().any(|goal| !remaining.contains(goal)) {
+            critical.push(candidate);
+        }
+    }
+    Ok(critical)
+}
+
+fn try_reached_goals(facts: &BaseFacts) -> Result<Vec<AttackerGoalReached>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (_, _, goals_reached) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals_reached
+    })
+}
+
+fn try_compromised_host_count(facts: &BaseFacts) -> Result<usize, AttackGraphError> {
+    Ok(try_compromised_hosts(facts)?.len())
+}
+
+// Hosts on which any attacker executes code, at any privilege, sorted.
+fn try_compromised_hosts(facts: &BaseFacts) -> Result<Vec<HostIdentifier>, AttackGraphError> {
+    try_run_collection_query(facts, |inputs| {
+        let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exec_code
+            .map(|execution| execution.compromised_host)
+            .distinct()
+    })
+}
+
+fn named_hosts(facts: &BaseFacts) -> BTreeSet<&HostIdentifier> {
+    let mut hosts = BTreeSet::new();
+    hosts.extend(facts.vulnerabilities.iter().map(|vuln| &vuln.host_name));
+    hosts.extend(
+        facts
+            .local_vulnerabilities
+            .iter()
+            .map(|vuln| &vuln.host_name),
+    );
+    for access in &facts.network_access {
+        hosts.insert(&access.source_host);
+        hosts.insert(&access.destination_host);
+    }
+    hosts.extend(
+        facts
+            .attacker_positions
+            .iter()
+            .map(|position| &position.starting_host),
+    );
+    hosts.extend(
+        facts
+            .attacker_goals
+            .iter()
+            .map(|goal| &goal.target_host_name),
+    );
+    hosts
+}
+
+// Outputs of one query dataflow, tagged so that a single capture can
+// carry several collections without running the fixed point again.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum TaggedOutput {
+    CodeExecution(AttackerCodeExecution),
+    TruncatedExecution(AttackerCodeExecution),
+    GoalReached(AttackerGoalReached),
+}
+
+fn bounded_outputs<'a>(
+    inputs: &QueryInputs<'a>,
+    options: &AttackGraphOptions,
+) -> AttackGraphOutputs<QueryScope<'a>> {
+    build_attack_graph_with_options(
+        &inputs.vulnerabilities,
+        &inputs.network_access,
+        &inputs.firewall_rules,
+        &inputs.attacker_positions,
+        &inputs.attacker_goals,
+        options,
+    )
+}
+
+// Distinct (attacker, host) pairs of execCode: a host reached at User
+// and at Root is one pair.
+fn reachable_host_pairs<'a>(
+    inputs: &QueryInputs<'a>,
+) -> Collection<QueryScope<'a>, AttackerAndHostKey> {
+    let (exec_code, _, _) = build_attack_graph_with_local_vulnerabilities(
+        &inputs.vulnerabilities,
+        &inputs.local_vulnerabilities,
+        &inputs.network_access,
+        &inputs.firewall_rules,
+        &inputs.attacker_positions,
+        &inputs.attacker_goals,
+    );
+    exec_code
+        .map(|execution| (execution.attacker_id, execution.compromised_host))
+        .distinct()
+}
+
+fn owned_machines<'a>(inputs: &QueryInputs<'a>) -> Collection<QueryScope<'a>, AttackerOwnsMachine> {
+    let (_, owns_machine, _) = build_attack_graph_with_local_vulnerabilities(
+        &inputs.vulnerabilities,
+        &inputs.local_vulnerabilities,
+        &inputs.network_access,
+        &inputs.firewall_rules,
+        &inputs.attacker_positions,
+        &inputs.attacker_goals,
+    );
+    owns_machine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmarks::{chain_base_facts, mesh_base_facts};
+
+    // The DMZ scenario from the hardcoded demo: the web server is
+    // reachable and vulnerable on both http and https.
+    fn dmz_facts() -> BaseFacts {
+        BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("web01", "CVE-2024-1234", "http", PrivilegeLevel::User),
+                VulnerabilityRecord::new("web01", "CVE-2024-1234", "https", PrivilegeLevel::User),
+                VulnerabilityRecord::new("db01", "CVE-2024-5678", "mysql", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("db01", "CVE-2024-9999", "ssh", PrivilegeLevel::User),
+                VulnerabilityRecord::new("admin01", "CVE-2024-8888", "smb", PrivilegeLevel::Root),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("internet", "web01", "http"),
+                NetworkAccessRule::new_default_port("internet", "web01", "https"),
+                NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+                NetworkAccessRule::new_default_port("web01", "db01", "ssh"),
+                NetworkAccessRule::new_default_port("db01", "admin01", "ssh"),
+                NetworkAccessRule::new_default_port("db01", "admin01", "smb"),
+            ],
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            attacker_goals: vec![AttackerTargetGoal::new("eve", "admin01")],
+            ..BaseFacts::default()
+        }
+    }
+
+    fn route(hosts: &[&str]) -> Vec<HostIdentifier> {
+        hosts.iter().map(|host| host.to_string()).collect()
+    }
+
+    #[test]
+    fn reachable_hosts_returns_one_exec_code_per_chain_node() {
+        let facts = chain_base_facts(5);
+
+        let executions = reachable_hosts(
+            facts.vulnerabilities,
+            facts.network_access,
+            Vec::new(),
+            facts.attacker_positions,
+        );
+
+        assert_eq!(executions.len(), 5);
+        assert!(executions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(executions
+            .iter()
+            .all(|execution| execution.obtained_privilege == PrivilegeLevel::Root));
+    }
+
+    #[test]
+    fn owned_hosts_lists_only_the_hosts_with_root() {
+        let facts = dmz_facts();
+
+        let owned = owned_hosts(
+            facts.vulnerabilities.clone(),
+            facts.network_access.clone(),
+            Vec::new(),
+            facts.attacker_positions.clone(),
+        );
+
+        // web01 only yields User, and eve starts on the internet as User.
+        assert_eq!(
+            owned,
+            vec![
+                ("eve".to_string(), "admin01".to_string()),
+                ("eve".to_string(), "db01".to_string()),
+            ]
+        );
+        let reachable = reachable_pairs(&facts);
+        assert!(owned.iter().all(|pair| reachable.contains(pair)));
+        assert_eq!(reachable.len(), 4);
+    }
+
+    #[test]
+    fn attackers_in_separate_subnets_keep_separate_reachability() {
+        // eve starts in subnet a, mallory in subnet b; no edge links the
+        // subnets.  Both want a2, but only eve can get there.
+        let facts = BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("a1", "CVE-A1", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("a2", "CVE-A2", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("b1", "CVE-B1", "ssh", PrivilegeLevel::Root),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("a0", "a1", "ssh"),
+                NetworkAccessRule::new_default_port("a1", "a2", "ssh"),
+                NetworkAccessRule::new_default_port("b0", "b1", "ssh"),
+            ],
+            attacker_positions: vec![
+                AttackerStartingPosition::new("eve", "a0", PrivilegeLevel::User),
+                AttackerStartingPosition::new("mallory", "b0", PrivilegeLevel::User),
+            ],
+            attacker_goals: vec![
+                AttackerTargetGoal::new("eve", "a2"),
+                AttackerTargetGoal::new("mallory", "a2"),
+            ],
+            ..BaseFacts::default()
+        };
+
+        let reachability = per_attacker_reachability(&facts);
+        let hosts_of = |attacker: &str| -> BTreeSet<HostIdentifier> {
+            reachability[attacker]
+                .code_executions
+                .iter()
+                .map(|execution| execution.compromised_host.clone())
+                .collect()
+        };
+
+        assert_eq!(
+            reachability.keys().collect::<Vec<_>>(),
+            vec!["eve", "mallory"]
+        );
+        assert_eq!(hosts_of("eve"), ["a0", "a1", "a2"].map(String::from).into());
+        assert_eq!(hosts_of("mallory"), ["b0", "b1"].map(String::from).into());
+        assert!(hosts_of("eve").is_disjoint(&hosts_of("mallory")));
+        assert_eq!(
+            reachability["eve"].goals_reached,
+            vec![AttackerGoalReached {
+                attacker_id: "eve".to_string(),
+                reached_target: "a2".to_string(),
+                required_privilege: PrivilegeLevel::Root,
+            }]
+        );
+        assert!(reachability["mallory"].goals_reached.is_empty());
+    }
+
+    #[test]
+    fn network_reachability_closes_a_chain_without_vulnerabilities() {
+        // No host has a vulnerability; a -> b -> c -> d over ssh, with
+        // the c -> d hop denied.
+        let network_access = vec![
+            NetworkAccessRule::new_default_port("a", "b", "ssh"),
+            NetworkAccessRule::new_default_port("b", "c", "ssh"),
+            NetworkAccessRule::new_default_port("c", "d", "ssh"),
+        ];
+        let firewall_rules = vec![FirewallRuleRecord::create_deny_rule("c", "d", "ssh")];
+
+        assert_eq!(
+            network_reachability(network_access.clone(), Vec::new()),
+            vec![
+                pair("a", "b"),
+                pair("a", "c"),
+                pair("a", "d"),
+                pair("b", "c"),
+                pair("b", "d"),
+                pair("c", "d"),
+            ]
+        );
+        assert_eq!(
+            network_reachability(network_access, firewall_rules),
+            vec![pair("a", "b"), pair("a", "c"), pair("b", "c")]
+        );
+    }
+
+    #[test]
+    fn network_reachability_keeps_components_apart() {
+        // Two components: a <-> b and x -> y.
+        let network_access = vec![
+            NetworkAccessRule::new_default_port("a", "b", "smb"),
+            NetworkAccessRule::new_default_port("b", "a", "smb"),
+            NetworkAccessRule::new_default_port("x", "y", "http"),
+        ];
+
+        assert_eq!(
+            network_reachability(network_access, Vec::new()),
+            vec![pair("a", "b"), pair("b", "a"), pair("x", "y")]
+        );
+    }
+
+    #[test]
+    fn a_deny_rule_removes_exactly_the_matching_edge() {
+        // Same hosts, different services and ports: only web -> db on
+        // mysql matches the deny.
+        let network_access = vec![
+            NetworkAccessRule::new_default_port("web", "db", "mysql"),
+            NetworkAccessRule::new_default_port("web", "db", "ssh"),
+            NetworkAccessRule::new("web", "db", "mysql", 3307),
+            NetworkAccessRule::new_default_port("app", "db", "mysql"),
+        ];
+        let firewall_rules = vec![FirewallRuleRecord::create_deny_rule("web", "db", "mysql")];
+        let edge = |source: &str, service: &str, port: PortNumber| EffectiveNetworkAccess {
+            source_host: source.to_string(),
+            destination_host: "db".to_string(),
+            service_name: service.to_string(),
+            port,
+        };
+
+        let unfiltered = effective_access(network_access.clone(), Vec::new());
+        let filtered = effective_access(network_access, firewall_rules);
+
+        assert_eq!(unfiltered.len(), 4);
+        assert_eq!(
+            filtered,
+            vec![
+                edge("app", "mysql", 3306),
+                edge("web", "mysql", 3307),
+                edge("web", "ssh", 22),
+            ]
+        );
+    }
+
+    #[test]
+    fn top_attack_paths_rank_by_length_then_privilege() {
+        // In a 3x3 mesh every route to node_2_2 has five hosts.  A
+        // User-level http bug on node_2_2 adds a shortcut from the start
+        // and a lower-privilege variant of each route through node_1_2.
+        let mut facts = mesh_base_facts(3, 3);
+        facts.vulnerabilities.push(VulnerabilityRecord::new(
+            "node_2_2",
+            "CVE-MESH-HTTP",
+            "http",
+            PrivilegeLevel::User,
+        ));
+        facts.network_access.extend([
+            NetworkAccessRule::new_default_port("node_0_0", "node_2_2", "http"),
+            NetworkAccessRule::new_default_port("node_1_2", "node_2_2", "http"),
+        ]);
+        let path = |hosts: &[&str], final_privilege| AttackPath {
+            attacker_id: "attacker".to_string(),
+            path: hosts.iter().map(|host| host.to_string()).collect(),
+            final_privilege,
+        };
+
+        let all = top_attack_paths(&facts, "node_2_2", usize::MAX);
+        let top = top_attack_paths(&facts, "node_2_2", 4);
+
+        assert_eq!(all.len(), 1 + 6 + 3);
+        assert_eq!(
+            top,
+            vec![
+                path(&["node_0_0", "node_2_2"], PrivilegeLevel::User),
+                path(
+                    &["node_0_0", "node_0_1", "node_0_2", "node_1_2", "node_2_2"],
+                    PrivilegeLevel::Root
+                ),
+                path(
+                    &["node_0_0", "node_0_1", "node_1_1", "node_1_2", "node_2_2"],
+                    PrivilegeLevel::Root
+                ),
+                path(
+                    &["node_0_0", "node_0_1", "node_1_1", "node_2_1", "node_2_2"],
+                    PrivilegeLevel::Root
+                ),
+            ]
+        );
+        // Equal-length routes: every Root path before any User path.
+        assert!(all[1..7]
+            .iter()
+            .all(|path| path.final_privilege == PrivilegeLevel::Root));
+        assert!(all[7..]
+            .iter()
+            .all(|path| path.final_privilege == PrivilegeLevel::User));
+        assert!(top_attack_paths(&facts, "node_0_0", 5).is_empty());
+    }
+
+    #[test]
+    fn attack_paths_keep_every_branch_and_cut_cycles() {
+        // internet reaches c through a or b; c loops back to a.
+        let facts = BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("a", "CVE-A", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("b", "CVE-B", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("c", "CVE-C-SSH", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("c", "CVE-C-HTTP", "http", PrivilegeLevel::Root),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("internet", "a", "ssh"),
+                NetworkAccessRule::new_default_port("internet", "b", "ssh"),
+                NetworkAccessRule::new_default_port("a", "c", "ssh"),
+                NetworkAccessRule::new_default_port("a", "c", "http"),
+                NetworkAccessRule::new_default_port("b", "c", "ssh"),
+                NetworkAccessRule::new_default_port("c", "a", "ssh"),
+            ],
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            ..BaseFacts::default()
+        };
+
+        let paths: Vec<_> = attack_paths(&facts)
+            .into_iter()
+            .filter(|path| path.path.len() > 1)
+            .map(|path| path.path)
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                route(&["internet", "a"]),
+                route(&["internet", "a", "c"]),
+                route(&["internet", "b"]),
+                route(&["internet", "b", "c"]),
+                route(&["internet", "b", "c", "a"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn host_distance_is_the_shorter_of_two_paths() {
+        // internet -> x -> vault (2 hops) and internet -> p -> q -> r ->
+        // vault (4 hops).
+        let mut facts = BaseFacts {
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            ..BaseFacts::default()
+        };
+        for (source, destination) in [
+            ("internet", "x"),
+            ("x", "vault"),
+            ("internet", "p"),
+            ("p", "q"),
+            ("q", "r"),
+            ("r", "vault"),
+        ] {
+            facts
+                .network_access
+                .push(NetworkAccessRule::new_default_port(
+                    source,
+                    destination,
+                    "ssh",
+                ));
+        }
+        for host in ["x", "p", "q", "r", "vault"] {
+            facts.vulnerabilities.push(VulnerabilityRecord::new(
+                host,
+                &format!("CVE-{host}"),
+                "ssh",
+                PrivilegeLevel::User,
+            ));
+        }
+
+        let hops: BTreeMap<_, _> = host_distances(&facts)
+            .into_iter()
+            .map(|distance| (distance.host_name, distance.hops))
+            .collect();
+
+        assert_eq!(hops["internet"], 0);
+        assert_eq!(hops["x"], 1);
+        assert_eq!(hops["r"], 3);
+        assert_eq!(hops["vault"], 2);
+        assert_eq!(hops.len(), 6);
+    }
+
+    #[test]
+    fn longer_easy_path_beats_short_hard_path() {
+        // internet -> vault directly needs a hard exploit (difficulty
+        // 9.0); internet -> a -> b -> vault uses three easy ones
+        // (difficulty 1.0 each).
+        let mut facts = BaseFacts {
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            ..BaseFacts::default()
+        };
+        for (source, destination, service) in [
+            ("internet", "vault", "rdp"),
+            ("internet", "a", "ssh"),
+            ("a", "b", "ssh"),
+            ("b", "vault", "ssh"),
+        ] {
+            facts
+                .network_access
+                .push(NetworkAccessRule::new_default_port(
+                    source,
+                    destination,
+                    service,
+                ));
+        }
+        facts.vulnerabilities = vec![
+            VulnerabilityRecord::new("vault", "CVE-HARD", "rdp", PrivilegeLevel::Root)
+                .with_exploitability(1.0),
+            VulnerabilityRecord::new("vault", "CVE-EASY", "ssh", PrivilegeLevel::Root)
+                .with_exploitability(9.0),
+            VulnerabilityRecord::new("a", "CVE-A", "ssh", PrivilegeLevel::User)
+                .with_exploitability(9.0),
+            VulnerabilityRecord::new("b", "CVE-B", "ssh", PrivilegeLevel::User)
+                .with_exploitability(9.0),
+        ];
+
+        let paths = cheapest_attack_paths(&facts);
+        let vault = paths
+            .iter()
+            .find(|path| path.host_name == "vault")
+            .expect("vault should be reachable");
+
+        assert_eq!(vault.path, vec!["internet", "a", "b", "vault"]);
+        assert_eq!(vault.cost_hundredths, 300);
+        assert!((vault.cost() - 3.0).abs() < 1e-9);
+        let start = paths
+            .iter()
+            .find(|path| path.host_name == "internet")
+            .expect("start should be reported");
+        assert_eq!(start.cost_hundredths, 0);
+    }
+
+    #[test]
+    fn justifications_name_each_source_service_and_cve_once() {
+        let justifications = exec_code_justifications(&dmz_facts());
+
+        let justification =
+            |host: &str, source: &str, service: &str, cve: &str| ExecCodeJustification {
+                attacker_id: "eve".to_string(),
+                host_name: host.to_string(),
+                via_source: source.to_string(),
+                via_service: service.to_string(),
+                via_cve: cve.to_string(),
+            };
+        // web01 is held with User only, db01 with both User and Root:
+        // the per-privilege duplicates collapse.
+        assert_eq!(
+            justifications,
+            vec![
+                justification("admin01", "db01", "smb", "CVE-2024-8888"),
+                justification("db01", "web01", "mysql", "CVE-2024-5678"),
+                justification("db01", "web01", "ssh", "CVE-2024-9999"),
+                justification("web01", "internet", "http", "CVE-2024-1234"),
+                justification("web01", "internet", "https", "CVE-2024-1234"),
+            ]
+        );
+    }
+
+    #[test]
+    fn goal_stays_unreachable_until_a_new_cve_opens_it() {
+        let mut facts = dmz_facts();
+        facts
+            .attacker_goals
+            .push(AttackerTargetGoal::new("eve", "backup01"));
+        facts
+            .network_access
+            .push(NetworkAccessRule::new_default_port(
+                "admin01", "backup01", "nfs",
+            ));
+
+        assert_eq!(
+            unreachable_goals(&facts),
+            vec![AttackerGoalUnreachable {
+                attacker_id: "eve".to_string(),
+                target_host_name: "backup01".to_string(),
+                required_privilege: PrivilegeLevel::Root,
+            }]
+        );
+
+        facts.vulnerabilities.push(VulnerabilityRecord::new(
+            "backup01",
+            "CVE-2024-7777",
+            "nfs",
+            PrivilegeLevel::Root,
+        ));
+        assert!(unreachable_goals(&facts).is_empty());
+    }
+
+    #[test]
+    fn critical_hosts_are_the_chokepoints_on_every_route() {
+        // internet -> gw -> {a, b} -> db: a and b back each other up,
+        // gw and the goal itself have no alternative.
+        let mut facts = BaseFacts {
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            attacker_goals: vec![AttackerTargetGoal::new("eve", "db")],
+            ..BaseFacts::default()
+        };
+        for (source, destination) in [
+            ("internet", "gw"),
+            ("gw", "a"),
+            ("gw", "b"),
+            ("a", "db"),
+            ("b", "db"),
+        ] {
+            facts
+                .network_access
+                .push(NetworkAccessRule::new_default_port(
+                    source,
+                    destination,
+                    "ssh",
+                ));
+        }
+        for host in ["gw", "a", "b", "db"] {
+            facts.vulnerabilities.push(VulnerabilityRecord::new(
+                host,
+                &format!("CVE-{host}"),
+                "ssh",
+                PrivilegeLevel::Root,
+            ));
+        }
+
+        assert_eq!(critical_hosts(&facts), route(&["db", "gw"]));
+    }
+
+    #[test]
+    fn no_critical_hosts_without_a_reached_goal() {
+        let mut facts = dmz_facts();
+        facts.attacker_goals = vec![AttackerTargetGoal::new("eve", "nowhere")];
+
+        assert!(critical_hosts(&facts).is_empty());
+    }
+
+    #[test]
+    fn http_and_https_variants_collapse_into_one_host_route() {
+        let facts = dmz_facts();
+
+        assert_eq!(
+            distinct_host_routes(&facts, "web01"),
+            vec![route(&["internet", "web01"])]
+        );
+        assert_eq!(
+            distinct_host_routes(&facts, "admin01"),
+            vec![route(&["internet", "web01", "db01", "admin01"])]
+        );
+    }
+
+    #[test]
+    fn distinct_host_routes_keeps_genuinely_different_routes() {
+        let mut facts = dmz_facts();
+        facts
+            .network_access
+            .push(NetworkAccessRule::new_default_port(
+                "web01", "admin01", "smb",
+            ));
+
+        assert_eq!(
+            distinct_host_routes(&facts, "admin01"),
+            vec![
+                route(&["internet", "web01", "admin01"]),
+                route(&["internet", "web01", "db01", "admin01"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn chain_has_a_single_attack_path_to_every_host() {
+        let expected: Vec<_> = (1..6).map(|index| (format!("node_{index}"), 1)).collect();
+
+        assert_eq!(count_attack_paths(&chain_base_facts(6)), expected);
+    }
+
+    #[test]
+    fn mesh_attack_paths_match_the_monotone_lattice_path_count() {
+        // Edges only point right and down, so the paths to node_x_y are
+        // the C(x + y, x) monotone lattice paths from the corner.
+        fn binomial(n: usize, k: usize) -> usize {
+            (0..k).fold(1, |product, i| product * (n - i) / (i + 1))
+        }
+
+        let counts: BTreeMap<_, _> = count_attack_paths(&mesh_base_facts(4, 3))
+            .into_iter()
+            .collect();
+
+        assert_eq!(counts.len(), 11);
+        for y in 0..3 {
+            for x in 0..4 {
+                if (x, y) != (0, 0) {
+                    assert_eq!(counts[&format!("node_{x}_{y}")], binomial(x + y, x));
+                }
+            }
+        }
+        assert_eq!(counts["node_3_2"], 10);
+    }
+
+    #[test]
+    fn patching_the_web_server_shrinks_the_attack_surface_to_the_foothold() {
+        let mut facts = dmz_facts();
+        assert_eq!(attack_surface(&facts), 1.0);
+
+        facts
+            .vulnerabilities
+            .retain(|vulnerability| vulnerability.host_name != "web01");
+        // Only the attacker's own host is left out of four.
+        assert_eq!(attack_surface(&facts), 0.25);
+        assert_eq!(attack_surface(&BaseFacts::default()), 0.0);
+    }
+
+    #[test]
+    fn patching_a_host_removes_its_value_and_everything_behind_it_from_the_risk() {
+        let asset_values = [
+            AssetValue::new("web01", 5.0),
+            AssetValue::new("db01", 20.0),
+            AssetValue::new("admin01", 50.0),
+        ];
+        let mut facts = dmz_facts();
+        // The attacker's own foothold on internet is not at risk.
+        assert_eq!(total_risk(&facts, &asset_values), 75.0);
+        assert_eq!(risk_at_goal(&facts, &asset_values), 50.0);
+
+        facts
+            .vulnerabilities
+            .retain(|vulnerability| vulnerability.host_name != "db01");
+        assert_eq!(total_risk(&facts, &asset_values), 5.0);
+        assert_eq!(risk_at_goal(&facts, &asset_values), 0.0);
+
+        facts
+            .vulnerabilities
+            .retain(|vulnerability| vulnerability.host_name != "web01");
+        assert_eq!(total_risk(&facts, &asset_values), 0.0);
+    }
+
+    #[test]
+    fn a_foothold_compromised_by_another_attacker_counts_towards_the_risk() {
+        let asset_values = [AssetValue::new("web01", 5.0)];
+        let mut facts = dmz_facts();
+        facts.attacker_positions.push(AttackerStartingPosition::new(
+            "mallory",
+            "web01",
+            PrivilegeLevel::User,
+        ));
+
+        // web01 is mallory's foothold but eve compromises it; internet
+        // is only eve's foothold.  db01 and admin01 count at 1.0.
+        assert_eq!(total_risk(&facts, &asset_values), 7.0);
+
+        facts.attacker_positions.remove(0);
+        assert_eq!(total_risk(&facts, &asset_values), 2.0);
+    }
+
+    #[test]
+    fn a_foothold_with_several_starting_privileges_is_left_out_once() {
+        let asset_values = [AssetValue::new("web01", 5.0)];
+        let mut facts = dmz_facts();
+        facts.attacker_positions.push(AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::Root,
+        ));
+
+        // eve starts on the internet at both User and Root; the
+        // foothold still counts for nothing rather than cancelling
+        // eve's other compromises.
+        assert_eq!(total_risk(&facts, &asset_values), 7.0);
+    }
+
+    #[test]
+    fn count_reachable_matches_materialized_pairs_on_chain() {
+        let facts = chain_base_facts(25);
+
+        assert_eq!(count_reachable(&facts), reachable_pairs(&facts).len());
+        assert_eq!(count_reachable(&facts), 25);
+    }
+
+    #[test]
+    fn reachable_pairs_include_hosts_reached_only_at_user() {
+        let facts = dmz_facts();
+
+        // web01 only ever yields User, and eve starts on the internet
+        // as User; neither is owned, but both are reached.
+        assert_eq!(
+            reachable_pairs(&facts),
+            vec![
+                ("eve".to_string(), "admin01".to_string()),
+                ("eve".to_string(), "db01".to_string()),
+                ("eve".to_string(), "internet".to_string()),
+                ("eve".to_string(), "web01".to_string()),
+            ]
+        );
+        assert_eq!(count_reachable(&facts), 4);
+    }
+
+    #[test]
+    fn count_reachable_is_zero_without_attackers() {
+        let mut facts = chain_base_facts(5);
+        facts.attacker_positions.clear();
+
+        assert_eq!(count_reachable(&facts), 0);
+    }
+
+    #[test]
+    fn foothold_marginal_contribution_counts_only_uniquely_dependent_hosts() {
+        // Both footholds reach "shared"; each also has one private host.
+        let facts = BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("left_only", "CVE-L", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("right_only", "CVE-R", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("shared", "CVE-S", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("behind_shared", "CVE-B", "ssh", PrivilegeLevel::Root),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("left_foothold", "left_only", "ssh"),
+                NetworkAccessRule::new_default_port("left_foothold", "shared", "ssh"),
+                NetworkAccessRule::new_default_port("right_foothold", "right_only", "ssh"),
+                NetworkAccessRule::new_default_port("right_foothold", "shared", "ssh"),
+                NetworkAccessRule::new_default_port("shared", "behind_shared", "ssh"),
+            ],
+            attacker_positions: vec![
+                AttackerStartingPosition::new("eve", "left_foothold", PrivilegeLevel::User),
+                AttackerStartingPosition::new("eve", "right_foothold", PrivilegeLevel::User),
+            ],
+            ..BaseFacts::default()
+        };
+
+        // Each foothold contributes itself and its private host; the
+        // shared host and what lies behind it survive either removal.
+        assert_eq!(
+            foothold_marginal_contribution(&facts),
+            vec![
+                ("left_foothold".to_string(), 2),
+                ("right_foothold".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_through_waypoint_keeps_only_routes_via_the_bastion() {
+        // Diamond: internet reaches the vault via either the bastion or
+        // the vpn gateway.
+        let facts = BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("bastion", "CVE-B", "ssh", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("vpn", "CVE-V", "ipsec", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("vault", "CVE-T", "https", PrivilegeLevel::Root),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("internet", "bastion", "ssh"),
+                NetworkAccessRule::new_default_port("internet", "vpn", "ipsec"),
+                NetworkAccessRule::new_default_port("bastion", "vault", "https"),
+                NetworkAccessRule::new_default_port("vpn", "vault", "https"),
+            ],
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            ..BaseFacts::default()
+        };
+
+        let routes = paths_through_waypoint(&facts, "vault", "bastion");
+
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].hosts, route(&["internet", "bastion", "vault"]));
+        assert_eq!(distinct_host_routes(&facts, "vault").len(), 2);
+    }
+
+    fn pair(origin: &str, destination: &str) -> (HostIdentifier, HostIdentifier) {
+        (origin.to_string(), destination.to_string())
+    }
+
+    #[test]
+    fn blast_radius_from_mid_chain_only_reaches_downstream_nodes() {
+        let facts = chain_base_facts(5);
+
+        assert_eq!(
+            blast_radius(&facts, "node_2", PrivilegeLevel::User),
+            route(&["node_3", "node_4"])
+        );
+    }
+
+    #[test]
+    fn bounded_reachability_stops_short_of_the_chain_end() {
+        let facts = chain_base_facts(5);
+
+        let bounded = bounded_reachability(&facts, 2);
+
+        let hosts: Vec<_> = bounded
+            .code_executions
+            .iter()
+            .map(|execution| execution.compromised_host.as_str())
+            .collect();
+        assert_eq!(hosts, vec!["node_0", "node_1", "node_2"]);
+        assert!(!bounded.converged);
+    }
+
+    #[test]
+    fn bounded_reachability_converges_once_the_bound_covers_the_chain() {
+        let facts = chain_base_facts(5);
+
+        let bounded = bounded_reachability(&facts, 4);
+
+        assert!(bounded.converged);
+        assert_eq!(
+            bounded.code_executions,
+            reachable_hosts(
+                facts.vulnerabilities,
+                facts.network_access,
+                facts.firewall_rules,
+                facts.attacker_positions,
+            )
+        );
+        assert!(!bounded_reachability(&chain_base_facts(5), 3).converged);
+    }
+
+    #[test]
+    fn asymmetry_report_lists_every_upstream_downstream_pair_of_a_chain() {
+        let facts = chain_base_facts(4);
+
+        assert_eq!(
+            asymmetry_report(&facts),
+            vec![
+                pair("node_0", "node_1"),
+                pair("node_0", "node_2"),
+                pair("node_0", "node_3"),
+                pair("node_1", "node_2"),
+                pair("node_1", "node_3"),
+                pair("node_2", "node_3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn asymmetry_report_omits_bidirectional_links() {
+        let mut facts = chain_base_facts(2);
+        facts
+            .network_access
+            .push(NetworkAccessRule::new_default_port(
+                "node_1", "node_0", "ssh",
+            ));
+
+        assert!(asymmetry_report(&facts).is_empty());
+    }
+
+    #[test]
+    fn root_on_a_host_whose_next_hop_needs_only_user_is_over_privileged() {
+        let facts = BaseFacts {
+            vulnerabilities: vec![
+                // Root where any code execution would do.
+                VulnerabilityRecord::new("web01", "CVE-WEB", "http", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("app01", "CVE-APP", "api", PrivilegeLevel::User),
+                VulnerabilityRecord::new("db01", "CVE-DB", "mysql", PrivilegeLevel::Root),
+                // Root, but off the path to the goal.
+                VulnerabilityRecord::new("printer", "CVE-PRN", "ipp", PrivilegeLevel::Root),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("internet", "web01", "http"),
+                NetworkAccessRule::new_default_port("web01", "app01", "api"),
+                NetworkAccessRule::new_default_port("app01", "db01", "mysql"),
+                NetworkAccessRule::new_default_port("web01", "printer", "ipp"),
+            ],
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            attacker_goals: vec![AttackerTargetGoal::new("eve", "db01")],
+            ..BaseFacts::default()
+        };
+
+        assert_eq!(
+            over_privileged_compromises(&facts),
+            vec!["web01".to_string()]
+        );
+    }
+
+    #[test]
+    fn root_on_a_goal_that_needs_only_user_is_over_privileged() {
+        let facts = BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("web01", "CVE-WEB", "http", PrivilegeLevel::Root),
+                VulnerabilityRecord::new("app01", "CVE-APP", "api", PrivilegeLevel::User),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("internet", "web01", "http"),
+                NetworkAccessRule::new_default_port("internet", "app01", "api"),
+            ],
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )],
+            attacker_goals: vec![
+                AttackerTargetGoal::new("eve", "web01")
+                    .with_required_privilege(PrivilegeLevel::User),
+                // Exactly what the goal asks for.
+                AttackerTargetGoal::new("eve", "app01")
+                    .with_required_privilege(PrivilegeLevel::User),
+            ],
+            ..BaseFacts::default()
+        };
+
+        assert_eq!(
+            over_privileged_compromises(&facts),
+            vec!["web01".to_string()]
+        );
+    }
+
+    #[test]
+    fn root_held_from_the_start_is_not_over_privileged() {
+        let facts = BaseFacts {
+            vulnerabilities: vec![
+                VulnerabilityRecord::new("app01", "CVE-APP", "api", PrivilegeLevel::User),
+                VulnerabilityRecord::new("db01", "CVE-DB", "mysql", PrivilegeLevel::Root),
+            ],
+            network_access: vec![
+                NetworkAccessRule::new_default_port("web01", "app01", "api"),
+                NetworkAccessRule::new_default_port("app01", "db01", "mysql"),
+            ],
+            // eve already holds Root on web01; no exploit granted it.
+            attacker_positions: vec![AttackerStartingPosition::new(
+                "eve",
+                "web01",
+                PrivilegeLevel::Root,
+            )],
+            attacker_goals: vec![AttackerTargetGoal::new("eve", "db01")],
+            ..BaseFacts::default()
+        };
+
+        assert!(over_privileged_compromises(&facts).is_empty());
+    }
+
+    #[test]
+    fn a_panicking_query_returns_a_computation_error() {
+        let result = try_run_collection_query(&dmz_facts(), |inputs| {
+            inputs
+                .vulnerabilities
+                .map(|vulnerability| -> HostIdentifier {
+                    panic!("no host for {}", vulnerability.vulnerability_id)
+                })
+        });
+
+        assert!(matches!(result, Err(AttackGraphError::ComputationError(_))));
+        // The runtime lock is still usable after the failed query.
+        assert_eq!(try_count_reachable(&dmz_facts()).unwrap(), 4);
+    }
+
+    #[test]
+    fn queries_recover_a_poisoned_runtime_lock() {
+        let _ = std::thread::spawn(|| {
+            let _guard = QUERY_RUNTIME_LOCK
+                .get_or_init(|| Mutex::new(()))
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            panic!("poison the query runtime lock");
+        })
+        .join();
+
+        assert_eq!(try_attack_surface(&BaseFacts::default()).unwrap(), 0.0);
+        assert_eq!(
+            try_blast_radius(&dmz_facts(), "db01", PrivilegeLevel::Root).unwrap(),
+            vec!["admin01".to_string()]
+        );
+    }
+}
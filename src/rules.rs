@@ -15,12 +15,19 @@
 // for joins, explains `enter()` for the iterative scope, and uses
 // `.distinct()` to ensure convergence of the fixed-point computation.
 
-use differential_dataflow::collection::Collection;
+use differential_dataflow::collection::{AsCollection, Collection};
+use differential_dataflow::operators::arrange::{ArrangeByKey, Arranged, TraceAgent};
 use differential_dataflow::operators::iterate::Iterate;
-use differential_dataflow::operators::Join;
+use differential_dataflow::operators::Reduce;
 use differential_dataflow::operators::Threshold;
+use differential_dataflow::operators::{Join, JoinCore};
+use differential_dataflow::trace::implementations::ValSpine;
+use timely::dataflow::operators::{Filter, Map};
+use timely::dataflow::scopes::child::Iterative;
+use timely::dataflow::scopes::ScopeParent;
 use timely::dataflow::Scope;
 
+use crate::interner::{NameId, StringInterner};
 use crate::schema::*;
 
 // ----------------------------------------------------------------
@@ -43,17 +50,436 @@ pub fn build_attack_graph<G>(
     Collection<G, AttackerOwnsMachine>,
     Collection<G, AttackerGoalReached>,
 )
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_with_options(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        &AttackGraphOptions::default(),
+    )
+    .without_paths()
+}
+
+/// Settings of the core rules that are plain values rather than extra
+/// fact collections.  The default is what `build_attack_graph` uses;
+/// new knobs belong here rather than in another positional parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackGraphOptions {
+    /// Least privilege at which execCode on a host counts as owning it.
+    pub ownership_threshold: PrivilegeLevel,
+    /// Whether edges need an explicit Allow rule or merely no Deny rule.
+    pub firewall_policy: FirewallPolicy,
+    /// Bounds the attacker to this many hops from its starting host,
+    /// using the unrolled expansion of `build_attack_graph_with_max_hops`
+    /// instead of the fixed point.
+    pub max_hops: Option<usize>,
+    /// Also derive every cycle-free attack path (see
+    /// `build_attack_graph_with_paths`).
+    pub track_paths: bool,
+    /// Stops the execCode fixed point after this many rounds, each
+    /// extending the attacker's reach by one derivation step, instead
+    /// of running it to convergence.  `truncated_executions` in the
+    /// outputs tells whether the bound cut anything off.  Ignored when
+    /// `max_hops` is set, which never runs the fixed point.
+    pub max_iterations: Option<usize>,
+    /// Also report the rounds the execCode fixed point ran for, as
+    /// `fixpoint_rounds` in the outputs.  Ignored when `max_hops` is
+    /// set.
+    pub track_rounds: bool,
+}
+
+impl Default for AttackGraphOptions {
+    fn default() -> Self {
+        Self {
+            ownership_threshold: PrivilegeLevel::DEFAULT_OWNERSHIP_THRESHOLD,
+            firewall_policy: FirewallPolicy::default(),
+            max_hops: None,
+            track_paths: false,
+            max_iterations: None,
+            track_rounds: false,
+        }
+    }
+}
+
+/// Outputs of `build_attack_graph_with_options`.
+pub struct AttackGraphOutputs<G: Scope> {
+    pub code_executions: Collection<G, AttackerCodeExecution>,
+    pub owned_machines: Collection<G, AttackerOwnsMachine>,
+    pub goals_reached: Collection<G, AttackerGoalReached>,
+    /// Present only when `AttackGraphOptions::track_paths` is set.
+    pub attack_paths: Option<Collection<G, AttackPath>>,
+    /// The execCode facts one more round would have added.  Present
+    /// only when the fixed point ran under
+    /// `AttackGraphOptions::max_iterations`; empty exactly when it
+    /// converged within the bound.
+    pub truncated_executions: Option<Collection<G, AttackerCodeExecution>>,
+    /// The round of every change to the execCode fixed point's loop
+    /// variable, round 0 being the starting positions.  Rounds leave
+    /// the loop as it runs rather than once the fixed point settles,
+    /// so they can drive progress reporting, and repeat once per
+    /// change.  One more than the largest round is the number of
+    /// rounds the fixed point ran for.  Present only when
+    /// `AttackGraphOptions::track_rounds` is set.
+    pub fixpoint_rounds: Option<Collection<G, u64>>,
+}
+
+impl<G: Scope> AttackGraphOutputs<G> {
+    // execCode, ownsMachine and goalReached, as `build_attack_graph`
+    // returns them.
+    fn without_paths(
+        self,
+    ) -> (
+        Collection<G, AttackerCodeExecution>,
+        Collection<G, AttackerOwnsMachine>,
+        Collection<G, AttackerGoalReached>,
+    ) {
+        (
+            self.code_executions,
+            self.owned_machines,
+            self.goals_reached,
+        )
+    }
+}
+
+/// Builds an attack graph with the given `options`.  ownsMachine holds
+/// wherever the execCode privilege dominates `options.ownership_threshold`,
+/// and with `max_hops` set, paths longer than the bound are dropped too.
+pub fn build_attack_graph_with_options<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    options: &AttackGraphOptions,
+) -> AttackGraphOutputs<G>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let (code_executions, owned_machines, goals_reached, truncated_executions, fixpoint_rounds) =
+        match options.max_hops {
+            Some(maximum_attack_hops) => {
+                let (code_executions, owned_machines, goals_reached) = build_bounded_attack_graph(
+                    vulnerability_collection,
+                    network_access_collection,
+                    firewall_rules_collection,
+                    attacker_positions_collection,
+                    attacker_goals_collection,
+                    maximum_attack_hops,
+                    options,
+                );
+                (code_executions, owned_machines, goals_reached, None, None)
+            }
+            None => {
+                let (
+                    code_executions,
+                    owned_machines,
+                    goals_reached,
+                    _,
+                    truncated_executions,
+                    fixpoint_rounds,
+                ) = build_attack_graph_arranged(
+                    vulnerability_collection,
+                    network_access_collection,
+                    firewall_rules_collection,
+                    attacker_positions_collection,
+                    attacker_goals_collection,
+                    RuleExtensions {
+                        ownership_threshold: options.ownership_threshold.clone(),
+                        firewall_policy: options.firewall_policy,
+                        max_iterations: options.max_iterations,
+                        track_rounds: options.track_rounds,
+                        ..RuleExtensions::default()
+                    },
+                );
+                (
+                    code_executions,
+                    owned_machines,
+                    goals_reached,
+                    truncated_executions,
+                    fixpoint_rounds,
+                )
+            }
+        };
+
+    let attack_paths = options.track_paths.then(|| {
+        let maximum_attack_hops = options.max_hops;
+        attack_routes_with_policy(
+            vulnerability_collection,
+            network_access_collection,
+            firewall_rules_collection,
+            attacker_positions_collection,
+            options.firewall_policy,
+        )
+        .map(AttackPath::from)
+        .filter(move |path| maximum_attack_hops.is_none_or(|hops| path.path.len() <= hops + 1))
+        .distinct()
+    });
+
+    AttackGraphOutputs {
+        code_executions,
+        owned_machines,
+        goals_reached,
+        attack_paths,
+        truncated_executions,
+        fixpoint_rounds,
+    }
+}
+
+/// Builds an attack graph under the given firewall policy.  With
+/// `FirewallPolicy::DefaultDeny`, network edges (and granted or
+/// role-gated edges) are only usable where an `Allow` rule matches
+/// them; `build_attack_graph` is the `DefaultAllow` case.
+pub fn build_attack_graph_with_firewall_policy<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    firewall_policy: FirewallPolicy,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_with_options(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        &AttackGraphOptions {
+            firewall_policy,
+            ..AttackGraphOptions::default()
+        },
+    )
+    .without_paths()
+}
+
+/// Builds an attack graph whose firewall rules may name zones and
+/// wildcards instead of hosts.  A rule's source or destination matches
+/// a host when it is the host's name, a zone the host belongs to in
+/// `host_zones_collection`, or `*`.
+///
+/// Precedence: for each network edge, only the most specific matching
+/// rules decide.  Each side of a rule scores 2 for a host name, 1 for
+/// a zone and 0 for `*`, and the rules with the highest total win, so
+/// a host-to-host `Allow` overrides a zone-wide `Deny`.  Between
+/// equally specific rules `Deny` wins.  Edges no rule matches fall back
+/// to `firewall_policy`.
+pub fn build_attack_graph_with_zones<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    host_zones_collection: &Collection<G, HostZone>,
+    firewall_policy: FirewallPolicy,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
 where
     G: Scope,
     G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
 {
     build_attack_graph_internal(
         vulnerability_collection,
-        None,
         network_access_collection,
         firewall_rules_collection,
         attacker_positions_collection,
         attacker_goals_collection,
+        RuleExtensions {
+            firewall_policy,
+            host_zones: Some(host_zones_collection),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Builds an attack graph that takes host operating systems into
+/// account: a vulnerability with `applies_to_os` only yields execCode on
+/// a host whose `HostInfo` names that OS.  Vulnerabilities without an
+/// OS apply everywhere.  The other builders do not see `HostInfo` and
+/// treat every vulnerability as OS-agnostic.
+pub fn build_attack_graph_with_host_info<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    host_info_collection: &Collection<G, HostInfo>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            host_info: Some(host_info_collection),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Builds an attack graph in which a vulnerability no longer applies
+/// once a `PatchApplied` for its (host, CVE) is present, as if it had
+/// been removed.  Patching and unpatching are ordinary base fact
+/// updates, so the vulnerability facts can stay as the scanner reports
+/// them.
+pub fn build_attack_graph_with_patches<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    patches_applied_collection: &Collection<G, PatchApplied>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            patches_applied: Some(patches_applied_collection),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Builds an attack graph with services that exploits switch on.  A
+/// vulnerability on a (host, service) named by an `EnablesService`
+/// fact is only exploitable by an attacker that has enabled the
+/// service, by executing code on the host with the fact's required
+/// privilege, and that can reach the service over the network.  The
+/// enabling happens inside the fixed point, so the execCode gained
+/// through an enabled service can enable further services.
+pub fn build_attack_graph_with_enabled_services<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    enables_service_collection: &Collection<G, EnablesService>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            enabled_services: Some(enables_service_collection),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Builds an attack graph where an attacker owns a machine once its
+/// execCode privilege there dominates `ownership_threshold`, instead of
+/// only at `PrivilegeLevel::Root`.
+pub fn build_attack_graph_with_ownership_threshold<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    ownership_threshold: PrivilegeLevel,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_with_options(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        &AttackGraphOptions {
+            ownership_threshold,
+            ..AttackGraphOptions::default()
+        },
+    )
+    .without_paths()
+}
+
+/// execCode, ownsMachine and goalReached, with execCode carrying the
+/// privilege lattice `P`.
+pub type AttackGraphOverLattice<G, P> = (
+    Collection<G, AttackerCodeExecution<P>>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+);
+
+/// Builds an attack graph over a privilege lattice of the caller's
+/// choosing: vulnerabilities, starting positions and the derived
+/// execCode facts carry `P`, and ownership needs a privilege that
+/// dominates `ownership_threshold`.  Goals still name a
+/// `PrivilegeLevel`, lifted into `P` with `From`.
+pub fn build_attack_graph_with_privilege_lattice<G, P>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord<P>>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition<P>>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    ownership_threshold: P,
+) -> AttackGraphOverLattice<G, P>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            ownership_threshold,
+            ..RuleExtensions::default()
+        },
     )
 }
 
@@ -77,17 +503,58 @@ where
 {
     build_attack_graph_internal(
         vulnerability_collection,
-        Some(local_vulnerability_collection),
         network_access_collection,
         firewall_rules_collection,
         attacker_positions_collection,
         attacker_goals_collection,
+        RuleExtensions {
+            local_vulnerabilities: Some(local_vulnerability_collection),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Builds an attack graph with local privilege escalation steps that
+/// each require a specific starting privilege on the host.  Escalated
+/// execCode facts feed back into the fixed point, so an escalation can
+/// unlock rules that need the higher privilege.
+pub fn build_attack_graph_with_privilege_escalation<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    privilege_escalation_collection: &Collection<G, PrivilegeEscalationRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            privilege_escalations: Some(privilege_escalation_collection),
+            ..RuleExtensions::default()
+        },
     )
 }
 
-fn build_attack_graph_internal<G>(
+/// Builds an attack graph where some vulnerabilities grant lateral
+/// network access instead of code execution: once an attacker owns the
+/// vulnerable host, it may reach `grants_access_to` on the granted
+/// service as if an effective-access edge existed.  Firewall denies
+/// still apply to granted edges.
+pub fn build_attack_graph_with_access_grants<G>(
     vulnerability_collection: &Collection<G, VulnerabilityRecord>,
-    local_vulnerability_collection: Option<&Collection<G, LocalVulnerabilityRecord>>,
+    access_granting_vulnerability_collection: &Collection<G, AccessGrantingVulnerabilityRecord>,
     network_access_collection: &Collection<G, NetworkAccessRule>,
     firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
     attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
@@ -101,99 +568,1853 @@ where
     G: Scope,
     G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
 {
-    // =========================================================================
-    // STRATUM 1: Effective network access (network edges minus denies)
-    // =========================================================================
-    // We implement: effectiveAccess(S,D,Svc) :- network(S,D,Svc), NOT deny(S,D,Svc).
-    // Implementation steps:
-    //  1. Key the network edges by (src,dst,svc) so joins/antijoins are keyed.
-    //  2. Extract the set of deny keys from firewall rules (filter by Deny).
-    //  3. Antijoin the network set with the deny set to remove blocked edges.
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            access_grants: Some(access_granting_vulnerability_collection),
+            ..RuleExtensions::default()
+        },
+    )
+}
 
-    // 1) key network rules by (src, dst, service)
-    let network_access_keyed_by_route = network_access_collection.map(|rule| {
-        // Create an explicit route key tuple for joining/antijoins.
-        let route_key = (
-            rule.source_host.clone(),
-            rule.destination_host.clone(),
-            rule.service_name.clone(),
-        );
-        (route_key, rule)
-    });
+/// Builds an attack graph where an attacker must escalate locally
+/// before it can pivot: access-granting vulnerabilities only open their
+/// edges from an owned host, and local vulnerabilities are the way a
+/// User foothold becomes Root.  Both feed the same fixed point, so an
+/// escalation in one round unlocks the granted edges in the next.
+pub fn build_attack_graph_with_escalation_and_access_grants<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    local_vulnerability_collection: &Collection<G, LocalVulnerabilityRecord>,
+    access_granting_vulnerability_collection: &Collection<G, AccessGrantingVulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            local_vulnerabilities: Some(local_vulnerability_collection),
+            access_grants: Some(access_granting_vulnerability_collection),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Role-based access control inputs for [`build_attack_graph_with_roles`].
+pub struct RoleBasedAccess<'a, G: Scope> {
+    pub role_grants: &'a Collection<G, RoleGrantRule>,
+    pub role_memberships: &'a Collection<G, AttackerRoleMembership>,
+    pub role_conferring_hosts: &'a Collection<G, RoleConferringHost>,
+}
 
-    // 2) extract deny keys from firewall rules
-    let blocked_route_keys = firewall_rules_collection
-        // Keep only explicit Deny rules; Allow rules are not used for
-        // negation here because the default behaviour is that the
-        // network rule permits unless denied.
-        .filter(|rule| rule.rule_action == FirewallRuleAction::Deny)
+/// Builds an attack graph with role-gated lateral movement: a role
+/// grant edge can only be traversed by an attacker holding its role,
+/// either from the start or by compromising a host that confers it
+/// (any execCode on that host is enough).
+pub fn build_attack_graph_with_roles<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    role_access: &RoleBasedAccess<'_, G>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            role_access: Some(role_access),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Credential theft inputs for [`build_attack_graph_with_credentials`].
+pub struct CredentialTheft<'a, G: Scope> {
+    pub stored_credentials: &'a Collection<G, CredentialStoredRecord>,
+    pub credential_grants: &'a Collection<G, CredentialGrantRecord>,
+}
+
+/// Builds an attack graph with credential reuse: executing code on a
+/// host that stores a credential lets the attacker log in wherever the
+/// credential is valid, with the privilege it grants there.  Credential
+/// logins need neither a vulnerability nor a network edge.
+pub fn build_attack_graph_with_credentials<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    credential_theft: &CredentialTheft<'_, G>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            credential_theft: Some(credential_theft),
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// Builds an attack graph with trust between hosts: executing code on a
+/// trustee yields the trust's privilege on every host that trusts it,
+/// without a vulnerability or a network edge.  Trust steps run inside
+/// the execCode fixed point, so trust chains compose with each other
+/// and with network exploits.
+pub fn build_attack_graph_with_trust_relationships<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    trust_relationships_collection: &Collection<G, TrustRelationship>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            derivation_rules: &[Box::new(TrustRelationshipRule::new(
+                trust_relationships_collection,
+            ))],
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// The intermediate collections a [`DerivationRule`] sees inside the
+/// execCode fixed point, already entered into the iterative scope.
+pub struct DerivationContext<'r, S: Scope, P = PrivilegeLevel> {
+    /// Network edges that survive the firewall.
+    pub effective_access: &'r Collection<S, EffectiveNetworkAccess>,
+    /// execCode facts derived so far in this round.
+    pub code_executions: &'r Collection<S, AttackerCodeExecution<P>>,
+}
+
+/// A user-supplied Datalog-style rule evaluated inside the execCode
+/// fixed point.  Every round, `derive` receives the current
+/// intermediate collections and returns extra execCode facts, which are
+/// unioned with those of the built-in rules, so custom rules compose
+/// with the built-in ones and with each other.
+///
+/// Rules that need base facts of their own keep them as collections in
+/// the outer scope `G` and `enter` them into the context's scope.
+pub trait DerivationRule<G: Scope, P = PrivilegeLevel> {
+    /// Short name, used in diagnostics.
+    fn name(&self) -> &str;
+
+    fn derive<'a>(
+        &self,
+        context: &DerivationContext<'_, Iterative<'a, G, u64>, P>,
+    ) -> Collection<Iterative<'a, G, u64>, AttackerCodeExecution<P>>;
+}
+
+/// Builds an attack graph with additional derivation rules layered on
+/// top of the built-in ones, e.g. [`TrustRelationshipRule`].
+pub fn build_attack_graph_with_rules<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    derivation_rules: Vec<Box<dyn DerivationRule<G> + '_>>,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_internal(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            derivation_rules: &derivation_rules,
+            ..RuleExtensions::default()
+        },
+    )
+}
+
+/// [`DerivationRule`] for trust relationships, the rule behind
+/// [`build_attack_graph_with_trust_relationships`].
+///
+/// `execCode(A, Truster, P) :- execCode(A, Trustee, _), trusts(Truster, Trustee, P).`
+pub struct TrustRelationshipRule<G: Scope> {
+    trust_relationships: Collection<G, TrustRelationship>,
+}
+
+impl<G: Scope> TrustRelationshipRule<G> {
+    pub fn new(trust_relationships: &Collection<G, TrustRelationship>) -> Self {
+        Self {
+            trust_relationships: trust_relationships.clone(),
+        }
+    }
+}
+
+impl<G> DerivationRule<G> for TrustRelationshipRule<G>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    fn name(&self) -> &str {
+        "trust_relationship"
+    }
+
+    fn derive<'a>(
+        &self,
+        context: &DerivationContext<'_, Iterative<'a, G, u64>>,
+    ) -> Collection<Iterative<'a, G, u64>, AttackerCodeExecution> {
+        let trusts_by_trustee = self
+            .trust_relationships
+            .enter(&context.code_executions.scope())
+            .map(|trust| {
+                (
+                    trust.trustee_host,
+                    (trust.truster_host, trust.granted_privilege),
+                )
+            });
+
+        context
+            .code_executions
+            .map(|exec| (exec.compromised_host, exec.attacker_id))
+            .join(&trusts_by_trustee)
+            .map(
+                |(_trustee, (attacker_id, (truster, privilege)))| AttackerCodeExecution {
+                    attacker_id,
+                    compromised_host: truster,
+                    obtained_privilege: privilege,
+                },
+            )
+    }
+}
+
+// Optional rule inputs layered on top of the core execCode rules.
+// Each public `build_attack_graph_with_*` entry point fills in the
+// extensions it needs and leaves the rest as `None`.
+struct RuleExtensions<'a, G: Scope, P = PrivilegeLevel> {
+    // Local privilege escalation: upgrades non-root execCode in place.
+    local_vulnerabilities: Option<&'a Collection<G, LocalVulnerabilityRecord>>,
+    // Local escalation from one specific privilege to another.
+    privilege_escalations: Option<&'a Collection<G, PrivilegeEscalationRecord>>,
+    // Vulnerabilities that grant network access once their host is owned.
+    access_grants: Option<&'a Collection<G, AccessGrantingVulnerabilityRecord>>,
+    // Role-gated edges and the roles attackers hold or can acquire.
+    role_access: Option<&'a RoleBasedAccess<'a, G>>,
+    // Credentials stored on hosts and where they can be reused.
+    credential_theft: Option<&'a CredentialTheft<'a, G>>,
+    // Restricts newly derived execCode to these (attacker, host) pairs.
+    retained_hosts: Option<&'a Collection<G, AttackerAndHostKey>>,
+    // User-supplied rules unioned into every round of the fixed point.
+    derivation_rules: &'a [Box<dyn DerivationRule<G, P> + 'a>],
+    // Least privilege at which execCode on a host counts as owning it.
+    ownership_threshold: P,
+    // Whether edges need an explicit Allow rule or merely no Deny rule.
+    firewall_policy: FirewallPolicy,
+    // Zone membership; enables zone and wildcard firewall matching.
+    host_zones: Option<&'a Collection<G, HostZone>>,
+    // Host operating systems; enables OS-specific vulnerability matching.
+    host_info: Option<&'a Collection<G, HostInfo>>,
+    // Installed patches; suppresses the vulnerabilities they fix.
+    patches_applied: Option<&'a Collection<G, PatchApplied>>,
+    // Services enabled by execCode; their vulnerabilities start dormant.
+    enabled_services: Option<&'a Collection<G, EnablesService>>,
+    // Rounds of the execCode fixed point to run before stopping.
+    max_iterations: Option<usize>,
+    // Whether to report the rounds the execCode fixed point ran for.
+    track_rounds: bool,
+}
+
+// Implemented by hand: deriving would require `G: Default`.
+impl<G: Scope, P: From<PrivilegeLevel>> Default for RuleExtensions<'_, G, P> {
+    fn default() -> Self {
+        Self {
+            local_vulnerabilities: None,
+            privilege_escalations: None,
+            access_grants: None,
+            role_access: None,
+            credential_theft: None,
+            retained_hosts: None,
+            derivation_rules: &[],
+            ownership_threshold: PrivilegeLevel::DEFAULT_OWNERSHIP_THRESHOLD.into(),
+            firewall_policy: FirewallPolicy::default(),
+            host_zones: None,
+            host_info: None,
+            patches_applied: None,
+            enabled_services: None,
+            max_iterations: None,
+            track_rounds: false,
+        }
+    }
+}
+
+fn build_attack_graph_internal<G, P>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord<P>>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition<P>>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    extensions: RuleExtensions<'_, G, P>,
+) -> AttackGraphOverLattice<G, P>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    let (exec_code, owns_machine, goals_reached, _, _, _) = build_attack_graph_arranged(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        extensions,
+    );
+    (exec_code, owns_machine, goals_reached)
+}
+
+// execCode, ownsMachine, goalReached, the execCode arrangement the
+// latter two were derived from, the execCode facts cut off by
+// `max_iterations` when it is set, and the fixed point's rounds when
+// `track_rounds` is set, in that order.
+type ArrangedAttackGraph<G, P = PrivilegeLevel> = (
+    Collection<G, AttackerCodeExecution<P>>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+    PrivilegesByAttackerHost<G, P>,
+    Option<Collection<G, AttackerCodeExecution<P>>>,
+    Option<Collection<G, u64>>,
+);
+
+// `build_attack_graph_internal` that also hands back the execCode
+// arrangement, for builders that derive further outputs from execCode.
+fn build_attack_graph_arranged<G, P>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord<P>>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition<P>>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    extensions: RuleExtensions<'_, G, P>,
+) -> ArrangedAttackGraph<G, P>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    let effective_network_access = effective_network_access_with_policy(
+        network_access_collection,
+        firewall_rules_collection,
+        extensions.firewall_policy,
+        extensions.host_zones,
+    );
+
+    let applicable_vulnerabilities =
+        applicable_vulnerabilities(vulnerability_collection, &extensions);
+    let (all_code_executions, truncated_executions, fixpoint_rounds) = derive_code_executions(
+        &applicable_vulnerabilities,
+        &effective_network_access,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        &extensions,
+    );
+
+    let privileges = arrange_privileges_by_attacker_host(&all_code_executions);
+    let (machines_owned_by_attackers, successfully_reached_goals) = derive_ownership_and_goals(
+        &privileges,
+        attacker_goals_collection,
+        extensions.ownership_threshold.clone(),
+    );
+
+    // Consolidate the outputs: this merges multiple diff updates for
+    // the same value into a single diff and reduces noise for the
+    // consumers of the returned collections.
+    (
+        all_code_executions.consolidate(),
+        machines_owned_by_attackers.consolidate(),
+        successfully_reached_goals.consolidate(),
+        privileges,
+        truncated_executions.map(|truncated| truncated.consolidate()),
+        fixpoint_rounds,
+    )
+}
+
+// ----------------------------------------------------------------
+// build_attack_graph_capacity_limited
+// ----------------------------------------------------------------
+// Models a resource-constrained attacker that can hold at most
+// `maximum_footholds` compromised hosts at once.  Choosing the K
+// footholds that maximise goal reachability is combinatorial, so this
+// implements the tractable variant: every attacker keeps the K
+// exploitable hosts closest to its starting position (ties broken by
+// host name) and reachability is re-derived using only those hosts.
+// Starting positions are always retained and do not count against
+// the budget.  Because the retained set is closed under hop distance,
+// every retained host still has a shortest attack path that stays
+// within the budget.
+pub fn build_attack_graph_capacity_limited<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    maximum_footholds: usize,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let effective_network_access =
+        effective_network_access(network_access_collection, firewall_rules_collection);
+
+    let hop_distances = minimum_hop_distances(
+        vulnerability_collection,
+        &effective_network_access,
+        attacker_positions_collection,
+    );
+
+    // Top-K reduce per attacker.  Values arrive sorted ascending, so
+    // ordering by (hop distance, host) keeps the nearest footholds.
+    let retained_footholds = hop_distances
+        .filter(|(_, hops)| *hops > 0)
+        .map(|((attacker_id, host), hops)| (attacker_id, (hops, host)))
+        .reduce(move |_attacker_id, input, output| {
+            for ((hops, host), _count) in input.iter().take(maximum_footholds) {
+                output.push(((*hops, (*host).clone()), 1));
+            }
+        })
+        .map(|(attacker_id, (_hops, host))| (attacker_id, host));
+
+    let starting_hosts = attacker_positions_collection
+        .map(|position| (position.attacker_id, position.starting_host));
+
+    let retained_hosts = retained_footholds.concat(&starting_hosts).distinct();
+
+    let (all_code_executions, _, _) = derive_code_executions(
+        vulnerability_collection,
+        &effective_network_access,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        &RuleExtensions {
+            retained_hosts: Some(&retained_hosts),
+            ..RuleExtensions::default()
+        },
+    );
+
+    let (machines_owned_by_attackers, successfully_reached_goals) = derive_ownership_and_goals(
+        &arrange_privileges_by_attacker_host(&all_code_executions),
+        attacker_goals_collection,
+        PrivilegeLevel::DEFAULT_OWNERSHIP_THRESHOLD,
+    );
+
+    (
+        all_code_executions.consolidate(),
+        machines_owned_by_attackers.consolidate(),
+        successfully_reached_goals.consolidate(),
+    )
+}
+
+// ----------------------------------------------------------------
+// observed_network_access
+// ----------------------------------------------------------------
+// Pre-filter for high-confidence analyses: keeps only network edges
+// with `Provenance::Observed`, so any rule builder fed with the result
+// ignores assumed connectivity.
+pub fn observed_network_access<G>(
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+) -> Collection<G, NetworkAccessRule>
+where
+    G: Scope,
+{
+    network_access_collection.filter(|rule| rule.provenance == Provenance::Observed)
+}
+
+// ----------------------------------------------------------------
+// attack_basin
+// ----------------------------------------------------------------
+// Backward reachability from a target host: the basin contains every
+// host from which an attacker holding a foothold could exploit its way
+// to `target_host`.  Edges are effective access (firewall denies
+// applied) gated by a vulnerability on the destination, walked in
+// reverse inside a fixed point.  The target itself is not part of its
+// own basin.  This is useful for prioritising which footholds to
+// prevent.
+pub fn attack_basin<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    target_host: &str,
+) -> Collection<G, HostIdentifier>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let effective_network_access =
+        effective_network_access(network_access_collection, firewall_rules_collection);
+
+    // Reverse the exploit edges: (dst, src)
+    let edges_by_destination = exploit_edges(vulnerability_collection, &effective_network_access)
+        .map(|(source, destination)| (destination, source));
+
+    let target_host = target_host.to_string();
+    let target_for_seed = target_host.clone();
+    let direct_predecessors = edges_by_destination
+        .filter(move |(destination, _)| *destination == target_for_seed)
+        .map(|(_, source)| source);
+
+    direct_predecessors
+        .iterate(|basin| {
+            let edges_in_scope = edges_by_destination.enter(&basin.scope());
+            let direct_in_scope = direct_predecessors.enter(&basin.scope());
+
+            basin
+                .map(|host| (host, ()))
+                .join(&edges_in_scope)
+                .map(|(_host, ((), source))| source)
+                .concat(&direct_in_scope)
+                .distinct()
+        })
+        .filter(move |host| *host != target_host)
+        .consolidate()
+}
+
+// ----------------------------------------------------------------
+// stealthiest_path_to_goal
+// ----------------------------------------------------------------
+// For every goal, the lowest total detection risk with which its
+// attacker can reach the goal host.  Each exploit edge costs the
+// detection risk of the quietest vulnerability usable on it (0 when no
+// risk is recorded).  Accumulated risk is propagated inside a fixed
+// point and min-reduced per (attacker, host), i.e. Bellman-Ford over
+// non-negative weights.  Risks are summed in parts per million and
+// only converted to `f64` on output; unreachable goals are absent.
+pub fn stealthiest_path_to_goal<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    detection_risk_collection: &Collection<G, ExploitDetectionRisk>,
+) -> Collection<G, (HostIdentifier, f64)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let effective_network_access =
+        effective_network_access(network_access_collection, firewall_rules_collection);
+
+    // Risk of each vulnerability, defaulting to 0 when unrecorded.
+    let vulnerabilities_by_id = vulnerability_collection.map(|vuln| {
+        (
+            (vuln.host_name, vuln.vulnerability_id),
+            (vuln.affected_service, vuln.port),
+        )
+    });
+    let recorded_risks = detection_risk_collection.map(|risk| {
+        (
+            (risk.host_name, risk.vulnerability_id),
+            risk.detection_risk_ppm,
+        )
+    });
+    let risky_vulnerabilities = vulnerabilities_by_id
+        .join(&recorded_risks)
+        .map(|((host, _id), ((service, port), risk))| ((host, service, port), risk));
+    let silent_vulnerabilities = vulnerabilities_by_id
+        .antijoin(&recorded_risks.map(|(key, _risk)| key).distinct())
+        .map(|((host, _id), (service, port))| ((host, service, port), 0u64));
+    let risk_by_host_service = risky_vulnerabilities.concat(&silent_vulnerabilities);
+
+    // Weighted exploit edges: (src, (dst, cheapest risk)).
+    let weighted_edges = effective_network_access
+        .map(|access| {
+            (
+                (access.destination_host, access.service_name, access.port),
+                access.source_host,
+            )
+        })
+        .join(&risk_by_host_service)
+        .map(|((destination, _service, _port), (source, risk))| ((source, destination), risk))
+        .reduce(|_key, input, output| output.push((*input[0].0, 1)))
+        .map(|((source, destination), risk)| (source, (destination, risk)));
+
+    let starting_risks = attacker_positions_collection
+        .map(|position| ((position.attacker_id, position.starting_host), 0u64));
+
+    let lowest_risks = starting_risks.iterate(|risks| {
+        let edges_in_scope = weighted_edges.enter(&risks.scope());
+        let starts_in_scope = starting_risks.enter(&risks.scope());
+
+        risks
+            .map(|((attacker_id, host), risk)| (host, (attacker_id, risk)))
+            .join(&edges_in_scope)
+            .map(
+                |(_source, ((attacker_id, risk), (destination, edge_risk)))| {
+                    ((attacker_id, destination), risk + edge_risk)
+                },
+            )
+            .concat(&starts_in_scope)
+            // Keep only the lowest accumulated risk (values arrive sorted).
+            .reduce(|_key, input, output| output.push((*input[0].0, 1)))
+    });
+
+    lowest_risks
+        .semijoin(&attacker_goals_collection.map(|goal| (goal.attacker_id, goal.target_host_name)))
+        .map(|((_attacker_id, host), risk)| (host, ppm_to_risk(risk)))
+}
+
+// ----------------------------------------------------------------
+// minimum_cost_attack_paths
+// ----------------------------------------------------------------
+// For every host an attacker can reach, the path with the lowest summed
+// exploit difficulty.  Like `stealthiest_path_to_goal` this carries an
+// accumulated cost through a fixed point and min-reduces per
+// (attacker, host), but it also carries the path so the winning route
+// can be reported.  Costs are non-negative, so extending a path back
+// onto one of its own hosts never helps; such extensions are dropped,
+// which keeps every carried path simple.
+pub fn minimum_cost_attack_paths<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+) -> Collection<G, CheapestAttackPath>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let effective_network_access =
+        effective_network_access(network_access_collection, firewall_rules_collection);
+
+    // Weighted exploit edges: (src, (dst, cost of the easiest exploit)).
+    let weighted_edges = effective_network_access
+        .map(|access| {
+            (
+                (access.destination_host, access.service_name, access.port),
+                access.source_host,
+            )
+        })
+        .join(&vulnerability_collection.map(|vuln| {
+            let difficulty = vuln.difficulty_hundredths();
+            (
+                (vuln.host_name, vuln.affected_service, vuln.port),
+                difficulty,
+            )
+        }))
+        .map(|((destination, _service, _port), (source, cost))| ((source, destination), cost))
+        .reduce(|_key, input, output| output.push((*input[0].0, 1)))
+        .map(|((source, destination), cost)| (source, (destination, cost)));
+
+    let starting_paths = attacker_positions_collection.map(|position| {
+        (
+            (position.attacker_id, position.starting_host.clone()),
+            (0u32, vec![position.starting_host]),
+        )
+    });
+
+    let cheapest_paths = starting_paths.iterate(|paths| {
+        let edges_in_scope = weighted_edges.enter(&paths.scope());
+        let starts_in_scope = starting_paths.enter(&paths.scope());
+
+        paths
+            .map(|((attacker_id, host), (cost, path))| (host, (attacker_id, cost, path)))
+            .join(&edges_in_scope)
+            .filter(
+                |(_source, ((_attacker_id, _cost, path), (destination, _edge_cost)))| {
+                    !path.contains(destination)
+                },
+            )
+            .map(
+                |(_source, ((attacker_id, cost, mut path), (destination, edge_cost)))| {
+                    path.push(destination.clone());
+                    ((attacker_id, destination), (cost + edge_cost, path))
+                },
+            )
+            .concat(&starts_in_scope)
+            // Keep the cheapest (cost, path); values arrive sorted.
+            .reduce(|_key, input, output| output.push((input[0].0.clone(), 1)))
+    });
+
+    cheapest_paths.map(
+        |((attacker_id, host_name), (cost_hundredths, path))| CheapestAttackPath {
+            attacker_id,
+            host_name,
+            cost_hundredths,
+            path,
+        },
+    )
+}
+
+// ----------------------------------------------------------------
+// max_privilege_per_host
+// ----------------------------------------------------------------
+// Highest privilege each attacker obtains on each host it executes
+// code on.  `reduce` hands the values in ascending order, and
+// `PrivilegeLevel` orders None < User < Root, so the last one wins.
+pub fn max_privilege_per_host<G>(
+    code_executions: &Collection<G, AttackerCodeExecution>,
+) -> Collection<G, (AttackerAndHostKey, PrivilegeLevel)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    code_executions
+        .map(|exec| {
+            (
+                (exec.attacker_id, exec.compromised_host),
+                exec.obtained_privilege,
+            )
+        })
+        .reduce(|_key, privileges, output| {
+            let (highest, _) = privileges[privileges.len() - 1];
+            output.push((highest.clone(), 1));
+        })
+}
+
+// ----------------------------------------------------------------
+// compromise_closure
+// ----------------------------------------------------------------
+// Pairwise transitive closure of the exploit edges: (a, b) is present
+// when an attacker holding a foothold on `a` can exploit its way to
+// `b`.  Like `attack_basin`, privileges are ignored, and a host is not
+// paired with itself even when it sits on a cycle.
+pub fn compromise_closure<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+) -> Collection<G, (HostIdentifier, HostIdentifier)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let effective_network_access =
+        effective_network_access(network_access_collection, firewall_rules_collection);
+    let edges = exploit_edges(vulnerability_collection, &effective_network_access);
+
+    transitive_closure(&edges)
+}
+
+// ----------------------------------------------------------------
+// network_closure
+// ----------------------------------------------------------------
+// Pairwise transitive closure of effective network access: (a, b) is
+// present when traffic from `a` can reach `b`, possibly relayed
+// through other hosts.  Vulnerabilities and attackers play no part,
+// which makes this the segmentation view of the topology.  As in
+// `compromise_closure`, services and ports are ignored and a host is
+// not paired with itself.
+pub fn network_closure<G>(
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+) -> Collection<G, (HostIdentifier, HostIdentifier)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let connections =
+        effective_network_access(network_access_collection, firewall_rules_collection)
+            .map(|access| (access.source_host, access.destination_host))
+            .distinct();
+
+    transitive_closure(&connections)
+}
+
+// ----------------------------------------------------------------
+// exploited_cycle_components
+// ----------------------------------------------------------------
+// Strongly connected components of the exploit edges the attackers
+// actually use: an edge counts once some attacker executes code on its
+// source.  Two hosts share a component when each can be compromised
+// from the other, i.e. the attackers can move laterally in a loop
+// between them.  Every component is emitted once, as its sorted host
+// list; hosts outside any loop are left out, so each list has at
+// least two hosts.
+pub fn exploited_cycle_components<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    code_executions: &Collection<G, AttackerCodeExecution>,
+) -> Collection<G, Vec<HostIdentifier>>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let effective_network_access =
+        effective_network_access(network_access_collection, firewall_rules_collection);
+    let compromised_hosts = code_executions
+        .map(|execution| execution.compromised_host)
+        .distinct();
+    let exploited_edges = exploit_edges(vulnerability_collection, &effective_network_access)
+        .semijoin(&compromised_hosts);
+    let closure = transitive_closure(&exploited_edges);
+
+    // (a, b) whose reverse (b, a) is in the closure as well.
+    closure
+        .map(|pair| (pair, ()))
+        .semijoin(&closure.map(|(origin, destination)| (destination, origin)))
+        .map(|((host, peer), ())| (host, peer))
+        .reduce(|host, peers, output| {
+            let mut component: Vec<HostIdentifier> =
+                peers.iter().map(|(peer, _)| (*peer).clone()).collect();
+            component.push(host.clone());
+            component.sort();
+            output.push((component, 1));
+        })
+        .map(|(_host, component)| component)
+        .distinct()
+}
+
+// Transitive closure of a host-to-host edge relation, without
+// self-pairs.
+fn transitive_closure<G>(
+    edges: &Collection<G, (HostIdentifier, HostIdentifier)>,
+) -> Collection<G, (HostIdentifier, HostIdentifier)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    edges
+        .iterate(|closure| {
+            let edges_in_scope = edges.enter(&closure.scope());
+
+            // (origin, via) + (via, next) => (origin, next)
+            closure
+                .map(|(origin, via)| (via, origin))
+                .join(&edges_in_scope)
+                .map(|(_via, (origin, next))| (origin, next))
+                .concat(&edges_in_scope)
+                .distinct()
+        })
+        .filter(|(origin, destination)| origin != destination)
+        .consolidate()
+}
+
+// ----------------------------------------------------------------
+// exfiltration_goals_reached
+// ----------------------------------------------------------------
+// Exfiltration composes forward compromise with a return path: an
+// `ExfilGoal` is satisfied when its attacker owns the data host and the
+// data host reaches the exit host over effective network access,
+// possibly relayed through other hosts.  Relays need no vulnerability,
+// as the data travels over legitimate connections.  `owns_machine` is
+// the ownership output of any of the rule builders.
+pub fn exfiltration_goals_reached<G>(
+    owns_machine: &Collection<G, AttackerOwnsMachine>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    exfil_goals_collection: &Collection<G, ExfilGoal>,
+) -> Collection<G, ExfilGoal>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let connections =
+        effective_network_access(network_access_collection, firewall_rules_collection)
+            .map(|access| (access.source_host, access.destination_host))
+            .distinct();
+
+    let owned_keys = owns_machine.map(|owned| (owned.attacker_id, owned.owned_host));
+    let owned_data_goals = exfil_goals_collection
+        .map(|goal| {
+            (
+                (goal.attacker_id.clone(), goal.data_host_name.clone()),
+                goal,
+            )
+        })
+        .semijoin(&owned_keys)
+        .map(|(_key, goal)| goal);
+
+    // Hosts reachable from each owned data host: (data_host, host).
+    let data_hosts = owned_data_goals.map(|goal| goal.data_host_name).distinct();
+    let direct_reach = data_hosts
+        .map(|host| (host, ()))
+        .join(&connections)
+        .map(|(data_host, ((), next))| (data_host, next));
+    let reachable_from_data_hosts = direct_reach.iterate(|reach| {
+        let connections_in_scope = connections.enter(&reach.scope());
+        let direct_in_scope = direct_reach.enter(&reach.scope());
+
+        reach
+            .map(|(data_host, via)| (via, data_host))
+            .join(&connections_in_scope)
+            .map(|(_via, (data_host, next))| (data_host, next))
+            .concat(&direct_in_scope)
+            .distinct()
+    });
+
+    owned_data_goals
+        .map(|goal| {
+            (
+                (goal.data_host_name.clone(), goal.exit_host_name.clone()),
+                goal,
+            )
+        })
+        .semijoin(&reachable_from_data_hosts)
+        .map(|(_key, goal)| goal)
+}
+
+// ----------------------------------------------------------------
+// build_attack_routes
+// ----------------------------------------------------------------
+// Path-carrying variant of stratum 2: instead of only recording that
+// an attacker reaches a host, every derived fact carries the full
+// sequence of hosts and exploited services that led there.  When a
+// route expands from Src to Dst, Dst and the service are appended.
+// Routes never revisit a host, which cuts cycles and keeps the set of
+// routes finite.  The number of simple routes grows quickly with the
+// graph's branching factor, so this is meant for analysis of small to
+// medium scenarios rather than for the incremental hot path.
+pub fn build_attack_routes<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+) -> Collection<G, AttackRoute>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    attack_routes_with_policy(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        FirewallPolicy::DefaultAllow,
+    )
+}
+
+fn attack_routes_with_policy<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    firewall_policy: FirewallPolicy,
+) -> Collection<G, AttackRoute>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let access_indexed_by_source = effective_network_access_with_policy(
+        network_access_collection,
+        firewall_rules_collection,
+        firewall_policy,
+        None,
+    )
+    .map(|access| {
+        (
+            access.source_host,
+            (access.destination_host, access.service_name, access.port),
+        )
+    });
+
+    let vulnerabilities_indexed_by_host_service = vulnerability_collection.map(|vuln| {
+        (
+            (vuln.host_name, vuln.affected_service, vuln.port),
+            vuln.privilege_gained_on_exploit,
+        )
+    });
+
+    let initial_routes = attacker_positions_collection.map(|position| AttackRoute {
+        attacker_id: position.attacker_id,
+        hosts: vec![position.starting_host],
+        services: Vec::new(),
+        final_privilege: position.initial_privilege,
+    });
+
+    initial_routes
+        .iterate(|current_routes| {
+            let access_in_scope = access_indexed_by_source.enter(&current_routes.scope());
+            let vulns_in_scope =
+                vulnerabilities_indexed_by_host_service.enter(&current_routes.scope());
+
+            let extended_routes = current_routes
+                .map(|route| (route.last_host().clone(), route))
+                .join(&access_in_scope)
+                // Cut cycles: never extend a route into a host it already visited.
+                .filter(|(_source, (route, (destination, _service, _port)))| {
+                    !route.hosts.contains(destination)
+                })
+                .map(|(_source, (route, (destination, service, port)))| {
+                    ((destination, service, port), route)
+                })
+                .join(&vulns_in_scope)
+                .map(|((destination, service, _port), (route, privilege))| {
+                    let mut hosts = route.hosts;
+                    let mut services = route.services;
+                    hosts.push(destination);
+                    services.push(service);
+                    AttackRoute {
+                        attacker_id: route.attacker_id,
+                        hosts,
+                        services,
+                        final_privilege: privilege,
+                    }
+                });
+
+            extended_routes.concat(current_routes).distinct()
+        })
+        .consolidate()
+}
+
+// ----------------------------------------------------------------
+// build_attack_graph_with_paths
+// ----------------------------------------------------------------
+// execCode, ownsMachine, goalReached and attackPath, in that order.
+pub type AttackGraphWithPaths<G> = (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+    Collection<G, AttackPath>,
+);
+
+// `build_attack_graph` plus every cycle-free attack path as a fourth
+// output.  Paths come from `build_attack_routes` with the services
+// projected out, deduplicated by the full host vector (and final
+// privilege), so several services on one hop yield a single path.  As
+// with routes, the path count can grow quickly on dense graphs.
+pub fn build_attack_graph_with_paths<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> AttackGraphWithPaths<G>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let outputs = build_attack_graph_with_options(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        &AttackGraphOptions {
+            track_paths: true,
+            ..AttackGraphOptions::default()
+        },
+    );
+    let attack_paths = outputs
+        .attack_paths
+        .expect("track_paths always builds the paths");
+
+    (
+        outputs.code_executions,
+        outputs.owned_machines,
+        outputs.goals_reached,
+        attack_paths,
+    )
+}
+
+// ----------------------------------------------------------------
+// build_attack_graph_with_distance
+// ----------------------------------------------------------------
+// execCode, ownsMachine, goalReached and hostDistance, in that order.
+pub type AttackGraphWithDistances<G> = (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+    Collection<G, HostDistance>,
+);
+
+// `build_attack_graph` plus the shortest attack path length to every
+// host an attacker reaches.  The hop counter is carried through its
+// own fixed point and min-reduced per (attacker, host), the same
+// computation that bounds `build_attack_graph_with_max_hops`.
+pub fn build_attack_graph_with_distance<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> AttackGraphWithDistances<G>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let (exec_code, owns_machine, goals_reached) = build_attack_graph(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+    );
+    let host_distances = minimum_hop_distances(
+        vulnerability_collection,
+        &effective_network_access(network_access_collection, firewall_rules_collection),
+        attacker_positions_collection,
+    )
+    .map(|((attacker_id, host_name), hops)| HostDistance {
+        attacker_id,
+        host_name,
+        hops: u32::try_from(hops).expect("hop count should fit in u32"),
+    });
+
+    (exec_code, owns_machine, goals_reached, host_distances)
+}
+
+// ----------------------------------------------------------------
+// build_attack_graph_with_justifications
+// ----------------------------------------------------------------
+// execCode, ownsMachine, goalReached and execCodeJustification, in that
+// order.
+pub type AttackGraphWithJustifications<G> = (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+    Collection<G, ExecCodeJustification>,
+);
+
+// `build_attack_graph` plus the exploit steps behind every derived
+// execCode fact.  The remote exploitation rule is replayed once over
+// the settled execCode relation, this time keeping the CVE that the
+// fixed point drops:
+//   justification(A,D,S,Svc,Cve) :- execCode(A,S,_), effectiveAccess(S,D,Svc,Port),
+//                                   vulnerability(D,Cve,Svc,Port,_).
+// Any privilege on the source host is enough, so the result is
+// distinct()-ed to collapse the per-privilege duplicates.
+pub fn build_attack_graph_with_justifications<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> AttackGraphWithJustifications<G>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let (exec_code, owns_machine, goals_reached, privileges, _, _) = build_attack_graph_arranged(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions::default(),
+    );
+
+    let footholds_by_source = privileges
+        .as_collection(|(attacker_id, host), _privilege| (host.clone(), attacker_id.clone()))
+        .distinct();
+    let access_by_source =
+        effective_network_access(network_access_collection, firewall_rules_collection).map(
+            |access| {
+                (
+                    access.source_host,
+                    (access.destination_host, access.service_name, access.port),
+                )
+            },
+        );
+    let vulnerabilities_by_target = vulnerability_collection.map(|vuln| {
+        (
+            (vuln.host_name, vuln.affected_service, vuln.port),
+            vuln.vulnerability_id,
+        )
+    });
+
+    let justifications = footholds_by_source
+        .join(&access_by_source)
+        .map(|(via_source, (attacker_id, target))| (target, (attacker_id, via_source)))
+        .join(&vulnerabilities_by_target)
+        .map(
+            |((host_name, via_service, _port), ((attacker_id, via_source), via_cve))| {
+                ExecCodeJustification {
+                    attacker_id,
+                    host_name,
+                    via_source,
+                    via_service,
+                    via_cve,
+                }
+            },
+        )
+        .distinct();
+
+    (exec_code, owns_machine, goals_reached, justifications)
+}
+
+// ----------------------------------------------------------------
+// build_attack_graph_full
+// ----------------------------------------------------------------
+// execCode, ownsMachine, goalReached and goalUnreachable, in that order.
+pub type AttackGraphFull<G> = (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+    Collection<G, AttackerGoalUnreachable>,
+);
+
+// `build_attack_graph_with_local_vulnerabilities` plus the goals that
+// are not reached:
+//   goalUnreachable(A,H) :- attackerGoal(A,H,Req), NOT goalReached(A,H,Req).
+// goalReached is complete once the fixed point settles, so the
+// negation is stratified.  A goal that turns reachable after an
+// update shows up as a retraction here and an insertion in
+// goalReached at the same timestamp.
+pub fn build_attack_graph_full<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    local_vulnerability_collection: &Collection<G, LocalVulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> AttackGraphFull<G>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let (exec_code, owns_machine, goals_reached, privileges, _, _) = build_attack_graph_arranged(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        RuleExtensions {
+            local_vulnerabilities: Some(local_vulnerability_collection),
+            ..RuleExtensions::default()
+        },
+    );
+    // Negate the satisfied goals themselves rather than goalReached, so
+    // a Root goal on a host is still unreachable when only a User goal
+    // on the same host holds.
+    let reached_goals = satisfied_goals(&privileges, attacker_goals_collection);
+    let goals_unreachable = attacker_goals_collection
+        .map(|goal| (goal, ()))
+        .distinct()
+        .antijoin(&reached_goals)
+        .map(|(goal, ())| AttackerGoalUnreachable {
+            attacker_id: goal.attacker_id,
+            target_host_name: goal.target_host_name,
+            required_privilege: goal.required_privilege,
+        })
+        .distinct();
+
+    (exec_code, owns_machine, goals_reached, goals_unreachable)
+}
+
+// =========================================================================
+// STRATUM 1: Effective network access (network edges minus denies)
+// =========================================================================
+// We implement: effectiveAccess(S,D,Svc,Port) :- network(S,D,Svc,Port), NOT deny(S,D,Svc,Port).
+// Implementation steps:
+//  1. Key the network edges by (src,dst,svc,port) so joins/antijoins are keyed.
+//  2. Extract the set of deny keys from firewall rules (filter by Deny).
+//  3. Antijoin the network set with the deny set to remove blocked edges.
+/// The network edges that survive the firewall under the default-allow
+/// policy, i.e. the effectiveAccess facts the execCode rules pivot over.
+pub fn effective_network_access<G>(
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+) -> Collection<G, EffectiveNetworkAccess>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    effective_network_access_with_policy(
+        network_access_collection,
+        firewall_rules_collection,
+        FirewallPolicy::DefaultAllow,
+        None,
+    )
+}
+
+fn effective_network_access_with_policy<G>(
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    firewall_policy: FirewallPolicy,
+    host_zones: Option<&Collection<G, HostZone>>,
+) -> Collection<G, EffectiveNetworkAccess>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    // 1) key network rules by (src, dst, service, port)
+    let network_access_keyed_by_route = network_access_collection.map(|rule| {
+        // Create an explicit route key tuple for joining/antijoins.
+        let route_key = (
+            rule.source_host.clone(),
+            rule.destination_host.clone(),
+            rule.service_name.clone(),
+            rule.port,
+        );
+        (route_key, rule)
+    });
+
+    // 2) keep the edges the policy permits: an antijoin against the
+    //    deny keys, or a semijoin against the allow keys
+    permitted_routes(
+        &network_access_keyed_by_route,
+        firewall_rules_collection,
+        firewall_policy,
+        host_zones,
+    )
+    // Restore the original structure but now only for effective edges
+    .map(|(_, original_rule)| EffectiveNetworkAccess {
+        source_host: original_rule.source_host,
+        destination_host: original_rule.destination_host,
+        service_name: original_rule.service_name,
+        port: original_rule.port,
+    })
+}
+
+// Keeps the routes keyed by (src, dst, service, port) that the firewall
+// permits under `firewall_policy`.  Without `host_zones`, rules match
+// routes literally; with them, see `zoned_route_decisions`.
+fn permitted_routes<G, V>(
+    routes: &Collection<G, (NetworkAccessKey, V)>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    firewall_policy: FirewallPolicy,
+    host_zones: Option<&Collection<G, HostZone>>,
+) -> Collection<G, (NetworkAccessKey, V)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    V: differential_dataflow::ExchangeData,
+{
+    if let Some(host_zones) = host_zones {
+        let decisions = zoned_route_decisions(
+            &routes.map(|(route_key, _)| route_key).distinct(),
+            firewall_rules_collection,
+            host_zones,
+        );
+        let allowed_route_keys = decisions
+            .filter(|(_, action)| *action == FirewallRuleAction::Allow)
+            .map(|(route_key, _)| route_key);
+        let allowed_routes = routes.semijoin(&allowed_route_keys);
+        return match firewall_policy {
+            // Routes no rule decides stay open.
+            FirewallPolicy::DefaultAllow => routes
+                .antijoin(&decisions.map(|(route_key, _)| route_key))
+                .concat(&allowed_routes),
+            FirewallPolicy::DefaultDeny => allowed_routes,
+        };
+    }
+
+    match firewall_policy {
+        FirewallPolicy::DefaultAllow => routes.antijoin(&route_keys_with_action(
+            firewall_rules_collection,
+            FirewallRuleAction::Deny,
+        )),
+        FirewallPolicy::DefaultDeny => routes.semijoin(&route_keys_with_action(
+            firewall_rules_collection,
+            FirewallRuleAction::Allow,
+        )),
+    }
+}
+
+// The deciding firewall action for every route some rule matches once
+// zones and wildcards are expanded:
+//   alias(H, H, 2).  alias(H, Z, 1) :- inZone(H, Z).  alias(H, "*", 0).
+//   match(S,D,Svc,Port, Rs+Rd, Act) :- alias(S,As,Rs), alias(D,Ad,Rd),
+//                                      firewall(As,Ad,Svc,Port,Act).
+// The matches with the highest rank decide; Deny wins among them.
+fn zoned_route_decisions<G>(
+    route_keys: &Collection<G, NetworkAccessKey>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    host_zones: &Collection<G, HostZone>,
+) -> Collection<G, (NetworkAccessKey, FirewallRuleAction)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let zones_by_host = host_zones.map(|membership| (membership.host_name, membership.zone_name));
+
+    // Every name a route's source (or destination) host can be
+    // referred to by in a rule, with the rank of that name.
+    let aliases = |endpoint: fn(&NetworkAccessKey) -> HostIdentifier| {
+        let zone_aliases = route_keys
+            .map(move |route_key| (endpoint(&route_key), route_key))
+            .join_map(&zones_by_host, |_, route_key, zone| {
+                (route_key.clone(), (zone.clone(), 1u8))
+            });
+        route_keys
+            .map(move |route_key| {
+                let host = endpoint(&route_key);
+                (route_key, (host, 2u8))
+            })
+            .concat(&route_keys.map(|route_key| (route_key, ("*".to_string(), 0u8))))
+            .concat(&zone_aliases)
+    };
+    let source_aliases = aliases(|(source, _, _, _)| source.clone());
+    let destination_aliases = aliases(|(_, destination, _, _)| destination.clone());
+
+    let rules_by_key = firewall_rules_collection.map(|rule| {
+        (
+            (
+                rule.source_zone,
+                rule.destination_host,
+                rule.service_name,
+                rule.port,
+            ),
+            rule.rule_action,
+        )
+    });
+
+    source_aliases
+        .join(&destination_aliases)
+        .map(
+            |(route_key, ((source_alias, source_rank), (destination_alias, destination_rank)))| {
+                let rule_key = (
+                    source_alias,
+                    destination_alias,
+                    route_key.2.clone(),
+                    route_key.3,
+                );
+                (rule_key, (route_key, source_rank + destination_rank))
+            },
+        )
+        .join_map(&rules_by_key, |_, (route_key, rank), action| {
+            (route_key.clone(), (*rank, action.clone()))
+        })
+        .reduce(|_, matches, output| {
+            // `matches` is sorted, so the most specific rank comes last.
+            let (highest_rank, _) = matches[matches.len() - 1].0;
+            let denied = matches.iter().any(|((rank, action), _)| {
+                *rank == *highest_rank && *action == FirewallRuleAction::Deny
+            });
+            let decision = if denied {
+                FirewallRuleAction::Deny
+            } else {
+                FirewallRuleAction::Allow
+            };
+            output.push((decision, 1));
+        })
+}
+
+// Keys (src, dst, service, port) of the firewall rules with `action`.
+fn route_keys_with_action<G>(
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    action: FirewallRuleAction,
+) -> Collection<G, NetworkAccessKey>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    firewall_rules_collection
+        // Only rules of one action are consulted: denies are negated
+        // under the default-allow policy, allows are required under
+        // default-deny.
+        .filter(move |rule| rule.rule_action == action)
         // Map to the same key shape as the network rules
         .map(|rule| {
             (
                 rule.source_zone.clone(),
                 rule.destination_host.clone(),
                 rule.service_name.clone(),
+                rule.port,
             )
         })
-        // distinct() removes duplicates and reduces work for the antijoin
+        // distinct() removes duplicates and reduces work for the join
+        .distinct()
+}
+
+// The vulnerabilities the execCode rules may exploit: all of them,
+// minus those fixed by an applied patch and those for another OS.
+fn applicable_vulnerabilities<G, P>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord<P>>,
+    extensions: &RuleExtensions<'_, G, P>,
+) -> Collection<G, VulnerabilityRecord<P>>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    let mut vulnerabilities = vulnerability_collection.clone();
+    if let Some(patches_applied) = extensions.patches_applied {
+        vulnerabilities = unpatched_vulnerabilities(&vulnerabilities, patches_applied);
+    }
+    if let Some(host_info) = extensions.host_info {
+        vulnerabilities = os_applicable_vulnerabilities(&vulnerabilities, host_info);
+    }
+    vulnerabilities
+}
+
+// Vulnerabilities no applied patch fixes:
+//   unpatched(V) :- vulnerability(V), NOT patchApplied(V.host, V.cve).
+fn unpatched_vulnerabilities<G, P>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord<P>>,
+    patches_applied_collection: &Collection<G, PatchApplied>,
+) -> Collection<G, VulnerabilityRecord<P>>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    let patched_keys = patches_applied_collection
+        .map(|patch| (patch.host_name, patch.fixed_vulnerability_id))
         .distinct();
 
-    // 3) antijoin: keep network edges that are NOT present in blocked_route_keys
-    let effective_network_access = network_access_keyed_by_route
-        .antijoin(&blocked_route_keys)
-        // Restore the original structure but now only for effective edges
-        .map(|(_, original_rule)| EffectiveNetworkAccess {
-            source_host: original_rule.source_host,
-            destination_host: original_rule.destination_host,
-            service_name: original_rule.service_name,
-        });
+    vulnerability_collection
+        .map(|vulnerability| {
+            (
+                (
+                    vulnerability.host_name.clone(),
+                    vulnerability.vulnerability_id.clone(),
+                ),
+                vulnerability,
+            )
+        })
+        .antijoin(&patched_keys)
+        .map(|(_host_and_cve, vulnerability)| vulnerability)
+}
+
+// Vulnerabilities exploitable given the hosts' operating systems:
+//   applicable(V) :- vulnerability(V), V.os = none.
+//   applicable(V) :- vulnerability(V), V.os = OS, hostInfo(V.host, OS).
+// Only base facts are involved, so this runs before the fixed point.
+fn os_applicable_vulnerabilities<G, P>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord<P>>,
+    host_info_collection: &Collection<G, HostInfo>,
+) -> Collection<G, VulnerabilityRecord<P>>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    let host_operating_systems = host_info_collection
+        .map(|info| (info.host_name, info.os))
+        .distinct();
+
+    let os_specific = vulnerability_collection
+        .flat_map(|vulnerability| {
+            vulnerability
+                .applies_to_os
+                .clone()
+                .map(|os| ((vulnerability.host_name.clone(), os), vulnerability))
+        })
+        .semijoin(&host_operating_systems)
+        .map(|(_host_and_os, vulnerability)| vulnerability);
+
+    vulnerability_collection
+        .filter(|vulnerability| vulnerability.applies_to_os.is_none())
+        .concat(&os_specific)
+}
+
+// execCode, the facts cut off by `max_iterations` and the fixed
+// point's rounds, as `derive_code_executions` returns them.
+// execCode inside the fixed point: (attacker, host, privilege), with
+// names interned.
+type InternedCodeExecution<P> = (NameId, NameId, P);
 
-    // =========================================================================
-    // STRATUM 2: Recursive computation of execCode (reachability + exploitation)
-    // =========================================================================
-    // High-level rules implemented:
-    //   execCode(A,H,P) :- attackerLocation(A,H,P).
-    //   execCode(A,D,P) :- execCode(A,Src,_), effectiveAccess(Src,D,Svc), vulnerability(D,_,Svc,P).
-    // We implement this using `iterate()` which repeatedly applies the
-    // body until no new facts are produced (fixed point).  Inside the
-    // iterate scope we must `enter()` static collections so they are
-    // visible in the inner scope.
+type DerivedCodeExecutions<G, P> = (
+    Collection<G, AttackerCodeExecution<P>>,
+    Option<Collection<G, AttackerCodeExecution<P>>>,
+    Option<Collection<G, u64>>,
+);
+
+// =========================================================================
+// STRATUM 2: Recursive computation of execCode (reachability + exploitation)
+// =========================================================================
+// High-level rules implemented:
+//   execCode(A,H,P) :- attackerLocation(A,H,P).
+//   execCode(A,D,P) :- execCode(A,Src,_), effectiveAccess(Src,D,Svc,Port), vulnerability(D,_,Svc,Port,P).
+// We implement this using `iterate()` which repeatedly applies the
+// body until no new facts are produced (fixed point).  Inside the
+// iterate scope we must `enter()` static collections so they are
+// visible in the inner scope.
+//
+// Access-granting vulnerabilities add one more rule whose body depends
+// on the recursive relation itself:
+//   reach(A,D,Svc) :- execCode(A,H,root), accessGrant(H,Svc,D), NOT deny(H,D,Svc).
+// Granted and role-gated edges name no port; they use the service's
+// default port, both for firewall matching and for vulnerabilities.
+// The firewall negation only touches base facts, so the program stays
+// stratified even though the granted edges appear during iteration.
+//
+// Privilege escalation facts upgrade execCode in place when the
+// attacker holds exactly the required privilege:
+//   execCode(A,H,To) :- execCode(A,H,From), privEscalation(H,_,From,To).
+//
+// Role-based access adds role-gated edges; the roles an attacker holds
+// grow during iteration as role-conferring hosts are compromised:
+//   hasRole(A,R) :- execCode(A,H,_), confersRole(H,R).
+//   reach(A,D,Svc) :- execCode(A,S,_), roleGrant(R,S,D,Svc), hasRole(A,R).
+//
+// Credential theft lets an attacker log in anywhere a credential it
+// dumped is valid:
+//   execCode(A,T,P) :- execCode(A,H,_), credentialStored(H,C), credentialGrants(C,T,P).
+//
+// When `retained_hosts` is given, newly derived facts are only kept
+// for (attacker, host) pairs present in that collection.
+//
+// With `max_iterations` set to N, facts derived at iteration N or later
+// are dropped, so execCode settles at the Nth round's value and the
+// loop goes quiet.  Alongside execCode this then returns the facts the
+// dropped round would have added; the second element is `None`
+// without a bound.
+//
+// With `track_rounds` set, the third element holds the loop round of
+// every update to the loop variable, read off `time.inner` and left
+// unconsolidated so that it leaves the loop round by round; it is
+// `None` otherwise.
+fn derive_code_executions<G, P>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord<P>>,
+    effective_network_access: &Collection<G, EffectiveNetworkAccess>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition<P>>,
+    extensions: &RuleExtensions<'_, G, P>,
+) -> DerivedCodeExecutions<G, P>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    // Names are interned on the way into the loop and resolved on the
+    // way out, so the rules below join and clone `NameId`s instead of
+    // `String`s.  execCode facts inside the loop are
+    // (attacker, host, privilege) triples of ids.
+    let names = StringInterner::global();
 
     // Base case: where the attacker starts
-    let initial_code_execution =
-        attacker_positions_collection.map(|position| AttackerCodeExecution {
-            attacker_id: position.attacker_id,
-            compromised_host: position.starting_host,
-            obtained_privilege: position.initial_privilege,
-        });
+    let initial_code_execution = attacker_positions_collection.map(move |position| {
+        (
+            names.intern(&position.attacker_id),
+            names.intern(&position.starting_host),
+            position.initial_privilege,
+        )
+    });
 
     // Index effective access by source host to accelerate joins
-    let access_indexed_by_source = effective_network_access.map(|access| {
+    let access_indexed_by_source = effective_network_access.map(move |access| {
         (
-            access.source_host.clone(),
-            (access.destination_host.clone(), access.service_name.clone()),
+            names.intern(&access.source_host),
+            (
+                names.intern(&access.destination_host),
+                names.intern(&access.service_name),
+                access.port,
+            ),
         )
     });
 
-    // Index vulnerabilities by (host, service) for efficient lookup
-    let vulnerabilities_indexed_by_host_service = vulnerability_collection.map(|vuln| {
+    // Index vulnerabilities by (host, service, port) for efficient lookup
+    let vulnerabilities_indexed_by_host_service = vulnerability_collection.map(move |vuln| {
         (
-            (vuln.host_name.clone(), vuln.affected_service.clone()),
-            vuln.privilege_gained_on_exploit.clone(),
+            (
+                names.intern(&vuln.host_name),
+                names.intern(&vuln.affected_service),
+                vuln.port,
+            ),
+            vuln.privilege_gained_on_exploit,
         )
     });
 
+    // Vulnerabilities on a service an `EnablesService` fact controls
+    // are dormant: split them off, keyed like the others, next to the
+    // enabling facts indexed by host: host -> (privilege, service).
+    let (vulnerabilities_indexed_by_host_service, service_enabling) = match extensions
+        .enabled_services
+    {
+        Some(collection) => {
+            let controlled_services = collection
+                .map(move |enables| {
+                    (
+                        names.intern(&enables.host_name),
+                        names.intern(&enables.service_name),
+                    )
+                })
+                .distinct();
+            let by_host_service = vulnerabilities_indexed_by_host_service
+                .map(|((host, service, port), privilege)| ((host, service), (port, privilege)));
+            let rekey = |((host, service), (port, privilege))| ((host, service, port), privilege);
+            let dormant_vulnerabilities = by_host_service.semijoin(&controlled_services).map(rekey);
+            let enabling_indexed_by_host = collection.map(move |enables| {
+                (
+                    names.intern(&enables.host_name),
+                    (
+                        P::from(enables.required_privilege),
+                        names.intern(&enables.service_name),
+                    ),
+                )
+            });
+            (
+                by_host_service.antijoin(&controlled_services).map(rekey),
+                Some((dormant_vulnerabilities, enabling_indexed_by_host)),
+            )
+        }
+        None => (vulnerabilities_indexed_by_host_service, None),
+    };
+
     // Index local privilege escalation vulnerabilities by host. These
     // do not require network movement; they upgrade an existing
     // non-root execCode fact on the same host.
-    let local_vulnerabilities_indexed_by_host = local_vulnerability_collection.map(|collection| {
-        collection.map(|vuln| {
+    let local_vulnerabilities_indexed_by_host =
+        extensions.local_vulnerabilities.map(|collection| {
+            collection.map(move |vuln| {
+                (
+                    names.intern(&vuln.host_name),
+                    P::from(vuln.privilege_gained_on_exploit),
+                )
+            })
+        });
+
+    // Index privilege escalations by (host, required privilege).
+    let privilege_escalations_indexed_by_host =
+        extensions.privilege_escalations.map(|collection| {
+            collection.map(move |escalation| {
+                (
+                    (
+                        names.intern(&escalation.host_name),
+                        P::from(escalation.from_privilege),
+                    ),
+                    P::from(escalation.to_privilege),
+                )
+            })
+        });
+
+    // Index granted access edges by the host that must be owned, after
+    // removing edges the firewall does not permit.
+    let granted_access_indexed_by_host = extensions.access_grants.map(|collection| {
+        let granted_routes = collection.map(|grant| {
             (
-                vuln.host_name.clone(),
-                vuln.privilege_gained_on_exploit.clone(),
+                (
+                    grant.host_name.clone(),
+                    grant.grants_access_to.clone(),
+                    grant.service_name.clone(),
+                    default_port(&grant.service_name),
+                ),
+                (),
+            )
+        });
+        permitted_routes(
+            &granted_routes,
+            firewall_rules_collection,
+            extensions.firewall_policy,
+            extensions.host_zones,
+        )
+        .map(move |((host, destination, service, port), ())| {
+            (
+                names.intern(&host),
+                (names.intern(&destination), names.intern(&service), port),
             )
         })
     });
+    // Index role-gated edges by source host, again only permitted edges.
+    let role_access = extensions.role_access.map(|role_access| {
+        let role_routes = role_access.role_grants.map(|grant| {
+            (
+                (
+                    grant.source_host.clone(),
+                    grant.destination_host.clone(),
+                    grant.service_name.clone(),
+                    default_port(&grant.service_name),
+                ),
+                grant.role,
+            )
+        });
+        let role_edges_indexed_by_source = permitted_routes(
+            &role_routes,
+            firewall_rules_collection,
+            extensions.firewall_policy,
+            extensions.host_zones,
+        )
+        .map(move |((source, destination, service, port), role)| {
+            (
+                names.intern(&source),
+                (
+                    names.intern(&role),
+                    names.intern(&destination),
+                    names.intern(&service),
+                    port,
+                ),
+            )
+        });
+        let initial_roles = role_access.role_memberships.map(move |membership| {
+            (
+                names.intern(&membership.attacker_id),
+                names.intern(&membership.role),
+            )
+        });
+        let roles_conferred_by_host = role_access.role_conferring_hosts.map(move |conferring| {
+            (
+                names.intern(&conferring.host_name),
+                names.intern(&conferring.role),
+            )
+        });
+        (
+            role_edges_indexed_by_source,
+            initial_roles,
+            roles_conferred_by_host,
+        )
+    });
+    // Join stored credentials with their grants once, outside the
+    // loop: host -> (target, privilege).
+    let credential_logins_by_host = extensions.credential_theft.map(|credential_theft| {
+        credential_theft
+            .stored_credentials
+            .map(|stored| (stored.credential_id, stored.host_name))
+            .join(&credential_theft.credential_grants.map(|grant| {
+                (
+                    grant.credential_id,
+                    (grant.target_host, P::from(grant.privilege)),
+                )
+            }))
+            .map(move |(_credential, (host, (target, privilege)))| {
+                (names.intern(&host), (names.intern(&target), privilege))
+            })
+    });
+    let retained_hosts = extensions.retained_hosts.map(|collection| {
+        collection.map(move |(attacker_id, host)| (names.intern(&attacker_id), names.intern(&host)))
+    });
+    let derivation_rules = extensions.derivation_rules;
+    let max_iterations = extensions.max_iterations;
+    let mut truncated_executions = None;
+    let mut fixpoint_rounds = None;
+    // Access grants and local vulnerabilities key off holding Root.
+    let root = P::from(PrivilegeLevel::Root);
 
     // Now run the fixed-point iteration.  `iterate()` provides the
     // inner collection `current_executions`, representing the set of
@@ -201,6 +2422,16 @@ where
     // frontier by one hop through effective network access and
     // exploits.
     let all_code_executions = initial_code_execution.iterate(|current_executions| {
+        if extensions.track_rounds {
+            fixpoint_rounds = Some(
+                current_executions
+                    .inner
+                    .map(|(_, time, _)| (time.inner, time, 1))
+                    .as_collection()
+                    .leave(),
+            );
+        }
+
         // `enter()` moves a collection from the outer scope into the
         // inner iterative scope. This is necessary because
         // `access_indexed_by_source` and `vulnerabilities_indexed_by_host_service`
@@ -211,101 +2442,395 @@ where
         let local_vulns_in_scope = local_vulnerabilities_indexed_by_host
             .as_ref()
             .map(|collection| collection.enter(&current_executions.scope()));
+        let privilege_escalations_in_scope = privilege_escalations_indexed_by_host
+            .as_ref()
+            .map(|collection| collection.enter(&current_executions.scope()));
+        let granted_access_in_scope = granted_access_indexed_by_host
+            .as_ref()
+            .map(|collection| collection.enter(&current_executions.scope()));
+        let role_access_in_scope = role_access.as_ref().map(|(edges, roles, conferring)| {
+            (
+                edges.enter(&current_executions.scope()),
+                roles.enter(&current_executions.scope()),
+                conferring.enter(&current_executions.scope()),
+            )
+        });
+        let credential_logins_in_scope = credential_logins_by_host
+            .as_ref()
+            .map(|collection| collection.enter(&current_executions.scope()));
+        let retained_hosts_in_scope = retained_hosts
+            .as_ref()
+            .map(|collection| collection.enter(&current_executions.scope()));
+        let service_enabling_in_scope = service_enabling.as_ref().map(|(dormant, enabling)| {
+            (
+                dormant.enter(&current_executions.scope()),
+                enabling.enter(&current_executions.scope()),
+            )
+        });
 
         // Step A: For every execCode(attacker, src, _), find reachable
-        // destinations (dst, service) using the indexed access table.
-        // The result shape is ((dst, service), attacker)
-        let reachable_destinations = current_executions
-            .map(|exec| (exec.compromised_host.clone(), exec.attacker_id.clone()))
-            // join on source host -> yields (src, ((attacker),(dst,service)))
+        // destinations (dst, service, port) using the indexed access table.
+        // The result shape is ((dst, service, port), attacker)
+        let executions_by_host =
+            current_executions.map(|(attacker_id, host, _)| (host, attacker_id));
+        let reachable_destinations = executions_by_host
+            // join on source host -> yields (src, ((attacker),(dst,service,port)))
             .join(&access_in_scope)
-            .map(|(_source, (attacker_id, (destination, service)))| {
-                // Re-key by (destination, service) so we can check for a vuln
-                ((destination, service), attacker_id)
+            .map(|(_source, (attacker_id, destination_key))| {
+                // Re-key by (destination, service, port) so we can check for a vuln
+                (destination_key, attacker_id)
             });
 
-        // Step B: For each reachable (dst, service) check if dst has a
-        // vulnerability on that service and produce a new execCode fact
-        // with the privilege obtained from the vulnerability.
+        // Step A': owned hosts with an access-granting vulnerability
+        // open additional (dst, service, port) keys for the owning attacker.
+        let reachable_destinations = match granted_access_in_scope {
+            Some(granted_access) => {
+                let granted_destinations = current_executions
+                    .filter({
+                        let root = root.clone();
+                        move |(_, _, privilege)| privilege.dominates(&root)
+                    })
+                    .map(|(attacker_id, host, _)| (host, attacker_id))
+                    .join(&granted_access)
+                    .map(|(_owned_host, (attacker_id, destination_key))| {
+                        (destination_key, attacker_id)
+                    });
+                reachable_destinations.concat(&granted_destinations)
+            }
+            None => reachable_destinations,
+        };
+
+        // Step A'': role-gated edges, usable only with the edge's role.
+        let reachable_destinations = match role_access_in_scope {
+            Some((role_edges, initial_roles, conferring_hosts)) => {
+                let held_roles = executions_by_host
+                    .join(&conferring_hosts)
+                    .map(|(_host, (attacker_id, role))| (attacker_id, role))
+                    .concat(&initial_roles)
+                    .distinct();
+                let role_gated_destinations = executions_by_host
+                    .join(&role_edges)
+                    .map(
+                        |(_source, (attacker_id, (role, destination, service, port)))| {
+                            ((attacker_id, role), (destination, service, port))
+                        },
+                    )
+                    .semijoin(&held_roles)
+                    .map(|((attacker_id, _role), destination_key)| (destination_key, attacker_id));
+                reachable_destinations.concat(&role_gated_destinations)
+            }
+            None => reachable_destinations,
+        };
+
+        // Step B: For each reachable (dst, service, port) check if dst
+        // has a vulnerability on that service and port and produce a new
+        // execCode fact with the privilege obtained from the vulnerability.
         let newly_compromised_hosts = reachable_destinations.join(&vulns_in_scope).map(
-            |((host, _service), (attacker_id, privilege))| AttackerCodeExecution {
-                attacker_id,
-                compromised_host: host,
-                obtained_privilege: privilege,
-            },
+            |((host, _service, _port), (attacker_id, privilege))| (attacker_id, host, privilege),
         );
 
+        // Step B': dormant vulnerabilities, exploitable once the attacker
+        // has enabled their service:
+        //   enabled(A,H,Svc) :- execCode(A,H,P), enablesService(H,Req,Svc), P >= Req.
+        //   execCode(A,H,P) :- reach(A,H,Svc,Port), enabled(A,H,Svc), dormantVuln(H,Svc,Port,P).
+        let newly_compromised_hosts = match service_enabling_in_scope {
+            Some((dormant_vulns, enabling)) => {
+                let enabled_services = current_executions
+                    .map(|(attacker_id, host, privilege)| (host, (attacker_id, privilege)))
+                    .join(&enabling)
+                    .filter(|(_host, ((_attacker_id, held), (required, _service)))| {
+                        held.dominates(required)
+                    })
+                    .map(|(host, ((attacker_id, _held), (_required, service)))| {
+                        (attacker_id, host, service)
+                    })
+                    .distinct();
+                let enabled_exploits = reachable_destinations
+                    .join(&dormant_vulns)
+                    .map(|((host, service, _port), (attacker_id, privilege))| {
+                        ((attacker_id, host, service), privilege)
+                    })
+                    .semijoin(&enabled_services)
+                    .map(|((attacker_id, host, _service), privilege)| {
+                        (attacker_id, host, privilege)
+                    });
+                newly_compromised_hosts.concat(&enabled_exploits)
+            }
+            None => newly_compromised_hosts,
+        };
+
         let locally_escalated_executions = match local_vulns_in_scope {
             Some(local_vulns) => current_executions
-                .filter(|exec| exec.obtained_privilege != PrivilegeLevel::Root)
-                .map(|exec| (exec.compromised_host.clone(), exec.attacker_id.clone()))
+                .filter({
+                    let root = root.clone();
+                    move |(_, _, privilege)| !privilege.dominates(&root)
+                })
+                .map(|(attacker_id, host, _)| (host, attacker_id))
                 .join(&local_vulns)
-                .map(|(host, (attacker_id, privilege))| AttackerCodeExecution {
-                    attacker_id,
-                    compromised_host: host,
-                    obtained_privilege: privilege,
-                }),
+                .map(|(host, (attacker_id, privilege))| (attacker_id, host, privilege)),
             None => current_executions.filter(|_| false),
         };
 
+        let mut newly_derived_executions =
+            newly_compromised_hosts.concat(&locally_escalated_executions);
+        if let Some(escalations) = privilege_escalations_in_scope {
+            let escalated_executions = current_executions
+                .map(|(attacker_id, host, privilege)| ((host, privilege), attacker_id))
+                .join(&escalations)
+                .map(|((host, _from_privilege), (attacker_id, privilege))| {
+                    (attacker_id, host, privilege)
+                });
+            newly_derived_executions = newly_derived_executions.concat(&escalated_executions);
+        }
+        if let Some(credential_logins) = credential_logins_in_scope {
+            let credential_executions = executions_by_host.join(&credential_logins).map(
+                |(_host, (attacker_id, (target, privilege)))| (attacker_id, target, privilege),
+            );
+            newly_derived_executions = newly_derived_executions.concat(&credential_executions);
+        }
+
+        // Custom rules see the same round of execCode facts as the
+        // built-in rules above, by name.
+        if !derivation_rules.is_empty() {
+            let effective_access_in_scope =
+                effective_network_access.enter(&current_executions.scope());
+            let named_executions =
+                current_executions.map(move |execution| resolve_code_execution(names, execution));
+            let context = DerivationContext {
+                effective_access: &effective_access_in_scope,
+                code_executions: &named_executions,
+            };
+            for rule in derivation_rules {
+                let derived = rule.derive(&context).map(move |exec| {
+                    (
+                        names.intern(&exec.attacker_id),
+                        names.intern(&exec.compromised_host),
+                        exec.obtained_privilege,
+                    )
+                });
+                newly_derived_executions = newly_derived_executions.concat(&derived);
+            }
+        }
+        let newly_derived_executions = match retained_hosts_in_scope {
+            Some(retained) => newly_derived_executions
+                .map(|(attacker_id, host, privilege)| ((attacker_id, host), privilege))
+                .semijoin(&retained)
+                .map(|((attacker_id, host), privilege)| (attacker_id, host, privilege)),
+            None => newly_derived_executions,
+        };
+
+        // Step C': under an iteration bound, record what the rules
+        // still derive beyond the current set, then drop everything
+        // derived at or after the last allowed round.  `time.inner`
+        // is the round counter of the iterative scope.
+        let newly_derived_executions = match max_iterations {
+            Some(rounds) => {
+                truncated_executions = Some(
+                    newly_derived_executions
+                        .map(|exec| (exec, ()))
+                        .antijoin(&current_executions.distinct())
+                        .map(|(exec, ())| exec)
+                        .distinct()
+                        .leave()
+                        .map(move |execution| resolve_code_execution(names, execution)),
+                );
+                let rounds = rounds as u64;
+                newly_derived_executions
+                    .inner
+                    .filter(move |(_, time, _)| time.inner < rounds)
+                    .as_collection()
+            }
+            None => newly_derived_executions,
+        };
+
         // Step C: combine newly discovered compromises with the
         // previously discovered set and deduplicate with `distinct()`.
         // The `distinct()` call is crucial: it ensures that once a
         // fact has been produced, it does not reappear in subsequent
         // iterations. This guarantees that the fixed point computation
         // will terminate even in presence of cycles.
-        newly_compromised_hosts
-            .concat(&locally_escalated_executions)
+        newly_derived_executions
             .concat(current_executions)
             .distinct()
     });
+    let all_code_executions =
+        all_code_executions.map(move |execution| resolve_code_execution(names, execution));
+
+    (all_code_executions, truncated_executions, fixpoint_rounds)
+}
+
+// An interned (attacker, host, privilege) execCode fact by name.
+fn resolve_code_execution<P>(
+    names: &StringInterner,
+    (attacker_id, host, privilege): InternedCodeExecution<P>,
+) -> AttackerCodeExecution<P> {
+    AttackerCodeExecution {
+        attacker_id: names.resolve(attacker_id),
+        compromised_host: names.resolve(host),
+        obtained_privilege: privilege,
+    }
+}
+
+// Computes the minimum number of exploitation hops each attacker needs
+// to reach every host it can compromise.  Starting positions have
+// distance 0; local privilege escalation does not move the attacker
+// and therefore never contributes a hop.
+fn minimum_hop_distances<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    effective_network_access: &Collection<G, EffectiveNetworkAccess>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+) -> Collection<G, (AttackerAndHostKey, usize)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let exploit_edges = exploit_edges(vulnerability_collection, effective_network_access);
+
+    let starting_distances = attacker_positions_collection
+        .map(|position| ((position.attacker_id, position.starting_host), 0usize));
+
+    starting_distances.iterate(|distances| {
+        let edges_in_scope = exploit_edges.enter(&distances.scope());
+        let starts_in_scope = starting_distances.enter(&distances.scope());
+
+        distances
+            .map(|((attacker_id, host), hops)| (host, (attacker_id, hops)))
+            .join(&edges_in_scope)
+            .map(|(_source, ((attacker_id, hops), destination))| {
+                ((attacker_id, destination), hops + 1)
+            })
+            .concat(&starts_in_scope)
+            // Keep only the smallest distance (values arrive sorted).
+            .reduce(|_key, input, output| output.push((*input[0].0, 1)))
+    })
+}
 
-    // =========================================================================
-    // STRATUM 3: Ownership and goal checking
-    // =========================================================================
-    // A machine is considered "owned" by an attacker if the attacker
-    // achieves Root privilege on it.  Goals are checked by semijoining
-    // the goal list with the owned machines.
-
-    // Machines where an attacker obtained Root privilege
-    let machines_owned_by_attackers = all_code_executions
-        .filter(|exec| exec.obtained_privilege == PrivilegeLevel::Root)
-        .map(|exec| AttackerOwnsMachine {
-            attacker_id: exec.attacker_id,
-            owned_host: exec.compromised_host,
+// An exploit edge (src, dst) exists when the destination has a
+// vulnerability on a service that the source can reach.
+fn exploit_edges<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    effective_network_access: &Collection<G, EffectiveNetworkAccess>,
+) -> Collection<G, (HostIdentifier, HostIdentifier)>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    effective_network_access
+        .map(|access| {
+            (
+                (access.destination_host, access.service_name, access.port),
+                access.source_host,
+            )
         })
-        .distinct();
+        .semijoin(
+            &vulnerability_collection
+                .map(|vuln| (vuln.host_name, vuln.affected_service, vuln.port)),
+        )
+        .map(|((destination, _service, _port), source)| (source, destination))
+        .distinct()
+}
+
+// =========================================================================
+// STRATUM 3: Ownership and goal checking
+// =========================================================================
+// A machine is considered "owned" by an attacker if the attacker
+// achieves a privilege on it that dominates the ownership threshold
+// (Root by default).  Goals are checked against execCode directly:
+// each goal names the privilege it needs, independently of the
+// ownership threshold.
+//
+// Both rules, and the justification replay in
+// `build_attack_graph_with_justifications`, look execCode up by
+// (attacker, host).  execCode is therefore re-keyed, deduplicated and
+// arranged once (`arrange_privileges_by_attacker_host`) and every
+// consumer reads that shared index instead of building its own.
+
+// execCode as (attacker, host) -> privilege, one entry per distinct
+// fact.
+type PrivilegesByAttackerHost<G, P = PrivilegeLevel> =
+    Arranged<G, TraceAgent<ValSpine<AttackerAndHostKey, P, <G as ScopeParent>::Timestamp, isize>>>;
+
+fn arrange_privileges_by_attacker_host<G, P>(
+    all_code_executions: &Collection<G, AttackerCodeExecution<P>>,
+) -> PrivilegesByAttackerHost<G, P>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    all_code_executions
+        .map(|exec| {
+            (
+                (exec.attacker_id, exec.compromised_host),
+                exec.obtained_privilege,
+            )
+        })
+        .distinct()
+        .arrange_by_key()
+}
 
-    // Key owned machines by (attacker, host) for semijoin
-    let owned_machine_keys = machines_owned_by_attackers
-        .map(|owned| (owned.attacker_id.clone(), owned.owned_host.clone()))
+fn derive_ownership_and_goals<G, P>(
+    privileges: &PrivilegesByAttackerHost<G, P>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    ownership_threshold: P,
+) -> (
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    // Machines where an attacker obtained the threshold privilege
+    let machines_owned_by_attackers = privileges
+        .flat_map_ref(move |(attacker_id, owned_host), privilege| {
+            privilege
+                .dominates(&ownership_threshold)
+                .then(|| AttackerOwnsMachine {
+                    attacker_id: attacker_id.clone(),
+                    owned_host: owned_host.clone(),
+                })
+        })
         .distinct();
 
-    // Index goals by (attacker, target)
-    let goals_keyed = attacker_goals_collection.map(|goal| {
-        (
-            (goal.attacker_id.clone(), goal.target_host_name.clone()),
-            goal,
-        )
-    });
+    let successfully_reached_goals = satisfied_goals(privileges, attacker_goals_collection)
+        .map(|goal| AttackerGoalReached {
+            attacker_id: goal.attacker_id,
+            reached_target: goal.target_host_name,
+            required_privilege: goal.required_privilege,
+        })
+        .distinct();
 
-    // Semijoin goals with owned machines to produce reached goals
-    let successfully_reached_goals =
-        goals_keyed
-            .semijoin(&owned_machine_keys)
-            .map(|(_, goal)| AttackerGoalReached {
-                attacker_id: goal.attacker_id,
-                reached_target: goal.target_host_name,
-            });
+    (machines_owned_by_attackers, successfully_reached_goals)
+}
 
-    // Consolidate the outputs: this merges multiple diff updates for
-    // the same value into a single diff and reduces noise for the
-    // consumers of the returned collections.
-    (
-        all_code_executions.consolidate(),
-        machines_owned_by_attackers.consolidate(),
-        successfully_reached_goals.consolidate(),
-    )
+// goalReached(A, H) :- attackGoal(A, H, Req), execCode(A, H, P),
+//                      P dominates Req.
+// Yields the goals themselves, so goals on the same host that differ
+// only in their required privilege are told apart.
+fn satisfied_goals<G, P>(
+    privileges: &PrivilegesByAttackerHost<G, P>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+) -> Collection<G, AttackerTargetGoal>
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+    P: AttackGraphPrivilege,
+{
+    attacker_goals_collection
+        .map(|goal| {
+            (
+                (goal.attacker_id.clone(), goal.target_host_name.clone()),
+                goal,
+            )
+        })
+        .join_core(privileges, |_, goal, privilege| {
+            privilege
+                .dominates(&P::from(goal.required_privilege.clone()))
+                .then(|| goal.clone())
+        })
+        .distinct()
 }
 
 // ----------------------------------------------------------------
@@ -315,12 +2840,53 @@ where
 // attacker's reach up to `maximum_attack_hops`.  This is useful for
 // experiments where you want an explicit bound on attacker depth or
 // when the underlying iterative engine is not desired.
+//
+// Equivalence with `build_attack_graph`: every execCode fact has a
+// derivation whose hops visit each host at most once, so the bounded
+// expansion derives exactly the same execCode, ownership and goal
+// facts whenever `maximum_attack_hops >= number_of_hosts - 1`.  Both
+// versions apply the same firewall antijoin (stratum 1); neither
+// applies local privilege escalation.
 pub fn build_attack_graph_with_max_hops<G>(
     vulnerability_collection: &Collection<G, VulnerabilityRecord>,
     network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
+    attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
+    attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
+    maximum_attack_hops: usize,
+) -> (
+    Collection<G, AttackerCodeExecution>,
+    Collection<G, AttackerOwnsMachine>,
+    Collection<G, AttackerGoalReached>,
+)
+where
+    G: Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    build_attack_graph_with_options(
+        vulnerability_collection,
+        network_access_collection,
+        firewall_rules_collection,
+        attacker_positions_collection,
+        attacker_goals_collection,
+        &AttackGraphOptions {
+            max_hops: Some(maximum_attack_hops),
+            ..AttackGraphOptions::default()
+        },
+    )
+    .without_paths()
+}
+
+// The unrolled expansion behind `AttackGraphOptions::max_hops`,
+// honouring the options' firewall policy and ownership threshold.
+fn build_bounded_attack_graph<G>(
+    vulnerability_collection: &Collection<G, VulnerabilityRecord>,
+    network_access_collection: &Collection<G, NetworkAccessRule>,
+    firewall_rules_collection: &Collection<G, FirewallRuleRecord>,
     attacker_positions_collection: &Collection<G, AttackerStartingPosition>,
     attacker_goals_collection: &Collection<G, AttackerTargetGoal>,
     maximum_attack_hops: usize,
+    options: &AttackGraphOptions,
 ) -> (
     Collection<G, AttackerCodeExecution>,
     Collection<G, AttackerOwnsMachine>,
@@ -338,17 +2904,33 @@ where
             obtained_privilege: position.initial_privilege,
         });
 
-    // Index network access and vulnerabilities similarly to above
-    let network_access_by_source = network_access_collection.map(|access| {
+    // Index effective access and vulnerabilities similarly to above.
+    // Firewall denies are removed first so the bounded expansion never
+    // crosses a blocked edge.
+    let network_access_by_source = effective_network_access_with_policy(
+        network_access_collection,
+        firewall_rules_collection,
+        options.firewall_policy,
+        None,
+    )
+    .map(|access| {
         (
             access.source_host.clone(),
-            (access.destination_host.clone(), access.service_name.clone()),
+            (
+                access.destination_host.clone(),
+                access.service_name.clone(),
+                access.port,
+            ),
         )
     });
 
     let vulnerabilities_by_host_and_service = vulnerability_collection.map(|vuln| {
         (
-            (vuln.host_name.clone(), vuln.affected_service.clone()),
+            (
+                vuln.host_name.clone(),
+                vuln.affected_service.clone(),
+                vuln.port,
+            ),
             vuln.privilege_gained_on_exploit.clone(),
         )
     });
@@ -363,12 +2945,12 @@ where
                 )
             })
             .join(&network_access_by_source)
-            .map(|(_source, (attacker_id, (destination, service)))| {
-                ((destination, service), attacker_id)
+            .map(|(_source, (attacker_id, (destination, service, port)))| {
+                ((destination, service, port), attacker_id)
             })
             .join(&vulnerabilities_by_host_and_service)
             .map(
-                |((host, _service), (attacker_id, privilege))| AttackerCodeExecution {
+                |((host, _service, _port), (attacker_id, privilege))| AttackerCodeExecution {
                     attacker_id,
                     compromised_host: host,
                     obtained_privilege: privilege,
@@ -384,31 +2966,11 @@ where
     }
 
     // Same ownership/goal extraction as in the iterative version
-    let machines_owned = current_code_executions
-        .filter(|execution| execution.obtained_privilege == PrivilegeLevel::Root)
-        .map(|execution| AttackerOwnsMachine {
-            attacker_id: execution.attacker_id,
-            owned_host: execution.compromised_host,
-        })
-        .distinct();
-
-    let owned_keys = machines_owned
-        .map(|owned| (owned.attacker_id.clone(), owned.owned_host.clone()))
-        .distinct();
-
-    let goals_indexed = attacker_goals_collection.map(|goal| {
-        (
-            (goal.attacker_id.clone(), goal.target_host_name.clone()),
-            goal,
-        )
-    });
-
-    let goals_reached = goals_indexed
-        .semijoin(&owned_keys)
-        .map(|(_, goal)| AttackerGoalReached {
-            attacker_id: goal.attacker_id,
-            reached_target: goal.target_host_name,
-        });
+    let (machines_owned, goals_reached) = derive_ownership_and_goals(
+        &arrange_privileges_by_attacker_host(&current_code_executions),
+        attacker_goals_collection,
+        options.ownership_threshold.clone(),
+    );
 
     (current_code_executions, machines_owned, goals_reached)
 }
@@ -0,0 +1,1065 @@
+// JSON scenario loader
+// --------------------
+// A scenario file lists the base facts of one network as plain JSON,
+// so that attack graphs can be described without writing Rust:
+//
+//   {
+//     "vulnerabilities": [{"host": "web01", "cve": "CVE-1", "service": "https", "privilege": "user"}],
+//     "network": [{"src": "internet", "dst": "web01", "service": "https"}],
+//     "firewall": [{"src": "internet", "dst": "db01", "service": "postgres"}],
+//     "attackers": [{"id": "eve", "host": "internet", "privilege": "user"}],
+//     "goals": [{"attacker": "eve", "host": "web01"}],
+//     "symmetric_edges": false
+//   }
+//
+// With "symmetric_edges" set, every network entry also grants the
+// reverse connection; firewall entries stay one-way.
+//
+// Every section is optional.  Ports default to the service's
+// well-known port, exactly like the .facts parser, and a goal without
+// a "privilege" requires root on its host.  Privileges are
+// kept as strings while deserializing so that a typo surfaces as a
+// `ScenarioError::UnknownPrivilege` instead of a generic serde error.
+//
+// Very large scenarios can also be stored in a compact binary form
+// with `Scenario::save_bin` / `Scenario::load_bin`, which skips JSON
+// parsing entirely.  The file is an 8-byte magic header, a format
+// version byte, the `symmetric_edges` flag, the writer's pointer width
+// and byte order, and the size and alignment of every entry type,
+// padded to 32 bytes; then each section as a little-endian u64 count
+// followed by its entries encoded with abomonation.  Abomonation
+// writes entries in their in-memory layout, so a file only loads in a
+// build whose platform and entry layouts match the header.  Every
+// entry is padded to 8 bytes: abomonation packs string bytes
+// unpadded, so decoding a whole `Vec<ScenarioVulnerability>` would put
+// later structs at unaligned addresses.
+//
+// `Scenario::validate` flags likely typos that would otherwise just
+// produce an empty result: goals, attackers, firewall entries and
+// vulnerabilities naming a host that appears in no network entry.
+//
+// Tests and other Rust callers can assemble the same structure with
+// `ScenarioBuilder` instead of writing JSON:
+//
+//   ScenarioBuilder::new()
+//       .host("internet")
+//       .host("web01")
+//       .edge("internet", "web01", "https")
+//       .vulnerability("web01", "CVE-1", "https", PrivilegeLevel::User)
+//       .attacker("eve", "internet", PrivilegeLevel::User)
+//       .goal("eve", "web01")
+//       .build()
+
+// See the note in schema.rs on `abomonation_derive`.
+#![allow(non_local_definitions)]
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use abomonation::Abomonation;
+use abomonation_derive::Abomonation;
+use serde::Deserialize;
+
+use crate::engine::BaseFacts;
+use crate::patching::minimum_patch_set;
+use crate::query::{critical_hosts, run_collection_query};
+use crate::rules::build_attack_graph;
+use crate::schema::{
+    AttackerGoalReached, AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord,
+    HostIdentifier, NetworkAccessRule, PortNumber, PrivilegeLevel, UnknownPrivilegeLevel,
+    VulnerabilityRecord,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scenario {
+    pub vulnerabilities: Vec<ScenarioVulnerability>,
+    pub network: Vec<ScenarioConnection>,
+    pub firewall: Vec<ScenarioConnection>,
+    pub attackers: Vec<ScenarioAttacker>,
+    pub goals: Vec<ScenarioGoal>,
+    pub symmetric_edges: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Abomonation, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioVulnerability {
+    pub host: String,
+    pub cve: String,
+    pub service: String,
+    pub privilege: String,
+    #[serde(default)]
+    pub port: Option<PortNumber>,
+}
+
+// Used both for `network` entries (hacl) and `firewall` entries
+// (firewallDeny); the two share the same shape.
+#[derive(Debug, Clone, PartialEq, Eq, Abomonation, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioConnection {
+    pub src: String,
+    pub dst: String,
+    pub service: String,
+    #[serde(default)]
+    pub port: Option<PortNumber>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Abomonation, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioAttacker {
+    pub id: String,
+    pub host: String,
+    pub privilege: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Abomonation, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioGoal {
+    pub attacker: String,
+    pub host: String,
+    #[serde(default)]
+    pub privilege: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScenarioError {
+    Io { path: PathBuf, message: String },
+    Json(String),
+    Binary(String),
+    UnknownPrivilege(String),
+    GoalWithoutAttacker { attacker: String, host: String },
+    UndeclaredHost { host: String, connection: String },
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioError::Io { path, message } => {
+                write!(formatter, "failed to read {}: {message}", path.display())
+            }
+            ScenarioError::Json(message) => write!(formatter, "invalid scenario JSON: {message}"),
+            ScenarioError::Binary(message) => {
+                write!(formatter, "invalid binary scenario: {message}")
+            }
+            ScenarioError::UnknownPrivilege(privilege) => write!(
+                formatter,
+                "unknown privilege {privilege:?}, expected none, user or root"
+            ),
+            ScenarioError::GoalWithoutAttacker { attacker, host } => write!(
+                formatter,
+                "goal {attacker} -> {host} names an attacker with no starting position"
+            ),
+            ScenarioError::UndeclaredHost { host, connection } => write!(
+                formatter,
+                "{connection} references host {host:?}, which was never declared"
+            ),
+        }
+    }
+}
+
+impl Error for ScenarioError {}
+
+/// A likely mistake found by [`Scenario::validate`].  Every variant
+/// names a host that appears in no `network` entry, so it can take no
+/// part in an attack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationWarning {
+    GoalHostNotInTopology { attacker: String, host: String },
+    UnknownAttackerHost { attacker: String, host: String },
+    FirewallHostNotInTopology { host: String, connection: String },
+    UnreachableVulnerability { host: String, cve: String },
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationWarning::GoalHostNotInTopology { attacker, host } => write!(
+                formatter,
+                "goal {attacker} -> {host} targets a host with no network entries"
+            ),
+            ValidationWarning::UnknownAttackerHost { attacker, host } => write!(
+                formatter,
+                "attacker {attacker} starts on {host:?}, which has no network entries"
+            ),
+            ValidationWarning::FirewallHostNotInTopology { host, connection } => write!(
+                formatter,
+                "{connection} references host {host:?}, which has no network entries"
+            ),
+            ValidationWarning::UnreachableVulnerability { host, cve } => write!(
+                formatter,
+                "{cve} on {host:?} is unreachable: the host has no network entries"
+            ),
+        }
+    }
+}
+
+impl Scenario {
+    /// Reads and validates a scenario from a JSON file.
+    pub fn from_json_file(path: &Path) -> Result<Scenario, ScenarioError> {
+        let contents = fs::read_to_string(path).map_err(|error| ScenarioError::Io {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        })?;
+        Scenario::from_json_str(&contents)
+    }
+
+    /// Parses and validates a scenario from a JSON string.
+    pub fn from_json_str(json: &str) -> Result<Scenario, ScenarioError> {
+        let scenario: Scenario =
+            serde_json::from_str(json).map_err(|error| ScenarioError::Json(error.to_string()))?;
+        scenario.to_base_facts()?;
+        Ok(scenario)
+    }
+
+    /// Writes the scenario in the binary format read by
+    /// [`Scenario::load_bin`].
+    pub fn save_bin(&self, path: &Path) -> Result<(), ScenarioError> {
+        let mut bytes = binary_header(self.symmetric_edges).to_vec();
+        encode_section(&self.vulnerabilities, &mut bytes);
+        encode_section(&self.network, &mut bytes);
+        encode_section(&self.firewall, &mut bytes);
+        encode_section(&self.attackers, &mut bytes);
+        encode_section(&self.goals, &mut bytes);
+        fs::write(path, bytes).map_err(|error| ScenarioError::Io {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        })
+    }
+
+    /// Reads and validates a scenario written by [`Scenario::save_bin`].
+    ///
+    /// Files from other tools, other format versions, or a platform
+    /// or build with different entry layouts are rejected, as are
+    /// truncated payloads.
+    ///
+    /// # Safety
+    ///
+    /// Abomonation decodes by reinterpreting bytes in place, so the
+    /// payload is trusted to be what `save_bin` wrote: a crafted or
+    /// corrupted file with a valid header can produce strings that are
+    /// not UTF-8 or other invalid values.  `path` must name a file
+    /// this crate wrote itself; use JSON for anything that crosses a
+    /// trust boundary.
+    pub unsafe fn load_bin(path: &Path) -> Result<Scenario, ScenarioError> {
+        let contents = fs::read(path).map_err(|error| ScenarioError::Io {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        })?;
+        if contents.len() < BINARY_HEADER_LEN || &contents[..BINARY_MAGIC.len()] != BINARY_MAGIC {
+            return Err(ScenarioError::Binary("missing scenario header".to_string()));
+        }
+        let header = &contents[..BINARY_HEADER_LEN];
+        let expected = binary_header(false);
+        let version = header[BINARY_VERSION_OFFSET];
+        if version != BINARY_FORMAT_VERSION {
+            return Err(ScenarioError::Binary(format!(
+                "unsupported format version {version}, expected {BINARY_FORMAT_VERSION}"
+            )));
+        }
+        if header[BINARY_PLATFORM_OFFSET..BINARY_LAYOUT_OFFSET]
+            != expected[BINARY_PLATFORM_OFFSET..BINARY_LAYOUT_OFFSET]
+        {
+            return Err(ScenarioError::Binary(format!(
+                "written on a {} platform, this build is {}",
+                describe_platform(&header[BINARY_PLATFORM_OFFSET..]),
+                describe_platform(&expected[BINARY_PLATFORM_OFFSET..])
+            )));
+        }
+        if header[BINARY_LAYOUT_OFFSET..] != expected[BINARY_LAYOUT_OFFSET..] {
+            return Err(ScenarioError::Binary(
+                "entry layouts differ from this build".to_string(),
+            ));
+        }
+
+        // Decoding reads entries in place, so they have to sit at
+        // addresses aligned for their fields; a `Vec<u8>` from
+        // `fs::read` gives no such guarantee.
+        let mut words = vec![0u64; contents.len().div_ceil(8)];
+        let buffer = &mut words_as_bytes_mut(&mut words)[..contents.len()];
+        buffer.copy_from_slice(&contents);
+
+        let mut offset = BINARY_HEADER_LEN;
+        let scenario = Scenario {
+            vulnerabilities: decode_section(buffer, &mut offset)?,
+            network: decode_section(buffer, &mut offset)?,
+            firewall: decode_section(buffer, &mut offset)?,
+            attackers: decode_section(buffer, &mut offset)?,
+            goals: decode_section(buffer, &mut offset)?,
+            symmetric_edges: contents[BINARY_SYMMETRIC_OFFSET] != 0,
+        };
+        if offset != buffer.len() {
+            return Err(ScenarioError::Binary(format!(
+                "{} trailing bytes after the scenario",
+                buffer.len() - offset
+            )));
+        }
+        scenario.to_base_facts()?;
+        Ok(scenario)
+    }
+
+    /// Converts the scenario into the typed base facts the engines
+    /// consume, checking privileges and goal attackers on the way.
+    pub fn to_base_facts(&self) -> Result<BaseFacts, ScenarioError> {
+        let mut facts = BaseFacts::default();
+
+        for vulnerability in &self.vulnerabilities {
+            let mut record = VulnerabilityRecord::new(
+                &vulnerability.host,
+                &vulnerability.cve,
+                &vulnerability.service,
+                vulnerability.privilege.parse().map_err(unknown_privilege)?,
+            );
+            if let Some(port) = vulnerability.port {
+                record = record.with_port(port);
+            }
+            facts.vulnerabilities.push(record);
+        }
+        for connection in &self.network {
+            facts.network_access.push(match connection.port {
+                Some(port) => NetworkAccessRule::new(
+                    &connection.src,
+                    &connection.dst,
+                    &connection.service,
+                    port,
+                ),
+                None => NetworkAccessRule::new_default_port(
+                    &connection.src,
+                    &connection.dst,
+                    &connection.service,
+                ),
+            });
+        }
+        if self.symmetric_edges {
+            let listed = facts.network_access.len();
+            for index in 0..listed {
+                let reverse = facts.network_access[index].reversed();
+                if !facts.network_access.contains(&reverse) {
+                    facts.network_access.push(reverse);
+                }
+            }
+        }
+        for connection in &self.firewall {
+            let mut rule = FirewallRuleRecord::create_deny_rule(
+                &connection.src,
+                &connection.dst,
+                &connection.service,
+            );
+            if let Some(port) = connection.port {
+                rule = rule.with_port(port);
+            }
+            facts.firewall_rules.push(rule);
+        }
+        for attacker in &self.attackers {
+            facts.attacker_positions.push(AttackerStartingPosition::new(
+                &attacker.id,
+                &attacker.host,
+                attacker.privilege.parse().map_err(unknown_privilege)?,
+            ));
+        }
+        for goal in &self.goals {
+            if !self
+                .attackers
+                .iter()
+                .any(|attacker| attacker.id == goal.attacker)
+            {
+                return Err(ScenarioError::GoalWithoutAttacker {
+                    attacker: goal.attacker.clone(),
+                    host: goal.host.clone(),
+                });
+            }
+            let mut target_goal = AttackerTargetGoal::new(&goal.attacker, &goal.host);
+            if let Some(privilege) = &goal.privilege {
+                target_goal = target_goal
+                    .with_required_privilege(privilege.parse().map_err(unknown_privilege)?);
+            }
+            facts.attacker_goals.push(target_goal);
+        }
+
+        Ok(facts)
+    }
+
+    /// Runs the scenario through `build_attack_graph` and returns the
+    /// goals that are reached, sorted.
+    pub fn run(&self) -> Result<Vec<AttackerGoalReached>, ScenarioError> {
+        let facts = self.to_base_facts()?;
+        Ok(run_collection_query(&facts, |inputs| {
+            let (_, _, goals_reached) = build_attack_graph(
+                &inputs.vulnerabilities,
+                &inputs.network_access,
+                &inputs.firewall_rules,
+                &inputs.attacker_positions,
+                &inputs.attacker_goals,
+            );
+            goals_reached
+        }))
+    }
+
+    /// Hosts whose patching alone would cut an attacker off from a
+    /// goal it currently reaches; see `query::critical_hosts`.
+    pub fn critical_hosts(&self) -> Result<Vec<HostIdentifier>, ScenarioError> {
+        Ok(critical_hosts(&self.to_base_facts()?))
+    }
+
+    /// The smallest set of vulnerabilities whose patching keeps every
+    /// attacker from its goals; see `patching::minimum_patch_set`.
+    pub fn minimum_patch_set(&self) -> Result<Vec<VulnerabilityRecord>, ScenarioError> {
+        Ok(minimum_patch_set(&self.to_base_facts()?))
+    }
+
+    /// Checks for hosts that appear only outside the network topology.
+    /// These are warnings, not errors: the scenario still runs, but
+    /// the flagged entries cannot affect the result.  Warnings come in
+    /// order of goals, attackers, firewall entries, vulnerabilities.
+    pub fn validate(&self) -> Result<(), Vec<ValidationWarning>> {
+        let topology: BTreeSet<&String> = self
+            .network
+            .iter()
+            .flat_map(|connection| [&connection.src, &connection.dst])
+            .collect();
+        let mut warnings = Vec::new();
+
+        for goal in &self.goals {
+            if !topology.contains(&goal.host) {
+                warnings.push(ValidationWarning::GoalHostNotInTopology {
+                    attacker: goal.attacker.clone(),
+                    host: goal.host.clone(),
+                });
+            }
+        }
+        for attacker in &self.attackers {
+            if !topology.contains(&attacker.host) {
+                warnings.push(ValidationWarning::UnknownAttackerHost {
+                    attacker: attacker.id.clone(),
+                    host: attacker.host.clone(),
+                });
+            }
+        }
+        for deny in &self.firewall {
+            for host in [&deny.src, &deny.dst] {
+                if !topology.contains(host) {
+                    warnings.push(ValidationWarning::FirewallHostNotInTopology {
+                        host: host.clone(),
+                        connection: format!("deny {} -> {} ({})", deny.src, deny.dst, deny.service),
+                    });
+                }
+            }
+        }
+        for vulnerability in &self.vulnerabilities {
+            if !topology.contains(&vulnerability.host) {
+                warnings.push(ValidationWarning::UnreachableVulnerability {
+                    host: vulnerability.host.clone(),
+                    cve: vulnerability.cve.clone(),
+                });
+            }
+        }
+
+        if warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(warnings)
+        }
+    }
+}
+
+/// Chainable construction of a `Scenario`.  Hosts used by edges and
+/// firewall denies must be declared with `host` first; `build` checks
+/// this along with everything `Scenario::to_base_facts` checks.
+#[derive(Debug, Clone, Default)]
+pub struct ScenarioBuilder {
+    hosts: BTreeSet<String>,
+    scenario: Scenario,
+}
+
+impl ScenarioBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn host(mut self, name: &str) -> Self {
+        self.hosts.insert(name.to_string());
+        self
+    }
+
+    pub fn vulnerability(
+        mut self,
+        host: &str,
+        cve: &str,
+        service: &str,
+        privilege: PrivilegeLevel,
+    ) -> Self {
+        self.scenario.vulnerabilities.push(ScenarioVulnerability {
+            host: host.to_string(),
+            cve: cve.to_string(),
+            service: service.to_string(),
+            privilege: privilege.to_string(),
+            port: None,
+        });
+        self
+    }
+
+    pub fn edge(mut self, src: &str, dst: &str, service: &str) -> Self {
+        self.scenario.network.push(connection(src, dst, service));
+        self
+    }
+
+    /// Makes every edge also connect `dst` back to `src`.
+    pub fn symmetric_edges(mut self) -> Self {
+        self.scenario.symmetric_edges = true;
+        self
+    }
+
+    pub fn deny(mut self, src: &str, dst: &str, service: &str) -> Self {
+        self.scenario.firewall.push(connection(src, dst, service));
+        self
+    }
+
+    pub fn attacker(mut self, id: &str, host: &str, privilege: PrivilegeLevel) -> Self {
+        self.scenario.attackers.push(ScenarioAttacker {
+            id: id.to_string(),
+            host: host.to_string(),
+            privilege: privilege.to_string(),
+        });
+        self
+    }
+
+    pub fn goal(mut self, attacker: &str, target: &str) -> Self {
+        self.scenario.goals.push(ScenarioGoal {
+            attacker: attacker.to_string(),
+            host: target.to_string(),
+            privilege: None,
+        });
+        self
+    }
+
+    pub fn build(self) -> Result<Scenario, ScenarioError> {
+        let edges = self.scenario.network.iter().map(|edge| ("edge", edge));
+        let denies = self.scenario.firewall.iter().map(|deny| ("deny", deny));
+        for (kind, connection) in edges.chain(denies) {
+            for host in [&connection.src, &connection.dst] {
+                if !self.hosts.contains(host) {
+                    return Err(ScenarioError::UndeclaredHost {
+                        host: host.clone(),
+                        connection: format!(
+                            "{kind} {} -> {} ({})",
+                            connection.src, connection.dst, connection.service
+                        ),
+                    });
+                }
+            }
+        }
+
+        self.scenario.to_base_facts()?;
+        Ok(self.scenario)
+    }
+}
+
+fn connection(src: &str, dst: &str, service: &str) -> ScenarioConnection {
+    ScenarioConnection {
+        src: src.to_string(),
+        dst: dst.to_string(),
+        service: service.to_string(),
+        port: None,
+    }
+}
+
+const BINARY_MAGIC: &[u8; 8] = b"DAGSCEN\0";
+const BINARY_FORMAT_VERSION: u8 = 2;
+const BINARY_VERSION_OFFSET: usize = 8;
+const BINARY_SYMMETRIC_OFFSET: usize = 9;
+// Pointer width in bytes, then 0 for little-endian or 1 for big-endian.
+const BINARY_PLATFORM_OFFSET: usize = 10;
+// Size and alignment of each entry type, as little-endian u16 pairs.
+const BINARY_LAYOUT_OFFSET: usize = 16;
+const BINARY_HEADER_LEN: usize = 32;
+
+fn binary_header(symmetric_edges: bool) -> [u8; BINARY_HEADER_LEN] {
+    let mut header = [0u8; BINARY_HEADER_LEN];
+    header[..BINARY_MAGIC.len()].copy_from_slice(BINARY_MAGIC);
+    header[BINARY_VERSION_OFFSET] = BINARY_FORMAT_VERSION;
+    header[BINARY_SYMMETRIC_OFFSET] = u8::from(symmetric_edges);
+    header[BINARY_PLATFORM_OFFSET] = std::mem::size_of::<usize>() as u8;
+    header[BINARY_PLATFORM_OFFSET + 1] = u8::from(cfg!(target_endian = "big"));
+
+    let layouts = [
+        entry_layout::<ScenarioVulnerability>(),
+        entry_layout::<ScenarioConnection>(),
+        entry_layout::<ScenarioAttacker>(),
+        entry_layout::<ScenarioGoal>(),
+    ];
+    for (index, (size, align)) in layouts.into_iter().enumerate() {
+        let at = BINARY_LAYOUT_OFFSET + index * 4;
+        header[at..at + 2].copy_from_slice(&size.to_le_bytes());
+        header[at + 2..at + 4].copy_from_slice(&align.to_le_bytes());
+    }
+    header
+}
+
+fn entry_layout<T>() -> (u16, u16) {
+    (
+        std::mem::size_of::<T>() as u16,
+        std::mem::align_of::<T>() as u16,
+    )
+}
+
+fn describe_platform(platform: &[u8]) -> String {
+    let endian = if platform[1] == 0 { "little" } else { "big" };
+    format!("{}-bit {endian}-endian", u32::from(platform[0]) * 8)
+}
+
+fn encode_section<T: Abomonation>(entries: &[T], bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    for entry in entries {
+        // Writing into a `Vec` cannot fail.
+        unsafe { abomonation::encode(entry, bytes) }.expect("encoding into a Vec should not fail");
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+    }
+}
+
+// Decodes one section starting at `offset`, which must be a multiple
+// of 8, and moves `offset` past it.
+fn decode_section<T: Abomonation + Clone>(
+    buffer: &mut [u8],
+    offset: &mut usize,
+) -> Result<Vec<T>, ScenarioError> {
+    let truncated = || ScenarioError::Binary("truncated scenario payload".to_string());
+    let count_bytes = buffer.get(*offset..*offset + 8).ok_or_else(truncated)?;
+    let count = u64::from_le_bytes(count_bytes.try_into().expect("slice has 8 bytes"));
+    *offset += 8;
+
+    // No capacity from `count`: a corrupt count would otherwise
+    // allocate before the payload runs out.
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let remaining = &mut buffer[*offset..];
+        let remaining_len = remaining.len();
+        let (entry, rest) = unsafe { abomonation::decode::<T>(remaining) }.ok_or_else(truncated)?;
+        entries.push(entry.clone());
+        let consumed = (remaining_len - rest.len()).next_multiple_of(8);
+        if consumed > remaining_len {
+            return Err(truncated());
+        }
+        *offset += consumed;
+    }
+    Ok(entries)
+}
+
+fn words_as_bytes_mut(words: &mut [u64]) -> &mut [u8] {
+    // Every bit pattern is a valid `u8`, and the byte view covers
+    // exactly the words' memory.
+    unsafe {
+        std::slice::from_raw_parts_mut(
+            words.as_mut_ptr().cast::<u8>(),
+            std::mem::size_of_val(words),
+        )
+    }
+}
+
+fn unknown_privilege(error: UnknownPrivilegeLevel) -> ScenarioError {
+    ScenarioError::UnknownPrivilege(error.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_scenario_reaches_admin_host() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/scenarios/simple_enterprise.json");
+
+        let scenario = Scenario::from_json_file(&path).expect("example scenario should load");
+
+        assert_eq!(
+            scenario.run(),
+            Ok(vec![AttackerGoalReached {
+                attacker_id: "eve".to_string(),
+                reached_target: "admin01".to_string(),
+                required_privilege: PrivilegeLevel::Root,
+            }])
+        );
+    }
+
+    #[test]
+    fn firewall_entries_block_the_goal() {
+        let scenario = Scenario::from_json_str(
+            r#"{
+                "vulnerabilities": [{"host": "web", "cve": "CVE-1", "service": "https", "privilege": "Root"}],
+                "network": [{"src": "internet", "dst": "web", "service": "https"}],
+                "firewall": [{"src": "internet", "dst": "web", "service": "https", "port": 443}],
+                "attackers": [{"id": "eve", "host": "internet", "privilege": "user"}],
+                "goals": [{"attacker": "eve", "host": "web"}]
+            }"#,
+        )
+        .expect("scenario should load");
+
+        assert_eq!(scenario.run(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn example_scenario_is_a_single_chain_of_critical_hosts() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/scenarios/simple_enterprise.json");
+
+        let scenario = Scenario::from_json_file(&path).expect("example scenario should load");
+
+        assert_eq!(
+            scenario.critical_hosts(),
+            Ok(vec![
+                "admin01".to_string(),
+                "app01".to_string(),
+                "web01".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn example_scenario_is_cut_by_one_patch() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/scenarios/simple_enterprise.json");
+
+        let scenario = Scenario::from_json_file(&path).expect("example scenario should load");
+        let patches = scenario
+            .minimum_patch_set()
+            .expect("example scenario should convert");
+
+        assert_eq!(patches.len(), 1);
+    }
+
+    #[test]
+    fn rejects_unknown_privilege() {
+        let result = Scenario::from_json_str(
+            r#"{"attackers": [{"id": "eve", "host": "internet", "privilege": "admin"}]}"#,
+        );
+
+        assert_eq!(
+            result,
+            Err(ScenarioError::UnknownPrivilege("admin".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_goal_for_attacker_without_starting_position() {
+        let result = Scenario::from_json_str(
+            r#"{
+                "attackers": [{"id": "eve", "host": "internet", "privilege": "user"}],
+                "goals": [{"attacker": "mallory", "host": "db"}]
+            }"#,
+        );
+
+        assert_eq!(
+            result,
+            Err(ScenarioError::GoalWithoutAttacker {
+                attacker: "mallory".to_string(),
+                host: "db".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn builder_matches_the_equivalent_json() {
+        let built = ScenarioBuilder::new()
+            .host("internet")
+            .host("web")
+            .host("db")
+            .vulnerability("web", "CVE-1", "https", PrivilegeLevel::Root)
+            .edge("internet", "web", "https")
+            .edge("web", "db", "postgres")
+            .deny("internet", "db", "postgres")
+            .attacker("eve", "internet", PrivilegeLevel::User)
+            .goal("eve", "web")
+            .build()
+            .expect("builder scenario should be valid");
+
+        let parsed = Scenario::from_json_str(
+            r#"{
+                "vulnerabilities": [{"host": "web", "cve": "CVE-1", "service": "https", "privilege": "root"}],
+                "network": [
+                    {"src": "internet", "dst": "web", "service": "https"},
+                    {"src": "web", "dst": "db", "service": "postgres"}
+                ],
+                "firewall": [{"src": "internet", "dst": "db", "service": "postgres"}],
+                "attackers": [{"id": "eve", "host": "internet", "privilege": "user"}],
+                "goals": [{"attacker": "eve", "host": "web"}]
+            }"#,
+        )
+        .expect("scenario should load");
+
+        assert_eq!(built, parsed);
+        assert_eq!(built.run().map(|goals| goals.len()), Ok(1));
+    }
+
+    #[test]
+    fn symmetric_edges_add_the_reverse_of_every_connection_once() {
+        let scenario = Scenario::from_json_str(
+            r#"{
+                "network": [
+                    {"src": "a", "dst": "b", "service": "ssh"},
+                    {"src": "b", "dst": "a", "service": "ssh"},
+                    {"src": "b", "dst": "c", "service": "smb"}
+                ],
+                "symmetric_edges": true
+            }"#,
+        )
+        .expect("scenario should load");
+
+        let mut expected = NetworkAccessRule::bidirectional("a", "b", "ssh").to_vec();
+        expected.extend(NetworkAccessRule::bidirectional("b", "c", "smb"));
+        let mut network_access = scenario
+            .to_base_facts()
+            .expect("scenario should convert")
+            .network_access;
+        network_access.sort();
+        expected.sort();
+        assert_eq!(network_access, expected);
+    }
+
+    #[test]
+    fn a_deny_on_a_symmetric_edge_only_blocks_its_own_direction() {
+        let scenario = ScenarioBuilder::new()
+            .host("lan_a")
+            .host("lan_b")
+            .symmetric_edges()
+            .edge("lan_a", "lan_b", "ssh")
+            .deny("lan_a", "lan_b", "ssh")
+            .vulnerability("lan_a", "CVE-A", "ssh", PrivilegeLevel::Root)
+            .vulnerability("lan_b", "CVE-B", "ssh", PrivilegeLevel::Root)
+            .attacker("alice", "lan_a", PrivilegeLevel::User)
+            .attacker("bob", "lan_b", PrivilegeLevel::User)
+            .goal("alice", "lan_b")
+            .goal("bob", "lan_a")
+            .build()
+            .expect("builder scenario should be valid");
+
+        assert_eq!(
+            scenario.run(),
+            Ok(vec![AttackerGoalReached {
+                attacker_id: "bob".to_string(),
+                reached_target: "lan_a".to_string(),
+                required_privilege: PrivilegeLevel::Root,
+            }])
+        );
+    }
+
+    #[test]
+    fn builder_rejects_an_edge_to_an_undeclared_host() {
+        let result = ScenarioBuilder::new()
+            .host("internet")
+            .edge("internet", "web", "https")
+            .build();
+
+        let error = result.expect_err("web was never declared");
+        assert_eq!(
+            error,
+            ScenarioError::UndeclaredHost {
+                host: "web".to_string(),
+                connection: "edge internet -> web (https)".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "edge internet -> web (https) references host \"web\", which was never declared"
+        );
+    }
+
+    fn temp_bin(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "dynamic_attack_graphs_scenario_test_{name}_{}.bin",
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn binary_round_trip_preserves_the_scenario() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/scenarios/simple_enterprise.json");
+        let scenario = Scenario::from_json_file(&path).expect("example scenario should load");
+        let binary = temp_bin("round_trip");
+
+        scenario
+            .save_bin(&binary)
+            .expect("binary scenario should save");
+        let loaded = unsafe { Scenario::load_bin(&binary) };
+        let _ = fs::remove_file(&binary);
+
+        assert_eq!(loaded, Ok(scenario));
+    }
+
+    #[test]
+    fn load_bin_rejects_a_file_without_the_header() {
+        let binary = temp_bin("no_header");
+        fs::write(&binary, br#"{"vulnerabilities": []}"#).expect("test file should write");
+
+        let result = unsafe { Scenario::load_bin(&binary) };
+        let _ = fs::remove_file(&binary);
+
+        assert_eq!(
+            result,
+            Err(ScenarioError::Binary("missing scenario header".to_string()))
+        );
+    }
+
+    #[test]
+    fn load_bin_rejects_another_format_version() {
+        let scenario = ScenarioBuilder::new()
+            .host("internet")
+            .attacker("eve", "internet", PrivilegeLevel::User)
+            .build()
+            .expect("scenario should build");
+        let binary = temp_bin("version");
+        scenario
+            .save_bin(&binary)
+            .expect("binary scenario should save");
+        let mut bytes = fs::read(&binary).expect("binary scenario should read");
+        bytes[BINARY_VERSION_OFFSET] = BINARY_FORMAT_VERSION + 1;
+        fs::write(&binary, bytes).expect("test file should write");
+
+        let result = unsafe { Scenario::load_bin(&binary) };
+        let _ = fs::remove_file(&binary);
+
+        assert_eq!(
+            result,
+            Err(ScenarioError::Binary(format!(
+                "unsupported format version {}, expected {BINARY_FORMAT_VERSION}",
+                BINARY_FORMAT_VERSION + 1
+            )))
+        );
+    }
+
+    #[test]
+    fn load_bin_rejects_a_truncated_payload() {
+        let scenario = ScenarioBuilder::new()
+            .host("internet")
+            .attacker("eve", "internet", PrivilegeLevel::User)
+            .build()
+            .expect("scenario should build");
+        let binary = temp_bin("truncated");
+        scenario
+            .save_bin(&binary)
+            .expect("binary scenario should save");
+        let bytes = fs::read(&binary).expect("binary scenario should read");
+        fs::write(&binary, &bytes[..bytes.len() - 1]).expect("test file should write");
+
+        let result = unsafe { Scenario::load_bin(&binary) };
+        let _ = fs::remove_file(&binary);
+
+        assert_eq!(
+            result,
+            Err(ScenarioError::Binary(
+                "truncated scenario payload".to_string()
+            ))
+        );
+    }
+
+    fn internet_only_binary(name: &str) -> (PathBuf, Vec<u8>) {
+        let scenario = ScenarioBuilder::new()
+            .host("internet")
+            .attacker("eve", "internet", PrivilegeLevel::User)
+            .build()
+            .expect("scenario should build");
+        let binary = temp_bin(name);
+        scenario
+            .save_bin(&binary)
+            .expect("binary scenario should save");
+        let bytes = fs::read(&binary).expect("binary scenario should read");
+        (binary, bytes)
+    }
+
+    #[test]
+    fn load_bin_rejects_a_file_from_another_platform() {
+        let (binary, mut bytes) = internet_only_binary("platform");
+        bytes[BINARY_PLATFORM_OFFSET] = 2;
+        bytes[BINARY_PLATFORM_OFFSET + 1] ^= 1;
+        fs::write(&binary, bytes).expect("test file should write");
+
+        let result = unsafe { Scenario::load_bin(&binary) };
+        let _ = fs::remove_file(&binary);
+
+        let expected = binary_header(false);
+        assert_eq!(
+            result,
+            Err(ScenarioError::Binary(format!(
+                "written on a {} platform, this build is {}",
+                describe_platform(&[2, expected[BINARY_PLATFORM_OFFSET + 1] ^ 1]),
+                describe_platform(&expected[BINARY_PLATFORM_OFFSET..])
+            )))
+        );
+    }
+
+    #[test]
+    fn load_bin_rejects_entries_laid_out_differently() {
+        let (binary, mut bytes) = internet_only_binary("layout");
+        bytes[BINARY_LAYOUT_OFFSET] += 8;
+        fs::write(&binary, bytes).expect("test file should write");
+
+        let result = unsafe { Scenario::load_bin(&binary) };
+        let _ = fs::remove_file(&binary);
+
+        assert_eq!(
+            result,
+            Err(ScenarioError::Binary(
+                "entry layouts differ from this build".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn example_scenario_validates_cleanly() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/scenarios/simple_enterprise.json");
+
+        let scenario = Scenario::from_json_file(&path).expect("example scenario should load");
+
+        assert_eq!(scenario.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_reports_hosts_missing_from_the_topology() {
+        let scenario = Scenario::from_json_str(
+            r#"{
+                "vulnerabilities": [
+                    {"host": "web01", "cve": "CVE-1", "service": "https", "privilege": "user"},
+                    {"host": "db1", "cve": "CVE-2", "service": "postgres", "privilege": "root"}
+                ],
+                "network": [{"src": "internet", "dst": "web01", "service": "https"}],
+                "firewall": [{"src": "web01", "dst": "db01", "service": "postgres"}],
+                "attackers": [{"id": "eve", "host": "internet", "privilege": "user"},
+                              {"id": "mallory", "host": "intranet", "privilege": "user"}],
+                "goals": [{"attacker": "eve", "host": "db01"}]
+            }"#,
+        )
+        .expect("scenario with dangling hosts should still load");
+
+        let warnings = scenario
+            .validate()
+            .expect_err("dangling hosts should be reported");
+
+        assert_eq!(
+            warnings,
+            vec![
+                ValidationWarning::GoalHostNotInTopology {
+                    attacker: "eve".to_string(),
+                    host: "db01".to_string(),
+                },
+                ValidationWarning::UnknownAttackerHost {
+                    attacker: "mallory".to_string(),
+                    host: "intranet".to_string(),
+                },
+                ValidationWarning::FirewallHostNotInTopology {
+                    host: "db01".to_string(),
+                    connection: "deny web01 -> db01 (postgres)".to_string(),
+                },
+                ValidationWarning::UnreachableVulnerability {
+                    host: "db1".to_string(),
+                    cve: "CVE-2".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[3].to_string(),
+            "CVE-2 on \"db1\" is unreachable: the host has no network entries"
+        );
+    }
+}
@@ -10,17 +10,58 @@
 // cheaply serializable / comparable / hashable.
 // ================================================================
 
+// `abomonation_derive` expands into impls nested inside an anonymous
+// const, which newer toolchains flag as non-local definitions.
+#![allow(non_local_definitions)]
+
 use abomonation_derive::Abomonation; // fast binary (de)serialization
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
 // ----------------------------------------------------------------
 // Type aliases
 // ----------------------------------------------------------------
 pub type HostIdentifier = String; // e.g. "webserver-1"
 pub type ServiceName = String; // e.g. "ssh", "http"
+pub type PortNumber = u16; // e.g. 22, 443
 pub type VulnerabilityIdentifier = String; // e.g. "CVE-2024-12345"
 pub type AttackerIdentifier = String; // e.g. "internet", "attacker-1"
+pub type RoleName = String; // e.g. "backup-operators"
+pub type CredentialIdentifier = String; // e.g. "svc-backup-password"
+pub type ZoneName = String; // e.g. "dmz", "internal"
+pub type OperatingSystem = String; // e.g. "windows", "linux"
+
+// ----------------------------------------------------------------
+// Default ports
+// ----------------------------------------------------------------
+// Port assumed for a service when a fact does not name one.  Unknown
+// services map to 0, so facts built with defaults still match each
+// other on the service name alone.
+pub fn default_port(service: &str) -> PortNumber {
+    match service {
+        "ftp" => 21,
+        "ssh" => 22,
+        "telnet" => 23,
+        "smtp" => 25,
+        "dns" => 53,
+        "http" => 80,
+        "pop3" => 110,
+        "imap" => 143,
+        "ldap" => 389,
+        "https" => 443,
+        "smb" => 445,
+        "mssql" => 1433,
+        "nfs" => 2049,
+        "mysql" => 3306,
+        "rdp" => 3389,
+        "postgres" => 5432,
+        "winrm" => 5985,
+        "redis" => 6379,
+        _ => 0,
+    }
+}
 
 // ----------------------------------------------------------------
 // Privilege levels
@@ -52,13 +93,84 @@ impl fmt::Display for PrivilegeLevel {
     }
 }
 
+// Parses the names `Display` prints, ignoring case, so the .facts
+// parser, the CSV loader and JSON scenarios accept the same spellings.
+impl FromStr for PrivilegeLevel {
+    type Err = UnknownPrivilegeLevel;
+
+    fn from_str(privilege: &str) -> Result<Self, Self::Err> {
+        match privilege.to_ascii_lowercase().as_str() {
+            "none" => Ok(PrivilegeLevel::None),
+            "user" => Ok(PrivilegeLevel::User),
+            "root" => Ok(PrivilegeLevel::Root),
+            _ => Err(UnknownPrivilegeLevel(privilege.to_string())),
+        }
+    }
+}
+
+/// A string that names no `PrivilegeLevel`; holds the string as given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownPrivilegeLevel(pub String);
+
+impl fmt::Display for UnknownPrivilegeLevel {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "unknown privilege level {:?}", self.0)
+    }
+}
+
+impl Error for UnknownPrivilegeLevel {}
+
+// ----------------------------------------------------------------
+// Privilege lattice
+// ----------------------------------------------------------------
+// Ownership is derived by comparing the privilege an attacker holds
+// against a threshold.  The comparison goes through this trait so
+// environments with more tiers (guest, service, domain admin, ...) can
+// supply their own ordering (see
+// `build_attack_graph_with_privilege_lattice`); `PrivilegeLevel` is
+// the default three-level lattice, where owning a machine means
+// holding Root.
+pub trait PrivilegeLattice {
+    // True when `self` grants at least everything `other` does.  Every
+    // privilege dominates itself.
+    fn dominates(&self, other: &Self) -> bool;
+}
+
+impl PrivilegeLattice for PrivilegeLevel {
+    fn dominates(&self, other: &Self) -> bool {
+        self >= other
+    }
+}
+
+// What the attack graph rules need from a privilege type: a lattice
+// they can shuffle between workers and join on, into which the three
+// built-in levels embed.  Facts that only speak `PrivilegeLevel`
+// (local vulnerabilities, credentials, goals, ...) are lifted into it
+// with `From`.  Implemented for every type that qualifies.
+pub trait AttackGraphPrivilege:
+    PrivilegeLattice + From<PrivilegeLevel> + differential_dataflow::ExchangeData + std::hash::Hash
+{
+}
+
+impl<P> AttackGraphPrivilege for P where
+    P: PrivilegeLattice
+        + From<PrivilegeLevel>
+        + differential_dataflow::ExchangeData
+        + std::hash::Hash
+{
+}
+
+impl PrivilegeLevel {
+    pub const DEFAULT_OWNERSHIP_THRESHOLD: PrivilegeLevel = PrivilegeLevel::Root;
+}
+
 // ----------------------------------------------------------------
 // Firewall rule action
 // ----------------------------------------------------------------
 // Simple enum to represent whether a firewall rule allows or denies
-// traffic.  When building `EffectiveNetworkAccess` we will remove
-// any NetworkAccess entries that match a `Deny` rule using an
-// anti-join.
+// traffic.  Which of the two the rules consult depends on the
+// `FirewallPolicy`: by default we remove any NetworkAccess entries
+// that match a `Deny` rule using an anti-join.
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
 )]
@@ -67,6 +179,31 @@ pub enum FirewallRuleAction {
     Deny,
 }
 
+// How network edges without a matching firewall rule are treated.
+// Under `DefaultAllow` every edge is effective unless a `Deny` rule
+// matches it, and `Allow` rules are ignored.  Under `DefaultDeny` an
+// edge is effective only if an `Allow` rule matches it, and `Deny`
+// rules are ignored.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Abomonation,
+    Serialize,
+    Deserialize,
+)]
+pub enum FirewallPolicy {
+    #[default]
+    DefaultAllow,
+    DefaultDeny,
+}
+
 // ----------------------------------------------------------------
 // Base facts (inputs)
 // ----------------------------------------------------------------
@@ -82,33 +219,87 @@ pub enum FirewallRuleAction {
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
 )]
-pub struct VulnerabilityRecord {
+pub struct VulnerabilityRecord<P = PrivilegeLevel> {
     // Host that has the vulnerability
     pub host_name: HostIdentifier,
     // The vulnerability identifier (CVE or internal tracker)
     pub vulnerability_id: VulnerabilityIdentifier,
     // Network service affected by this vulnerability (e.g. "ssh")
     pub affected_service: ServiceName,
+    // Port the vulnerable service listens on.  Facts serialised before
+    // this field existed load with port 0, as do their edges.
+    #[serde(default)]
+    pub port: PortNumber,
     // The privilege level the attacker obtains when exploiting
-    pub privilege_gained_on_exploit: PrivilegeLevel,
+    pub privilege_gained_on_exploit: P,
+    // How easy the exploit is on the CVSS 0.0-10.0 scale, in
+    // hundredths so the record stays `Eq + Ord + Hash`.  `None` means
+    // unscored; see `exploitability()`.
+    pub exploitability_hundredths: Option<u16>,
+    // Operating system the exploit works against.  `None` means any
+    // OS; otherwise the host needs a matching `HostInfo` (see
+    // `build_attack_graph_with_host_info`).
+    pub applies_to_os: Option<OperatingSystem>,
 }
 
-impl VulnerabilityRecord {
+// Unscored vulnerabilities are assumed to be trivially exploitable,
+// which errs on the side of the defender.
+pub const DEFAULT_EXPLOITABILITY: f64 = 10.0;
+
+impl<P> VulnerabilityRecord<P> {
     // Convenience constructor to avoid repeated `.to_string()` calls
-    // at call sites.  This keeps tests and examples concise.
+    // at call sites.  This keeps tests and examples concise.  The
+    // service is assumed to listen on its default port; use
+    // `with_port` for anything else.
     pub fn new(
         host_name: &str,
         vulnerability_id: &str,
         affected_service: &str,
-        privilege_gained: PrivilegeLevel,
+        privilege_gained: P,
     ) -> Self {
         Self {
             host_name: host_name.to_string(),
             vulnerability_id: vulnerability_id.to_string(),
             affected_service: affected_service.to_string(),
+            port: default_port(affected_service),
             privilege_gained_on_exploit: privilege_gained,
+            exploitability_hundredths: None,
+            applies_to_os: None,
         }
     }
+
+    pub fn with_port(self, port: PortNumber) -> Self {
+        Self { port, ..self }
+    }
+
+    pub fn with_os(self, os: &str) -> Self {
+        Self {
+            applies_to_os: Some(os.to_string()),
+            ..self
+        }
+    }
+
+    // `exploitability` is clamped to 0.0-10.0.
+    pub fn with_exploitability(self, exploitability: f64) -> Self {
+        Self {
+            exploitability_hundredths: Some(
+                (exploitability.clamp(0.0, 10.0) * 100.0).round() as u16
+            ),
+            ..self
+        }
+    }
+
+    pub fn exploitability(&self) -> f64 {
+        self.exploitability_hundredths
+            .map_or(DEFAULT_EXPLOITABILITY, |hundredths| {
+                f64::from(hundredths) / 100.0
+            })
+    }
+
+    // Difficulty of one exploit, `10 - exploitability`, in hundredths.
+    pub fn difficulty_hundredths(&self) -> u32 {
+        1000 - u32::from(self.exploitability_hundredths.unwrap_or(1000))
+    }
 }
 
 // A local privilege escalation vulnerability observed on a host.
@@ -133,9 +324,66 @@ impl LocalVulnerabilityRecord {
     }
 }
 
+// A local privilege escalation that only applies from a specific
+// privilege: an attacker holding exactly `from_privilege` on
+// `host_name` can raise it to `to_privilege` without moving.  Unlike
+// `LocalVulnerabilityRecord`, the precondition is explicit, so a
+// None -> User step and a User -> Root step can be chained.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct PrivilegeEscalationRecord {
+    pub host_name: HostIdentifier,
+    pub vulnerability_id: VulnerabilityIdentifier,
+    pub from_privilege: PrivilegeLevel,
+    pub to_privilege: PrivilegeLevel,
+}
+
+impl PrivilegeEscalationRecord {
+    pub fn new(
+        host_name: &str,
+        vulnerability_id: &str,
+        from_privilege: PrivilegeLevel,
+        to_privilege: PrivilegeLevel,
+    ) -> Self {
+        Self {
+            host_name: host_name.to_string(),
+            vulnerability_id: vulnerability_id.to_string(),
+            from_privilege,
+            to_privilege,
+        }
+    }
+}
+
+// A vulnerability that grants lateral access rather than code
+// execution, e.g. an open file share or exposed credentials.  Once an
+// attacker owns `host_name`, it can reach `grants_access_to` on
+// `service_name` as if an effective-access edge existed.  This maps to
+// `accessGrantingVul(Host, Svc, Target)`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct AccessGrantingVulnerabilityRecord {
+    pub host_name: HostIdentifier,
+    pub service_name: ServiceName,
+    pub grants_access_to: HostIdentifier,
+}
+
+impl AccessGrantingVulnerabilityRecord {
+    pub fn new(host: &str, service: &str, grants_access_to: &str) -> Self {
+        Self {
+            host_name: host.to_string(),
+            service_name: service.to_string(),
+            grants_access_to: grants_access_to.to_string(),
+        }
+    }
+}
+
 // Network connectivity / access rule.  This represents that traffic
-// from `source_host` can reach `destination_host` on `service_name`.
-// In MulVAL this would be `hacl(Src, Dst, Service)`.  Note that the
+// from `source_host` can reach `destination_host` on `service_name`
+// at `port`.  In MulVAL this would be `hacl(Src, Dst, Protocol, Port)`.
+// An edge only reaches a vulnerability on the same service and port,
+// so ssh on 22 and a tunnel on 2222 are distinct.  Note that the
 // presence of a NetworkAccessRule does not mean traffic is actually
 // allowed - firewall rules can block it.  Effective access is
 // computed later by combining these facts with firewall rules.
@@ -146,16 +394,347 @@ pub struct NetworkAccessRule {
     pub source_host: HostIdentifier,
     pub destination_host: HostIdentifier,
     pub service_name: ServiceName,
+    // Port 0 for facts serialised before this field existed.
+    #[serde(default)]
+    pub port: PortNumber,
+    // Whether the edge was seen by a scanner or assumed.  Facts
+    // serialised before this field existed are treated as observed.
+    #[serde(default)]
+    pub provenance: Provenance,
 }
 
 impl NetworkAccessRule {
     // Small helper constructor; used by examples and tests to keep
-    // call sites readable.
-    pub fn new(source: &str, destination: &str, service: &str) -> Self {
+    // call sites readable.  The edge is marked as observed.
+    pub fn new(source: &str, destination: &str, service: &str, port: PortNumber) -> Self {
         Self {
             source_host: source.to_string(),
             destination_host: destination.to_string(),
             service_name: service.to_string(),
+            port,
+            provenance: Provenance::Observed,
+        }
+    }
+
+    // Same as `new`, on the service's default port.
+    pub fn new_default_port(source: &str, destination: &str, service: &str) -> Self {
+        Self::new(source, destination, service, default_port(service))
+    }
+
+    // Same as `new_default_port`, for connectivity that is assumed
+    // rather than observed (e.g. derived from a network diagram).
+    pub fn inferred(source: &str, destination: &str, service: &str) -> Self {
+        Self {
+            provenance: Provenance::Inferred,
+            ..Self::new_default_port(source, destination, service)
+        }
+    }
+
+    // Both directions of a symmetric link, on the service's default
+    // port: `first -> second`, then `second -> first`.
+    pub fn bidirectional(first: &str, second: &str, service: &str) -> [Self; 2] {
+        [
+            Self::new_default_port(first, second, service),
+            Self::new_default_port(second, first, service),
+        ]
+    }
+
+    // The same edge in the opposite direction.
+    pub fn reversed(&self) -> Self {
+        Self {
+            source_host: self.destination_host.clone(),
+            destination_host: self.source_host.clone(),
+            ..self.clone()
+        }
+    }
+}
+
+// Where a network edge comes from.  Observed edges were confirmed by
+// scanning; inferred edges are assumed connectivity.  High-confidence
+// reports can restrict the analysis to observed edges.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Abomonation,
+    Serialize,
+    Deserialize,
+)]
+pub enum Provenance {
+    #[default]
+    Observed,
+    Inferred,
+}
+
+// Role-based access control.  A role grant is a network edge that
+// only attackers holding `role` may traverse; it maps to
+// `roleGrant(Role, Src, Dst, Service)`.  Firewall denies still apply.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct RoleGrantRule {
+    pub role: RoleName,
+    pub source_host: HostIdentifier,
+    pub destination_host: HostIdentifier,
+    pub service_name: ServiceName,
+}
+
+impl RoleGrantRule {
+    pub fn new(role: &str, source: &str, destination: &str, service: &str) -> Self {
+        Self {
+            role: role.to_string(),
+            source_host: source.to_string(),
+            destination_host: destination.to_string(),
+            service_name: service.to_string(),
+        }
+    }
+}
+
+// A role the attacker holds from the start: `hasRole(Attacker, Role)`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct AttackerRoleMembership {
+    pub attacker_id: AttackerIdentifier,
+    pub role: RoleName,
+}
+
+impl AttackerRoleMembership {
+    pub fn new(attacker_id: &str, role: &str) -> Self {
+        Self {
+            attacker_id: attacker_id.to_string(),
+            role: role.to_string(),
+        }
+    }
+}
+
+// A host whose compromise gives the attacker a role, e.g. a machine
+// holding a service account's credentials: `confersRole(Host, Role)`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct RoleConferringHost {
+    pub host_name: HostIdentifier,
+    pub role: RoleName,
+}
+
+impl RoleConferringHost {
+    pub fn new(host: &str, role: &str) -> Self {
+        Self {
+            host_name: host.to_string(),
+            role: role.to_string(),
+        }
+    }
+}
+
+// Membership of a host in a network zone: `inZone(Host, Zone)`.  A
+// host may belong to several zones.  Firewall rules whose source or
+// destination names the zone apply to the host (see
+// `build_attack_graph_with_zones`).
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct HostZone {
+    pub host_name: HostIdentifier,
+    pub zone_name: ZoneName,
+}
+
+impl HostZone {
+    pub fn new(host: &str, zone: &str) -> Self {
+        Self {
+            host_name: host.to_string(),
+            zone_name: zone.to_string(),
+        }
+    }
+}
+
+// Platform details of a host: `hostInfo(Host, OS)`.  Used to decide
+// which OS-specific vulnerabilities are exploitable on it.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct HostInfo {
+    pub host_name: HostIdentifier,
+    pub os: OperatingSystem,
+}
+
+impl HostInfo {
+    pub fn new(host: &str, os: &str) -> Self {
+        Self {
+            host_name: host.to_string(),
+            os: os.to_string(),
+        }
+    }
+}
+
+// A patch installed on a host that fixes one vulnerability:
+// `patchApplied(Host, CVE)`.  A scanner may still report the
+// vulnerability; the patch suppresses it (see
+// `build_attack_graph_with_patches`).
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct PatchApplied {
+    pub host_name: HostIdentifier,
+    pub fixed_vulnerability_id: VulnerabilityIdentifier,
+}
+
+impl PatchApplied {
+    pub fn new(host: &str, fixes_cve: &str) -> Self {
+        Self {
+            host_name: host.to_string(),
+            fixed_vulnerability_id: fixes_cve.to_string(),
+        }
+    }
+}
+
+// An exploit side effect that starts a service: once an attacker
+// executes code on `host_name` with at least `required_privilege`,
+// `service_name` runs there for that attacker, e.g. enabling RDP:
+// `enablesService(Host, Privilege, Service)`.  Vulnerabilities on a
+// service named by such a fact are dormant until it is enabled (see
+// `build_attack_graph_with_enabled_services`).
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct EnablesService {
+    pub host_name: HostIdentifier,
+    pub required_privilege: PrivilegeLevel,
+    pub service_name: ServiceName,
+}
+
+impl EnablesService {
+    pub fn new(host: &str, required_privilege: PrivilegeLevel, service: &str) -> Self {
+        Self {
+            host_name: host.to_string(),
+            required_privilege,
+            service_name: service.to_string(),
+        }
+    }
+}
+
+// Business value of a host, used to weight risk metrics (see
+// `query::total_risk`).  Hosts without one are worth 1.0.  Only
+// consumed outside the dataflow, so `value` can stay an `f64`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetValue {
+    pub host_name: HostIdentifier,
+    pub value: f64,
+}
+
+impl AssetValue {
+    pub fn new(host: &str, value: f64) -> Self {
+        Self {
+            host_name: host.to_string(),
+            value,
+        }
+    }
+}
+
+// Value of a host without an `AssetValue`.
+pub const DEFAULT_ASSET_VALUE: f64 = 1.0;
+
+// A credential that can be dumped from `host_name` by any attacker
+// executing code there: `credentialStored(Host, Cred)`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct CredentialStoredRecord {
+    pub host_name: HostIdentifier,
+    pub credential_id: CredentialIdentifier,
+}
+
+impl CredentialStoredRecord {
+    pub fn new(host: &str, credential: &str) -> Self {
+        Self {
+            host_name: host.to_string(),
+            credential_id: credential.to_string(),
+        }
+    }
+}
+
+// Logging in to `target_host` with `credential_id` yields `privilege`
+// there, with no vulnerability or network edge required:
+// `credentialGrants(Cred, Target, Priv)`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct CredentialGrantRecord {
+    pub credential_id: CredentialIdentifier,
+    pub target_host: HostIdentifier,
+    pub privilege: PrivilegeLevel,
+}
+
+impl CredentialGrantRecord {
+    pub fn new(credential: &str, target_host: &str, privilege: PrivilegeLevel) -> Self {
+        Self {
+            credential_id: credential.to_string(),
+            target_host: target_host.to_string(),
+            privilege,
+        }
+    }
+}
+
+// How likely exploiting `vulnerability_id` on `host_name` is to be
+// detected.  Stored as parts per million so the record stays
+// `Eq + Ord + Hash` for differential dataflow; use `detection_risk()`
+// for the value as a float.  Exploits without a record are silent.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct ExploitDetectionRisk {
+    pub host_name: HostIdentifier,
+    pub vulnerability_id: VulnerabilityIdentifier,
+    pub detection_risk_ppm: u64,
+}
+
+impl ExploitDetectionRisk {
+    pub fn new(host_name: &str, vulnerability_id: &str, detection_risk: f64) -> Self {
+        Self {
+            host_name: host_name.to_string(),
+            vulnerability_id: vulnerability_id.to_string(),
+            detection_risk_ppm: risk_to_ppm(detection_risk),
+        }
+    }
+
+    pub fn detection_risk(&self) -> f64 {
+        ppm_to_risk(self.detection_risk_ppm)
+    }
+}
+
+pub fn risk_to_ppm(risk: f64) -> u64 {
+    (risk.max(0.0) * 1_000_000.0).round() as u64
+}
+
+pub fn ppm_to_risk(ppm: u64) -> f64 {
+    ppm as f64 / 1_000_000.0
+}
+
+// Trust between hosts (shared NFS exports, domain trust, rhosts): code
+// execution on `trustee_host` yields `granted_privilege` on
+// `truster_host` without exploiting anything.
+// `trusts(Truster, Trustee, Privilege)`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct TrustRelationship {
+    pub truster_host: HostIdentifier,
+    pub trustee_host: HostIdentifier,
+    pub granted_privilege: PrivilegeLevel,
+}
+
+impl TrustRelationship {
+    pub fn new(truster: &str, trustee: &str, granted_privilege: PrivilegeLevel) -> Self {
+        Self {
+            truster_host: truster.to_string(),
+            trustee_host: trustee.to_string(),
+            granted_privilege,
         }
     }
 }
@@ -176,19 +755,38 @@ pub struct FirewallRuleRecord {
     pub source_zone: HostIdentifier,
     pub destination_host: HostIdentifier,
     pub service_name: ServiceName,
+    // Rules are port-scoped: denying 80 leaves 443 open.  Port 0 for
+    // rules serialised before this field existed.
+    #[serde(default)]
+    pub port: PortNumber,
     pub rule_action: FirewallRuleAction,
 }
 
 impl FirewallRuleRecord {
     // Helper to create a deny rule quickly in examples/benchmarks.
+    // The rule covers the service's default port; use `with_port` to
+    // target another one.
     pub fn create_deny_rule(source: &str, destination: &str, service: &str) -> Self {
         Self {
             source_zone: source.to_string(),
             destination_host: destination.to_string(),
             service_name: service.to_string(),
+            port: default_port(service),
             rule_action: FirewallRuleAction::Deny,
         }
     }
+
+    // Same as `create_deny_rule`, for default-deny firewalls.
+    pub fn create_allow_rule(source: &str, destination: &str, service: &str) -> Self {
+        Self {
+            rule_action: FirewallRuleAction::Allow,
+            ..Self::create_deny_rule(source, destination, service)
+        }
+    }
+
+    pub fn with_port(self, port: PortNumber) -> Self {
+        Self { port, ..self }
+    }
 }
 
 // Attacker's initial / starting position.  This corresponds to the
@@ -198,14 +796,14 @@ impl FirewallRuleRecord {
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
 )]
-pub struct AttackerStartingPosition {
+pub struct AttackerStartingPosition<P = PrivilegeLevel> {
     pub attacker_id: AttackerIdentifier,
     pub starting_host: HostIdentifier,
-    pub initial_privilege: PrivilegeLevel,
+    pub initial_privilege: P,
 }
 
-impl AttackerStartingPosition {
-    pub fn new(attacker_id: &str, starting_host: &str, initial_privilege: PrivilegeLevel) -> Self {
+impl<P> AttackerStartingPosition<P> {
+    pub fn new(attacker_id: &str, starting_host: &str, initial_privilege: P) -> Self {
         Self {
             attacker_id: attacker_id.to_string(),
             starting_host: starting_host.to_string(),
@@ -214,21 +812,62 @@ impl AttackerStartingPosition {
     }
 }
 
-// The attacker's goal: which host they wish to compromise.  This is
-// used by the evaluation to check whether a target was reached.
+// The attacker's goal: which host they wish to compromise, and with
+// what privilege.  This is used by the evaluation to check whether a
+// target was reached: the goal holds once the attacker executes code
+// on the host with a privilege that dominates `required_privilege`.
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
 )]
 pub struct AttackerTargetGoal {
     pub attacker_id: AttackerIdentifier,
     pub target_host_name: HostIdentifier,
+    // Goals written before this field existed meant "own the host".
+    #[serde(default = "default_required_privilege")]
+    pub required_privilege: PrivilegeLevel,
+}
+
+fn default_required_privilege() -> PrivilegeLevel {
+    PrivilegeLevel::Root
 }
 
 impl AttackerTargetGoal {
+    // Goal requiring Root on the target; use `with_required_privilege`
+    // for a weaker foothold.
     pub fn new(attacker_id: &str, target_host: &str) -> Self {
         Self {
             attacker_id: attacker_id.to_string(),
             target_host_name: target_host.to_string(),
+            required_privilege: default_required_privilege(),
+        }
+    }
+
+    pub fn with_required_privilege(self, required_privilege: PrivilegeLevel) -> Self {
+        Self {
+            required_privilege,
+            ..self
+        }
+    }
+}
+
+// Exfiltration goal: the attacker must own `data_host_name` and the
+// data host must be able to reach `exit_host_name` (an
+// attacker-controlled drop point) over effective network access.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct ExfilGoal {
+    pub attacker_id: AttackerIdentifier,
+    pub data_host_name: HostIdentifier,
+    pub exit_host_name: HostIdentifier,
+}
+
+impl ExfilGoal {
+    pub fn new(attacker_id: &str, data_host: &str, exit_host: &str) -> Self {
+        Self {
+            attacker_id: attacker_id.to_string(),
+            data_host_name: data_host.to_string(),
+            exit_host_name: exit_host.to_string(),
         }
     }
 }
@@ -248,6 +887,8 @@ pub struct EffectiveNetworkAccess {
     pub source_host: HostIdentifier,
     pub destination_host: HostIdentifier,
     pub service_name: ServiceName,
+    #[serde(default)]
+    pub port: PortNumber,
 }
 
 // execCode: attacker can execute code on host with some privilege.
@@ -255,13 +896,13 @@ pub struct EffectiveNetworkAccess {
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
 )]
-pub struct AttackerCodeExecution {
+pub struct AttackerCodeExecution<P = PrivilegeLevel> {
     pub attacker_id: AttackerIdentifier,
     pub compromised_host: HostIdentifier,
-    pub obtained_privilege: PrivilegeLevel,
+    pub obtained_privilege: P,
 }
 
-impl fmt::Display for AttackerCodeExecution {
+impl<P: fmt::Display> fmt::Display for AttackerCodeExecution<P> {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         // Nice human-readable printing for logs and debugging
         write!(
@@ -272,6 +913,18 @@ impl fmt::Display for AttackerCodeExecution {
     }
 }
 
+impl<P: fmt::Display> AttackerCodeExecution<P> {
+    // MulVAL-compatible predicate text, e.g. `execCode(attacker,'web01',root)`.
+    pub fn to_mulval_string(&self) -> String {
+        format!(
+            "execCode({},{},{})",
+            mulval_atom(&self.attacker_id),
+            mulval_quoted_constant(&self.compromised_host),
+            mulval_atom(&self.obtained_privilege.to_string())
+        )
+    }
+}
+
 // ownsMachine: a convenience derived fact when the attacker has
 // Root on a host.  Useful for goal checking and for generating
 // alerts in examples.
@@ -293,27 +946,223 @@ impl fmt::Display for AttackerOwnsMachine {
     }
 }
 
+impl AttackerOwnsMachine {
+    // MulVAL-compatible predicate text, e.g. `ownsMachine(attacker,'web01')`.
+    pub fn to_mulval_string(&self) -> String {
+        format!(
+            "ownsMachine({},{})",
+            mulval_atom(&self.attacker_id),
+            mulval_quoted_constant(&self.owned_host)
+        )
+    }
+}
+
 // goalReached: indicates the attacker successfully reached their
-// declared goal.  This is computed by semijoining the goal list with
-// the set of owned machines.
+// declared goal: the attacker executes code on the target at a
+// privilege dominating the goal's `required_privilege`.  Goals on the
+// same host that differ only in that privilege are reached separately.
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
 )]
 pub struct AttackerGoalReached {
     pub attacker_id: AttackerIdentifier,
     pub reached_target: HostIdentifier,
+    // Results written before this field existed were all Root goals.
+    #[serde(default = "default_required_privilege")]
+    pub required_privilege: PrivilegeLevel,
 }
 
 impl fmt::Display for AttackerGoalReached {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            formatter,
-            "goalReached({}, {})",
-            self.attacker_id, self.reached_target
-        )
+        // Root is the implied requirement, as in `attackGoal`.
+        match &self.required_privilege {
+            PrivilegeLevel::Root => write!(
+                formatter,
+                "goalReached({}, {})",
+                self.attacker_id, self.reached_target
+            ),
+            required_privilege => write!(
+                formatter,
+                "goalReached({}, {}, {})",
+                self.attacker_id, self.reached_target, required_privilege
+            ),
+        }
     }
 }
 
+impl AttackerGoalReached {
+    // MulVAL-compatible predicate text, e.g. `goalReached(attacker,'db01')`,
+    // with the required privilege appended when it is not Root.
+    pub fn to_mulval_string(&self) -> String {
+        match &self.required_privilege {
+            PrivilegeLevel::Root => format!(
+                "goalReached({},{})",
+                mulval_atom(&self.attacker_id),
+                mulval_quoted_constant(&self.reached_target)
+            ),
+            required_privilege => format!(
+                "goalReached({},{},{})",
+                mulval_atom(&self.attacker_id),
+                mulval_quoted_constant(&self.reached_target),
+                mulval_atom(&required_privilege.to_string())
+            ),
+        }
+    }
+}
+
+// goalUnreachable: a declared goal that is not reached once the fixed
+// point has settled.  Derived by antijoining the goal list with
+// goalReached, so it is retracted as soon as the goal becomes
+// reachable.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct AttackerGoalUnreachable {
+    pub attacker_id: AttackerIdentifier,
+    pub target_host_name: HostIdentifier,
+    #[serde(default = "default_required_privilege")]
+    pub required_privilege: PrivilegeLevel,
+}
+
+impl fmt::Display for AttackerGoalUnreachable {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.required_privilege {
+            PrivilegeLevel::Root => write!(
+                formatter,
+                "goalUnreachable({}, {})",
+                self.attacker_id, self.target_host_name
+            ),
+            required_privilege => write!(
+                formatter,
+                "goalUnreachable({}, {}, {})",
+                self.attacker_id, self.target_host_name, required_privilege
+            ),
+        }
+    }
+}
+
+// attackRoute: one cycle-free sequence of exploitation hops.  `hosts`
+// starts with the attacker's starting host; `services[i]` is the
+// service exploited to move from `hosts[i]` to `hosts[i + 1]`, so it
+// always has one element fewer than `hosts`.  `final_privilege` is
+// the privilege obtained on the last host.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct AttackRoute {
+    pub attacker_id: AttackerIdentifier,
+    pub hosts: Vec<HostIdentifier>,
+    pub services: Vec<ServiceName>,
+    pub final_privilege: PrivilegeLevel,
+}
+
+impl AttackRoute {
+    // The host this route ends on.
+    pub fn last_host(&self) -> &HostIdentifier {
+        self.hosts
+            .last()
+            .expect("attack routes always contain the starting host")
+    }
+}
+
+// attackPath: the hosts of an attack route without the services used
+// between them.  Routes that differ only by service collapse into one
+// path; `path` starts with the attacker's starting host.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct AttackPath {
+    pub attacker_id: AttackerIdentifier,
+    pub path: Vec<HostIdentifier>,
+    pub final_privilege: PrivilegeLevel,
+}
+
+impl From<AttackRoute> for AttackPath {
+    fn from(route: AttackRoute) -> Self {
+        Self {
+            attacker_id: route.attacker_id,
+            path: route.hosts,
+            final_privilege: route.final_privilege,
+        }
+    }
+}
+
+// cheapestAttackPath: the attack path to `host_name` with the lowest
+// summed difficulty (`10 - exploitability` per exploit), ties broken by
+// the lexicographically smallest host sequence.  `path` starts with the
+// attacker's starting host; the cost is kept in hundredths so the
+// record stays `Eq + Ord + Hash`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct CheapestAttackPath {
+    pub attacker_id: AttackerIdentifier,
+    pub host_name: HostIdentifier,
+    pub cost_hundredths: u32,
+    pub path: Vec<HostIdentifier>,
+}
+
+impl CheapestAttackPath {
+    pub fn cost(&self) -> f64 {
+        f64::from(self.cost_hundredths) / 100.0
+    }
+}
+
+// hostDistance: the fewest exploited edges an attacker needs to reach
+// `host_name` from any of its starting positions (0 for the starting
+// host itself).
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct HostDistance {
+    pub attacker_id: AttackerIdentifier,
+    pub host_name: HostIdentifier,
+    pub hops: u32,
+}
+
+// execCodeJustification: one exploit step that derives execCode for
+// `host_name`: the attacker, already executing on `via_source`,
+// reached the host over `via_service` and exploited `via_cve`.  A
+// host reachable in several ways has one justification per way;
+// starting positions have none.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Abomonation, Serialize, Deserialize,
+)]
+pub struct ExecCodeJustification {
+    pub attacker_id: AttackerIdentifier,
+    pub host_name: HostIdentifier,
+    pub via_source: HostIdentifier,
+    pub via_service: ServiceName,
+    pub via_cve: VulnerabilityIdentifier,
+}
+
+// ----------------------------------------------------------------
+// MulVAL term formatting
+// ----------------------------------------------------------------
+// MulVAL reads its facts as Prolog terms.  Host names are always
+// emitted as single-quoted constants so names such as `192.168.1.10`
+// or `WebServer` are never parsed as numbers or variables.  Principals
+// and privileges stay bare atoms when they already are valid Prolog
+// atoms (lowercase first letter, alphanumerics and `_`), which matches
+// the usual `execCode(attacker,'host',root)` form.
+fn mulval_atom(value: &str) -> String {
+    let mut characters = value.chars();
+    let is_plain_atom = characters
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase())
+        && characters.all(|character| character.is_ascii_alphanumeric() || character == '_');
+
+    if is_plain_atom {
+        value.to_string()
+    } else {
+        mulval_quoted_constant(value)
+    }
+}
+
+fn mulval_quoted_constant(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
 // ----------------------------------------------------------------
 // Key types for joins
 // ----------------------------------------------------------------
@@ -321,5 +1170,162 @@ impl fmt::Display for AttackerGoalReached {
 // collections for joins and semijoins inside the dataflow.  Using
 // aliases reduces duplication and clarifies intent at join sites.
 pub type AttackerAndHostKey = (AttackerIdentifier, HostIdentifier);
-pub type NetworkAccessKey = (HostIdentifier, HostIdentifier, ServiceName);
-pub type HostAndServiceKey = (HostIdentifier, ServiceName);
+pub type NetworkAccessKey = (HostIdentifier, HostIdentifier, ServiceName, PortNumber);
+pub type HostAndServiceKey = (HostIdentifier, ServiceName, PortNumber);
+
+// ----------------------------------------------------------------
+// Short names
+// ----------------------------------------------------------------
+// The descriptive names above are canonical: every module in the crate
+// uses them.  The MulVAL-style short names below are aliases for code
+// written against the shorter naming scheme; both spellings name the
+// same types and can be mixed freely.
+//
+//   Vulnerability    = VulnerabilityRecord       (vulExists)
+//   PrivilegeEscalation = PrivilegeEscalationRecord
+//   CredentialStored = CredentialStoredRecord    (credentialStored)
+//   CredentialGrants = CredentialGrantRecord     (credentialGrants)
+//   NetworkAccess    = NetworkAccessRule         (hacl)
+//   Trusts           = TrustRelationship         (trusts)
+//   FirewallRule     = FirewallRuleRecord
+//   FirewallAction   = FirewallRuleAction
+//   AttackerLocation = AttackerStartingPosition  (attackerLocated)
+//   AttackerGoal     = AttackerTargetGoal
+//   Privilege        = PrivilegeLevel
+//   ExecCode         = AttackerCodeExecution     (execCode)
+//   OwnsMachine      = AttackerOwnsMachine       (ownsMachine)
+//   GoalReached      = AttackerGoalReached       (goalReached)
+//   GoalUnreachable  = AttackerGoalUnreachable   (goalUnreachable)
+pub type Vulnerability = VulnerabilityRecord;
+pub type PrivilegeEscalation = PrivilegeEscalationRecord;
+pub type CredentialStored = CredentialStoredRecord;
+pub type CredentialGrants = CredentialGrantRecord;
+pub type NetworkAccess = NetworkAccessRule;
+pub type Trusts = TrustRelationship;
+pub type FirewallRule = FirewallRuleRecord;
+pub type FirewallAction = FirewallRuleAction;
+pub type AttackerLocation = AttackerStartingPosition;
+pub type AttackerGoal = AttackerTargetGoal;
+pub type Privilege = PrivilegeLevel;
+pub type ExecCode = AttackerCodeExecution;
+pub type OwnsMachine = AttackerOwnsMachine;
+pub type GoalReached = AttackerGoalReached;
+pub type GoalUnreachable = AttackerGoalUnreachable;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_code_matches_mulval_predicate_syntax() {
+        let exec = AttackerCodeExecution {
+            attacker_id: "attacker".to_string(),
+            compromised_host: "webServer".to_string(),
+            obtained_privilege: PrivilegeLevel::Root,
+        };
+
+        assert_eq!(
+            exec.to_mulval_string(),
+            "execCode(attacker,'webServer',root)"
+        );
+    }
+
+    #[test]
+    fn owns_machine_and_goal_reached_match_mulval_predicate_syntax() {
+        let owned = AttackerOwnsMachine {
+            attacker_id: "attacker".to_string(),
+            owned_host: "192.168.1.10".to_string(),
+        };
+        let goal = AttackerGoalReached {
+            attacker_id: "Eve".to_string(),
+            reached_target: "o'brien-db".to_string(),
+            required_privilege: PrivilegeLevel::Root,
+        };
+
+        assert_eq!(
+            owned.to_mulval_string(),
+            "ownsMachine(attacker,'192.168.1.10')"
+        );
+        assert_eq!(goal.to_mulval_string(), "goalReached('Eve','o\\'brien-db')");
+    }
+
+    #[test]
+    fn privilege_levels_parse_their_display_names_in_any_case() {
+        for privilege in [
+            PrivilegeLevel::None,
+            PrivilegeLevel::User,
+            PrivilegeLevel::Root,
+        ] {
+            assert_eq!(privilege.to_string().parse(), Ok(privilege.clone()));
+            assert_eq!(privilege.to_string().to_uppercase().parse(), Ok(privilege));
+        }
+        assert_eq!(
+            "admin".parse::<PrivilegeLevel>(),
+            Err(UnknownPrivilegeLevel("admin".to_string()))
+        );
+    }
+
+    #[test]
+    fn short_names_construct_the_canonical_types() {
+        let vulnerability: VulnerabilityRecord =
+            Vulnerability::new("web", "CVE-1", "http", Privilege::Root);
+        let access: NetworkAccessRule = NetworkAccess::new("internet", "web", "http", 80);
+        let firewall: FirewallRuleRecord = FirewallRule::create_deny_rule("internet", "db", "sql");
+        let location: AttackerStartingPosition =
+            AttackerLocation::new("attacker", "internet", Privilege::User);
+        let goal: AttackerTargetGoal = AttackerGoal::new("attacker", "web");
+        let exec: AttackerCodeExecution = ExecCode {
+            attacker_id: "attacker".to_string(),
+            compromised_host: "web".to_string(),
+            obtained_privilege: Privilege::Root,
+        };
+        let owned: AttackerOwnsMachine = OwnsMachine {
+            attacker_id: "attacker".to_string(),
+            owned_host: "web".to_string(),
+        };
+        let reached: AttackerGoalReached = GoalReached {
+            attacker_id: "attacker".to_string(),
+            reached_target: "web".to_string(),
+            required_privilege: PrivilegeLevel::Root,
+        };
+
+        assert_eq!(
+            vulnerability.privilege_gained_on_exploit,
+            PrivilegeLevel::Root
+        );
+        assert_eq!(access.service_name, "http");
+        assert_eq!(firewall.rule_action, FirewallAction::Deny);
+        assert_eq!(location.initial_privilege, PrivilegeLevel::User);
+        assert_eq!(goal.target_host_name, "web");
+        assert_eq!(exec.to_mulval_string(), "execCode(attacker,'web',root)");
+        assert_eq!(owned.to_mulval_string(), "ownsMachine(attacker,'web')");
+        assert_eq!(reached.to_mulval_string(), "goalReached(attacker,'web')");
+
+        let user_goal = GoalReached {
+            required_privilege: Privilege::User,
+            ..reached
+        };
+        assert_eq!(user_goal.to_string(), "goalReached(attacker, web, user)");
+        assert_eq!(
+            user_goal.to_mulval_string(),
+            "goalReached(attacker,'web',user)"
+        );
+    }
+
+    #[test]
+    fn facts_serialised_without_a_port_load_with_port_zero() {
+        let access: NetworkAccessRule = serde_json::from_str(
+            r#"{"source_host":"internet","destination_host":"web","service_name":"http"}"#,
+        )
+        .expect("network access without a port should deserialize");
+        let vulnerability: VulnerabilityRecord = serde_json::from_str(
+            r#"{"host_name":"web","vulnerability_id":"CVE-1","affected_service":"http",
+                "privilege_gained_on_exploit":"Root","exploitability_hundredths":null,
+                "applies_to_os":null}"#,
+        )
+        .expect("vulnerability without a port should deserialize");
+
+        assert_eq!(access.port, 0);
+        assert_eq!(vulnerability.port, 0);
+    }
+}
@@ -0,0 +1,323 @@
+// Attack graph session
+// --------------------
+// A long-lived dataflow for interactive what-if analysis.  The query
+// helpers in `query.rs` build a fresh dataflow per call and feed it
+// every base fact again; a session instead keeps its worker, its
+// input sessions and one arrangement per base relation alive between
+// calls.
+//
+// The base relations are arranged in their own dataflow, which holds
+// the current facts between calls.  The goal dataflow that answers
+// `current_goals_reached` stays alive, so `apply` costs what the
+// change costs.  Each `run_query` call imports the arrangements and
+// reads them back as collections: the query skips copying the facts
+// out of `BaseFacts`, but its rules still index those collections
+// afresh, since they join the facts only after the firewall and
+// applicability filters and so cannot use the arrangements directly.
+//
+//   let mut session = AttackGraphSession::new(&facts);
+//   session.apply(&[FactUpdate::RemoveVulnerability(patched)]);
+//   let goals = session.current_goals_reached();
+//
+// `apply_and_diff` applies a batch the same way and also returns the
+// goals it made reachable (+1) or unreachable (-1).  Callbacks
+// registered with `on_goal_change` receive the same changes as each
+// batch settles, so a caller can raise and clear alerts without
+// polling.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use differential_dataflow::collection::Collection;
+use differential_dataflow::input::Input;
+use differential_dataflow::operators::arrange::{ArrangeBySelf, TraceAgent};
+use differential_dataflow::trace::implementations::KeySpine;
+use differential_dataflow::trace::TraceReader;
+use timely::communication::allocator::thread::Thread;
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::frontier::AntichainRef;
+use timely::worker::{Config as WorkerConfig, Worker};
+
+use crate::driver::FactInputs;
+use crate::engine::{BaseFacts, FactUpdate};
+use crate::query::{QueryInputs, QueryScope};
+use crate::rules::build_attack_graph_with_local_vulnerabilities;
+use crate::schema::*;
+
+type FactTrace<D> = TraceAgent<KeySpine<D, usize, isize>>;
+type GoalChangeCallback = Box<dyn FnMut(AttackerGoalReached, isize)>;
+
+// Shared arrangements of the base relations, each arranged by value:
+// they only hold the facts for later dataflows to import.
+struct BaseTraces {
+    vulnerabilities: FactTrace<VulnerabilityRecord>,
+    local_vulnerabilities: FactTrace<LocalVulnerabilityRecord>,
+    network_access: FactTrace<NetworkAccessRule>,
+    firewall_rules: FactTrace<FirewallRuleRecord>,
+    attacker_positions: FactTrace<AttackerStartingPosition>,
+    attacker_goals: FactTrace<AttackerTargetGoal>,
+}
+
+impl BaseTraces {
+    // The base relations as collections of a new dataflow, read from
+    // the shared arrangements.
+    fn import<'a>(&mut self, scope: &mut QueryScope<'a>) -> QueryInputs<'a> {
+        QueryInputs {
+            vulnerabilities: self
+                .vulnerabilities
+                .import(scope)
+                .as_collection(|vulnerability, ()| vulnerability.clone()),
+            local_vulnerabilities: self
+                .local_vulnerabilities
+                .import(scope)
+                .as_collection(|vulnerability, ()| vulnerability.clone()),
+            network_access: self
+                .network_access
+                .import(scope)
+                .as_collection(|access, ()| access.clone()),
+            firewall_rules: self
+                .firewall_rules
+                .import(scope)
+                .as_collection(|rule, ()| rule.clone()),
+            attacker_positions: self
+                .attacker_positions
+                .import(scope)
+                .as_collection(|position, ()| position.clone()),
+            attacker_goals: self
+                .attacker_goals
+                .import(scope)
+                .as_collection(|goal, ()| goal.clone()),
+        }
+    }
+
+    // Lets the arrangements consolidate everything before `time`.
+    fn compact_to(&mut self, time: usize) {
+        let frontier = [time];
+        macro_rules! compact {
+            ($($trace:ident),*) => {
+                $(
+                    self.$trace.set_logical_compaction(AntichainRef::new(&frontier));
+                    self.$trace.set_physical_compaction(AntichainRef::new(&frontier));
+                )*
+            };
+        }
+        compact!(
+            vulnerabilities,
+            local_vulnerabilities,
+            network_access,
+            firewall_rules,
+            attacker_positions,
+            attacker_goals
+        );
+    }
+}
+
+/// A worker with the attack graph of one network loaded, kept alive
+/// for repeated updates and queries.  See the module comment.
+pub struct AttackGraphSession {
+    worker: Worker<Thread>,
+    inputs: FactInputs<usize>,
+    traces: BaseTraces,
+    probe: Handle<usize>,
+    // Goal changes seen by the goal dataflow since the last batch
+    // settled, with the time they were emitted at.
+    goal_changes: Arc<Mutex<Vec<(AttackerGoalReached, usize, isize)>>>,
+    goals_reached: BTreeMap<AttackerGoalReached, isize>,
+    goal_callbacks: Vec<GoalChangeCallback>,
+    facts: BaseFacts,
+    // Time of the next batch; every earlier batch has settled.
+    next_time: usize,
+}
+
+impl AttackGraphSession {
+    /// Builds the session's dataflows and loads `facts` as the first
+    /// batch.
+    pub fn new(facts: &BaseFacts) -> Self {
+        let mut worker = Worker::new(WorkerConfig::default(), Thread::new());
+
+        let (inputs, mut traces) = worker.dataflow::<usize, _, _>(|scope| {
+            let (vulnerability_handle, vulnerabilities) =
+                scope.new_collection::<VulnerabilityRecord, isize>();
+            let (local_vulnerability_handle, local_vulnerabilities) =
+                scope.new_collection::<LocalVulnerabilityRecord, isize>();
+            let (network_handle, network_access) =
+                scope.new_collection::<NetworkAccessRule, isize>();
+            let (firewall_handle, firewall_rules) =
+                scope.new_collection::<FirewallRuleRecord, isize>();
+            let (position_handle, attacker_positions) =
+                scope.new_collection::<AttackerStartingPosition, isize>();
+            let (goal_handle, attacker_goals) = scope.new_collection::<AttackerTargetGoal, isize>();
+
+            let traces = BaseTraces {
+                vulnerabilities: vulnerabilities.arrange_by_self().trace,
+                local_vulnerabilities: local_vulnerabilities.arrange_by_self().trace,
+                network_access: network_access.arrange_by_self().trace,
+                firewall_rules: firewall_rules.arrange_by_self().trace,
+                attacker_positions: attacker_positions.arrange_by_self().trace,
+                attacker_goals: attacker_goals.arrange_by_self().trace,
+            };
+            let inputs = FactInputs {
+                vulnerabilities: vulnerability_handle,
+                local_vulnerabilities: local_vulnerability_handle,
+                network_access: network_handle,
+                firewall_rules: firewall_handle,
+                attacker_positions: position_handle,
+                attacker_goals: goal_handle,
+            };
+            (inputs, traces)
+        });
+
+        let mut probe = Handle::new();
+        let goal_changes = Arc::new(Mutex::new(Vec::new()));
+        let captured_changes = Arc::clone(&goal_changes);
+        worker.dataflow::<usize, _, _>(|scope| {
+            let inputs = traces.import(scope);
+            let (_, _, goals) = build_attack_graph_with_local_vulnerabilities(
+                &inputs.vulnerabilities,
+                &inputs.local_vulnerabilities,
+                &inputs.network_access,
+                &inputs.firewall_rules,
+                &inputs.attacker_positions,
+                &inputs.attacker_goals,
+            );
+            goals
+                .inspect(move |(goal, time, diff)| {
+                    captured_changes
+                        .lock()
+                        .expect("captured goal changes mutex should not be poisoned")
+                        .push((goal.clone(), *time, *diff));
+                })
+                .probe_with(&mut probe);
+        });
+
+        let mut session = Self {
+            worker,
+            inputs,
+            traces,
+            probe,
+            goal_changes,
+            goals_reached: BTreeMap::new(),
+            goal_callbacks: Vec::new(),
+            facts: BaseFacts::default(),
+            next_time: 0,
+        };
+        session.apply(&facts.insert_updates());
+        session
+    }
+
+    /// Applies `updates` as one batch and waits until the goals have
+    /// settled.  Removing a fact that is not present is ignored.
+    pub fn apply(&mut self, updates: &[FactUpdate]) {
+        self.apply_and_diff(updates);
+    }
+
+    /// Registers `callback` to be called with every goal a later batch
+    /// makes reachable (+1) or unreachable (-1), in the order
+    /// `apply_and_diff` returns them.  Goals reached by the initial
+    /// facts are not replayed; see `current_goals_reached`.
+    pub fn on_goal_change(&mut self, callback: impl FnMut(AttackerGoalReached, isize) + 'static) {
+        self.goal_callbacks.push(Box::new(callback));
+    }
+
+    /// Like `apply`, returning the goals whose reachability the batch
+    /// changed: +1 for newly reached, -1 for no longer reached, sorted
+    /// by goal.  A batch that changes no goal returns an empty diff.
+    pub fn apply_and_diff(&mut self, updates: &[FactUpdate]) -> Vec<(AttackerGoalReached, isize)> {
+        for update in updates {
+            if self.facts.apply_update(update.clone()) {
+                self.inputs.send(update.clone());
+            }
+        }
+
+        let settled_time = self.next_time;
+        self.next_time += 1;
+        self.inputs.advance_and_flush(self.next_time);
+        while self.probe.less_than(&self.next_time) {
+            self.worker.step();
+        }
+        self.traces.compact_to(settled_time);
+
+        let mut diff: BTreeMap<AttackerGoalReached, isize> = BTreeMap::new();
+        for (goal, time, change) in self
+            .goal_changes
+            .lock()
+            .expect("captured goal changes mutex should not be poisoned")
+            .drain(..)
+        {
+            debug_assert_eq!(time, settled_time, "goal change outside the batch");
+            *diff.entry(goal).or_insert(0) += change;
+        }
+        diff.retain(|_, change| *change != 0);
+
+        for (goal, change) in &diff {
+            let count = self.goals_reached.entry(goal.clone()).or_insert(0);
+            *count += change;
+            if *count == 0 {
+                self.goals_reached.remove(goal);
+            }
+            for callback in &mut self.goal_callbacks {
+                callback(goal.clone(), *change);
+            }
+        }
+        diff.into_iter().collect()
+    }
+
+    /// Goals reached as of the last `apply`, sorted.
+    pub fn current_goals_reached(&self) -> Vec<AttackerGoalReached> {
+        self.goals_reached
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(goal, _)| goal.clone())
+            .collect()
+    }
+
+    /// Base facts as of the last `apply`.
+    pub fn base_facts(&self) -> &BaseFacts {
+        &self.facts
+    }
+
+    /// Like `run_collection_query`, over the session's current facts:
+    /// `build` reads them from the shared arrangements instead of a
+    /// fresh copy of `BaseFacts`, though its rules still index them
+    /// anew.  The query's dataflow is dropped once it has settled.
+    pub fn run_query<T, F>(&mut self, build: F) -> Vec<T>
+    where
+        T: timely::ExchangeData + Ord,
+        F: for<'a> Fn(&QueryInputs<'a>) -> Collection<QueryScope<'a>, T>,
+    {
+        let captured = Arc::new(Mutex::new(Vec::new()));
+        let captured_in_dataflow = Arc::clone(&captured);
+        let mut probe = Handle::new();
+        let traces = &mut self.traces;
+
+        let dataflow_index = self.worker.next_dataflow_index();
+        self.worker.dataflow::<usize, _, _>(|scope| {
+            build(&traces.import(scope))
+                .inspect(move |(record, _time, diff)| {
+                    captured_in_dataflow
+                        .lock()
+                        .expect("captured query output mutex should not be poisoned")
+                        .push((record.clone(), *diff));
+                })
+                .probe_with(&mut probe);
+        });
+        while probe.less_than(&self.next_time) {
+            self.worker.step();
+        }
+        self.worker.drop_dataflow(dataflow_index);
+
+        let mut counts: BTreeMap<T, isize> = BTreeMap::new();
+        for (record, diff) in captured
+            .lock()
+            .expect("captured query output mutex should not be poisoned")
+            .drain(..)
+        {
+            *counts.entry(record).or_insert(0) += diff;
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(record, _)| record)
+            .collect()
+    }
+}
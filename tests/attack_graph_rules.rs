@@ -1,10 +1,12 @@
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex, OnceLock};
 
 use differential_dataflow::input::Input;
+use differential_dataflow::operators::{Join, Threshold};
 use dynamic_attack_graphs::{
-    build_attack_graph, AttackerCodeExecution, AttackerGoalReached, AttackerOwnsMachine,
-    AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord, NetworkAccessRule,
-    PrivilegeLevel, VulnerabilityRecord,
+    build_attack_graph, generate_mesh_network, AttackerCodeExecution, AttackerGoalReached,
+    AttackerOwnsMachine, AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord,
+    NetworkAccessRule, PrivilegeLattice, PrivilegeLevel, VulnerabilityRecord,
 };
 use timely::dataflow::operators::probe::Handle;
 
@@ -102,8 +104,10 @@ where
             )
         });
 
-        network_input.insert(NetworkAccessRule::new("internet", "web", "https"));
-        network_input.insert(NetworkAccessRule::new("web", "db", "postgres"));
+        network_input.insert(NetworkAccessRule::new_default_port(
+            "internet", "web", "https",
+        ));
+        network_input.insert(NetworkAccessRule::new_default_port("web", "db", "postgres"));
 
         vulnerability_input.insert(VulnerabilityRecord::new(
             "web",
@@ -193,6 +197,7 @@ fn initial_rules_reach_goal_through_vulnerable_chain() {
         AttackerGoalReached {
             attacker_id: "eve".to_string(),
             reached_target: "db".to_string(),
+            required_privilege: PrivilegeLevel::Root,
         },
         0,
         1,
@@ -220,6 +225,7 @@ fn adding_firewall_deny_retracts_dependent_compromise() {
         AttackerGoalReached {
             attacker_id: "eve".to_string(),
             reached_target: "db".to_string(),
+            required_privilege: PrivilegeLevel::Root,
         },
         1,
         -1,
@@ -258,8 +264,274 @@ fn patching_intermediate_vulnerability_retracts_downstream_goal() {
         AttackerGoalReached {
             attacker_id: "eve".to_string(),
             reached_target: "db".to_string(),
+            required_privilege: PrivilegeLevel::Root,
         },
         1,
         -1,
     )));
 }
+
+#[test]
+fn firewall_deny_on_another_port_leaves_the_service_reachable() {
+    let changes = run_two_step_scenario(|_, network_input, firewall_input| {
+        network_input.insert(NetworkAccessRule::new("internet", "web", "http", 80));
+        firewall_input
+            .insert(FirewallRuleRecord::create_deny_rule("internet", "web", "http").with_port(80));
+    });
+
+    assert!(!changes
+        .exec_code
+        .iter()
+        .any(|(_record, time, diff)| *time == 1 && *diff < 0));
+    assert!(!changes
+        .goals_reached
+        .iter()
+        .any(|(_record, time, _diff)| *time == 1));
+}
+
+#[test]
+fn vulnerability_is_only_reachable_on_its_own_port() {
+    let changes = run_two_step_scenario(|vulnerability_input, _, _| {
+        // Move the database onto a non-default port; the postgres edge
+        // still targets 5432, so the exploit no longer applies.
+        vulnerability_input.remove(VulnerabilityRecord::new(
+            "db",
+            "CVE-DB",
+            "postgres",
+            PrivilegeLevel::Root,
+        ));
+        vulnerability_input.insert(
+            VulnerabilityRecord::new("db", "CVE-DB", "postgres", PrivilegeLevel::Root)
+                .with_port(6432),
+        );
+    });
+
+    assert!(changes.goals_reached.contains(&(
+        AttackerGoalReached {
+            attacker_id: "eve".to_string(),
+            reached_target: "db".to_string(),
+            required_privilege: PrivilegeLevel::Root,
+        },
+        1,
+        -1,
+    )));
+}
+
+// Ownership and goals as `build_attack_graph` derived them before
+// execCode was arranged once and shared: each rule re-keys execCode on
+// its own.
+fn reference_ownership_and_goals<G>(
+    exec_code: &differential_dataflow::Collection<G, AttackerCodeExecution>,
+    attacker_goals: &differential_dataflow::Collection<G, AttackerTargetGoal>,
+) -> (
+    differential_dataflow::Collection<G, AttackerOwnsMachine>,
+    differential_dataflow::Collection<G, AttackerGoalReached>,
+)
+where
+    G: timely::dataflow::Scope,
+    G::Timestamp: differential_dataflow::lattice::Lattice + Ord,
+{
+    let owns_machine = exec_code
+        .filter(|exec| {
+            exec.obtained_privilege
+                .dominates(&PrivilegeLevel::DEFAULT_OWNERSHIP_THRESHOLD)
+        })
+        .map(|exec| AttackerOwnsMachine {
+            attacker_id: exec.attacker_id,
+            owned_host: exec.compromised_host,
+        })
+        .distinct();
+    let privileges_by_attacker_host = exec_code
+        .map(|exec| {
+            (
+                (exec.attacker_id, exec.compromised_host),
+                exec.obtained_privilege,
+            )
+        })
+        .distinct();
+    let goals_reached = attacker_goals
+        .map(|goal| {
+            (
+                (goal.attacker_id.clone(), goal.target_host_name.clone()),
+                goal,
+            )
+        })
+        .join(&privileges_by_attacker_host)
+        .filter(|(_, (goal, privilege))| privilege.dominates(&goal.required_privilege))
+        .map(|(_, (goal, _))| goal)
+        .distinct()
+        .map(|goal| AttackerGoalReached {
+            attacker_id: goal.attacker_id,
+            reached_target: goal.target_host_name,
+            required_privilege: goal.required_privilege,
+        })
+        .distinct();
+
+    (owns_machine.consolidate(), goals_reached.consolidate())
+}
+
+type ChangeLog<D> = Arc<Mutex<BTreeMap<(D, usize), isize>>>;
+
+fn record_changes<G, D>(collection: &differential_dataflow::Collection<G, D>, log: &ChangeLog<D>)
+where
+    G: timely::dataflow::Scope<Timestamp = usize>,
+    D: differential_dataflow::Data + Ord,
+{
+    let log = Arc::clone(log);
+    collection.inspect(move |(record, time, diff)| {
+        let mut log = log.lock().expect("change log mutex should not be poisoned");
+        *log.entry((record.clone(), *time)).or_insert(0) += diff;
+        log.retain(|_, diff| *diff != 0);
+    });
+}
+
+#[test]
+fn shared_execcode_arrangement_matches_the_per_rule_derivation() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let owns: ChangeLog<AttackerOwnsMachine> = Arc::default();
+    let reference_owns: ChangeLog<AttackerOwnsMachine> = Arc::default();
+    let goals: ChangeLog<AttackerGoalReached> = Arc::default();
+    let reference_goals: ChangeLog<AttackerGoalReached> = Arc::default();
+    let logs = (
+        Arc::clone(&owns),
+        Arc::clone(&reference_owns),
+        Arc::clone(&goals),
+        Arc::clone(&reference_goals),
+    );
+
+    timely::execute_directly(move |worker| {
+        let mut probe = Handle::new();
+        let (owns, reference_owns, goals, reference_goals) = &logs;
+        let (mut vulnerabilities, mut network, mut firewall, mut positions, mut targets) =
+            worker.dataflow::<usize, _, _>(|scope| {
+                let (vulnerability_handle, vulnerability_collection) =
+                    scope.new_collection::<VulnerabilityRecord, isize>();
+                let (network_handle, network_collection) =
+                    scope.new_collection::<NetworkAccessRule, isize>();
+                let (firewall_handle, firewall_collection) =
+                    scope.new_collection::<FirewallRuleRecord, isize>();
+                let (position_handle, position_collection) =
+                    scope.new_collection::<AttackerStartingPosition, isize>();
+                let (goal_handle, goal_collection) =
+                    scope.new_collection::<AttackerTargetGoal, isize>();
+
+                let (exec_code, owns_machine, goals_reached) = build_attack_graph(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                );
+                let (expected_owns, expected_goals) =
+                    reference_ownership_and_goals(&exec_code, &goal_collection);
+
+                record_changes(&owns_machine, owns);
+                record_changes(&expected_owns, reference_owns);
+                record_changes(&goals_reached, goals);
+                record_changes(&expected_goals, reference_goals);
+                exec_code.probe_with(&mut probe);
+
+                (
+                    vulnerability_handle,
+                    network_handle,
+                    firewall_handle,
+                    position_handle,
+                    goal_handle,
+                )
+            });
+
+        let (network_access, vulnerability_records, starts, goal_records) =
+            generate_mesh_network(3, 3);
+        for access in network_access {
+            network.insert(access);
+        }
+        for vulnerability in vulnerability_records {
+            vulnerabilities.insert(vulnerability);
+        }
+        // A User foothold on node_2_0 satisfies the User goal there but
+        // neither owns the host nor reaches its Root goal.
+        vulnerabilities.remove(VulnerabilityRecord::new(
+            "node_2_0",
+            "CVE-MESH-2-0",
+            "ssh",
+            PrivilegeLevel::Root,
+        ));
+        vulnerabilities.insert(VulnerabilityRecord::new(
+            "node_2_0",
+            "CVE-MESH-2-0",
+            "ssh",
+            PrivilegeLevel::User,
+        ));
+        for start in starts {
+            positions.insert(start);
+        }
+        for goal in goal_records {
+            targets.insert(goal);
+        }
+        targets.insert(
+            AttackerTargetGoal::new("attacker", "node_2_0")
+                .with_required_privilege(PrivilegeLevel::User),
+        );
+        targets.insert(AttackerTargetGoal::new("attacker", "node_2_0"));
+
+        let patched =
+            VulnerabilityRecord::new("node_1_1", "CVE-MESH-1-1", "ssh", PrivilegeLevel::Root);
+        let cut = FirewallRuleRecord::create_deny_rule("node_0_0", "node_1_0", "ssh");
+        // Time 1 patches the centre of the mesh, time 2 cuts the only
+        // remaining way to node_2_0 and time 3 undoes both.
+        for time in 0..4 {
+            match time {
+                1 => vulnerabilities.remove(patched.clone()),
+                2 => firewall.insert(cut.clone()),
+                3 => {
+                    vulnerabilities.insert(patched.clone());
+                    firewall.remove(cut.clone());
+                }
+                _ => {}
+            }
+            vulnerabilities.advance_to(time + 1);
+            network.advance_to(time + 1);
+            firewall.advance_to(time + 1);
+            positions.advance_to(time + 1);
+            targets.advance_to(time + 1);
+            vulnerabilities.flush();
+            network.flush();
+            firewall.flush();
+            positions.flush();
+            targets.flush();
+            while probe.less_than(&(time + 1)) {
+                worker.step();
+            }
+        }
+    });
+
+    let owns = owns
+        .lock()
+        .expect("change log mutex should not be poisoned");
+    let goals = goals
+        .lock()
+        .expect("change log mutex should not be poisoned");
+    assert!(
+        owns.keys().any(|(_, time)| *time == 1),
+        "patch changes ownership"
+    );
+    assert!(
+        goals.keys().any(|(_, time)| *time == 2),
+        "cut changes goals"
+    );
+    assert_eq!(
+        *owns,
+        *reference_owns
+            .lock()
+            .expect("change log mutex should not be poisoned")
+    );
+    assert_eq!(
+        *goals,
+        *reference_goals
+            .lock()
+            .expect("change log mutex should not be poisoned")
+    );
+}
@@ -0,0 +1,146 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use differential_dataflow::collection::Collection;
+use dynamic_attack_graphs::rules::build_attack_graph_with_local_vulnerabilities;
+use dynamic_attack_graphs::{
+    chain_base_facts, generate_chain_edit_sequence, run_collection_query, AttackGraphSession,
+    AttackerCodeExecution, AttackerGoalReached, BaseFacts, FactUpdate, QueryInputs, QueryScope,
+};
+
+fn rebuilt_goals_reached(facts: &BaseFacts) -> Vec<AttackerGoalReached> {
+    run_collection_query(facts, |inputs| {
+        let (_, _, goals) = build_attack_graph_with_local_vulnerabilities(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals
+    })
+}
+
+fn code_executions<'a>(
+    inputs: &QueryInputs<'a>,
+) -> Collection<QueryScope<'a>, AttackerCodeExecution> {
+    let (code_executions, _, _) = build_attack_graph_with_local_vulnerabilities(
+        &inputs.vulnerabilities,
+        &inputs.local_vulnerabilities,
+        &inputs.network_access,
+        &inputs.firewall_rules,
+        &inputs.attacker_positions,
+        &inputs.attacker_goals,
+    );
+    code_executions
+}
+
+#[test]
+fn session_goals_follow_every_update() {
+    let mut facts = chain_base_facts(12);
+    let mut session = AttackGraphSession::new(&facts);
+    assert_eq!(
+        session.current_goals_reached(),
+        rebuilt_goals_reached(&facts)
+    );
+    assert!(!session.current_goals_reached().is_empty());
+
+    for edit in generate_chain_edit_sequence(12, 30) {
+        facts.apply_update(edit.clone());
+        session.apply(&[edit]);
+        assert_eq!(
+            session.current_goals_reached(),
+            rebuilt_goals_reached(&facts)
+        );
+    }
+    assert_eq!(session.base_facts(), &facts);
+}
+
+#[test]
+fn removing_an_absent_fact_leaves_the_session_unchanged() {
+    let facts = chain_base_facts(4);
+    let mut session = AttackGraphSession::new(&facts);
+    let patched = facts.vulnerabilities[1].clone();
+
+    session.apply(&[FactUpdate::RemoveVulnerability(patched.clone())]);
+    session.apply(&[FactUpdate::RemoveVulnerability(patched.clone())]);
+    session.apply(&[FactUpdate::InsertVulnerability(patched)]);
+
+    assert_eq!(
+        session.current_goals_reached(),
+        rebuilt_goals_reached(&facts)
+    );
+}
+
+#[test]
+fn session_queries_read_the_shared_arrangements() {
+    let mut facts = chain_base_facts(8);
+    let mut session = AttackGraphSession::new(&facts);
+    let patch = FactUpdate::RemoveVulnerability(facts.vulnerabilities[5].clone());
+    facts.apply_update(patch.clone());
+    session.apply(&[patch]);
+
+    let from_session: Vec<AttackerCodeExecution> = session.run_query(code_executions);
+    assert_eq!(from_session, run_collection_query(&facts, code_executions));
+    // The query's dataflow is dropped, so a second query starts afresh.
+    assert_eq!(session.run_query(code_executions), from_session);
+}
+
+#[test]
+fn apply_and_diff_reports_only_the_goals_the_batch_changed() {
+    let mut facts = chain_base_facts(6);
+    let mut session = AttackGraphSession::new(&facts);
+    let before = session.current_goals_reached();
+    let patch = facts.vulnerabilities[3].clone();
+
+    let removed = session.apply_and_diff(&[FactUpdate::RemoveVulnerability(patch.clone())]);
+    facts.apply_update(FactUpdate::RemoveVulnerability(patch.clone()));
+    let after = rebuilt_goals_reached(&facts);
+    assert!(!removed.is_empty());
+    for (goal, diff) in &removed {
+        assert_eq!(*diff, -1);
+        assert!(before.contains(goal) && !after.contains(goal));
+    }
+    assert_eq!(removed.len(), before.len() - after.len());
+
+    let restored = session.apply_and_diff(&[FactUpdate::InsertVulnerability(patch)]);
+    let negated: Vec<_> = removed.iter().map(|(goal, _)| (goal.clone(), 1)).collect();
+    assert_eq!(restored, negated);
+    assert_eq!(session.current_goals_reached(), before);
+}
+
+#[test]
+fn re_adding_a_present_fact_yields_an_empty_diff() {
+    let facts = chain_base_facts(4);
+    let mut session = AttackGraphSession::new(&facts);
+    let present = facts.vulnerabilities[2].clone();
+
+    assert!(session
+        .apply_and_diff(&[FactUpdate::InsertVulnerability(present)])
+        .is_empty());
+    assert_eq!(
+        session.current_goals_reached(),
+        rebuilt_goals_reached(&facts)
+    );
+}
+
+#[test]
+fn goal_change_callbacks_see_removals_and_restorations() {
+    let facts = chain_base_facts(6);
+    let mut session = AttackGraphSession::new(&facts);
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let recorded = Rc::clone(&seen);
+    session.on_goal_change(move |goal, diff| recorded.borrow_mut().push((goal, diff)));
+    assert!(seen.borrow().is_empty());
+
+    let patch = facts.vulnerabilities[3].clone();
+    let removed = session.apply_and_diff(&[FactUpdate::RemoveVulnerability(patch.clone())]);
+    assert!(!removed.is_empty());
+    assert_eq!(*seen.borrow(), removed);
+
+    seen.borrow_mut().clear();
+    session.apply(&[FactUpdate::InsertVulnerability(patch)]);
+    let restored: Vec<_> = removed.iter().map(|(goal, _)| (goal.clone(), 1)).collect();
+    assert_eq!(*seen.borrow(), restored);
+}
@@ -0,0 +1,543 @@
+use std::collections::BTreeSet;
+use std::sync::{Mutex, OnceLock};
+
+use dynamic_attack_graphs::{
+    attack_graph_diff, chain_base_facts, compare_derived_facts, evaluate_base_facts,
+    generate_chain_edit_sequence, run_scenario_with_log, run_timeline, run_timeline_from_args,
+    AffectedHosts, AttackerCodeExecution, AttackerGoalReached, AttackerStartingPosition,
+    AttackerTargetGoal, BaseFacts, BatchDriver, BatchDriverConfig, DerivedFactKind, DerivedFacts,
+    FactChange, FactUpdate, FirewallRuleRecord, GraphDiff, GraphSnapshot, InputFact,
+    NetworkAccessRule, PrivilegeLevel, TimedScenario, VulnerabilityRecord,
+};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+fn snapshot_updates(facts: BaseFacts) -> Vec<FactUpdate> {
+    facts
+        .vulnerabilities
+        .into_iter()
+        .map(FactUpdate::InsertVulnerability)
+        .chain(
+            facts
+                .network_access
+                .into_iter()
+                .map(FactUpdate::InsertNetworkAccess),
+        )
+        .chain(
+            facts
+                .attacker_positions
+                .into_iter()
+                .map(FactUpdate::InsertAttackerPosition),
+        )
+        .chain(facts.attacker_goals.into_iter().map(FactUpdate::InsertGoal))
+        .collect()
+}
+
+fn run_driver(initial_facts: BaseFacts, edits: Vec<FactUpdate>, batch_size: usize) -> DerivedFacts {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    timely::execute_directly(move |worker| {
+        let mut driver = BatchDriver::new(
+            worker,
+            BatchDriverConfig {
+                batch_size,
+                max_batch_delay: None,
+            },
+        );
+
+        let snapshot = snapshot_updates(initial_facts);
+        driver.apply_updates(worker, &snapshot);
+        driver.flush_now(worker);
+
+        driver.apply_updates(worker, &edits);
+        driver.flush_now(worker);
+        assert_eq!(driver.pending_edits(), 0);
+
+        driver.current_derived_facts()
+    })
+}
+
+#[test]
+fn batched_application_matches_per_edit_application() {
+    let initial_facts = chain_base_facts(30);
+    let edits = generate_chain_edit_sequence(30, 500);
+
+    let mut expected_facts = initial_facts.clone();
+    expected_facts.apply_updates(&edits);
+    let expected = evaluate_base_facts(&expected_facts);
+
+    let per_edit = run_driver(initial_facts.clone(), edits.clone(), 1);
+    let batched = run_driver(initial_facts, edits, 50);
+
+    compare_derived_facts(&per_edit, &batched).unwrap();
+    compare_derived_facts(&batched, &expected).unwrap();
+}
+
+#[test]
+fn batch_driver_ignores_removal_of_absent_fact() {
+    let initial_facts = chain_base_facts(5);
+    let absent = initial_facts.vulnerabilities[2].clone();
+    let edits = vec![
+        FactUpdate::RemoveVulnerability(absent.clone()),
+        FactUpdate::RemoveVulnerability(absent),
+    ];
+
+    let mut expected_facts = initial_facts.clone();
+    expected_facts.apply_updates(&edits);
+
+    let derived = run_driver(initial_facts, edits, 4);
+
+    compare_derived_facts(&derived, &evaluate_base_facts(&expected_facts)).unwrap();
+}
+
+#[test]
+fn batch_driver_runs_at_epoch_millisecond_timestamps() {
+    const SCAN_TIME_MS: u64 = 1_700_000_000_000;
+    const PATCH_TIME_MS: u64 = SCAN_TIME_MS + 86_400_000;
+
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    let initial_facts = chain_base_facts(10);
+    let patch = FactUpdate::RemoveVulnerability(initial_facts.vulnerabilities[5].clone());
+    let mut patched_facts = initial_facts.clone();
+    patched_facts.apply_updates(std::slice::from_ref(&patch));
+
+    let (scanned, patched) = timely::execute_directly(move |worker| {
+        let mut driver = BatchDriver::<u64>::with_timestamp(worker, BatchDriverConfig::default());
+
+        driver.apply_updates(worker, &snapshot_updates(initial_facts));
+        driver.flush_at(worker, SCAN_TIME_MS);
+        assert_eq!(driver.current_time(), SCAN_TIME_MS);
+        let scanned = driver.current_derived_facts();
+
+        driver.apply(worker, patch);
+        driver.flush_at(worker, PATCH_TIME_MS);
+        assert_eq!(driver.current_time(), PATCH_TIME_MS);
+
+        (scanned, driver.current_derived_facts())
+    });
+
+    compare_derived_facts(&scanned, &evaluate_base_facts(&chain_base_facts(10))).unwrap();
+    compare_derived_facts(&patched, &evaluate_base_facts(&patched_facts)).unwrap();
+}
+
+#[test]
+fn toggling_a_deny_rule_reports_only_the_downstream_hosts() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    let cut = FirewallRuleRecord::create_deny_rule("node_4", "node_5", "ssh");
+
+    let (blocked, reopened, unrelated) = timely::execute_directly(move |worker| {
+        let mut driver = BatchDriver::new(worker, BatchDriverConfig::default());
+        driver.apply_updates(worker, &snapshot_updates(chain_base_facts(10)));
+
+        let blocked = driver.toggle_firewall_rule(worker, cut.clone());
+        let reopened = driver.toggle_firewall_rule(worker, cut);
+        // Nothing reaches node_0 through ssh, so this deny changes nothing.
+        let unrelated = driver.toggle_firewall_rule(
+            worker,
+            FirewallRuleRecord::create_deny_rule("node_9", "node_0", "ssh"),
+        );
+
+        (blocked, reopened, unrelated)
+    });
+
+    let downstream: BTreeSet<String> = (5..10).map(|index| format!("node_{index}")).collect();
+    assert_eq!(
+        blocked,
+        AffectedHosts {
+            newly_compromised: BTreeSet::new(),
+            newly_safe: downstream.clone(),
+        }
+    );
+    assert_eq!(
+        reopened,
+        AffectedHosts {
+            newly_compromised: downstream,
+            newly_safe: BTreeSet::new(),
+        }
+    );
+    assert!(unrelated.is_empty());
+}
+
+#[test]
+fn scenario_log_records_derived_changes_per_timestamp() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    let scenario = BaseFacts {
+        vulnerabilities: vec![VulnerabilityRecord::new(
+            "web01",
+            "CVE-WEB",
+            "http",
+            PrivilegeLevel::Root,
+        )],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet", "web01", "http",
+        )],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "web01")],
+        ..BaseFacts::default()
+    };
+    let deny = FirewallRuleRecord::create_deny_rule("internet", "web01", "http");
+
+    let log = run_scenario_with_log(
+        &scenario,
+        &[
+            (3, vec![FactUpdate::InsertFirewallDeny(deny.clone())]),
+            (7, vec![FactUpdate::RemoveFirewallDeny(deny)]),
+        ],
+    );
+
+    let change = |timestamp, kind, fact: &str, diff| FactChange {
+        timestamp,
+        kind,
+        fact_display: fact.to_string(),
+        diff,
+    };
+    let web01_facts = |timestamp, diff| {
+        vec![
+            change(
+                timestamp,
+                DerivedFactKind::ExecCode,
+                "execCode(eve, web01, root)",
+                diff,
+            ),
+            change(
+                timestamp,
+                DerivedFactKind::OwnsMachine,
+                "ownsMachine(eve, web01)",
+                diff,
+            ),
+            change(
+                timestamp,
+                DerivedFactKind::GoalReached,
+                "goalReached(eve, web01)",
+                diff,
+            ),
+        ]
+    };
+    let mut expected = vec![
+        change(
+            0,
+            DerivedFactKind::ExecCode,
+            "execCode(eve, internet, user)",
+            1,
+        ),
+        change(
+            0,
+            DerivedFactKind::ExecCode,
+            "execCode(eve, web01, root)",
+            1,
+        ),
+        change(
+            0,
+            DerivedFactKind::OwnsMachine,
+            "ownsMachine(eve, web01)",
+            1,
+        ),
+        change(
+            0,
+            DerivedFactKind::GoalReached,
+            "goalReached(eve, web01)",
+            1,
+        ),
+    ];
+    expected.extend(web01_facts(3, -1));
+    expected.extend(web01_facts(7, 1));
+
+    assert_eq!(log, expected);
+}
+
+// Phase 1 of the hardcoded demo in `main.rs`.
+fn demo_phase_one_facts() -> BaseFacts {
+    BaseFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web01", "CVE-2024-1234", "http", PrivilegeLevel::User),
+            VulnerabilityRecord::new("web01", "CVE-2024-1234", "https", PrivilegeLevel::User),
+            VulnerabilityRecord::new("db01", "CVE-2024-5678", "mysql", PrivilegeLevel::Root),
+            VulnerabilityRecord::new("db01", "CVE-2024-9999", "ssh", PrivilegeLevel::User),
+            VulnerabilityRecord::new("admin01", "CVE-2024-8888", "smb", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "http"),
+            NetworkAccessRule::new_default_port("internet", "web01", "https"),
+            NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+            NetworkAccessRule::new_default_port("web01", "db01", "ssh"),
+            NetworkAccessRule::new_default_port("db01", "admin01", "ssh"),
+            NetworkAccessRule::new_default_port("db01", "admin01", "smb"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "admin01")],
+        ..BaseFacts::default()
+    }
+}
+
+#[test]
+fn restored_snapshot_reaches_the_same_goals() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    let snapshot = timely::execute_directly(|worker| {
+        let mut driver = BatchDriver::new(worker, BatchDriverConfig::default());
+        driver.apply_updates(worker, &demo_phase_one_facts().insert_updates());
+        driver.snapshot(worker)
+    });
+    assert_eq!(snapshot.timestamp, 1);
+    assert_eq!(snapshot.goals_reached.len(), 1);
+
+    let json = serde_json::to_string(&snapshot).expect("snapshot should serialize");
+    let reloaded: GraphSnapshot = serde_json::from_str(&json).expect("snapshot should deserialize");
+    assert_eq!(reloaded, snapshot);
+
+    let (restored, after_deny) = timely::execute_directly(move |worker| {
+        let mut driver = BatchDriver::restore(worker, BatchDriverConfig::default(), &reloaded);
+        let restored = driver.snapshot(worker);
+
+        // Branch off: phase 2 of the demo blocks http, https stays open.
+        driver.apply(
+            worker,
+            FactUpdate::InsertFirewallDeny(FirewallRuleRecord::create_deny_rule(
+                "internet", "web01", "http",
+            )),
+        );
+        (restored, driver.snapshot(worker))
+    });
+
+    assert_eq!(restored, snapshot);
+    assert_eq!(after_deny.timestamp, 2);
+    assert_eq!(after_deny.goals_reached, snapshot.goals_reached);
+}
+
+#[test]
+fn expiring_vulnerability_is_exploitable_only_inside_its_window() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    let log = TimedScenario::new()
+        .fact(
+            InputFact::Hacl(NetworkAccessRule::new_default_port(
+                "internet", "web01", "http",
+            )),
+            0,
+        )
+        .fact(
+            InputFact::AttackerLocated(AttackerStartingPosition::new(
+                "eve",
+                "internet",
+                PrivilegeLevel::User,
+            )),
+            0,
+        )
+        .fact(
+            InputFact::AttackGoal(AttackerTargetGoal::new("eve", "web01")),
+            0,
+        )
+        .expiring_fact(
+            InputFact::VulExists(VulnerabilityRecord::new(
+                "web01",
+                "CVE-WEB",
+                "http",
+                PrivilegeLevel::Root,
+            )),
+            2,
+            4,
+        )
+        .run_with_log();
+
+    let web01_changes: Vec<_> = log
+        .iter()
+        .filter(|change| change.fact_display.contains("web01"))
+        .map(|change| (change.timestamp, change.kind, change.diff))
+        .collect();
+    assert_eq!(
+        web01_changes,
+        vec![
+            (2, DerivedFactKind::ExecCode, 1),
+            (2, DerivedFactKind::OwnsMachine, 1),
+            (2, DerivedFactKind::GoalReached, 1),
+            (4, DerivedFactKind::ExecCode, -1),
+            (4, DerivedFactKind::OwnsMachine, -1),
+            (4, DerivedFactKind::GoalReached, -1),
+        ]
+    );
+    // The attacker's foothold is never retracted.
+    assert_eq!(
+        log.iter()
+            .filter(|change| !change.fact_display.contains("web01"))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn patching_node_1_removes_every_downstream_code_execution() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    let patch = FactUpdate::RemoveVulnerability(VulnerabilityRecord::new(
+        "node_1",
+        "CVE-CHAIN-1",
+        "ssh",
+        PrivilegeLevel::Root,
+    ));
+    let diff = attack_graph_diff(&chain_base_facts(10), &[patch]);
+
+    assert_eq!(
+        diff,
+        GraphDiff {
+            added_code_executions: Vec::new(),
+            removed_code_executions: (1..10)
+                .map(|index| AttackerCodeExecution {
+                    attacker_id: "attacker".to_string(),
+                    compromised_host: format!("node_{index}"),
+                    obtained_privilege: PrivilegeLevel::Root,
+                })
+                .collect(),
+            added_goals_reached: Vec::new(),
+            removed_goals_reached: vec![AttackerGoalReached {
+                attacker_id: "attacker".to_string(),
+                reached_target: "node_9".to_string(),
+                required_privilege: PrivilegeLevel::Root,
+            }],
+        }
+    );
+    // Patching nothing changes nothing.
+    assert!(attack_graph_diff(&chain_base_facts(10), &[]).is_empty());
+}
+
+// The four phases of the built-in `attack-graph` demo: load the
+// network, deny HTTP into web01, patch CVE-2024-1234 on web01, then
+// disclose a new HTTPS vulnerability there.
+fn four_phase_demo() -> (BaseFacts, Vec<Vec<FactUpdate>>) {
+    let web_vulnerability = |service: &str| {
+        VulnerabilityRecord::new("web01", "CVE-2024-1234", service, PrivilegeLevel::User)
+    };
+    let facts = BaseFacts {
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "http"),
+            NetworkAccessRule::new_default_port("internet", "web01", "https"),
+            NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+            NetworkAccessRule::new_default_port("web01", "db01", "ssh"),
+            NetworkAccessRule::new_default_port("db01", "admin01", "ssh"),
+            NetworkAccessRule::new_default_port("db01", "admin01", "smb"),
+        ],
+        vulnerabilities: vec![
+            web_vulnerability("http"),
+            web_vulnerability("https"),
+            VulnerabilityRecord::new("db01", "CVE-2024-5678", "mysql", PrivilegeLevel::Root),
+            VulnerabilityRecord::new("db01", "CVE-2024-9999", "ssh", PrivilegeLevel::User),
+            VulnerabilityRecord::new("admin01", "CVE-2024-8888", "smb", PrivilegeLevel::Root),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "admin01")],
+        ..BaseFacts::default()
+    };
+    let steps = vec![
+        vec![FactUpdate::InsertFirewallDeny(
+            FirewallRuleRecord::create_deny_rule("internet", "web01", "http"),
+        )],
+        vec![
+            FactUpdate::RemoveVulnerability(web_vulnerability("http")),
+            FactUpdate::RemoveVulnerability(web_vulnerability("https")),
+        ],
+        vec![FactUpdate::InsertVulnerability(VulnerabilityRecord::new(
+            "web01",
+            "CVE-2024-0DAY",
+            "https",
+            PrivilegeLevel::User,
+        ))],
+    ];
+    (facts, steps)
+}
+
+#[test]
+fn timeline_replays_the_four_phase_demo() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let (facts, steps) = four_phase_demo();
+
+    let timeline = run_timeline(&facts, &steps);
+
+    let admin_goal = AttackerGoalReached {
+        attacker_id: "eve".to_string(),
+        reached_target: "admin01".to_string(),
+        required_privilege: PrivilegeLevel::Root,
+    };
+    let goals_by_phase: Vec<_> = timeline
+        .iter()
+        .map(|step| step.goals_reached.clone())
+        .collect();
+    assert_eq!(
+        timeline
+            .iter()
+            .map(|step| step.timestamp)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    // Phase 1 reaches the goal, the deny in phase 2 leaves HTTPS open,
+    // the patch in phase 3 cuts it and the new CVE in phase 4 restores it.
+    assert_eq!(
+        goals_by_phase,
+        vec![
+            vec![admin_goal.clone()],
+            vec![admin_goal.clone()],
+            Vec::new(),
+            vec![admin_goal],
+        ]
+    );
+    let internet_only = vec![AttackerCodeExecution {
+        attacker_id: "eve".to_string(),
+        compromised_host: "internet".to_string(),
+        obtained_privilege: PrivilegeLevel::User,
+    }];
+    assert_eq!(timeline[2].code_executions, internet_only);
+    assert_eq!(timeline[3].code_executions, timeline[0].code_executions);
+}
+
+#[test]
+fn timeline_on_several_workers_matches_the_single_worker_run() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let (facts, steps) = four_phase_demo();
+
+    let args = ["-w", "3"].map(String::from);
+    let timeline = run_timeline_from_args(args.into_iter(), &facts, &steps)
+        .expect("timeline workers should not fail");
+
+    assert_eq!(timeline, run_timeline(&facts, &steps));
+    assert!(!timeline[0].machines_owned.is_empty());
+}
@@ -0,0 +1,120 @@
+use std::sync::{Mutex, OnceLock};
+
+use dynamic_attack_graphs::{
+    AttackerGoalReached, AttackerStartingPosition, AttackerTargetGoal, BaseFacts,
+    ConcurrentSourcesDriver, FirewallRuleRecord, NetworkAccessRule, PrivilegeLevel, ScannerTime,
+    VulnerabilityRecord,
+};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+// internet -> web -> db, with web -> db denied and db not yet known to
+// be vulnerable.  Reaching db takes both a scan that finds the MySQL
+// bug and a firewall change that lifts the deny.
+fn facts() -> BaseFacts {
+    BaseFacts {
+        vulnerabilities: vec![VulnerabilityRecord::new(
+            "web",
+            "CVE-WEB",
+            "http",
+            PrivilegeLevel::User,
+        )],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web", "http"),
+            NetworkAccessRule::new_default_port("web", "db", "mysql"),
+        ],
+        firewall_rules: vec![web_db_deny()],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "db")],
+        ..BaseFacts::default()
+    }
+}
+
+fn web_db_deny() -> FirewallRuleRecord {
+    FirewallRuleRecord::create_deny_rule("web", "db", "mysql")
+}
+
+fn db_vulnerability() -> VulnerabilityRecord {
+    VulnerabilityRecord::new("db", "CVE-DB", "mysql", PrivilegeLevel::Root)
+}
+
+fn db_reached() -> Vec<AttackerGoalReached> {
+    vec![AttackerGoalReached {
+        attacker_id: "eve".to_string(),
+        reached_target: "db".to_string(),
+        required_privilege: PrivilegeLevel::Root,
+    }]
+}
+
+// Goals at (0, 0), (1, 0), (0, 1) and (1, 1) after advancing the two
+// clocks in the given order.
+fn goal_grid(firewall_first: bool) -> [Vec<AttackerGoalReached>; 4] {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    timely::execute_directly(move |worker| {
+        let mut driver = ConcurrentSourcesDriver::new(worker, &facts());
+        driver.insert_vulnerability(db_vulnerability());
+        driver.remove_firewall_rule(web_db_deny());
+        if firewall_first {
+            driver.advance_firewall_clock(worker);
+            driver.advance_vulnerability_clock(worker);
+        } else {
+            driver.advance_vulnerability_clock(worker);
+            driver.advance_firewall_clock(worker);
+        }
+        assert_eq!(driver.settled_time(), ScannerTime::new(1, 1));
+
+        [
+            driver.goals_reached_at(0, 0),
+            driver.goals_reached_at(1, 0),
+            driver.goals_reached_at(0, 1),
+            driver.goals_reached_at(1, 1),
+        ]
+    })
+}
+
+#[test]
+fn either_source_alone_leaves_the_goal_unreached() {
+    let [initial, scanned, opened, both] = goal_grid(false);
+
+    assert!(initial.is_empty());
+    assert!(scanned.is_empty());
+    assert!(opened.is_empty());
+    assert_eq!(both, db_reached());
+}
+
+#[test]
+fn the_order_the_clocks_advance_in_does_not_matter() {
+    assert_eq!(goal_grid(true), goal_grid(false));
+}
+
+#[test]
+fn earlier_times_stay_readable_as_one_clock_runs_ahead() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+
+    timely::execute_directly(|worker| {
+        let mut driver = ConcurrentSourcesDriver::new(worker, &facts());
+        driver.insert_vulnerability(db_vulnerability());
+        driver.advance_vulnerability_clock(worker);
+        driver.remove_firewall_rule(web_db_deny());
+        driver.advance_firewall_clock(worker);
+        // The firewall is locked down again before the next scan.
+        driver.insert_firewall_rule(web_db_deny());
+        driver.advance_firewall_clock(worker);
+
+        assert_eq!(driver.settled_time(), ScannerTime::new(1, 2));
+        assert_eq!(driver.goals_reached_at(1, 1), db_reached());
+        assert!(driver.goals_reached_at(1, 2).is_empty());
+        assert!(driver.goals_reached_at(0, 1).is_empty());
+    });
+}
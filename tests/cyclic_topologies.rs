@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph, exploited_cycles, generate_clique_network, AttackerCodeExecution,
+    AttackerStartingPosition, AttackerTargetGoal, BaseFacts, FirewallRuleRecord, NetworkAccessRule,
+    PrivilegeLevel, VulnerabilityRecord,
+};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+// A regression that breaks fixpoint termination must fail the test
+// instead of hanging the suite.
+const TERMINATION_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Default)]
+struct StaticFacts {
+    vulnerabilities: Vec<VulnerabilityRecord>,
+    network_access: Vec<NetworkAccessRule>,
+    attacker_positions: Vec<AttackerStartingPosition>,
+    attacker_goals: Vec<AttackerTargetGoal>,
+}
+
+impl StaticFacts {
+    fn from_generated(
+        generated: (
+            Vec<NetworkAccessRule>,
+            Vec<VulnerabilityRecord>,
+            Vec<AttackerStartingPosition>,
+            Vec<AttackerTargetGoal>,
+        ),
+    ) -> Self {
+        let (network_access, vulnerabilities, attacker_positions, attacker_goals) = generated;
+        Self {
+            vulnerabilities,
+            network_access,
+            attacker_positions,
+            attacker_goals,
+        }
+    }
+
+    // Directed ring over `hosts`, each host Root-vulnerable on ssh.
+    fn add_cycle(&mut self, hosts: &[String]) {
+        for (index, host) in hosts.iter().enumerate() {
+            let next_host = &hosts[(index + 1) % hosts.len()];
+            self.network_access
+                .push(NetworkAccessRule::new_default_port(host, next_host, "ssh"));
+            let vulnerability = VulnerabilityRecord::new(
+                host,
+                &format!("CVE-RING-{host}"),
+                "ssh",
+                PrivilegeLevel::Root,
+            );
+            if !self.vulnerabilities.contains(&vulnerability) {
+                self.vulnerabilities.push(vulnerability);
+            }
+        }
+    }
+}
+
+fn compromised_hosts_within_timeout(facts: StaticFacts) -> BTreeSet<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver may have given up already; nothing to report then.
+        let _ = sender.send(run_standard_rules(facts));
+    });
+
+    receiver
+        .recv_timeout(TERMINATION_TIMEOUT)
+        .expect("attack graph fixpoint did not terminate within the timeout")
+        .into_iter()
+        .map(|execution| execution.compromised_host)
+        .collect()
+}
+
+fn run_standard_rules(facts: StaticFacts) -> BTreeSet<AttackerCodeExecution> {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let captured_in_dataflow = Arc::clone(&captured);
+
+    timely::execute_directly(move |worker| {
+        let (
+            mut vulnerability_input,
+            mut network_input,
+            firewall_input,
+            mut position_input,
+            mut goal_input,
+        ) = worker.dataflow::<usize, _, _>(|scope| {
+            let (vulnerability_handle, vulnerability_collection) =
+                scope.new_collection::<VulnerabilityRecord, isize>();
+            let (network_handle, network_collection) =
+                scope.new_collection::<NetworkAccessRule, isize>();
+            let (firewall_handle, firewall_collection) =
+                scope.new_collection::<FirewallRuleRecord, isize>();
+            let (position_handle, position_collection) =
+                scope.new_collection::<AttackerStartingPosition, isize>();
+            let (goal_handle, goal_collection) =
+                scope.new_collection::<AttackerTargetGoal, isize>();
+
+            let (exec_code, _owns_machine, _goals_reached) = build_attack_graph(
+                &vulnerability_collection,
+                &network_collection,
+                &firewall_collection,
+                &position_collection,
+                &goal_collection,
+            );
+
+            let captured_exec = Arc::clone(&captured_in_dataflow);
+            exec_code.inspect(move |(record, _time, diff)| {
+                captured_exec
+                    .lock()
+                    .expect("captured exec mutex should not be poisoned")
+                    .push((record.clone(), *diff));
+            });
+
+            (
+                vulnerability_handle,
+                network_handle,
+                firewall_handle,
+                position_handle,
+                goal_handle,
+            )
+        });
+
+        for record in facts.vulnerabilities {
+            vulnerability_input.insert(record);
+        }
+        for record in facts.network_access {
+            network_input.insert(record);
+        }
+        for record in facts.attacker_positions {
+            position_input.insert(record);
+        }
+        for record in facts.attacker_goals {
+            goal_input.insert(record);
+        }
+
+        vulnerability_input.close();
+        network_input.close();
+        firewall_input.close();
+        position_input.close();
+        goal_input.close();
+
+        while worker.step() {}
+    });
+
+    let changes = captured
+        .lock()
+        .expect("captured exec mutex should not be poisoned")
+        .clone();
+    let mut counts = BTreeMap::new();
+    for (record, diff) in changes {
+        *counts.entry(record).or_insert(0isize) += diff;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(record, _)| record)
+        .collect()
+}
+
+fn node_names(prefix: &str, count: usize) -> Vec<String> {
+    (0..count)
+        .map(|index| format!("{prefix}_{index}"))
+        .collect()
+}
+
+#[test]
+fn clique_fixpoint_terminates_with_every_host_compromised() {
+    for number_of_nodes in [3, 50, 300] {
+        let facts = StaticFacts::from_generated(generate_clique_network(number_of_nodes));
+
+        let compromised = compromised_hosts_within_timeout(facts);
+
+        assert_eq!(
+            compromised,
+            node_names("node", number_of_nodes).into_iter().collect(),
+            "clique of {number_of_nodes} hosts"
+        );
+    }
+}
+
+#[test]
+fn long_cycle_fixpoint_terminates_with_every_host_compromised() {
+    let ring = node_names("ring", 200);
+    let mut facts = StaticFacts::default();
+    facts.add_cycle(&ring);
+    facts.attacker_positions.push(AttackerStartingPosition::new(
+        "attacker",
+        "ring_0",
+        PrivilegeLevel::Root,
+    ));
+
+    let compromised = compromised_hosts_within_timeout(facts);
+
+    assert_eq!(compromised, ring.into_iter().collect());
+}
+
+#[test]
+fn figure_eight_fixpoint_terminates_with_both_loops_compromised() {
+    // Two rings that share the "hub" host; the attacker starts on the
+    // left ring and must cross the hub to reach the right one.
+    let mut left_ring = node_names("left", 20);
+    left_ring.push("hub".to_string());
+    let mut right_ring = vec!["hub".to_string()];
+    right_ring.extend(node_names("right", 20));
+
+    let mut facts = StaticFacts::default();
+    facts.add_cycle(&left_ring);
+    facts.add_cycle(&right_ring);
+    facts.attacker_positions.push(AttackerStartingPosition::new(
+        "attacker",
+        "left_0",
+        PrivilegeLevel::Root,
+    ));
+
+    let compromised = compromised_hosts_within_timeout(facts);
+
+    let expected: BTreeSet<String> = left_ring.into_iter().chain(right_ring).collect();
+    assert_eq!(expected.len(), 41);
+    assert_eq!(compromised, expected);
+}
+
+#[test]
+fn exploited_cycles_report_the_loop_the_attacker_moves_around() {
+    // web01 -> app01 -> db01 -> web01 is a loop the attacker enters from
+    // the internet; db01 also leads on to a dead-end backup host.  The
+    // mutual pair lab_a <-> lab_b is never reached.
+    let ring = ["web01", "app01", "db01"].map(String::from);
+    let mut facts = StaticFacts::default();
+    facts.add_cycle(&ring);
+    facts.add_cycle(&["lab_a", "lab_b"].map(String::from));
+    facts
+        .network_access
+        .push(NetworkAccessRule::new_default_port(
+            "internet", "web01", "ssh",
+        ));
+    facts
+        .network_access
+        .push(NetworkAccessRule::new_default_port(
+            "db01", "backup01", "ssh",
+        ));
+    facts.vulnerabilities.push(VulnerabilityRecord::new(
+        "backup01",
+        "CVE-BACKUP",
+        "ssh",
+        PrivilegeLevel::Root,
+    ));
+    facts.attacker_positions.push(AttackerStartingPosition::new(
+        "attacker",
+        "internet",
+        PrivilegeLevel::User,
+    ));
+
+    let base_facts = BaseFacts {
+        vulnerabilities: facts.vulnerabilities,
+        network_access: facts.network_access,
+        attacker_positions: facts.attacker_positions,
+        ..BaseFacts::default()
+    };
+
+    assert_eq!(
+        exploited_cycles(&base_facts),
+        vec![vec![
+            "app01".to_string(),
+            "db01".to_string(),
+            "web01".to_string()
+        ]]
+    );
+}
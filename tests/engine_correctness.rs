@@ -11,8 +11,8 @@ fn chain_facts() -> BaseFacts {
             VulnerabilityRecord::new("db", "CVE-DB", "postgres", PrivilegeLevel::Root),
         ],
         network_access: vec![
-            NetworkAccessRule::new("internet", "web", "https"),
-            NetworkAccessRule::new("web", "db", "postgres"),
+            NetworkAccessRule::new_default_port("internet", "web", "https"),
+            NetworkAccessRule::new_default_port("web", "db", "postgres"),
         ],
         attacker_positions: vec![AttackerStartingPosition::new(
             "eve",
@@ -87,7 +87,9 @@ fn differential_with_local_privilege_escalation_matches_full_recompute() {
             "CVE-LOCAL",
             PrivilegeLevel::Root,
         )],
-        network_access: vec![NetworkAccessRule::new("internet", "web", "https")],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet", "web", "https",
+        )],
         attacker_positions: vec![AttackerStartingPosition::new(
             "eve",
             "internet",
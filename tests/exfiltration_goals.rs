@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph, exfiltration_goals_reached, AttackerOwnsMachine, AttackerStartingPosition,
+    AttackerTargetGoal, ExfilGoal, FirewallRuleRecord, NetworkAccessRule, PrivilegeLevel,
+    VulnerabilityRecord,
+};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+#[derive(Debug, Clone, Default)]
+struct StaticFacts {
+    vulnerabilities: Vec<VulnerabilityRecord>,
+    network_access: Vec<NetworkAccessRule>,
+    attacker_positions: Vec<AttackerStartingPosition>,
+    exfil_goals: Vec<ExfilGoal>,
+}
+
+#[derive(Debug, Default)]
+struct CapturedChanges {
+    owns_machine: Vec<(AttackerOwnsMachine, usize, isize)>,
+    exfil_goals: Vec<(ExfilGoal, usize, isize)>,
+}
+
+// Loads `facts` at time 0 and inserts `later_denies` at time 1.
+fn run_exfiltration(facts: StaticFacts, later_denies: Vec<FirewallRuleRecord>) -> CapturedChanges {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let captured = Arc::new(Mutex::new(CapturedChanges::default()));
+    let captured_in_dataflow = Arc::clone(&captured);
+
+    timely::execute_directly(move |worker| {
+        let (
+            mut vulnerability_input,
+            mut network_input,
+            mut firewall_input,
+            mut position_input,
+            goal_input,
+            mut exfil_input,
+        ) = worker.dataflow::<usize, _, _>(|scope| {
+            let (vulnerability_handle, vulnerability_collection) =
+                scope.new_collection::<VulnerabilityRecord, isize>();
+            let (network_handle, network_collection) =
+                scope.new_collection::<NetworkAccessRule, isize>();
+            let (firewall_handle, firewall_collection) =
+                scope.new_collection::<FirewallRuleRecord, isize>();
+            let (position_handle, position_collection) =
+                scope.new_collection::<AttackerStartingPosition, isize>();
+            let (goal_handle, goal_collection) =
+                scope.new_collection::<AttackerTargetGoal, isize>();
+            let (exfil_handle, exfil_collection) = scope.new_collection::<ExfilGoal, isize>();
+
+            let (_exec_code, owns_machine, _goals_reached) = build_attack_graph(
+                &vulnerability_collection,
+                &network_collection,
+                &firewall_collection,
+                &position_collection,
+                &goal_collection,
+            );
+            let exfil_reached = exfiltration_goals_reached(
+                &owns_machine,
+                &network_collection,
+                &firewall_collection,
+                &exfil_collection,
+            );
+
+            let captured_owns = Arc::clone(&captured_in_dataflow);
+            owns_machine.inspect(move |(record, time, diff)| {
+                captured_owns
+                    .lock()
+                    .expect("captured changes mutex should not be poisoned")
+                    .owns_machine
+                    .push((record.clone(), *time, *diff));
+            });
+            let captured_exfil = Arc::clone(&captured_in_dataflow);
+            exfil_reached.inspect(move |(record, time, diff)| {
+                captured_exfil
+                    .lock()
+                    .expect("captured changes mutex should not be poisoned")
+                    .exfil_goals
+                    .push((record.clone(), *time, *diff));
+            });
+
+            (
+                vulnerability_handle,
+                network_handle,
+                firewall_handle,
+                position_handle,
+                goal_handle,
+                exfil_handle,
+            )
+        });
+
+        for record in facts.vulnerabilities {
+            vulnerability_input.insert(record);
+        }
+        for record in facts.network_access {
+            network_input.insert(record);
+        }
+        for record in facts.attacker_positions {
+            position_input.insert(record);
+        }
+        for record in facts.exfil_goals {
+            exfil_input.insert(record);
+        }
+
+        vulnerability_input.advance_to(1);
+        network_input.advance_to(1);
+        firewall_input.advance_to(1);
+        position_input.advance_to(1);
+        exfil_input.advance_to(1);
+
+        for record in later_denies {
+            firewall_input.insert(record);
+        }
+
+        vulnerability_input.close();
+        network_input.close();
+        firewall_input.close();
+        position_input.close();
+        goal_input.close();
+        exfil_input.close();
+
+        while worker.step() {}
+    });
+
+    Arc::try_unwrap(captured)
+        .expect("dataflow should have released the captured changes")
+        .into_inner()
+        .expect("captured changes mutex should not be poisoned")
+}
+
+fn state_at<T: Ord + Clone>(changes: &[(T, usize, isize)], time: usize) -> BTreeSet<T> {
+    let mut counts = BTreeMap::new();
+    for (record, change_time, diff) in changes {
+        if *change_time <= time {
+            *counts.entry(record.clone()).or_insert(0isize) += diff;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(record, _)| record)
+        .collect()
+}
+
+// internet -> web -> db, with the database's only way out relayed
+// through a proxy to the attacker's drop server.
+fn database_facts(with_return_path: bool) -> StaticFacts {
+    let mut network_access = vec![
+        NetworkAccessRule::new_default_port("internet", "web", "http"),
+        NetworkAccessRule::new_default_port("web", "db", "mysql"),
+    ];
+    if with_return_path {
+        network_access.push(NetworkAccessRule::new_default_port("db", "proxy", "http"));
+        network_access.push(NetworkAccessRule::new_default_port(
+            "proxy", "drop", "https",
+        ));
+    }
+
+    StaticFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web", "CVE-WEB", "http", PrivilegeLevel::Root),
+            VulnerabilityRecord::new("db", "CVE-DB", "mysql", PrivilegeLevel::Root),
+        ],
+        network_access,
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        exfil_goals: vec![ExfilGoal::new("attacker", "db", "drop")],
+    }
+}
+
+fn database_owned(changes: &CapturedChanges, time: usize) -> bool {
+    state_at(&changes.owns_machine, time).contains(&AttackerOwnsMachine {
+        attacker_id: "attacker".to_string(),
+        owned_host: "db".to_string(),
+    })
+}
+
+#[test]
+fn owned_database_without_return_path_cannot_exfiltrate() {
+    let changes = run_exfiltration(database_facts(false), Vec::new());
+
+    assert!(database_owned(&changes, 0));
+    assert!(state_at(&changes.exfil_goals, 0).is_empty());
+}
+
+#[test]
+fn return_path_to_exit_satisfies_exfil_goal() {
+    let changes = run_exfiltration(database_facts(true), Vec::new());
+
+    assert_eq!(
+        state_at(&changes.exfil_goals, 0),
+        BTreeSet::from([ExfilGoal::new("attacker", "db", "drop")])
+    );
+}
+
+#[test]
+fn blocking_return_path_retracts_exfil_goal_but_not_ownership() {
+    let changes = run_exfiltration(
+        database_facts(true),
+        vec![FirewallRuleRecord::create_deny_rule("db", "proxy", "http")],
+    );
+
+    assert_eq!(state_at(&changes.exfil_goals, 0).len(), 1);
+    assert!(state_at(&changes.exfil_goals, 1).is_empty());
+    assert!(database_owned(&changes, 1));
+}
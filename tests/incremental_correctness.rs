@@ -3,9 +3,10 @@ use std::sync::{Arc, Mutex, OnceLock};
 
 use differential_dataflow::input::{Input, InputSession};
 use dynamic_attack_graphs::{
-    build_attack_graph, evaluate_attack_graph_naive, AttackerCodeExecution, AttackerGoalReached,
-    AttackerOwnsMachine, AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord,
-    NetworkAccessRule, PrivilegeLevel, VulnerabilityRecord,
+    build_attack_graph, evaluate_attack_graph_naive, generate_chain_network, generate_mesh_network,
+    generate_star_network, AttackerCodeExecution, AttackerGoalReached, AttackerOwnsMachine,
+    AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord, NetworkAccessRule,
+    PrivilegeLevel, VulnerabilityRecord,
 };
 use timely::dataflow::operators::probe::Handle;
 
@@ -23,6 +24,7 @@ enum FactUpdate {
     InsertVulnerability(VulnerabilityRecord),
     RemoveVulnerability(VulnerabilityRecord),
     InsertNetworkAccess(NetworkAccessRule),
+    RemoveNetworkAccess(NetworkAccessRule),
     InsertFirewallRule(FirewallRuleRecord),
 }
 
@@ -196,6 +198,9 @@ fn apply_updates_to_facts(facts: &mut BaseFacts, updates: &[FactUpdate]) {
             FactUpdate::InsertNetworkAccess(access) => {
                 facts.network_access.push(access.clone());
             }
+            FactUpdate::RemoveNetworkAccess(access) => {
+                facts.network_access.retain(|item| item != access);
+            }
             FactUpdate::InsertFirewallRule(rule) => {
                 facts.firewall_rules.push(rule.clone());
             }
@@ -245,6 +250,9 @@ fn apply_updates(
             FactUpdate::InsertNetworkAccess(access) => {
                 network_input.insert(access.clone());
             }
+            FactUpdate::RemoveNetworkAccess(access) => {
+                network_input.remove(access.clone());
+            }
             FactUpdate::InsertFirewallRule(rule) => {
                 firewall_input.insert(rule.clone());
             }
@@ -303,7 +311,9 @@ fn chain_base_facts() -> BaseFacts {
             VulnerabilityRecord::new("web", "CVE-WEB", "https", PrivilegeLevel::User),
             VulnerabilityRecord::new("db", "CVE-DB", "postgres", PrivilegeLevel::Root),
         ],
-        network_access: vec![NetworkAccessRule::new("internet", "web", "https")],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet", "web", "https",
+        )],
         firewall_rules: Vec::new(),
         attacker_positions: vec![AttackerStartingPosition::new(
             "attacker",
@@ -314,6 +324,24 @@ fn chain_base_facts() -> BaseFacts {
     }
 }
 
+fn generated_base_facts(
+    generated: (
+        Vec<NetworkAccessRule>,
+        Vec<VulnerabilityRecord>,
+        Vec<AttackerStartingPosition>,
+        Vec<AttackerTargetGoal>,
+    ),
+) -> BaseFacts {
+    let (network_access, vulnerabilities, attacker_positions, attacker_goals) = generated;
+    BaseFacts {
+        vulnerabilities,
+        network_access,
+        firewall_rules: Vec::new(),
+        attacker_positions,
+        attacker_goals,
+    }
+}
+
 fn exec_on(host: &str, privilege: PrivilegeLevel) -> AttackerCodeExecution {
     AttackerCodeExecution {
         attacker_id: "attacker".to_string(),
@@ -333,6 +361,7 @@ fn reached(host: &str) -> AttackerGoalReached {
     AttackerGoalReached {
         attacker_id: "attacker".to_string(),
         reached_target: host.to_string(),
+        required_privilege: PrivilegeLevel::Root,
     }
 }
 
@@ -354,6 +383,43 @@ fn assert_incremental_update_matches_recompute(
     (initial_output, incremental_after_update)
 }
 
+// Retraction end-state invariant: removing every vulnerability in one
+// timestamp must leave only the attackers' initial footholds in
+// execCode and no goal reached.  Anything else lingering means some
+// derived fact was not retracted.
+fn assert_empty_without_vulns(initial_facts: BaseFacts) {
+    let updates: Vec<_> = initial_facts
+        .vulnerabilities
+        .iter()
+        .cloned()
+        .map(FactUpdate::RemoveVulnerability)
+        .collect();
+    let footholds: BTreeMap<_, _> = initial_facts
+        .attacker_positions
+        .iter()
+        .map(|position| {
+            (
+                AttackerCodeExecution {
+                    attacker_id: position.attacker_id.clone(),
+                    compromised_host: position.starting_host.clone(),
+                    obtained_privilege: position.initial_privilege.clone(),
+                },
+                1,
+            )
+        })
+        .collect();
+
+    let (initial_output, output_without_vulns) =
+        collect_incremental_outputs(initial_facts, &updates);
+
+    assert!(
+        initial_output.exec_code.len() > footholds.len(),
+        "scenario should derive compromises before the vulnerabilities are removed"
+    );
+    assert_eq!(output_without_vulns.exec_code, footholds);
+    assert!(output_without_vulns.goals_reached.is_empty());
+}
+
 fn assert_dataflow_matches_naive(facts: BaseFacts) {
     let dataflow_output = collect_recomputed_outputs(facts.clone());
     let naive_output = evaluate_attack_graph_naive(
@@ -395,9 +461,9 @@ fn simple_chain_update_matches_recompute() {
     let facts = chain_base_facts();
     let (_initial_output, after_update) = assert_incremental_update_matches_recompute(
         facts,
-        vec![FactUpdate::InsertNetworkAccess(NetworkAccessRule::new(
-            "web", "db", "postgres",
-        ))],
+        vec![FactUpdate::InsertNetworkAccess(
+            NetworkAccessRule::new_default_port("web", "db", "postgres"),
+        )],
     );
 
     assert!(after_update
@@ -412,7 +478,7 @@ fn firewall_deny_update_matches_recompute() {
     let mut facts = chain_base_facts();
     facts
         .network_access
-        .push(NetworkAccessRule::new("web", "db", "postgres"));
+        .push(NetworkAccessRule::new_default_port("web", "db", "postgres"));
 
     let (initial_output, after_update) = assert_incremental_update_matches_recompute(
         facts,
@@ -433,7 +499,7 @@ fn vulnerability_patch_update_matches_recompute() {
     let mut facts = chain_base_facts();
     facts
         .network_access
-        .push(NetworkAccessRule::new("web", "db", "postgres"));
+        .push(NetworkAccessRule::new_default_port("web", "db", "postgres"));
 
     let patched_vulnerability =
         VulnerabilityRecord::new("web", "CVE-WEB", "https", PrivilegeLevel::User);
@@ -459,8 +525,8 @@ fn new_vulnerability_discovered_update_matches_recompute() {
             PrivilegeLevel::User,
         )],
         network_access: vec![
-            NetworkAccessRule::new("internet", "web", "https"),
-            NetworkAccessRule::new("web", "db", "postgres"),
+            NetworkAccessRule::new_default_port("internet", "web", "https"),
+            NetworkAccessRule::new_default_port("web", "db", "postgres"),
         ],
         firewall_rules: Vec::new(),
         attacker_positions: vec![AttackerStartingPosition::new(
@@ -497,10 +563,10 @@ fn alternate_path_patch_still_reaches_goal_and_matches_recompute() {
             VulnerabilityRecord::new("db", "CVE-DB", "postgres", PrivilegeLevel::Root),
         ],
         network_access: vec![
-            NetworkAccessRule::new("internet", "web_a", "https"),
-            NetworkAccessRule::new("internet", "web_b", "https"),
-            NetworkAccessRule::new("web_a", "db", "postgres"),
-            NetworkAccessRule::new("web_b", "db", "postgres"),
+            NetworkAccessRule::new_default_port("internet", "web_a", "https"),
+            NetworkAccessRule::new_default_port("internet", "web_b", "https"),
+            NetworkAccessRule::new_default_port("web_a", "db", "postgres"),
+            NetworkAccessRule::new_default_port("web_b", "db", "postgres"),
         ],
         firewall_rules: Vec::new(),
         attacker_positions: vec![AttackerStartingPosition::new(
@@ -538,7 +604,7 @@ fn naive_oracle_matches_dataflow_on_simple_chain() {
     let mut facts = chain_base_facts();
     facts
         .network_access
-        .push(NetworkAccessRule::new("web", "db", "postgres"));
+        .push(NetworkAccessRule::new_default_port("web", "db", "postgres"));
 
     assert_dataflow_matches_naive(facts);
 }
@@ -548,7 +614,7 @@ fn naive_oracle_matches_dataflow_with_firewall_deny() {
     let mut facts = chain_base_facts();
     facts
         .network_access
-        .push(NetworkAccessRule::new("web", "db", "postgres"));
+        .push(NetworkAccessRule::new_default_port("web", "db", "postgres"));
     facts
         .firewall_rules
         .push(FirewallRuleRecord::create_deny_rule(
@@ -567,10 +633,10 @@ fn naive_oracle_matches_dataflow_with_alternate_path_patch() {
             VulnerabilityRecord::new("db", "CVE-DB", "postgres", PrivilegeLevel::Root),
         ],
         network_access: vec![
-            NetworkAccessRule::new("internet", "web_a", "https"),
-            NetworkAccessRule::new("internet", "web_b", "https"),
-            NetworkAccessRule::new("web_a", "db", "postgres"),
-            NetworkAccessRule::new("web_b", "db", "postgres"),
+            NetworkAccessRule::new_default_port("internet", "web_a", "https"),
+            NetworkAccessRule::new_default_port("internet", "web_b", "https"),
+            NetworkAccessRule::new_default_port("web_a", "db", "postgres"),
+            NetworkAccessRule::new_default_port("web_b", "db", "postgres"),
         ],
         firewall_rules: Vec::new(),
         attacker_positions: vec![AttackerStartingPosition::new(
@@ -593,3 +659,57 @@ fn naive_oracle_matches_dataflow_with_alternate_path_patch() {
 
     assert_dataflow_matches_naive(facts);
 }
+
+// A link going down: once the chain's first edge is removed, every
+// execCode fact downstream of it has lost its only support and must
+// retract through the fixpoint rather than linger behind `distinct`.
+#[test]
+fn removing_the_first_chain_edge_retracts_every_downstream_execution() {
+    let facts = generated_base_facts(generate_chain_network(20));
+    let first_edge = facts
+        .network_access
+        .iter()
+        .find(|access| access.source_host == "node_0")
+        .cloned()
+        .expect("chain should start with an edge out of node_0");
+
+    let (initial_output, after_update) = assert_incremental_update_matches_recompute(
+        facts,
+        vec![FactUpdate::RemoveNetworkAccess(first_edge)],
+    );
+
+    for index in 1..20 {
+        let host = format!("node_{index}");
+        assert!(initial_output
+            .exec_code
+            .contains_key(&exec_on(&host, PrivilegeLevel::Root)));
+        assert!(!after_update
+            .exec_code
+            .keys()
+            .any(|execution| execution.compromised_host == host));
+    }
+    assert_eq!(
+        after_update.exec_code,
+        BTreeMap::from([(exec_on("node_0", PrivilegeLevel::Root), 1)])
+    );
+    assert_eq!(
+        after_update.owns_machine,
+        BTreeMap::from([(owns("node_0"), 1)])
+    );
+    assert!(after_update.goals_reached.is_empty());
+}
+
+#[test]
+fn removing_all_vulnerabilities_empties_chain_graph() {
+    assert_empty_without_vulns(generated_base_facts(generate_chain_network(20)));
+}
+
+#[test]
+fn removing_all_vulnerabilities_empties_mesh_graph() {
+    assert_empty_without_vulns(generated_base_facts(generate_mesh_network(5, 5)));
+}
+
+#[test]
+fn removing_all_vulnerabilities_empties_star_graph() {
+    assert_empty_without_vulns(generated_base_facts(generate_star_network(15)));
+}
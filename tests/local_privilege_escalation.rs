@@ -198,7 +198,9 @@ fn local_escalation_facts() -> LocalEscalationFacts {
             "cve_2024_local",
             PrivilegeLevel::Root,
         )],
-        network_access: vec![NetworkAccessRule::new("internet", "web01", "https")],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet", "web01", "https",
+        )],
         firewall_rules: Vec::new(),
         attacker_positions: vec![AttackerStartingPosition::new(
             "eve",
@@ -228,6 +230,7 @@ fn reached(attacker_id: &str, host: &str) -> AttackerGoalReached {
     AttackerGoalReached {
         attacker_id: attacker_id.to_string(),
         reached_target: host.to_string(),
+        required_privilege: PrivilegeLevel::Root,
     }
 }
 
@@ -0,0 +1,103 @@
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use dynamic_attack_graphs::{
+    attack_graph_diff, chain_base_facts, print_benchmark_table, BatchingBenchmarkResults,
+};
+use log::{Level, Log, Metadata, Record};
+
+// Set when the test binary re-runs itself to capture its stdout.
+const CHILD_ENVIRONMENT_VARIABLE: &str = "DYNAMIC_ATTACK_GRAPHS_LOGGING_CHILD";
+
+static CAPTURED_RECORDS: OnceLock<Mutex<Vec<(Level, String)>>> = OnceLock::new();
+
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        records()
+            .lock()
+            .expect("captured log records mutex should not be poisoned")
+            .push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+fn records() -> &'static Mutex<Vec<(Level, String)>> {
+    CAPTURED_RECORDS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn install_capturing_logger() {
+    static INSTALLED: OnceLock<()> = OnceLock::new();
+    INSTALLED.get_or_init(|| {
+        log::set_boxed_logger(Box::new(CapturingLogger)).expect("no other logger is installed");
+        log::set_max_level(log::LevelFilter::Trace);
+    });
+}
+
+fn batching_results() -> BatchingBenchmarkResults {
+    BatchingBenchmarkResults {
+        number_of_nodes: 10,
+        number_of_edits: 8,
+        batch_size: 4,
+        per_edit_time: Duration::from_millis(8),
+        batched_time: Duration::from_millis(2),
+        speedup_factor: 4.0,
+    }
+}
+
+// Everything the library reports, without a logger configured.
+fn produce_library_output() {
+    batching_results().print_summary();
+    print_benchmark_table(&[]);
+    attack_graph_diff(&chain_base_facts(3), &[]);
+}
+
+#[test]
+fn library_diagnostics_are_log_records() {
+    install_capturing_logger();
+    produce_library_output();
+
+    let records = records()
+        .lock()
+        .expect("captured log records mutex should not be poisoned");
+    assert!(records.contains(&(Level::Info, "Batch size: 4".to_string())));
+    assert!(records
+        .iter()
+        .any(|(level, message)| *level == Level::Info && message.starts_with("| Nodes |")));
+    assert!(records
+        .iter()
+        .any(|(level, message)| *level == Level::Debug
+            && message.starts_with("batch driver settled")));
+}
+
+#[test]
+fn library_prints_nothing_without_a_logger() {
+    if std::env::var_os(CHILD_ENVIRONMENT_VARIABLE).is_some() {
+        produce_library_output();
+        return;
+    }
+
+    let output = Command::new(std::env::current_exe().expect("test binary path is known"))
+        .args([
+            "--exact",
+            "library_prints_nothing_without_a_logger",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env(CHILD_ENVIRONMENT_VARIABLE, "1")
+        .output()
+        .expect("test binary should re-run");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "child run failed: {stdout}");
+    assert!(stdout.contains("1 passed"), "child did not run: {stdout}");
+    assert!(!stdout.contains("Batch size"), "stray output: {stdout}");
+    assert!(!stdout.contains("| Nodes |"), "stray output: {stdout}");
+}
@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph, chain_base_facts, AttackerGoalReached, AttackerStartingPosition,
+    AttackerTargetGoal, BaseFacts, FirewallRuleRecord, NetworkAccessRule, PrivilegeLevel,
+    VulnerabilityRecord,
+};
+use timely::dataflow::operators::probe::Handle;
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+const CHAIN_LENGTH: usize = 30;
+
+// A chain with a goal on every host, a second attacker half way down
+// and a firewall deny near the end, so the goal set exercises joins
+// and the firewall antijoin across workers.
+fn chain_scenario() -> BaseFacts {
+    let mut facts = chain_base_facts(CHAIN_LENGTH);
+    facts.attacker_positions.push(AttackerStartingPosition::new(
+        "insider",
+        "node_15",
+        PrivilegeLevel::User,
+    ));
+    for node_index in 0..CHAIN_LENGTH {
+        let host = format!("node_{node_index}");
+        facts
+            .attacker_goals
+            .push(AttackerTargetGoal::new("attacker", &host));
+        facts
+            .attacker_goals
+            .push(AttackerTargetGoal::new("insider", &host));
+    }
+    facts
+        .firewall_rules
+        .push(FirewallRuleRecord::create_deny_rule(
+            "node_25", "node_26", "ssh",
+        ));
+    facts
+}
+
+fn patched_vulnerability() -> VulnerabilityRecord {
+    VulnerabilityRecord::new("node_10", "CVE-CHAIN-10", "ssh", PrivilegeLevel::Root)
+}
+
+// Goals reached after the initial load (time 0) and after patching
+// node_10 (time 1), gathered from every worker.
+fn goals_reached_with_workers(
+    workers: usize,
+) -> (BTreeSet<AttackerGoalReached>, BTreeSet<AttackerGoalReached>) {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let facts = chain_scenario();
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let captured_after_run = Arc::clone(&captured);
+
+    timely::execute(timely::Config::process(workers), move |worker| {
+        let mut probe = Handle::new();
+        let captured = Arc::clone(&captured);
+
+        let (
+            mut vulnerability_input,
+            mut network_input,
+            mut firewall_input,
+            mut attacker_position_input,
+            mut attacker_goal_input,
+        ) = worker.dataflow::<usize, _, _>(|scope| {
+            let (vulnerability_handle, vulnerability_collection) =
+                scope.new_collection::<VulnerabilityRecord, isize>();
+            let (network_handle, network_collection) =
+                scope.new_collection::<NetworkAccessRule, isize>();
+            let (firewall_handle, firewall_collection) =
+                scope.new_collection::<FirewallRuleRecord, isize>();
+            let (position_handle, position_collection) =
+                scope.new_collection::<AttackerStartingPosition, isize>();
+            let (goal_handle, goal_collection) =
+                scope.new_collection::<AttackerTargetGoal, isize>();
+
+            let (_, _, goals_reached) = build_attack_graph(
+                &vulnerability_collection,
+                &network_collection,
+                &firewall_collection,
+                &position_collection,
+                &goal_collection,
+            );
+
+            goals_reached
+                .inspect(move |(goal, time, diff)| {
+                    captured
+                        .lock()
+                        .expect("captured goal changes mutex should not be poisoned")
+                        .push((goal.clone(), *time, *diff));
+                })
+                .probe_with(&mut probe);
+
+            (
+                vulnerability_handle,
+                network_handle,
+                firewall_handle,
+                position_handle,
+                goal_handle,
+            )
+        });
+
+        // Only worker 0 feeds the inputs; differential partitions them.
+        if worker.index() == 0 {
+            for fact in facts.vulnerabilities.iter().cloned() {
+                vulnerability_input.insert(fact);
+            }
+            for fact in facts.network_access.iter().cloned() {
+                network_input.insert(fact);
+            }
+            for fact in facts.firewall_rules.iter().cloned() {
+                firewall_input.insert(fact);
+            }
+            for fact in facts.attacker_positions.iter().cloned() {
+                attacker_position_input.insert(fact);
+            }
+            for fact in facts.attacker_goals.iter().cloned() {
+                attacker_goal_input.insert(fact);
+            }
+        }
+        for time in 1..=2 {
+            if time == 2 && worker.index() == 0 {
+                vulnerability_input.remove(patched_vulnerability());
+            }
+            vulnerability_input.advance_to(time);
+            network_input.advance_to(time);
+            firewall_input.advance_to(time);
+            attacker_position_input.advance_to(time);
+            attacker_goal_input.advance_to(time);
+            vulnerability_input.flush();
+            network_input.flush();
+            firewall_input.flush();
+            attacker_position_input.flush();
+            attacker_goal_input.flush();
+            while probe.less_than(&time) {
+                worker.step();
+            }
+        }
+    })
+    .expect("timely computation should start")
+    .join()
+    .into_iter()
+    .collect::<Result<Vec<()>, String>>()
+    .expect("no worker should panic");
+
+    let changes = captured_after_run
+        .lock()
+        .expect("captured goal changes mutex should not be poisoned")
+        .clone();
+    (goals_at(&changes, 0), goals_at(&changes, 1))
+}
+
+fn goals_at(
+    changes: &[(AttackerGoalReached, usize, isize)],
+    time: usize,
+) -> BTreeSet<AttackerGoalReached> {
+    let mut counts = BTreeMap::new();
+    for (goal, change_time, diff) in changes {
+        if *change_time <= time {
+            *counts.entry(goal.clone()).or_insert(0) += diff;
+        }
+    }
+    for (goal, count) in &counts {
+        assert!(*count == 0 || *count == 1, "{goal} has count {count}");
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(goal, _)| goal)
+        .collect()
+}
+
+#[test]
+fn four_workers_reach_the_same_goals_as_one() {
+    let (initial_single, patched_single) = goals_reached_with_workers(1);
+    let (initial_multi, patched_multi) = goals_reached_with_workers(4);
+
+    // The attacker reaches node_0..=node_25 and the insider, whose
+    // User foothold on node_15 does not meet a Root goal,
+    // node_16..=node_25.  The patch cuts the attacker off at node_10.
+    assert_eq!(initial_single.len(), 26 + 10);
+    assert_eq!(patched_single.len(), 10 + 10);
+    assert_eq!(initial_multi, initial_single);
+    assert_eq!(patched_multi, patched_single);
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph, build_attack_graph_with_host_info, AttackerCodeExecution,
+    AttackerStartingPosition, AttackerTargetGoal, FirewallRuleRecord, HostInfo, NetworkAccessRule,
+    PrivilegeLevel, VulnerabilityRecord,
+};
+use timely::dataflow::operators::probe::Handle;
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+// One Windows and one Linux host, both reachable from the internet and
+// both carrying the same Windows-only CVE, plus an OS-agnostic CVE on
+// a second Linux host.
+fn vulnerabilities() -> Vec<VulnerabilityRecord> {
+    vec![
+        VulnerabilityRecord::new("win01", "CVE-2017-0144", "smb", PrivilegeLevel::Root)
+            .with_os("windows"),
+        VulnerabilityRecord::new("lin01", "CVE-2017-0144", "smb", PrivilegeLevel::Root)
+            .with_os("windows"),
+        VulnerabilityRecord::new("lin02", "CVE-2014-6271", "http", PrivilegeLevel::User),
+    ]
+}
+
+fn host_info() -> Vec<HostInfo> {
+    vec![
+        HostInfo::new("win01", "windows"),
+        HostInfo::new("lin01", "linux"),
+        HostInfo::new("lin02", "linux"),
+    ]
+}
+
+fn network_access() -> Vec<NetworkAccessRule> {
+    vec![
+        NetworkAccessRule::new_default_port("internet", "win01", "smb"),
+        NetworkAccessRule::new_default_port("internet", "lin01", "smb"),
+        NetworkAccessRule::new_default_port("internet", "lin02", "http"),
+    ]
+}
+
+fn attacker_position() -> AttackerStartingPosition {
+    AttackerStartingPosition::new("eve", "internet", PrivilegeLevel::User)
+}
+
+// Hosts eve executes code on after loading the facts (time 0) and
+// after applying `host_info_changes` (time 1).  Without host info the
+// OS-agnostic `build_attack_graph` is used.
+fn compromised_hosts(
+    with_host_info: bool,
+    host_info_changes: Vec<(HostInfo, isize)>,
+) -> (BTreeSet<String>, BTreeSet<String>) {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let captured_in_dataflow = Arc::clone(&captured);
+
+    timely::execute_directly(move |worker| {
+        let mut probe = Handle::new();
+
+        let (mut vulnerability_input, mut host_info_input, mut network_input, mut position_input) =
+            worker.dataflow::<usize, _, _>(|scope| {
+                let (vulnerability_handle, vulnerability_collection) =
+                    scope.new_collection::<VulnerabilityRecord, isize>();
+                let (host_info_handle, host_info_collection) =
+                    scope.new_collection::<HostInfo, isize>();
+                let (network_handle, network_collection) =
+                    scope.new_collection::<NetworkAccessRule, isize>();
+                let (_, firewall_collection) = scope.new_collection::<FirewallRuleRecord, isize>();
+                let (position_handle, position_collection) =
+                    scope.new_collection::<AttackerStartingPosition, isize>();
+                let (_, goal_collection) = scope.new_collection::<AttackerTargetGoal, isize>();
+
+                let (exec_code, _, _) = if with_host_info {
+                    build_attack_graph_with_host_info(
+                        &vulnerability_collection,
+                        &host_info_collection,
+                        &network_collection,
+                        &firewall_collection,
+                        &position_collection,
+                        &goal_collection,
+                    )
+                } else {
+                    build_attack_graph(
+                        &vulnerability_collection,
+                        &network_collection,
+                        &firewall_collection,
+                        &position_collection,
+                        &goal_collection,
+                    )
+                };
+
+                exec_code
+                    .inspect(move |(record, time, diff)| {
+                        captured_in_dataflow
+                            .lock()
+                            .expect("captured execCode mutex should not be poisoned")
+                            .push((record.clone(), *time, *diff));
+                    })
+                    .probe_with(&mut probe);
+
+                (
+                    vulnerability_handle,
+                    host_info_handle,
+                    network_handle,
+                    position_handle,
+                )
+            });
+
+        for vulnerability in vulnerabilities() {
+            vulnerability_input.insert(vulnerability);
+        }
+        for info in host_info() {
+            host_info_input.insert(info);
+        }
+        for access in network_access() {
+            network_input.insert(access);
+        }
+        position_input.insert(attacker_position());
+
+        for time in 1..=2 {
+            if time == 2 {
+                for (info, diff) in host_info_changes.iter().cloned() {
+                    host_info_input.update(info, diff);
+                }
+            }
+            vulnerability_input.advance_to(time);
+            host_info_input.advance_to(time);
+            network_input.advance_to(time);
+            position_input.advance_to(time);
+            vulnerability_input.flush();
+            host_info_input.flush();
+            network_input.flush();
+            position_input.flush();
+            while probe.less_than(&time) {
+                worker.step();
+            }
+        }
+    });
+
+    let changes = captured
+        .lock()
+        .expect("captured execCode mutex should not be poisoned")
+        .clone();
+    (hosts_at(&changes, 0), hosts_at(&changes, 1))
+}
+
+fn hosts_at(changes: &[(AttackerCodeExecution, usize, isize)], time: usize) -> BTreeSet<String> {
+    let mut counts = BTreeMap::new();
+    for (record, change_time, diff) in changes {
+        if *change_time <= time {
+            *counts.entry(record.compromised_host.clone()).or_insert(0) += diff;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(host, _)| host)
+        .collect()
+}
+
+fn hosts(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn windows_cve_only_exploits_the_windows_host() {
+    let (initial, _) = compromised_hosts(true, Vec::new());
+
+    assert_eq!(initial, hosts(&["internet", "lin02", "win01"]));
+}
+
+#[test]
+fn without_host_info_every_vulnerability_applies() {
+    let (initial, _) = compromised_hosts(false, Vec::new());
+
+    assert_eq!(initial, hosts(&["internet", "lin01", "lin02", "win01"]));
+}
+
+#[test]
+fn reinstalling_a_host_updates_which_cves_apply() {
+    let (initial, reinstalled) = compromised_hosts(
+        true,
+        vec![
+            (HostInfo::new("lin01", "linux"), -1),
+            (HostInfo::new("lin01", "windows"), 1),
+            (HostInfo::new("win01", "windows"), -1),
+        ],
+    );
+
+    assert_eq!(initial, hosts(&["internet", "lin02", "win01"]));
+    assert_eq!(reinstalled, hosts(&["internet", "lin01", "lin02"]));
+}
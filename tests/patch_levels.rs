@@ -0,0 +1,73 @@
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph, build_attack_graph_with_patches, chain_base_facts, run_collection_query,
+    AttackerGoalReached, FactUpdate, PatchApplied,
+};
+
+const CHAIN_LENGTH: usize = 6;
+
+fn goals_with_patches(patches: Vec<PatchApplied>) -> Vec<AttackerGoalReached> {
+    run_collection_query(&chain_base_facts(CHAIN_LENGTH), move |inputs| {
+        let (_, patches_applied) = inputs
+            .vulnerabilities
+            .scope()
+            .new_collection_from(patches.clone());
+        let (_, _, goals) = build_attack_graph_with_patches(
+            &inputs.vulnerabilities,
+            &patches_applied,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals
+    })
+}
+
+fn goals_without_patches(updates: &[FactUpdate]) -> Vec<AttackerGoalReached> {
+    let mut facts = chain_base_facts(CHAIN_LENGTH);
+    facts.apply_updates(updates);
+    run_collection_query(&facts, |inputs| {
+        let (_, _, goals) = build_attack_graph(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals
+    })
+}
+
+#[test]
+fn a_patch_breaks_the_chain_like_removing_the_vulnerability() {
+    let facts = chain_base_facts(CHAIN_LENGTH);
+    let node_1_vulnerability = facts
+        .vulnerabilities
+        .iter()
+        .find(|vulnerability| vulnerability.host_name == "node_1")
+        .expect("the chain has a vulnerability on node_1")
+        .clone();
+    let patch = PatchApplied::new("node_1", &node_1_vulnerability.vulnerability_id);
+
+    let unpatched = goals_with_patches(Vec::new());
+    let patched = goals_with_patches(vec![patch]);
+    let removed = goals_without_patches(&[FactUpdate::RemoveVulnerability(node_1_vulnerability)]);
+
+    assert_eq!(unpatched, goals_without_patches(&[]));
+    assert!(!unpatched.is_empty());
+    assert_eq!(patched, removed);
+    assert!(patched.len() < unpatched.len());
+}
+
+#[test]
+fn a_patch_for_another_host_or_cve_changes_nothing() {
+    let unpatched = goals_with_patches(Vec::new());
+
+    let misdirected = goals_with_patches(vec![
+        PatchApplied::new("node_2", "CVE-CHAIN-1"),
+        PatchApplied::new("node_1", "CVE-CHAIN-2"),
+    ]);
+
+    assert_eq!(misdirected, unpatched);
+}
@@ -0,0 +1,488 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph, build_attack_graph_with_options,
+    build_attack_graph_with_ownership_threshold, build_attack_graph_with_privilege_lattice,
+    evaluate_base_facts, run_collection_query, unreachable_goals, AttackGraphOptions,
+    AttackerCodeExecution, AttackerGoalReached, AttackerGoalUnreachable, AttackerOwnsMachine,
+    AttackerStartingPosition, AttackerTargetGoal, BaseFacts, FirewallRuleRecord, NetworkAccessRule,
+    PrivilegeLattice, PrivilegeLevel, VulnerabilityRecord,
+};
+use serde::{Deserialize, Serialize};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// A five-tier lattice of the kind found in Windows domains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+enum DomainPrivilege {
+    Guest,
+    Service,
+    User,
+    Administrator,
+    DomainAdmin,
+}
+
+impl PrivilegeLattice for DomainPrivilege {
+    fn dominates(&self, other: &Self) -> bool {
+        self >= other
+    }
+}
+
+impl From<PrivilegeLevel> for DomainPrivilege {
+    fn from(privilege: PrivilegeLevel) -> Self {
+        match privilege {
+            PrivilegeLevel::None => DomainPrivilege::Guest,
+            PrivilegeLevel::User => DomainPrivilege::User,
+            PrivilegeLevel::Root => DomainPrivilege::DomainAdmin,
+        }
+    }
+}
+
+type DomainExecution = AttackerCodeExecution<DomainPrivilege>;
+
+// A chain internet -> kiosk -> print-spooler -> workstation ->
+// file-server -> dc01 where each hop yields the next tier up.
+fn domain_chain() -> (
+    Vec<VulnerabilityRecord<DomainPrivilege>>,
+    Vec<NetworkAccessRule>,
+) {
+    let hops = [
+        ("internet", "kiosk", DomainPrivilege::Guest),
+        ("kiosk", "print-spooler", DomainPrivilege::Service),
+        ("print-spooler", "workstation", DomainPrivilege::User),
+        ("workstation", "file-server", DomainPrivilege::Administrator),
+        ("file-server", "dc01", DomainPrivilege::DomainAdmin),
+    ];
+    let vulnerabilities = hops
+        .iter()
+        .map(|(_, host, privilege)| {
+            VulnerabilityRecord::new(host, &format!("CVE-{host}"), "smb", *privilege)
+        })
+        .collect();
+    let network_access = hops
+        .iter()
+        .map(|(source, host, _)| NetworkAccessRule::new_default_port(source, host, "smb"))
+        .collect();
+    (vulnerabilities, network_access)
+}
+
+// Runs `build_attack_graph_with_privilege_lattice` over the domain
+// chain and returns its settled execCode, ownsMachine and goalReached.
+fn domain_attack_graph(
+    goals: Vec<AttackerTargetGoal>,
+    threshold: DomainPrivilege,
+) -> (
+    BTreeSet<DomainExecution>,
+    BTreeSet<AttackerOwnsMachine>,
+    BTreeSet<AttackerGoalReached>,
+) {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let captured = Arc::new(Mutex::new((Vec::new(), Vec::new(), Vec::new())));
+    let captured_in_dataflow = Arc::clone(&captured);
+
+    timely::execute_directly(move |worker| {
+        let (vulnerabilities, network_access) = domain_chain();
+        let captured = Arc::clone(&captured_in_dataflow);
+        let (mut vulnerability_input, mut network_input, mut position_input, mut goal_input) =
+            worker.dataflow::<usize, _, _>(|scope| {
+                let (vulnerability_handle, vulnerability_collection) =
+                    scope.new_collection::<VulnerabilityRecord<DomainPrivilege>, isize>();
+                let (network_handle, network_collection) =
+                    scope.new_collection::<NetworkAccessRule, isize>();
+                let (_, firewall_collection) = scope.new_collection::<FirewallRuleRecord, isize>();
+                let (position_handle, position_collection) =
+                    scope.new_collection::<AttackerStartingPosition<DomainPrivilege>, isize>();
+                let (goal_handle, goal_collection) =
+                    scope.new_collection::<AttackerTargetGoal, isize>();
+
+                let (exec_code, owns_machine, goals_reached) =
+                    build_attack_graph_with_privilege_lattice(
+                        &vulnerability_collection,
+                        &network_collection,
+                        &firewall_collection,
+                        &position_collection,
+                        &goal_collection,
+                        threshold,
+                    );
+                let exec_captured = Arc::clone(&captured);
+                exec_code.inspect(move |(record, _time, diff)| {
+                    exec_captured
+                        .lock()
+                        .expect("captured outputs mutex should not be poisoned")
+                        .0
+                        .push((record.clone(), *diff));
+                });
+                let owns_captured = Arc::clone(&captured);
+                owns_machine.inspect(move |(record, _time, diff)| {
+                    owns_captured
+                        .lock()
+                        .expect("captured outputs mutex should not be poisoned")
+                        .1
+                        .push((record.clone(), *diff));
+                });
+                goals_reached.inspect(move |(record, _time, diff)| {
+                    captured
+                        .lock()
+                        .expect("captured outputs mutex should not be poisoned")
+                        .2
+                        .push((record.clone(), *diff));
+                });
+
+                (
+                    vulnerability_handle,
+                    network_handle,
+                    position_handle,
+                    goal_handle,
+                )
+            });
+
+        for vulnerability in vulnerabilities {
+            vulnerability_input.insert(vulnerability);
+        }
+        for access in network_access {
+            network_input.insert(access);
+        }
+        position_input.insert(AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            DomainPrivilege::Guest,
+        ));
+        for goal in goals {
+            goal_input.insert(goal);
+        }
+        vulnerability_input.close();
+        network_input.close();
+        position_input.close();
+        goal_input.close();
+
+        while worker.step() {}
+    });
+
+    fn settled<D: Ord + Clone>(updates: &[(D, isize)]) -> BTreeSet<D> {
+        let mut counts = BTreeMap::new();
+        for (record, diff) in updates {
+            *counts.entry(record.clone()).or_insert(0) += diff;
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(record, _)| record)
+            .collect()
+    }
+    let captured = captured
+        .lock()
+        .expect("captured outputs mutex should not be poisoned");
+    (
+        settled(&captured.0),
+        settled(&captured.1),
+        settled(&captured.2),
+    )
+}
+
+fn owned(host: &str) -> AttackerOwnsMachine {
+    AttackerOwnsMachine {
+        attacker_id: "eve".to_string(),
+        owned_host: host.to_string(),
+    }
+}
+
+#[test]
+fn five_level_lattice_owns_hosts_at_the_configured_threshold() {
+    let (exec_code, owned_at_administrator, _) =
+        domain_attack_graph(Vec::new(), DomainPrivilege::Administrator);
+
+    assert!(exec_code.contains(&AttackerCodeExecution {
+        attacker_id: "eve".to_string(),
+        compromised_host: "print-spooler".to_string(),
+        obtained_privilege: DomainPrivilege::Service,
+    }));
+    assert_eq!(exec_code.len(), 6);
+    assert_eq!(
+        owned_at_administrator,
+        BTreeSet::from([owned("dc01"), owned("file-server")])
+    );
+
+    let (_, owned_at_service, _) = domain_attack_graph(Vec::new(), DomainPrivilege::Service);
+    assert_eq!(
+        owned_at_service,
+        BTreeSet::from([
+            owned("dc01"),
+            owned("file-server"),
+            owned("print-spooler"),
+            owned("workstation"),
+        ])
+    );
+}
+
+#[test]
+fn five_level_lattice_lifts_goal_privileges_into_the_lattice() {
+    // A Root goal means DomainAdmin; a User goal means User.
+    let (_, _, goals_reached) = domain_attack_graph(
+        vec![
+            AttackerTargetGoal::new("eve", "dc01"),
+            AttackerTargetGoal::new("eve", "file-server"),
+            AttackerTargetGoal::new("eve", "workstation")
+                .with_required_privilege(PrivilegeLevel::User),
+        ],
+        DomainPrivilege::Administrator,
+    );
+
+    let reached = |host: &str, required_privilege: PrivilegeLevel| AttackerGoalReached {
+        attacker_id: "eve".to_string(),
+        reached_target: host.to_string(),
+        required_privilege,
+    };
+    assert_eq!(
+        goals_reached,
+        BTreeSet::from([
+            reached("dc01", PrivilegeLevel::Root),
+            reached("workstation", PrivilegeLevel::User),
+        ])
+    );
+}
+
+#[test]
+fn user_threshold_owns_hosts_exploited_for_user_privileges() {
+    let facts = BaseFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web01", "CVE-WEB", "https", PrivilegeLevel::User),
+            VulnerabilityRecord::new("db01", "CVE-DB", "mysql", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "https"),
+            NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "web01")],
+        ..BaseFacts::default()
+    };
+
+    let owned_with = |threshold: PrivilegeLevel| {
+        run_collection_query(&facts, move |inputs| {
+            let (_, owns_machine, _) = build_attack_graph_with_ownership_threshold(
+                &inputs.vulnerabilities,
+                &inputs.network_access,
+                &inputs.firewall_rules,
+                &inputs.attacker_positions,
+                &inputs.attacker_goals,
+                threshold.clone(),
+            );
+            owns_machine
+        })
+    };
+
+    assert_eq!(owned_with(PrivilegeLevel::Root), vec![owned("db01")]);
+    // The attacker's own starting host counts once User suffices.
+    assert_eq!(
+        owned_with(PrivilegeLevel::User),
+        vec![owned("db01"), owned("internet"), owned("web01")]
+    );
+}
+
+#[test]
+fn user_goal_is_reached_by_a_user_foothold() {
+    // web01 only ever yields User; db01 yields Root.
+    let mut facts = BaseFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web01", "CVE-WEB", "https", PrivilegeLevel::User),
+            VulnerabilityRecord::new("db01", "CVE-DB", "mysql", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "https"),
+            NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![
+            AttackerTargetGoal::new("eve", "web01").with_required_privilege(PrivilegeLevel::User),
+            AttackerTargetGoal::new("eve", "web01"),
+            AttackerTargetGoal::new("eve", "db01"),
+        ],
+        ..BaseFacts::default()
+    };
+
+    let goals_reached = |facts: &BaseFacts| {
+        run_collection_query(facts, |inputs| {
+            let (_, _, goals_reached) = build_attack_graph(
+                &inputs.vulnerabilities,
+                &inputs.network_access,
+                &inputs.firewall_rules,
+                &inputs.attacker_positions,
+                &inputs.attacker_goals,
+            );
+            goals_reached
+        })
+    };
+    let reached = |host: &str, required_privilege: PrivilegeLevel| AttackerGoalReached {
+        attacker_id: "eve".to_string(),
+        reached_target: host.to_string(),
+        required_privilege,
+    };
+
+    // Each goal is reported with its own privilege, so the User goal
+    // on web01 is not mistaken for the Root one.
+    assert_eq!(
+        goals_reached(&facts),
+        vec![
+            reached("db01", PrivilegeLevel::Root),
+            reached("web01", PrivilegeLevel::User),
+        ]
+    );
+    // The Root goal on web01 stays open even though the User one holds.
+    assert_eq!(
+        unreachable_goals(&facts),
+        vec![AttackerGoalUnreachable {
+            attacker_id: "eve".to_string(),
+            target_host_name: "web01".to_string(),
+            required_privilege: PrivilegeLevel::Root,
+        }]
+    );
+
+    // Without the User goal, a User foothold on web01 reaches nothing there.
+    facts.attacker_goals.remove(0);
+    assert_eq!(
+        goals_reached(&facts),
+        vec![reached("db01", PrivilegeLevel::Root)]
+    );
+}
+
+#[test]
+fn user_and_root_goals_on_one_host_are_reached_separately() {
+    // db01 yields Root, so both of its goals hold; web01 only User.
+    let facts = BaseFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web01", "CVE-WEB", "https", PrivilegeLevel::User),
+            VulnerabilityRecord::new("db01", "CVE-DB", "mysql", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "https"),
+            NetworkAccessRule::new_default_port("web01", "db01", "mysql"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![
+            AttackerTargetGoal::new("eve", "db01").with_required_privilege(PrivilegeLevel::User),
+            AttackerTargetGoal::new("eve", "db01"),
+            AttackerTargetGoal::new("eve", "web01").with_required_privilege(PrivilegeLevel::None),
+            AttackerTargetGoal::new("eve", "web01"),
+        ],
+        ..BaseFacts::default()
+    };
+
+    let goals_reached = run_collection_query(&facts, |inputs| {
+        let (_, _, goals_reached) = build_attack_graph(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals_reached
+    });
+    let reached = |host: &str, required_privilege: PrivilegeLevel| AttackerGoalReached {
+        attacker_id: "eve".to_string(),
+        reached_target: host.to_string(),
+        required_privilege,
+    };
+
+    assert_eq!(
+        goals_reached,
+        vec![
+            reached("db01", PrivilegeLevel::User),
+            reached("db01", PrivilegeLevel::Root),
+            reached("web01", PrivilegeLevel::None),
+        ]
+    );
+    assert_eq!(
+        evaluate_base_facts(&facts)
+            .goals_reached
+            .into_iter()
+            .collect::<BTreeSet<_>>(),
+        goals_reached.iter().cloned().collect()
+    );
+    assert_eq!(
+        unreachable_goals(&facts),
+        vec![AttackerGoalUnreachable {
+            attacker_id: "eve".to_string(),
+            target_host_name: "web01".to_string(),
+            required_privilege: PrivilegeLevel::Root,
+        }]
+    );
+}
+
+#[test]
+fn options_with_a_user_threshold_own_more_machines_than_the_default() {
+    // Two User footholds in a row before a Root one.
+    let facts = BaseFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web01", "CVE-WEB", "https", PrivilegeLevel::User),
+            VulnerabilityRecord::new("app01", "CVE-APP", "rpc", PrivilegeLevel::User),
+            VulnerabilityRecord::new("db01", "CVE-DB", "mysql", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "https"),
+            NetworkAccessRule::new_default_port("web01", "app01", "rpc"),
+            NetworkAccessRule::new_default_port("app01", "db01", "mysql"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        ..BaseFacts::default()
+    };
+
+    let owned_with = |options: AttackGraphOptions| {
+        run_collection_query(&facts, move |inputs| {
+            build_attack_graph_with_options(
+                &inputs.vulnerabilities,
+                &inputs.network_access,
+                &inputs.firewall_rules,
+                &inputs.attacker_positions,
+                &inputs.attacker_goals,
+                &options,
+            )
+            .owned_machines
+        })
+    };
+    let owned_by_default = run_collection_query(&facts, |inputs| {
+        let (_, owns_machine, _) = build_attack_graph(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        owns_machine
+    });
+
+    assert_eq!(owned_with(AttackGraphOptions::default()), owned_by_default);
+    assert_eq!(owned_by_default, vec![owned("db01")]);
+    assert_eq!(
+        owned_with(AttackGraphOptions {
+            ownership_threshold: PrivilegeLevel::User,
+            ..AttackGraphOptions::default()
+        }),
+        vec![
+            owned("app01"),
+            owned("db01"),
+            owned("internet"),
+            owned("web01")
+        ]
+    );
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeSet;
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph_with_escalation_and_access_grants, run_collection_query,
+    AccessGrantingVulnerabilityRecord, AttackerGoalReached, AttackerStartingPosition,
+    AttackerTargetGoal, BaseFacts, LocalVulnerabilityRecord, NetworkAccessRule, PrivilegeLevel,
+    VulnerabilityRecord,
+};
+
+// internet -> workstation, where eve lands as User.  The workstation
+// holds a cached admin share that reaches dc over SMB, but only Root
+// can use it; a local kernel bug is the way from User to Root.
+fn user_foothold_facts() -> BaseFacts {
+    BaseFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("workstation", "CVE-WS-HTTP", "http", PrivilegeLevel::User),
+            VulnerabilityRecord::new("dc", "CVE-DC-SMB", "smb", PrivilegeLevel::Root),
+        ],
+        local_vulnerabilities: vec![LocalVulnerabilityRecord::new(
+            "workstation",
+            "CVE-WS-KERNEL",
+            PrivilegeLevel::Root,
+        )],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet",
+            "workstation",
+            "http",
+        )],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "dc")],
+        ..BaseFacts::default()
+    }
+}
+
+fn admin_share() -> Vec<AccessGrantingVulnerabilityRecord> {
+    vec![AccessGrantingVulnerabilityRecord::new(
+        "workstation",
+        "smb",
+        "dc",
+    )]
+}
+
+// (host, privilege) pairs eve executes code with, and the goals reached.
+fn derive(facts: &BaseFacts) -> (BTreeSet<(String, PrivilegeLevel)>, Vec<AttackerGoalReached>) {
+    let executions = run_collection_query(facts, |inputs| {
+        let (_, access_grants) = inputs
+            .vulnerabilities
+            .scope()
+            .new_collection_from(admin_share());
+        let (exec_code, _, _) = build_attack_graph_with_escalation_and_access_grants(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &access_grants,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exec_code.map(|exec| (exec.compromised_host, exec.obtained_privilege))
+    });
+    let goals = run_collection_query(facts, |inputs| {
+        let (_, access_grants) = inputs
+            .vulnerabilities
+            .scope()
+            .new_collection_from(admin_share());
+        let (_, _, goals) = build_attack_graph_with_escalation_and_access_grants(
+            &inputs.vulnerabilities,
+            &inputs.local_vulnerabilities,
+            &access_grants,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals
+    });
+    (executions.into_iter().collect(), goals)
+}
+
+fn held(pairs: &[(&str, PrivilegeLevel)]) -> BTreeSet<(String, PrivilegeLevel)> {
+    pairs
+        .iter()
+        .map(|(host, privilege)| (host.to_string(), privilege.clone()))
+        .collect()
+}
+
+#[test]
+fn local_escalation_unlocks_the_root_only_pivot() {
+    let (executions, goals) = derive(&user_foothold_facts());
+
+    assert_eq!(
+        executions,
+        held(&[
+            ("internet", PrivilegeLevel::User),
+            ("workstation", PrivilegeLevel::User),
+            ("workstation", PrivilegeLevel::Root),
+            ("dc", PrivilegeLevel::Root),
+        ])
+    );
+    assert_eq!(
+        goals,
+        vec![AttackerGoalReached {
+            attacker_id: "eve".to_string(),
+            reached_target: "dc".to_string(),
+            required_privilege: PrivilegeLevel::Root,
+        }]
+    );
+}
+
+#[test]
+fn a_user_foothold_alone_cannot_pivot() {
+    let mut facts = user_foothold_facts();
+    facts.local_vulnerabilities.clear();
+
+    let (executions, goals) = derive(&facts);
+
+    assert_eq!(
+        executions,
+        held(&[
+            ("internet", PrivilegeLevel::User),
+            ("workstation", PrivilegeLevel::User),
+        ])
+    );
+    assert!(goals.is_empty());
+}
+
+#[test]
+fn escalating_on_another_host_does_not_open_the_pivot() {
+    let mut facts = user_foothold_facts();
+    // The kernel bug now sits on a host eve never reaches.
+    facts.local_vulnerabilities = vec![LocalVulnerabilityRecord::new(
+        "build_server",
+        "CVE-WS-KERNEL",
+        PrivilegeLevel::Root,
+    )];
+
+    let (executions, goals) = derive(&facts);
+
+    assert!(!executions.contains(&("workstation".to_string(), PrivilegeLevel::Root)));
+    assert!(!executions.contains(&("dc".to_string(), PrivilegeLevel::Root)));
+    assert!(goals.is_empty());
+}
@@ -60,7 +60,7 @@ fn generate_scenario(rng: &mut StdRng) -> BaseFacts {
     for source in &hosts {
         for destination in &hosts {
             if source != destination && rng.gen_bool(0.25) {
-                network_access.push(NetworkAccessRule::new(
+                network_access.push(NetworkAccessRule::new_default_port(
                     source,
                     destination,
                     choose_service(rng),
@@ -71,7 +71,9 @@ fn generate_scenario(rng: &mut StdRng) -> BaseFacts {
 
     // Ensure every randomized scenario has at least a simple candidate path.
     for window in hosts.windows(2) {
-        network_access.push(NetworkAccessRule::new(&window[0], &window[1], "https"));
+        network_access.push(NetworkAccessRule::new_default_port(
+            &window[0], &window[1], "https",
+        ));
     }
 
     let mut vulnerabilities = Vec::new();
@@ -113,6 +115,7 @@ fn generate_scenario(rng: &mut StdRng) -> BaseFacts {
                 &access.destination_host,
                 &access.service_name,
             )
+            .with_port(access.port)
         })
         .collect();
 
@@ -149,7 +152,8 @@ fn generate_updates(rng: &mut StdRng, facts: &BaseFacts) -> Vec<FactUpdate> {
                 &access.source_host,
                 &access.destination_host,
                 &access.service_name,
-            ),
+            )
+            .with_port(access.port),
         ));
     }
 
@@ -0,0 +1,153 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    attack_basin, FirewallRuleRecord, NetworkAccessRule, PrivilegeLevel, VulnerabilityRecord,
+};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// Which host-valued query from the library a test evaluates.
+#[derive(Debug, Clone)]
+enum HostQuery {
+    AttackBasin(String),
+}
+
+#[derive(Debug, Clone, Default)]
+struct StaticFacts {
+    vulnerabilities: Vec<VulnerabilityRecord>,
+    network_access: Vec<NetworkAccessRule>,
+    firewall_rules: Vec<FirewallRuleRecord>,
+}
+
+fn run_host_query(facts: StaticFacts, query: HostQuery) -> BTreeSet<String> {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let captured_in_dataflow = Arc::clone(&captured);
+
+    timely::execute_directly(move |worker| {
+        let (mut vulnerability_input, mut network_input, mut firewall_input) = worker
+            .dataflow::<usize, _, _>(|scope| {
+                let (vulnerability_handle, vulnerability_collection) =
+                    scope.new_collection::<VulnerabilityRecord, isize>();
+                let (network_handle, network_collection) =
+                    scope.new_collection::<NetworkAccessRule, isize>();
+                let (firewall_handle, firewall_collection) =
+                    scope.new_collection::<FirewallRuleRecord, isize>();
+
+                let hosts = match &query {
+                    HostQuery::AttackBasin(target_host) => attack_basin(
+                        &vulnerability_collection,
+                        &network_collection,
+                        &firewall_collection,
+                        target_host,
+                    ),
+                };
+
+                let captured_hosts = Arc::clone(&captured_in_dataflow);
+                hosts.inspect(move |(host, _time, diff)| {
+                    captured_hosts
+                        .lock()
+                        .expect("captured hosts mutex should not be poisoned")
+                        .push((host.clone(), *diff));
+                });
+
+                (vulnerability_handle, network_handle, firewall_handle)
+            });
+
+        for record in facts.vulnerabilities {
+            vulnerability_input.insert(record);
+        }
+        for record in facts.network_access {
+            network_input.insert(record);
+        }
+        for record in facts.firewall_rules {
+            firewall_input.insert(record);
+        }
+
+        vulnerability_input.close();
+        network_input.close();
+        firewall_input.close();
+
+        while worker.step() {}
+    });
+
+    let changes = captured
+        .lock()
+        .expect("captured hosts mutex should not be poisoned")
+        .clone();
+    let mut counts = BTreeMap::new();
+    for (host, diff) in changes {
+        *counts.entry(host).or_insert(0isize) += diff;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(host, _)| host)
+        .collect()
+}
+
+fn chain_facts(number_of_nodes: usize) -> StaticFacts {
+    let mut facts = StaticFacts::default();
+    for index in 1..number_of_nodes {
+        let host = format!("node_{index}");
+        facts.vulnerabilities.push(VulnerabilityRecord::new(
+            &host,
+            &format!("CVE-CHAIN-{index}"),
+            "ssh",
+            PrivilegeLevel::Root,
+        ));
+        facts
+            .network_access
+            .push(NetworkAccessRule::new_default_port(
+                &format!("node_{}", index - 1),
+                &host,
+                "ssh",
+            ));
+    }
+    facts
+}
+
+fn hosts(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn attack_basin_of_chain_end_is_every_upstream_node() {
+    let basin = run_host_query(chain_facts(5), HostQuery::AttackBasin("node_4".to_string()));
+
+    assert_eq!(basin, hosts(&["node_0", "node_1", "node_2", "node_3"]));
+}
+
+#[test]
+fn attack_basin_of_isolated_node_is_empty() {
+    let mut facts = chain_facts(3);
+    facts.vulnerabilities.push(VulnerabilityRecord::new(
+        "isolated",
+        "CVE-ISOLATED",
+        "ssh",
+        PrivilegeLevel::Root,
+    ));
+
+    let basin = run_host_query(facts, HostQuery::AttackBasin("isolated".to_string()));
+
+    assert!(basin.is_empty());
+}
+
+#[test]
+fn attack_basin_stops_at_firewall_deny() {
+    let mut facts = chain_facts(5);
+    facts
+        .firewall_rules
+        .push(FirewallRuleRecord::create_deny_rule(
+            "node_1", "node_2", "ssh",
+        ));
+
+    let basin = run_host_query(facts, HostQuery::AttackBasin("node_4".to_string()));
+
+    assert_eq!(basin, hosts(&["node_2", "node_3"]));
+}
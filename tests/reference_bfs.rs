@@ -0,0 +1,240 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use dynamic_attack_graphs::{
+    build_attack_graph, run_collection_query, AttackerCodeExecution, AttackerGoalReached,
+    AttackerOwnsMachine, AttackerStartingPosition, AttackerTargetGoal, BaseFacts,
+    FirewallRuleAction, FirewallRuleRecord, NetworkAccessRule, PrivilegeLattice, PrivilegeLevel,
+    VulnerabilityRecord,
+};
+use proptest::prelude::*;
+
+// Two services, so generated edges, vulnerabilities and denies collide
+// often enough to exercise the joins and the firewall antijoin.
+const SERVICES: &[&str] = &["ssh", "http"];
+
+fn host(index: usize) -> String {
+    format!("host_{index}")
+}
+
+fn privilege() -> impl Strategy<Value = PrivilegeLevel> {
+    prop_oneof![
+        Just(PrivilegeLevel::None),
+        Just(PrivilegeLevel::User),
+        Just(PrivilegeLevel::Root),
+    ]
+}
+
+// Up to seven hosts with random edges, vulnerabilities, exact-match
+// denies, one or two attackers and goals.  Every part is a vector, so
+// proptest shrinks a failure by dropping facts one at a time.
+fn scenario() -> impl Strategy<Value = BaseFacts> {
+    (2usize..=7)
+        .prop_flat_map(|host_count| {
+            let hosts = 0..host_count;
+            let services = 0..SERVICES.len();
+            (
+                prop::collection::vec((hosts.clone(), hosts.clone(), services.clone()), 0..16),
+                prop::collection::vec((hosts.clone(), services.clone(), privilege()), 0..12),
+                prop::collection::vec((hosts.clone(), hosts.clone(), services), 0..4),
+                prop::collection::vec((hosts.clone(), privilege()), 1..=2),
+                prop::collection::vec((0..2usize, hosts, privilege()), 0..3),
+            )
+        })
+        .prop_map(
+            |(edges, vulnerabilities, denies, positions, goals)| BaseFacts {
+                network_access: edges
+                    .into_iter()
+                    .map(|(source, destination, service)| {
+                        NetworkAccessRule::new_default_port(
+                            &host(source),
+                            &host(destination),
+                            SERVICES[service],
+                        )
+                    })
+                    .collect(),
+                vulnerabilities: vulnerabilities
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, (target, service, privilege))| {
+                        VulnerabilityRecord::new(
+                            &host(target),
+                            &format!("CVE-{index}"),
+                            SERVICES[service],
+                            privilege,
+                        )
+                    })
+                    .collect(),
+                firewall_rules: denies
+                    .into_iter()
+                    .map(|(source, destination, service)| {
+                        FirewallRuleRecord::create_deny_rule(
+                            &host(source),
+                            &host(destination),
+                            SERVICES[service],
+                        )
+                    })
+                    .collect(),
+                attacker_positions: positions
+                    .into_iter()
+                    .enumerate()
+                    .map(|(attacker, (start, privilege))| {
+                        AttackerStartingPosition::new(
+                            &format!("attacker_{attacker}"),
+                            &host(start),
+                            privilege,
+                        )
+                    })
+                    .collect(),
+                attacker_goals: goals
+                    .into_iter()
+                    .map(|(attacker, target, privilege)| {
+                        AttackerTargetGoal::new(&format!("attacker_{attacker}"), &host(target))
+                            .with_required_privilege(privilege)
+                    })
+                    .collect(),
+                ..BaseFacts::default()
+            },
+        )
+}
+
+// The rules of `build_attack_graph`, evaluated with a worklist:
+//   execCode(A, H, P)  :- attackerLocated(A, H, P).
+//   execCode(A, D, P)  :- execCode(A, S, _), hacl(S, D, Svc, Port),
+//                         NOT deny(S, D, Svc, Port), vulExists(D, Svc, Port, P).
+//   ownsMachine(A, H)  :- execCode(A, H, root).
+//   goalReached(A, H)  :- goal(A, H, Req), execCode(A, H, P), P dominates Req.
+struct ReferenceGraph {
+    code_executions: BTreeSet<AttackerCodeExecution>,
+    owned_machines: BTreeSet<AttackerOwnsMachine>,
+    goals_reached: BTreeSet<AttackerGoalReached>,
+}
+
+fn evaluate_with_worklist(facts: &BaseFacts) -> ReferenceGraph {
+    let is_denied = |access: &NetworkAccessRule| {
+        facts.firewall_rules.iter().any(|rule| {
+            rule.rule_action == FirewallRuleAction::Deny
+                && rule.source_zone == access.source_host
+                && rule.destination_host == access.destination_host
+                && rule.service_name == access.service_name
+                && rule.port == access.port
+        })
+    };
+
+    let mut code_executions = BTreeSet::new();
+    let mut worklist: VecDeque<_> = facts
+        .attacker_positions
+        .iter()
+        .map(|position| AttackerCodeExecution {
+            attacker_id: position.attacker_id.clone(),
+            compromised_host: position.starting_host.clone(),
+            obtained_privilege: position.initial_privilege.clone(),
+        })
+        .collect();
+
+    while let Some(execution) = worklist.pop_front() {
+        if !code_executions.insert(execution.clone()) {
+            continue;
+        }
+        for access in facts
+            .network_access
+            .iter()
+            .filter(|access| access.source_host == execution.compromised_host && !is_denied(access))
+        {
+            for vulnerability in facts.vulnerabilities.iter().filter(|vulnerability| {
+                vulnerability.host_name == access.destination_host
+                    && vulnerability.affected_service == access.service_name
+                    && vulnerability.port == access.port
+            }) {
+                worklist.push_back(AttackerCodeExecution {
+                    attacker_id: execution.attacker_id.clone(),
+                    compromised_host: vulnerability.host_name.clone(),
+                    obtained_privilege: vulnerability.privilege_gained_on_exploit.clone(),
+                });
+            }
+        }
+    }
+
+    let owned_machines = code_executions
+        .iter()
+        .filter(|execution| execution.obtained_privilege == PrivilegeLevel::Root)
+        .map(|execution| AttackerOwnsMachine {
+            attacker_id: execution.attacker_id.clone(),
+            owned_host: execution.compromised_host.clone(),
+        })
+        .collect();
+    let goals_reached = facts
+        .attacker_goals
+        .iter()
+        .filter(|goal| {
+            code_executions.iter().any(|execution| {
+                execution.attacker_id == goal.attacker_id
+                    && execution.compromised_host == goal.target_host_name
+                    && execution
+                        .obtained_privilege
+                        .dominates(&goal.required_privilege)
+            })
+        })
+        .map(|goal| AttackerGoalReached {
+            attacker_id: goal.attacker_id.clone(),
+            reached_target: goal.target_host_name.clone(),
+            required_privilege: goal.required_privilege.clone(),
+        })
+        .collect();
+
+    ReferenceGraph {
+        code_executions,
+        owned_machines,
+        goals_reached,
+    }
+}
+
+fn differential_graph(facts: &BaseFacts) -> ReferenceGraph {
+    let code_executions = run_collection_query(facts, |inputs| {
+        build_attack_graph(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        )
+        .0
+    });
+    let owned_machines = run_collection_query(facts, |inputs| {
+        build_attack_graph(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        )
+        .1
+    });
+    let goals_reached = run_collection_query(facts, |inputs| {
+        build_attack_graph(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        )
+        .2
+    });
+
+    ReferenceGraph {
+        code_executions: code_executions.into_iter().collect(),
+        owned_machines: owned_machines.into_iter().collect(),
+        goals_reached: goals_reached.into_iter().collect(),
+    }
+}
+
+proptest! {
+    #[test]
+    fn build_attack_graph_matches_a_worklist_evaluation(facts in scenario()) {
+        let expected = evaluate_with_worklist(&facts);
+        let derived = differential_graph(&facts);
+
+        prop_assert_eq!(derived.code_executions, expected.code_executions);
+        prop_assert_eq!(derived.owned_machines, expected.owned_machines);
+        prop_assert_eq!(derived.goals_reached, expected.goals_reached);
+    }
+}
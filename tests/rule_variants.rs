@@ -0,0 +1,1164 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph, build_attack_graph_capacity_limited, build_attack_graph_with_access_grants,
+    build_attack_graph_with_credentials, build_attack_graph_with_firewall_policy,
+    build_attack_graph_with_max_hops, build_attack_graph_with_options,
+    build_attack_graph_with_privilege_escalation, build_attack_graph_with_roles,
+    build_attack_graph_with_rules, build_attack_graph_with_trust_relationships,
+    build_attack_graph_with_zones, generate_chain_network, generate_mesh_network,
+    generate_star_network, observed_network_access, run_collection_query,
+    AccessGrantingVulnerabilityRecord, AttackGraphOptions, AttackerCodeExecution,
+    AttackerRoleMembership, AttackerStartingPosition, AttackerTargetGoal, BaseFacts,
+    CredentialGrantRecord, CredentialStoredRecord, CredentialTheft, DerivationRule, FirewallPolicy,
+    FirewallRuleRecord, HostZone, NetworkAccessRule, PrivilegeEscalationRecord, PrivilegeLevel,
+    RoleBasedAccess, RoleConferringHost, RoleGrantRule, TrustRelationship, TrustRelationshipRule,
+    VulnerabilityRecord,
+};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// Which rule builder from `rules.rs` a test scenario is evaluated with.
+#[derive(Debug, Clone, Copy)]
+enum RuleVariant {
+    Standard,
+    CapacityLimited(usize),
+    MaxHops(usize),
+    AccessGrants,
+    PrivilegeEscalation,
+    Credentials,
+    Roles,
+    ObservedOnly,
+    TrustPlugin,
+    Trust,
+    Firewall(FirewallPolicy),
+    Zones(FirewallPolicy),
+}
+
+#[derive(Debug, Clone, Default)]
+struct StaticFacts {
+    vulnerabilities: Vec<VulnerabilityRecord>,
+    network_access: Vec<NetworkAccessRule>,
+    firewall_rules: Vec<FirewallRuleRecord>,
+    attacker_positions: Vec<AttackerStartingPosition>,
+    attacker_goals: Vec<AttackerTargetGoal>,
+    access_grants: Vec<AccessGrantingVulnerabilityRecord>,
+    privilege_escalations: Vec<PrivilegeEscalationRecord>,
+    stored_credentials: Vec<CredentialStoredRecord>,
+    credential_grants: Vec<CredentialGrantRecord>,
+    role_grants: Vec<RoleGrantRule>,
+    role_memberships: Vec<AttackerRoleMembership>,
+    role_conferring_hosts: Vec<RoleConferringHost>,
+    trust_relationships: Vec<TrustRelationship>,
+    host_zones: Vec<HostZone>,
+}
+
+fn run_rule_variant(facts: StaticFacts, variant: RuleVariant) -> BTreeSet<AttackerCodeExecution> {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let captured_in_dataflow = Arc::clone(&captured);
+
+    timely::execute_directly(move |worker| {
+        let (
+            mut vulnerability_input,
+            mut network_input,
+            mut firewall_input,
+            mut attacker_position_input,
+            mut attacker_goal_input,
+            mut access_grant_input,
+            mut privilege_escalation_input,
+            mut stored_credential_input,
+            mut credential_grant_input,
+            mut role_grant_input,
+            mut role_membership_input,
+            mut role_conferring_input,
+            mut trust_input,
+            mut host_zone_input,
+        ) = worker.dataflow::<usize, _, _>(|scope| {
+            let (vulnerability_handle, vulnerability_collection) =
+                scope.new_collection::<VulnerabilityRecord, isize>();
+            let (network_handle, network_collection) =
+                scope.new_collection::<NetworkAccessRule, isize>();
+            let (firewall_handle, firewall_collection) =
+                scope.new_collection::<FirewallRuleRecord, isize>();
+            let (position_handle, position_collection) =
+                scope.new_collection::<AttackerStartingPosition, isize>();
+            let (goal_handle, goal_collection) =
+                scope.new_collection::<AttackerTargetGoal, isize>();
+            let (access_grant_handle, access_grant_collection) =
+                scope.new_collection::<AccessGrantingVulnerabilityRecord, isize>();
+            let (privilege_escalation_handle, privilege_escalation_collection) =
+                scope.new_collection::<PrivilegeEscalationRecord, isize>();
+            let (stored_credential_handle, stored_credential_collection) =
+                scope.new_collection::<CredentialStoredRecord, isize>();
+            let (credential_grant_handle, credential_grant_collection) =
+                scope.new_collection::<CredentialGrantRecord, isize>();
+            let (role_grant_handle, role_grant_collection) =
+                scope.new_collection::<RoleGrantRule, isize>();
+            let (role_membership_handle, role_membership_collection) =
+                scope.new_collection::<AttackerRoleMembership, isize>();
+            let (role_conferring_handle, role_conferring_collection) =
+                scope.new_collection::<RoleConferringHost, isize>();
+            let (trust_handle, trust_collection) =
+                scope.new_collection::<TrustRelationship, isize>();
+            let (host_zone_handle, host_zone_collection) =
+                scope.new_collection::<HostZone, isize>();
+
+            let (exec_code, _, _) = match variant {
+                RuleVariant::Standard => build_attack_graph(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                ),
+                RuleVariant::TrustPlugin => {
+                    let rules: Vec<Box<dyn DerivationRule<_>>> =
+                        vec![Box::new(TrustRelationshipRule::new(&trust_collection))];
+                    build_attack_graph_with_rules(
+                        &vulnerability_collection,
+                        &network_collection,
+                        &firewall_collection,
+                        &position_collection,
+                        &goal_collection,
+                        rules,
+                    )
+                }
+                RuleVariant::Firewall(policy) => build_attack_graph_with_firewall_policy(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                    policy,
+                ),
+                RuleVariant::Zones(policy) => build_attack_graph_with_zones(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                    &host_zone_collection,
+                    policy,
+                ),
+                RuleVariant::Trust => build_attack_graph_with_trust_relationships(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                    &trust_collection,
+                ),
+                RuleVariant::ObservedOnly => build_attack_graph(
+                    &vulnerability_collection,
+                    &observed_network_access(&network_collection),
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                ),
+                RuleVariant::CapacityLimited(maximum_footholds) => {
+                    build_attack_graph_capacity_limited(
+                        &vulnerability_collection,
+                        &network_collection,
+                        &firewall_collection,
+                        &position_collection,
+                        &goal_collection,
+                        maximum_footholds,
+                    )
+                }
+                RuleVariant::MaxHops(maximum_attack_hops) => build_attack_graph_with_max_hops(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                    maximum_attack_hops,
+                ),
+                RuleVariant::AccessGrants => build_attack_graph_with_access_grants(
+                    &vulnerability_collection,
+                    &access_grant_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                ),
+                RuleVariant::PrivilegeEscalation => build_attack_graph_with_privilege_escalation(
+                    &vulnerability_collection,
+                    &privilege_escalation_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                ),
+                RuleVariant::Credentials => build_attack_graph_with_credentials(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                    &CredentialTheft {
+                        stored_credentials: &stored_credential_collection,
+                        credential_grants: &credential_grant_collection,
+                    },
+                ),
+                RuleVariant::Roles => build_attack_graph_with_roles(
+                    &vulnerability_collection,
+                    &network_collection,
+                    &firewall_collection,
+                    &position_collection,
+                    &goal_collection,
+                    &RoleBasedAccess {
+                        role_grants: &role_grant_collection,
+                        role_memberships: &role_membership_collection,
+                        role_conferring_hosts: &role_conferring_collection,
+                    },
+                ),
+            };
+
+            let captured_exec = Arc::clone(&captured_in_dataflow);
+            exec_code.inspect(move |(record, _time, diff)| {
+                captured_exec
+                    .lock()
+                    .expect("captured exec mutex should not be poisoned")
+                    .push((record.clone(), *diff));
+            });
+
+            (
+                vulnerability_handle,
+                network_handle,
+                firewall_handle,
+                position_handle,
+                goal_handle,
+                access_grant_handle,
+                privilege_escalation_handle,
+                stored_credential_handle,
+                credential_grant_handle,
+                role_grant_handle,
+                role_membership_handle,
+                role_conferring_handle,
+                trust_handle,
+                host_zone_handle,
+            )
+        });
+
+        for record in facts.vulnerabilities {
+            vulnerability_input.insert(record);
+        }
+        for record in facts.network_access {
+            network_input.insert(record);
+        }
+        for record in facts.firewall_rules {
+            firewall_input.insert(record);
+        }
+        for record in facts.attacker_positions {
+            attacker_position_input.insert(record);
+        }
+        for record in facts.attacker_goals {
+            attacker_goal_input.insert(record);
+        }
+        for record in facts.access_grants {
+            access_grant_input.insert(record);
+        }
+        for record in facts.privilege_escalations {
+            privilege_escalation_input.insert(record);
+        }
+        for record in facts.stored_credentials {
+            stored_credential_input.insert(record);
+        }
+        for record in facts.credential_grants {
+            credential_grant_input.insert(record);
+        }
+        for record in facts.role_grants {
+            role_grant_input.insert(record);
+        }
+        for record in facts.role_memberships {
+            role_membership_input.insert(record);
+        }
+        for record in facts.role_conferring_hosts {
+            role_conferring_input.insert(record);
+        }
+        for record in facts.trust_relationships {
+            trust_input.insert(record);
+        }
+        for record in facts.host_zones {
+            host_zone_input.insert(record);
+        }
+
+        vulnerability_input.close();
+        network_input.close();
+        firewall_input.close();
+        attacker_position_input.close();
+        attacker_goal_input.close();
+        access_grant_input.close();
+        privilege_escalation_input.close();
+        stored_credential_input.close();
+        credential_grant_input.close();
+        role_grant_input.close();
+        role_membership_input.close();
+        role_conferring_input.close();
+        trust_input.close();
+        host_zone_input.close();
+
+        while worker.step() {}
+    });
+
+    let changes = captured
+        .lock()
+        .expect("captured exec mutex should not be poisoned")
+        .clone();
+    let mut counts = BTreeMap::new();
+    for (record, diff) in changes {
+        *counts.entry(record).or_insert(0isize) += diff;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(record, _)| record)
+        .collect()
+}
+
+type GeneratedNetwork = (
+    Vec<NetworkAccessRule>,
+    Vec<VulnerabilityRecord>,
+    Vec<AttackerStartingPosition>,
+    Vec<AttackerTargetGoal>,
+);
+
+impl StaticFacts {
+    fn from_generated(generated: GeneratedNetwork) -> Self {
+        let (network_access, vulnerabilities, attacker_positions, attacker_goals) = generated;
+        Self {
+            vulnerabilities,
+            network_access,
+            firewall_rules: Vec::new(),
+            attacker_positions,
+            attacker_goals,
+            ..StaticFacts::default()
+        }
+    }
+
+    fn number_of_hosts(&self) -> usize {
+        let mut hosts = BTreeSet::new();
+        for rule in &self.network_access {
+            hosts.insert(rule.source_host.clone());
+            hosts.insert(rule.destination_host.clone());
+        }
+        for position in &self.attacker_positions {
+            hosts.insert(position.starting_host.clone());
+        }
+        hosts.len()
+    }
+}
+
+fn chain_facts(number_of_nodes: usize) -> StaticFacts {
+    let mut facts = StaticFacts::default();
+    for index in 0..number_of_nodes {
+        let host = format!("node_{index}");
+        if index > 0 {
+            facts.vulnerabilities.push(VulnerabilityRecord::new(
+                &host,
+                &format!("CVE-CHAIN-{index}"),
+                "ssh",
+                PrivilegeLevel::Root,
+            ));
+        }
+        if index + 1 < number_of_nodes {
+            facts
+                .network_access
+                .push(NetworkAccessRule::new_default_port(
+                    &host,
+                    &format!("node_{}", index + 1),
+                    "ssh",
+                ));
+        }
+    }
+    facts.attacker_positions.push(AttackerStartingPosition::new(
+        "attacker",
+        "node_0",
+        PrivilegeLevel::User,
+    ));
+    facts.attacker_goals.push(AttackerTargetGoal::new(
+        "attacker",
+        &format!("node_{}", number_of_nodes - 1),
+    ));
+    facts
+}
+
+fn compromised_hosts(executions: &BTreeSet<AttackerCodeExecution>) -> BTreeSet<String> {
+    executions
+        .iter()
+        .map(|exec| exec.compromised_host.clone())
+        .collect()
+}
+
+#[test]
+fn capacity_limit_of_two_stops_chain_after_second_hop() {
+    let executions = run_rule_variant(chain_facts(5), RuleVariant::CapacityLimited(2));
+
+    let expected: BTreeSet<String> = ["node_0", "node_1", "node_2"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    assert_eq!(compromised_hosts(&executions), expected);
+}
+
+#[test]
+fn capacity_limit_larger_than_graph_matches_unbounded_rules() {
+    let unbounded = run_rule_variant(chain_facts(5), RuleVariant::Standard);
+    let capacity_limited = run_rule_variant(chain_facts(5), RuleVariant::CapacityLimited(10));
+
+    assert_eq!(compromised_hosts(&unbounded).len(), 5);
+    assert_eq!(capacity_limited, unbounded);
+}
+
+#[test]
+fn max_hops_with_hop_cap_at_host_count_matches_build_attack_graph() {
+    let mut firewalled_chain = StaticFacts::from_generated(generate_chain_network(6));
+    firewalled_chain
+        .firewall_rules
+        .push(FirewallRuleRecord::create_deny_rule(
+            "node_2", "node_3", "ssh",
+        ));
+
+    let mut branching_cycle = chain_facts(4);
+    branching_cycle
+        .network_access
+        .push(NetworkAccessRule::new_default_port(
+            "node_3", "node_1", "ssh",
+        ));
+    branching_cycle
+        .network_access
+        .push(NetworkAccessRule::new_default_port(
+            "node_1", "node_3", "ssh",
+        ));
+    branching_cycle
+        .firewall_rules
+        .push(FirewallRuleRecord::create_deny_rule(
+            "node_1", "node_2", "ssh",
+        ));
+
+    let topologies = vec![
+        (
+            "chain",
+            StaticFacts::from_generated(generate_chain_network(6)),
+        ),
+        (
+            "mesh",
+            StaticFacts::from_generated(generate_mesh_network(3, 3)),
+        ),
+        (
+            "star",
+            StaticFacts::from_generated(generate_star_network(5)),
+        ),
+        ("firewalled chain", firewalled_chain),
+        ("branching cycle", branching_cycle),
+    ];
+
+    for (name, facts) in topologies {
+        let number_of_hosts = facts.number_of_hosts();
+        let unbounded = run_rule_variant(facts.clone(), RuleVariant::Standard);
+        let bounded = run_rule_variant(facts, RuleVariant::MaxHops(number_of_hosts));
+
+        assert!(
+            unbounded.len() > 1,
+            "{name} should derive execCode beyond the start"
+        );
+        assert_eq!(bounded, unbounded, "{name} execCode sets differ");
+    }
+}
+
+#[test]
+fn max_hops_deny_on_first_hop_leaves_only_the_start() {
+    let mut facts = chain_facts(5);
+    facts
+        .firewall_rules
+        .push(FirewallRuleRecord::create_deny_rule(
+            "node_0", "node_1", "ssh",
+        ));
+
+    let unbounded = run_rule_variant(facts.clone(), RuleVariant::Standard);
+    let bounded = run_rule_variant(facts, RuleVariant::MaxHops(5));
+
+    let start_only: BTreeSet<String> = BTreeSet::from(["node_0".to_string()]);
+    assert_eq!(compromised_hosts(&bounded), start_only);
+    assert_eq!(bounded, unbounded);
+}
+
+#[test]
+fn options_bound_paths_by_max_hops_too() {
+    let chain = chain_facts(6);
+    let facts = BaseFacts {
+        vulnerabilities: chain.vulnerabilities.clone(),
+        network_access: chain.network_access.clone(),
+        attacker_positions: chain.attacker_positions.clone(),
+        ..BaseFacts::default()
+    };
+    let options = AttackGraphOptions {
+        max_hops: Some(2),
+        track_paths: true,
+        ..AttackGraphOptions::default()
+    };
+
+    let paths = run_collection_query(&facts, move |inputs| {
+        build_attack_graph_with_options(
+            &inputs.vulnerabilities,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+            &options,
+        )
+        .attack_paths
+        .expect("track_paths is set")
+    });
+    let bounded = run_rule_variant(chain, RuleVariant::MaxHops(2));
+
+    let path_ends: BTreeSet<String> = paths
+        .iter()
+        .map(|path| path.path.last().expect("paths are never empty").clone())
+        .collect();
+    assert!(paths.iter().all(|path| path.path.len() <= 3));
+    assert_eq!(path_ends, compromised_hosts(&bounded));
+    assert_eq!(path_ends.len(), 3);
+}
+
+// Hosts compromised and hosts cut off by the bound at one time.
+type BoundedSnapshot = (BTreeSet<String>, BTreeSet<String>);
+// Captured (host, time, diff) updates of one output.
+type CapturedHosts = Arc<Mutex<Vec<(String, usize, isize)>>>;
+
+#[test]
+fn iteration_bound_holds_as_edges_come_and_go() {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let chain = chain_facts(5);
+    let shortcut = NetworkAccessRule::new_default_port("node_0", "node_3", "ssh");
+
+    let snapshots: Vec<BoundedSnapshot> = timely::execute_directly(move |worker| {
+        let executions: CapturedHosts = Arc::default();
+        let truncated: CapturedHosts = Arc::default();
+        let mut probe = timely::dataflow::operators::probe::Handle::new();
+
+        let (mut vulnerability_input, mut network_input, mut position_input) = worker
+            .dataflow::<usize, _, _>(|scope| {
+                let (vulnerability_handle, vulnerabilities) =
+                    scope.new_collection::<VulnerabilityRecord, isize>();
+                let (network_handle, network_access) =
+                    scope.new_collection::<NetworkAccessRule, isize>();
+                let (position_handle, positions) =
+                    scope.new_collection::<AttackerStartingPosition, isize>();
+                let outputs = build_attack_graph_with_options(
+                    &vulnerabilities,
+                    &network_access,
+                    &scope.new_collection::<FirewallRuleRecord, isize>().1,
+                    &positions,
+                    &scope.new_collection::<AttackerTargetGoal, isize>().1,
+                    &AttackGraphOptions {
+                        max_iterations: Some(2),
+                        ..AttackGraphOptions::default()
+                    },
+                );
+
+                let captured_executions = Arc::clone(&executions);
+                outputs
+                    .code_executions
+                    .inspect(move |(record, time, diff)| {
+                        captured_executions
+                            .lock()
+                            .expect("captured exec mutex should not be poisoned")
+                            .push((record.compromised_host.clone(), *time, *diff));
+                    })
+                    .probe_with(&mut probe);
+                let captured_truncated = Arc::clone(&truncated);
+                outputs
+                    .truncated_executions
+                    .expect("max_iterations is set")
+                    .inspect(move |(record, time, diff)| {
+                        captured_truncated
+                            .lock()
+                            .expect("captured truncation mutex should not be poisoned")
+                            .push((record.compromised_host.clone(), *time, *diff));
+                    })
+                    .probe_with(&mut probe);
+
+                (vulnerability_handle, network_handle, position_handle)
+            });
+
+        for record in chain.vulnerabilities {
+            vulnerability_input.insert(record);
+        }
+        for record in chain.network_access {
+            network_input.insert(record);
+        }
+        for record in chain.attacker_positions {
+            position_input.insert(record);
+        }
+        vulnerability_input.close();
+        position_input.close();
+        // The shortcut appears at time 1 and is gone again at time 2.
+        let settle = |worker: &mut timely::worker::Worker<_>, time: usize| {
+            worker.step_while(|| probe.less_than(&time));
+        };
+        network_input.advance_to(1);
+        network_input.flush();
+        settle(worker, 1);
+        network_input.insert(shortcut.clone());
+        network_input.advance_to(2);
+        network_input.flush();
+        settle(worker, 2);
+        network_input.remove(shortcut);
+        network_input.advance_to(3);
+        network_input.flush();
+        settle(worker, 3);
+
+        let accumulated = |captured: &CapturedHosts, at: usize| {
+            let mut counts = BTreeMap::new();
+            for (host, time, diff) in captured
+                .lock()
+                .expect("captured update mutex should not be poisoned")
+                .iter()
+            {
+                if *time <= at {
+                    *counts.entry(host.clone()).or_insert(0) += diff;
+                }
+            }
+            counts
+                .into_iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(host, _)| host)
+                .collect::<BTreeSet<String>>()
+        };
+        (0..3)
+            .map(|at| (accumulated(&executions, at), accumulated(&truncated, at)))
+            .collect()
+    });
+
+    let hosts = |names: &[&str]| -> BTreeSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    };
+    let two_hops = (hosts(&["node_0", "node_1", "node_2"]), hosts(&["node_3"]));
+    let whole_chain = (
+        hosts(&["node_0", "node_1", "node_2", "node_3", "node_4"]),
+        BTreeSet::new(),
+    );
+    assert_eq!(snapshots, vec![two_hops.clone(), whole_chain, two_hops]);
+}
+
+// A five-node ssh chain with one allow rule on the first hop and one
+// deny rule on the third.  Each policy reads only its own kind of rule.
+fn mixed_firewall_chain_facts() -> StaticFacts {
+    let mut facts = chain_facts(5);
+    facts.firewall_rules = vec![
+        FirewallRuleRecord::create_allow_rule("node_0", "node_1", "ssh"),
+        FirewallRuleRecord::create_deny_rule("node_2", "node_3", "ssh"),
+    ];
+    facts
+}
+
+#[test]
+fn default_allow_ignores_allow_rules_and_stops_at_the_deny() {
+    let facts = mixed_firewall_chain_facts();
+
+    let executions = run_rule_variant(
+        facts.clone(),
+        RuleVariant::Firewall(FirewallPolicy::DefaultAllow),
+    );
+
+    let expected: BTreeSet<String> = ["node_0", "node_1", "node_2"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    assert_eq!(compromised_hosts(&executions), expected);
+    assert_eq!(executions, run_rule_variant(facts, RuleVariant::Standard));
+}
+
+#[test]
+fn default_deny_only_uses_explicitly_allowed_edges() {
+    let executions = run_rule_variant(
+        mixed_firewall_chain_facts(),
+        RuleVariant::Firewall(FirewallPolicy::DefaultDeny),
+    );
+
+    let expected: BTreeSet<String> = ["node_0", "node_1"].into_iter().map(String::from).collect();
+    assert_eq!(compromised_hosts(&executions), expected);
+}
+
+#[test]
+fn default_deny_with_every_hop_allowed_matches_the_open_chain() {
+    let mut facts = chain_facts(5);
+    facts.firewall_rules = (0..4)
+        .map(|index| {
+            FirewallRuleRecord::create_allow_rule(
+                &format!("node_{index}"),
+                &format!("node_{}", index + 1),
+                "ssh",
+            )
+        })
+        .collect();
+
+    let default_deny = run_rule_variant(
+        facts.clone(),
+        RuleVariant::Firewall(FirewallPolicy::DefaultDeny),
+    );
+
+    assert_eq!(default_deny, run_rule_variant(facts, RuleVariant::Standard));
+    assert_eq!(compromised_hosts(&default_deny).len(), 5);
+}
+
+// Two web servers in the "dmz" zone, both reachable from the internet
+// over https and both exploitable.
+fn dmz_zone_facts(firewall_rules: Vec<FirewallRuleRecord>) -> StaticFacts {
+    StaticFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web1", "CVE-WEB-1", "https", PrivilegeLevel::Root),
+            VulnerabilityRecord::new("web2", "CVE-WEB-2", "https", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web1", "https"),
+            NetworkAccessRule::new_default_port("internet", "web2", "https"),
+        ],
+        firewall_rules,
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        host_zones: vec![HostZone::new("web1", "dmz"), HostZone::new("web2", "dmz")],
+        ..StaticFacts::default()
+    }
+}
+
+fn hosts(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn zone_deny_blocks_every_host_in_the_zone() {
+    let facts = dmz_zone_facts(vec![FirewallRuleRecord::create_deny_rule(
+        "internet", "dmz", "https",
+    )]);
+
+    let executions = run_rule_variant(facts, RuleVariant::Zones(FirewallPolicy::DefaultAllow));
+
+    assert_eq!(compromised_hosts(&executions), hosts(&["internet"]));
+}
+
+#[test]
+fn host_allow_overrides_zone_deny() {
+    let facts = dmz_zone_facts(vec![
+        FirewallRuleRecord::create_deny_rule("internet", "dmz", "https"),
+        FirewallRuleRecord::create_allow_rule("internet", "web1", "https"),
+    ]);
+
+    let executions = run_rule_variant(facts, RuleVariant::Zones(FirewallPolicy::DefaultAllow));
+
+    assert_eq!(compromised_hosts(&executions), hosts(&["internet", "web1"]));
+}
+
+#[test]
+fn deny_wins_between_equally_specific_rules() {
+    let facts = dmz_zone_facts(vec![
+        FirewallRuleRecord::create_allow_rule("*", "web2", "https"),
+        FirewallRuleRecord::create_deny_rule("*", "web2", "https"),
+    ]);
+
+    let executions = run_rule_variant(facts, RuleVariant::Zones(FirewallPolicy::DefaultAllow));
+
+    assert_eq!(compromised_hosts(&executions), hosts(&["internet", "web1"]));
+}
+
+#[test]
+fn wildcard_source_matches_any_host() {
+    let facts = dmz_zone_facts(vec![FirewallRuleRecord::create_deny_rule(
+        "*", "web2", "https",
+    )]);
+
+    let zoned = run_rule_variant(
+        facts.clone(),
+        RuleVariant::Zones(FirewallPolicy::DefaultAllow),
+    );
+    // Without zone semantics the rule only matches a host named "*".
+    let literal = run_rule_variant(facts, RuleVariant::Standard);
+
+    assert_eq!(compromised_hosts(&zoned), hosts(&["internet", "web1"]));
+    assert_eq!(
+        compromised_hosts(&literal),
+        hosts(&["internet", "web1", "web2"])
+    );
+}
+
+#[test]
+fn default_deny_zone_allow_is_narrowed_by_a_host_deny() {
+    let zone_allow = FirewallRuleRecord::create_allow_rule("*", "dmz", "https");
+    let host_deny = FirewallRuleRecord::create_deny_rule("internet", "web2", "https");
+
+    let open = run_rule_variant(
+        dmz_zone_facts(vec![zone_allow.clone()]),
+        RuleVariant::Zones(FirewallPolicy::DefaultDeny),
+    );
+    let narrowed = run_rule_variant(
+        dmz_zone_facts(vec![zone_allow, host_deny]),
+        RuleVariant::Zones(FirewallPolicy::DefaultDeny),
+    );
+    let closed = run_rule_variant(
+        dmz_zone_facts(Vec::new()),
+        RuleVariant::Zones(FirewallPolicy::DefaultDeny),
+    );
+
+    assert_eq!(
+        compromised_hosts(&open),
+        hosts(&["internet", "web1", "web2"])
+    );
+    assert_eq!(compromised_hosts(&narrowed), hosts(&["internet", "web1"]));
+    assert_eq!(compromised_hosts(&closed), hosts(&["internet"]));
+}
+
+// internet -> fileserver over smb; the vault has an ssh vulnerability
+// but no network edge leads to it.
+fn file_share_facts(fileserver_privilege: PrivilegeLevel) -> StaticFacts {
+    StaticFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("fileserver", "CVE-SMB", "smb", fileserver_privilege),
+            VulnerabilityRecord::new("vault", "CVE-SSH", "ssh", PrivilegeLevel::Root),
+        ],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet",
+            "fileserver",
+            "smb",
+        )],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        access_grants: vec![AccessGrantingVulnerabilityRecord::new(
+            "fileserver",
+            "ssh",
+            "vault",
+        )],
+        ..StaticFacts::default()
+    }
+}
+
+#[test]
+fn owning_file_server_grants_access_to_unreachable_host() {
+    let without_grants = run_rule_variant(
+        file_share_facts(PrivilegeLevel::Root),
+        RuleVariant::Standard,
+    );
+    let with_grants = run_rule_variant(
+        file_share_facts(PrivilegeLevel::Root),
+        RuleVariant::AccessGrants,
+    );
+
+    assert!(!compromised_hosts(&without_grants).contains("vault"));
+    assert!(with_grants.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "vault".to_string(),
+        obtained_privilege: PrivilegeLevel::Root,
+    }));
+}
+
+#[test]
+fn access_grant_requires_ownership_and_respects_firewall() {
+    let user_only = run_rule_variant(
+        file_share_facts(PrivilegeLevel::User),
+        RuleVariant::AccessGrants,
+    );
+    assert!(!compromised_hosts(&user_only).contains("vault"));
+
+    let mut denied = file_share_facts(PrivilegeLevel::Root);
+    denied
+        .firewall_rules
+        .push(FirewallRuleRecord::create_deny_rule(
+            "fileserver",
+            "vault",
+            "ssh",
+        ));
+    let denied = run_rule_variant(denied, RuleVariant::AccessGrants);
+    assert!(!compromised_hosts(&denied).contains("vault"));
+}
+
+// The file share scenario with only a User foothold on the file
+// server, plus a local escalation to Root from `escalation_from`.  A
+// Root execCode is exactly what ownsMachine is derived from.
+fn privilege_escalation_facts(escalation_from: PrivilegeLevel) -> StaticFacts {
+    StaticFacts {
+        privilege_escalations: vec![PrivilegeEscalationRecord::new(
+            "fileserver",
+            "CVE-LPE",
+            escalation_from,
+            PrivilegeLevel::Root,
+        )],
+        ..file_share_facts(PrivilegeLevel::User)
+    }
+}
+
+#[test]
+fn user_foothold_with_local_escalation_owns_the_host() {
+    let executions = run_rule_variant(
+        privilege_escalation_facts(PrivilegeLevel::User),
+        RuleVariant::PrivilegeEscalation,
+    );
+
+    assert!(executions.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "fileserver".to_string(),
+        obtained_privilege: PrivilegeLevel::Root,
+    }));
+}
+
+#[test]
+fn escalation_needs_its_exact_starting_privilege() {
+    let executions = run_rule_variant(
+        privilege_escalation_facts(PrivilegeLevel::None),
+        RuleVariant::PrivilegeEscalation,
+    );
+
+    assert!(!executions.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "fileserver".to_string(),
+        obtained_privilege: PrivilegeLevel::Root,
+    }));
+}
+
+// internet -> web -> app, where app stores the backup admin password.
+// The backup server has no vulnerability and no network edge, so the
+// harvested credential is the only way in.
+fn credential_reuse_facts() -> StaticFacts {
+    StaticFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web", "CVE-WEB", "http", PrivilegeLevel::User),
+            VulnerabilityRecord::new("app", "CVE-APP", "ssh", PrivilegeLevel::User),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web", "http"),
+            NetworkAccessRule::new_default_port("web", "app", "ssh"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        stored_credentials: vec![CredentialStoredRecord::new("app", "backup-admin")],
+        credential_grants: vec![CredentialGrantRecord::new(
+            "backup-admin",
+            "backup",
+            PrivilegeLevel::Root,
+        )],
+        ..StaticFacts::default()
+    }
+}
+
+#[test]
+fn credential_harvested_mid_path_reaches_unexploitable_host() {
+    let without_credentials = run_rule_variant(credential_reuse_facts(), RuleVariant::Standard);
+    let with_credentials = run_rule_variant(credential_reuse_facts(), RuleVariant::Credentials);
+
+    assert!(!compromised_hosts(&without_credentials).contains("backup"));
+    assert!(with_credentials.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "backup".to_string(),
+        obtained_privilege: PrivilegeLevel::Root,
+    }));
+}
+
+#[test]
+fn credential_is_unusable_until_its_host_is_compromised() {
+    let mut facts = credential_reuse_facts();
+    facts
+        .network_access
+        .retain(|access| access.destination_host != "app");
+
+    let executions = run_rule_variant(facts, RuleVariant::Credentials);
+
+    assert!(!compromised_hosts(&executions).contains("app"));
+    assert!(!compromised_hosts(&executions).contains("backup"));
+}
+
+// The domain controller is only reachable over a role-gated edge that
+// requires "domain-admins"; the helpdesk host holds those credentials.
+fn role_gated_facts() -> StaticFacts {
+    StaticFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("helpdesk", "CVE-HTTP", "http", PrivilegeLevel::User),
+            VulnerabilityRecord::new("dc01", "CVE-SSH", "ssh", PrivilegeLevel::Root),
+        ],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet", "helpdesk", "http",
+        )],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        role_grants: vec![RoleGrantRule::new(
+            "domain-admins",
+            "helpdesk",
+            "dc01",
+            "ssh",
+        )],
+        ..StaticFacts::default()
+    }
+}
+
+#[test]
+fn role_gated_edge_is_blocked_without_role() {
+    let executions = run_rule_variant(role_gated_facts(), RuleVariant::Roles);
+
+    assert!(compromised_hosts(&executions).contains("helpdesk"));
+    assert!(!compromised_hosts(&executions).contains("dc01"));
+}
+
+#[test]
+fn compromising_role_conferring_host_unlocks_role_gated_edge() {
+    let mut facts = role_gated_facts();
+    facts
+        .role_conferring_hosts
+        .push(RoleConferringHost::new("helpdesk", "domain-admins"));
+
+    let executions = run_rule_variant(facts, RuleVariant::Roles);
+
+    assert!(executions.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "dc01".to_string(),
+        obtained_privilege: PrivilegeLevel::Root,
+    }));
+}
+
+#[test]
+fn initial_role_membership_unlocks_role_gated_edge() {
+    let mut facts = role_gated_facts();
+    facts
+        .role_memberships
+        .push(AttackerRoleMembership::new("attacker", "domain-admins"));
+
+    let executions = run_rule_variant(facts, RuleVariant::Roles);
+
+    assert!(compromised_hosts(&executions).contains("dc01"));
+}
+
+// The only route to the database crosses an edge that was assumed from
+// a network diagram rather than seen by the scanner.
+fn partially_inferred_facts() -> StaticFacts {
+    StaticFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web01", "CVE-WEB", "http", PrivilegeLevel::User),
+            VulnerabilityRecord::new("db01", "CVE-DB", "mysql", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web01", "http"),
+            NetworkAccessRule::inferred("web01", "db01", "mysql"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("attacker", "db01")],
+        ..StaticFacts::default()
+    }
+}
+
+#[test]
+fn inferred_edges_are_dropped_in_observed_only_mode() {
+    let all_edges = run_rule_variant(partially_inferred_facts(), RuleVariant::Standard);
+    let observed_only = run_rule_variant(partially_inferred_facts(), RuleVariant::ObservedOnly);
+
+    assert!(compromised_hosts(&all_edges).contains("db01"));
+    assert!(compromised_hosts(&observed_only).contains("web01"));
+    assert!(!compromised_hosts(&observed_only).contains("db01"));
+}
+
+#[test]
+fn trust_plugin_rule_composes_with_network_exploits() {
+    // Only "web" is reachable; "files" trusts "web" and "backup" trusts
+    // "files", so the trust chain carries the attacker two hosts further.
+    let facts = StaticFacts {
+        vulnerabilities: vec![VulnerabilityRecord::new(
+            "web",
+            "CVE-WEB",
+            "http",
+            PrivilegeLevel::User,
+        )],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet", "web", "http",
+        )],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        trust_relationships: vec![
+            TrustRelationship::new("files", "web", PrivilegeLevel::User),
+            TrustRelationship::new("backup", "files", PrivilegeLevel::Root),
+        ],
+        ..StaticFacts::default()
+    };
+
+    let standard = run_rule_variant(facts.clone(), RuleVariant::Standard);
+    let with_plugin = run_rule_variant(facts, RuleVariant::TrustPlugin);
+
+    assert!(!compromised_hosts(&standard).contains("files"));
+    assert!(compromised_hosts(&with_plugin).contains("files"));
+    assert!(with_plugin.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "backup".to_string(),
+        obtained_privilege: PrivilegeLevel::Root,
+    }));
+}
+
+#[test]
+fn trust_chain_compromises_hosts_without_network_access() {
+    // Only "web" is network-reachable.  "files" trusts "web" and
+    // "backup" trusts "files"; neither has an edge or a vulnerability.
+    let facts = StaticFacts {
+        vulnerabilities: vec![VulnerabilityRecord::new(
+            "web",
+            "CVE-WEB",
+            "http",
+            PrivilegeLevel::User,
+        )],
+        network_access: vec![NetworkAccessRule::new_default_port(
+            "internet", "web", "http",
+        )],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        trust_relationships: vec![
+            TrustRelationship::new("files", "web", PrivilegeLevel::User),
+            TrustRelationship::new("backup", "files", PrivilegeLevel::Root),
+        ],
+        ..StaticFacts::default()
+    };
+
+    let standard = run_rule_variant(facts.clone(), RuleVariant::Standard);
+    let built_in = run_rule_variant(facts.clone(), RuleVariant::Trust);
+    let with_plugin = run_rule_variant(facts, RuleVariant::TrustPlugin);
+
+    assert_eq!(compromised_hosts(&standard), hosts(&["internet", "web"]));
+    assert_eq!(
+        compromised_hosts(&built_in),
+        hosts(&["internet", "web", "files", "backup"])
+    );
+    assert!(built_in.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "files".to_string(),
+        obtained_privilege: PrivilegeLevel::User,
+    }));
+    assert!(built_in.contains(&AttackerCodeExecution {
+        attacker_id: "attacker".to_string(),
+        compromised_host: "backup".to_string(),
+        obtained_privilege: PrivilegeLevel::Root,
+    }));
+    assert_eq!(built_in, with_plugin);
+}
@@ -0,0 +1,142 @@
+use std::collections::BTreeSet;
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    build_attack_graph_with_enabled_services, run_collection_query, AttackerGoalReached,
+    AttackerStartingPosition, AttackerTargetGoal, BaseFacts, EnablesService, NetworkAccessRule,
+    PrivilegeLevel, VulnerabilityRecord,
+};
+
+// internet -> web -> db.  Each host has a User-level bug on an exposed
+// service and a Root-level bug on a service that is off until a User
+// foothold enables it: RDP on web, WinRM on db.
+fn two_stage_facts() -> BaseFacts {
+    BaseFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("web", "CVE-WEB-HTTP", "http", PrivilegeLevel::User),
+            VulnerabilityRecord::new("web", "CVE-WEB-RDP", "rdp", PrivilegeLevel::Root),
+            VulnerabilityRecord::new("db", "CVE-DB-SMB", "smb", PrivilegeLevel::User),
+            VulnerabilityRecord::new("db", "CVE-DB-WINRM", "winrm", PrivilegeLevel::Root),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "web", "http"),
+            NetworkAccessRule::new_default_port("internet", "web", "rdp"),
+            NetworkAccessRule::new_default_port("web", "db", "smb"),
+            NetworkAccessRule::new_default_port("web", "db", "winrm"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "eve",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("eve", "db")],
+        ..BaseFacts::default()
+    }
+}
+
+fn enables(db_requires: PrivilegeLevel) -> Vec<EnablesService> {
+    vec![
+        EnablesService::new("web", PrivilegeLevel::User, "rdp"),
+        EnablesService::new("db", db_requires, "winrm"),
+    ]
+}
+
+// (host, privilege) pairs eve executes code with.
+fn executions(
+    facts: &BaseFacts,
+    enables: Vec<EnablesService>,
+) -> BTreeSet<(String, PrivilegeLevel)> {
+    run_collection_query(facts, move |inputs| {
+        let (_, enables_service) = inputs
+            .vulnerabilities
+            .scope()
+            .new_collection_from(enables.clone());
+        let (exec_code, _, _) = build_attack_graph_with_enabled_services(
+            &inputs.vulnerabilities,
+            &enables_service,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        exec_code.map(|exec| (exec.compromised_host, exec.obtained_privilege))
+    })
+    .into_iter()
+    .collect()
+}
+
+fn goals(facts: &BaseFacts, enables: Vec<EnablesService>) -> Vec<AttackerGoalReached> {
+    run_collection_query(facts, move |inputs| {
+        let (_, enables_service) = inputs
+            .vulnerabilities
+            .scope()
+            .new_collection_from(enables.clone());
+        let (_, _, goals) = build_attack_graph_with_enabled_services(
+            &inputs.vulnerabilities,
+            &enables_service,
+            &inputs.network_access,
+            &inputs.firewall_rules,
+            &inputs.attacker_positions,
+            &inputs.attacker_goals,
+        );
+        goals
+    })
+}
+
+fn held(pairs: &[(&str, PrivilegeLevel)]) -> BTreeSet<(String, PrivilegeLevel)> {
+    pairs
+        .iter()
+        .map(|(host, privilege)| (host.to_string(), privilege.clone()))
+        .collect()
+}
+
+#[test]
+fn enabling_a_service_unlocks_the_next_hop() {
+    let facts = two_stage_facts();
+
+    let derived = executions(&facts, enables(PrivilegeLevel::User));
+
+    assert_eq!(
+        derived,
+        held(&[
+            ("internet", PrivilegeLevel::User),
+            ("web", PrivilegeLevel::User),
+            ("web", PrivilegeLevel::Root),
+            ("db", PrivilegeLevel::User),
+            ("db", PrivilegeLevel::Root),
+        ])
+    );
+    assert_eq!(
+        goals(&facts, enables(PrivilegeLevel::User)),
+        vec![AttackerGoalReached {
+            attacker_id: "eve".to_string(),
+            reached_target: "db".to_string(),
+            required_privilege: PrivilegeLevel::Root,
+        }]
+    );
+}
+
+#[test]
+fn a_service_stays_off_until_its_privilege_is_held() {
+    let facts = two_stage_facts();
+
+    // A User foothold on db no longer turns WinRM on.
+    let derived = executions(&facts, enables(PrivilegeLevel::Root));
+
+    assert!(derived.contains(&("db".to_string(), PrivilegeLevel::User)));
+    assert!(!derived.contains(&("db".to_string(), PrivilegeLevel::Root)));
+    assert!(goals(&facts, enables(PrivilegeLevel::Root)).is_empty());
+}
+
+#[test]
+fn a_dormant_vulnerability_alone_gives_no_foothold() {
+    let mut facts = two_stage_facts();
+    facts
+        .vulnerabilities
+        .retain(|vulnerability| vulnerability.vulnerability_id != "CVE-WEB-HTTP");
+
+    // RDP is reachable and vulnerable, but nothing can switch it on.
+    let derived = executions(&facts, enables(PrivilegeLevel::User));
+
+    assert_eq!(derived, held(&[("internet", PrivilegeLevel::User)]));
+}
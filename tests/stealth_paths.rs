@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use differential_dataflow::input::Input;
+use dynamic_attack_graphs::{
+    stealthiest_path_to_goal, AttackerStartingPosition, AttackerTargetGoal, ExploitDetectionRisk,
+    FirewallRuleRecord, NetworkAccessRule, PrivilegeLevel, VulnerabilityRecord,
+};
+
+static TIMELY_TEST_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+#[derive(Debug, Clone, Default)]
+struct StaticFacts {
+    vulnerabilities: Vec<VulnerabilityRecord>,
+    network_access: Vec<NetworkAccessRule>,
+    attacker_positions: Vec<AttackerStartingPosition>,
+    attacker_goals: Vec<AttackerTargetGoal>,
+    detection_risks: Vec<ExploitDetectionRisk>,
+}
+
+// Goal host -> lowest accumulated detection risk.
+fn run_stealthiest_paths(facts: StaticFacts) -> BTreeMap<String, f64> {
+    let _runtime_guard = TIMELY_TEST_LOCK
+        .get_or_init(|| Mutex::new(()))
+        .lock()
+        .expect("timely test runtime lock should not be poisoned");
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let captured_in_dataflow = Arc::clone(&captured);
+
+    timely::execute_directly(move |worker| {
+        let (
+            mut vulnerability_input,
+            mut network_input,
+            firewall_input,
+            mut position_input,
+            mut goal_input,
+            mut risk_input,
+        ) = worker.dataflow::<usize, _, _>(|scope| {
+            let (vulnerability_handle, vulnerability_collection) =
+                scope.new_collection::<VulnerabilityRecord, isize>();
+            let (network_handle, network_collection) =
+                scope.new_collection::<NetworkAccessRule, isize>();
+            let (firewall_handle, firewall_collection) =
+                scope.new_collection::<FirewallRuleRecord, isize>();
+            let (position_handle, position_collection) =
+                scope.new_collection::<AttackerStartingPosition, isize>();
+            let (goal_handle, goal_collection) =
+                scope.new_collection::<AttackerTargetGoal, isize>();
+            let (risk_handle, risk_collection) =
+                scope.new_collection::<ExploitDetectionRisk, isize>();
+
+            let risks = stealthiest_path_to_goal(
+                &vulnerability_collection,
+                &network_collection,
+                &firewall_collection,
+                &position_collection,
+                &goal_collection,
+                &risk_collection,
+            );
+
+            let captured_risks = Arc::clone(&captured_in_dataflow);
+            risks.inspect(move |((host, risk), _time, diff)| {
+                captured_risks
+                    .lock()
+                    .expect("captured risks mutex should not be poisoned")
+                    .push(((host.clone(), risk.to_bits()), *diff));
+            });
+
+            (
+                vulnerability_handle,
+                network_handle,
+                firewall_handle,
+                position_handle,
+                goal_handle,
+                risk_handle,
+            )
+        });
+
+        for record in facts.vulnerabilities {
+            vulnerability_input.insert(record);
+        }
+        for record in facts.network_access {
+            network_input.insert(record);
+        }
+        for record in facts.attacker_positions {
+            position_input.insert(record);
+        }
+        for record in facts.attacker_goals {
+            goal_input.insert(record);
+        }
+        for record in facts.detection_risks {
+            risk_input.insert(record);
+        }
+
+        vulnerability_input.close();
+        network_input.close();
+        firewall_input.close();
+        position_input.close();
+        goal_input.close();
+        risk_input.close();
+
+        while worker.step() {}
+    });
+
+    // f64 is not `Ord`, so accumulate on its bit pattern.
+    let changes = captured
+        .lock()
+        .expect("captured risks mutex should not be poisoned")
+        .clone();
+    let mut counts = BTreeMap::new();
+    for (record, diff) in changes {
+        *counts.entry(record).or_insert(0isize) += diff;
+    }
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|((host, bits), _)| (host, f64::from_bits(bits)))
+        .collect()
+}
+
+// Two routes from the internet to the vault: one hop through a noisy
+// exploit, or three hops through quiet ones.
+fn noisy_shortcut_facts() -> StaticFacts {
+    StaticFacts {
+        vulnerabilities: vec![
+            VulnerabilityRecord::new("vault", "CVE-NOISY", "smb", PrivilegeLevel::Root),
+            VulnerabilityRecord::new("vault", "CVE-QUIET", "https", PrivilegeLevel::Root),
+            VulnerabilityRecord::new("mail", "CVE-MAIL", "imap", PrivilegeLevel::User),
+            VulnerabilityRecord::new("wiki", "CVE-WIKI", "http", PrivilegeLevel::User),
+        ],
+        network_access: vec![
+            NetworkAccessRule::new_default_port("internet", "vault", "smb"),
+            NetworkAccessRule::new_default_port("internet", "mail", "imap"),
+            NetworkAccessRule::new_default_port("mail", "wiki", "http"),
+            NetworkAccessRule::new_default_port("wiki", "vault", "https"),
+        ],
+        attacker_positions: vec![AttackerStartingPosition::new(
+            "attacker",
+            "internet",
+            PrivilegeLevel::User,
+        )],
+        attacker_goals: vec![AttackerTargetGoal::new("attacker", "vault")],
+        detection_risks: vec![
+            ExploitDetectionRisk::new("vault", "CVE-NOISY", 0.9),
+            ExploitDetectionRisk::new("mail", "CVE-MAIL", 0.1),
+            ExploitDetectionRisk::new("wiki", "CVE-WIKI", 0.2),
+            ExploitDetectionRisk::new("vault", "CVE-QUIET", 0.05),
+        ],
+    }
+}
+
+#[test]
+fn stealthier_longer_path_beats_noisy_shortcut() {
+    let risks = run_stealthiest_paths(noisy_shortcut_facts());
+
+    assert_eq!(risks.len(), 1);
+    assert!((risks["vault"] - 0.35).abs() < 1e-9, "{risks:?}");
+}
+
+#[test]
+fn noisy_shortcut_is_used_when_it_is_the_only_route() {
+    let mut facts = noisy_shortcut_facts();
+    facts
+        .network_access
+        .retain(|rule| rule.destination_host != "wiki");
+
+    let risks = run_stealthiest_paths(facts);
+
+    assert!((risks["vault"] - 0.9).abs() < 1e-9, "{risks:?}");
+}